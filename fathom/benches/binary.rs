@@ -0,0 +1,59 @@
+//! Benchmarks for reading arrays from binary data.
+//!
+//! Run with `cargo bench --bench binary`.
+
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use fathom::core::binary::{Buffer, Context};
+use fathom::core::semantics::{ArcValue, Value};
+use fathom::core::{Const, Prim, UIntStyle};
+use fathom::source::Spanned;
+
+const DATA_LEN: usize = 1024 * 1024;
+const ITERATIONS: u32 = 20;
+
+fn prim(prim: Prim, params: impl IntoIterator<Item = ArcValue<'static>>) -> ArcValue<'static> {
+    Spanned::empty(Arc::new(Value::prim(prim, params)))
+}
+
+/// A format that repeats `elem_format` enough times to consume all the data,
+/// given that each element is `elem_len` bytes long.
+fn repeat_format(elem_len: usize, elem_format: ArcValue<'static>) -> ArcValue<'static> {
+    let len = u32::try_from(DATA_LEN / elem_len).unwrap();
+    let len = Spanned::empty(Arc::new(Value::ConstLit(Const::U32(
+        len,
+        UIntStyle::Decimal,
+    ))));
+    prim(Prim::FormatRepeatLen32, [len, elem_format])
+}
+
+/// Read `format` repeatedly, printing the average time taken by each read.
+fn bench(name: &str, data: &[u8], format: &ArcValue<'static>) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let mut context = Context::new(Buffer::from(data));
+        let buffer = Buffer::from(black_box(data));
+        black_box(context.read_format(&mut buffer.reader(), format).unwrap());
+    }
+    println!("{name:<24} {:>12.2?}/iter", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let data = (0..DATA_LEN).map(|n| n as u8).collect::<Vec<_>>();
+
+    // Arrays of bytes are read as a single slice, and arrays of other
+    // primitive formats are read without re-entering the reader for each
+    // element, whereas arrays of compound formats take the general path.
+    let u8_format = prim(Prim::FormatU8, []);
+    let u16be_format = prim(Prim::FormatU16Be, []);
+    let u32le_format = prim(Prim::FormatU32Le, []);
+    let pair_len = Spanned::empty(Arc::new(Value::ConstLit(Const::U8(2, UIntStyle::Decimal))));
+    let pair_format = prim(Prim::FormatRepeatLen8, [pair_len, u8_format.clone()]);
+
+    bench("u8 array", &data, &repeat_format(1, u8_format));
+    bench("u16be array", &data, &repeat_format(2, u16be_format));
+    bench("u32le array", &data, &repeat_format(4, u32le_format));
+    bench("u8 pair array", &data, &repeat_format(2, pair_format));
+}
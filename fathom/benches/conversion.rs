@@ -0,0 +1,92 @@
+//! Benchmarks for checking the equality of values during conversion, and for
+//! elaborating formats that require many conversion checks.
+//!
+//! Run with `cargo bench --bench conversion`.
+
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+use fathom::core::semantics::{ArcValue, ElimEnv, Value};
+use fathom::core::{Const, Prim, UIntStyle};
+use fathom::env::{EnvLen, SliceEnv};
+use fathom::files::FileId;
+use fathom::source::{ProgramSource, Spanned, StringInterner};
+use fathom::surface::elaboration::{self, ItemEnv};
+use fathom::surface::Module;
+use scoped_arena::Scope;
+
+const ARRAY_LEN: u32 = 100_000;
+const ITERATIONS: u32 = 100;
+
+/// An array literal of `len` constants.
+fn array_lit(len: u32) -> ArcValue<'static> {
+    let elems = (0..len)
+        .map(|n| Value::ConstLit(Const::U32(n, UIntStyle::Decimal)))
+        .map(|elem| Spanned::empty(Arc::new(elem)))
+        .collect();
+    Spanned::empty(Arc::new(Value::ArrayLit(elems)))
+}
+
+/// Run `f` repeatedly, printing the average time taken by each iteration.
+fn bench(name: &str, mut f: impl FnMut() -> bool) {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(black_box(f()));
+    }
+    println!("{name:<24} {:>12.2?}/iter", start.elapsed() / ITERATIONS);
+}
+
+fn main() {
+    let item_exprs: &SliceEnv<_> = (&[][..]).into();
+    let meta_exprs: &SliceEnv<_> = (&[][..]).into();
+    let elim_env = ElimEnv::new(item_exprs, meta_exprs);
+    let mut conversion_env = elim_env.conversion_env(EnvLen::new());
+
+    let value = array_lit(ARRAY_LEN);
+    let value_copy = array_lit(ARRAY_LEN);
+
+    // Comparing a value with itself returns as soon as the values are found to
+    // be the same allocation, whereas comparing it with an identical copy
+    // requires every element to be compared.
+    bench("self comparison", || {
+        conversion_env.is_equal(black_box(&value), black_box(&value))
+    });
+    bench("structural comparison", || {
+        conversion_env.is_equal(black_box(&value), black_box(&value_copy))
+    });
+
+    // Values with different constructors are rejected without being forced,
+    // whereas stuck values must be forced first, as they might be solved
+    // metavariables.
+    let universe = Spanned::empty(Arc::new(Value::Universe));
+    let u8_type = Spanned::empty(Arc::new(Value::prim(Prim::U8Type, [])));
+    bench("distinct constructors", || {
+        (0..ARRAY_LEN).all(|_| !conversion_env.is_equal(black_box(&universe), black_box(&value)))
+    });
+    bench("stuck comparison", || {
+        (0..ARRAY_LEN).all(|_| !conversion_env.is_equal(black_box(&universe), black_box(&u8_type)))
+    });
+
+    // Elaborating a large format checks the types of many terms for
+    // conversion along the way.
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../formats/opentype.fathom");
+    let source = std::fs::read_to_string(path).unwrap();
+    let source = ProgramSource::try_from(source).unwrap();
+    let interner = RefCell::new(StringInterner::new());
+    let surface_scope = Scope::new();
+    let (surface_module, messages) = Module::parse(&interner, &surface_scope, &source);
+    assert!(messages.is_empty());
+
+    let file_id = FileId::try_from(1).unwrap();
+    bench("opentype elaboration", || {
+        let core_scope = Scope::new();
+        let mut context =
+            elaboration::Context::new(file_id, &interner, &core_scope, ItemEnv::new());
+        let mut is_ok = true;
+        let module = context.elab_module(&core_scope, &surface_module, &mut |_| is_ok = false);
+        black_box(module);
+        is_ok
+    });
+}
@@ -2,13 +2,18 @@
 
 use std::fmt;
 
-use crate::env::{Index, Level};
+use scoped_arena::Scope;
+
+use crate::core::visit::TermVisitor;
+use crate::env::{self, EnvLen, Index, Level, SliceEnv};
 use crate::source::{Span, StringId};
 
 pub mod binary;
+pub mod codegen;
 pub mod pretty;
 pub mod prim;
 pub mod semantics;
+pub mod visit;
 
 /// Modules
 pub struct Module<'arena> {
@@ -19,6 +24,9 @@ pub struct Module<'arena> {
 pub enum Item<'arena> {
     /// Top-level definitions
     Def {
+        /// Lines of the doc comments attached to the definition, without
+        /// their leading `///`
+        doc: &'arena [StringId],
         /// The label that identifies this definition
         label: StringId,
         /// The type of the defined expression
@@ -40,7 +48,7 @@ pub enum LocalInfo {
     Param,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Plicity {
     Explicit,
     Implicit,
@@ -180,6 +188,10 @@ pub enum Term<'arena> {
 
     /// Array literals.
     ArrayLit(Span, &'arena [Term<'arena>]),
+    /// The number of array elements that were left out when [normalizing a
+    /// term for display][semantics::EvalEnv::normalise_for_display]. This
+    /// only appears at the end of array literals, and can't be evaluated.
+    ElidedElems(Span, usize),
 
     /// Record formats, consisting of a list of dependent formats.
     FormatRecord(Span, &'arena [StringId], &'arena [Term<'arena>]),
@@ -194,16 +206,25 @@ pub enum Term<'arena> {
 
     /// Constant literals.
     ConstLit(Span, Const),
-    /// Match on a constant. The pattern branches should be unique, and listed
-    /// in lexicographic order.
+    /// String literals decoded from binary data. Unlike string constants,
+    /// these are not stored in the string interner.
+    StringLit(Span, &'arena str),
+    /// Match on a constant. The pattern branches should not overlap, and
+    /// should be listed in lexicographic order.
     ConstMatch(
         Span,
         &'arena Term<'arena>,
-        &'arena [(Const, Term<'arena>)],
+        &'arena [(ConstPattern, Term<'arena>)],
         Option<(Option<StringId>, &'arena Term<'arena>)>,
     ),
 }
 
+/// Copy a string into the arena, for use in [string literals][Term::StringLit].
+pub fn to_scope_str<'arena>(scope: &'arena Scope<'arena>, string: &str) -> &'arena str {
+    // The copied bytes are the bytes of a valid string
+    std::str::from_utf8(scope.to_scope_from_iter(string.bytes())).unwrap()
+}
+
 impl<'arena> Term<'arena> {
     /// Get the source span of the term.
     pub fn span(&self) -> Span {
@@ -222,11 +243,13 @@ impl<'arena> Term<'arena> {
             | Term::RecordLit(span, _, _)
             | Term::RecordProj(span, _, _)
             | Term::ArrayLit(span, _)
+            | Term::ElidedElems(span, _)
             | Term::FormatRecord(span, _, _)
             | Term::FormatCond(span, _, _, _)
             | Term::FormatOverlap(span, _, _)
             | Term::Prim(span, _)
             | Term::ConstLit(span, _)
+            | Term::StringLit(span, _)
             | Term::ConstMatch(span, _, _, _) => *span,
         }
     }
@@ -235,12 +258,19 @@ impl<'arena> Term<'arena> {
     pub fn binds_local(&self, mut var: Index) -> bool {
         match self {
             Term::LocalVar(_, v) => *v == var,
+            // Inserted metavariables are applied to the parameters that were
+            // in scope when they were inserted.
+            Term::InsertedMeta(_, _, infos) => {
+                let infos: &SliceEnv<_> = (*infos).into();
+                matches!(infos.get_index(var), Some(LocalInfo::Param))
+            }
             Term::ItemVar(_, _)
             | Term::MetaVar(_, _)
-            | Term::InsertedMeta(_, _, _)
             | Term::Universe(_)
+            | Term::ElidedElems(_, _)
             | Term::Prim(_, _)
-            | Term::ConstLit(_, _) => false,
+            | Term::ConstLit(_, _)
+            | Term::StringLit(_, _) => false,
 
             Term::Ann(_, expr, r#type) => expr.binds_local(var) || r#type.binds_local(var),
             Term::Let(_, _, def_type, def_expr, body_expr) => {
@@ -276,15 +306,83 @@ impl<'arena> Term<'arena> {
         }
     }
 
+    /// Returns `true` if the term contains no free local variables.
+    pub fn is_closed(&self) -> bool {
+        self.max_free_local().is_none()
+    }
+
+    /// Returns the index of the outermost local variable that occurs free in
+    /// the term, or `None` if the term is [closed][Term::is_closed].
+    ///
+    /// The term only depends on the local variables up to and including this
+    /// index, so any variables bound before it can be dropped from the
+    /// environment the term is evaluated in. Inserted metavariables depend on
+    /// all of the parameters in scope when they were inserted.
+    pub fn max_free_local(&self) -> Option<Index> {
+        let mut visitor = MaxFreeLocal {
+            binders: EnvLen::new(),
+            max_free_local: None,
+        };
+        visitor.visit_term(self);
+        visitor.max_free_local
+    }
+
+    /// Returns the level of the outermost local variable that occurs free in
+    /// the term, given the length of the environment the term is in.
+    pub fn max_free_level(&self, local_len: EnvLen) -> Option<Level> {
+        local_len.index_to_level(self.max_free_local()?)
+    }
+
     pub fn is_error(&self) -> bool {
         matches!(self, Term::Prim(_, Prim::ReportedError))
     }
 }
 
+/// Finds the outermost local variable that occurs free in a term.
+struct MaxFreeLocal {
+    /// The number of binders the current term is under.
+    binders: EnvLen,
+    max_free_local: Option<Index>,
+}
+
+impl MaxFreeLocal {
+    fn add_free_local(&mut self, var: Index) {
+        let var = self.binders.unbind_index(var);
+        self.max_free_local = Option::max(self.max_free_local, var);
+    }
+}
+
+impl<'arena> TermVisitor<'arena> for MaxFreeLocal {
+    fn visit_term(&mut self, term: &Term<'arena>) {
+        match term {
+            Term::LocalVar(_, var) => self.add_free_local(*var),
+            // Inserted metavariables depend on the outermost parameter that
+            // was in scope when they were inserted.
+            Term::InsertedMeta(_, _, infos) => {
+                let param_level = (infos.iter()).position(|info| matches!(info, LocalInfo::Param));
+                let param_index = param_level
+                    .and_then(|param_level| env::indices().nth(infos.len() - param_level - 1));
+                if let Some(param_index) = param_index {
+                    self.add_free_local(param_index);
+                }
+            }
+            _ => visit::visit_subterms(self, term),
+        }
+    }
+
+    fn push_local(&mut self) {
+        self.binders.push();
+    }
+
+    fn pop_local(&mut self) {
+        self.binders.pop();
+    }
+}
+
 macro_rules! def_prims {
     ($($(#[$prim_attr:meta])* $PrimName:ident => $prim_name:literal),* $(,)?) => {
         /// Primitives.
-        #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
         pub enum Prim {
             $($(#[$prim_attr])* $PrimName),*
         }
@@ -316,6 +414,8 @@ def_prims! {
     U32Type => "U32",
     /// Type of unsigned, 64-bit integers.
     U64Type => "U64",
+    /// Type of unsigned, 128-bit integers.
+    U128Type => "U128",
     /// Type of signed, two's complement, 8-bit integers.
     S8Type => "S8",
     /// Type of signed, two's complement, 16-bit integers.
@@ -324,6 +424,8 @@ def_prims! {
     S32Type => "S32",
     /// Type of signed, two's complement, 64-bit integers.
     S64Type => "S64",
+    /// Type of signed, two's complement, 128-bit integers.
+    S128Type => "S128",
     /// Type of 32-bit, IEEE-754 floating point numbers.
     F32Type => "F32",
     /// Type of 64-bit, IEEE-754 floating point numbers.
@@ -344,6 +446,8 @@ def_prims! {
     PosType => "Pos",
     /// Type of stream references.
     RefType => "Ref",
+    /// Type of interned strings.
+    StringType => "String",
 
     /// Type of format descriptions.
     FormatType => "Format",
@@ -353,6 +457,10 @@ def_prims! {
     FormatU16Be => "u16be",
     /// Unsigned, 16-bit integer formats (little-endian).
     FormatU16Le => "u16le",
+    /// Unsigned, 24-bit integer formats (big-endian), represented as `U32`.
+    FormatU24Be => "u24be",
+    /// Unsigned, 24-bit integer formats (little-endian), represented as `U32`.
+    FormatU24Le => "u24le",
     /// Unsigned, 32-bit integer formats (big-endian).
     FormatU32Be => "u32be",
     /// Unsigned, 32-bit integer formats (little-endian).
@@ -361,6 +469,10 @@ def_prims! {
     FormatU64Be => "u64be",
     /// Unsigned, 64-bit integer formats (little-endian).
     FormatU64Le => "u64le",
+    /// Unsigned, 128-bit integer formats (big-endian).
+    FormatU128Be => "u128be",
+    /// Unsigned, 128-bit integer formats (little-endian).
+    FormatU128Le => "u128le",
     /// Signed, two's complement, 8-bit integer formats.
     FormatS8 => "s8",
     /// Signed, two's complement, 16-bit integer formats (big-endian).
@@ -375,6 +487,10 @@ def_prims! {
     FormatS64Be => "s64be",
     /// Signed, two's complement, 64-bit integer formats (little-endian).
     FormatS64Le => "s64le",
+    /// Signed, two's complement, 128-bit integer formats (big-endian).
+    FormatS128Be => "s128be",
+    /// Signed, two's complement, 128-bit integer formats (little-endian).
+    FormatS128Le => "s128le",
     /// 32-bit, IEEE-754 floating point formats (big-endian).
     FormatF32Be => "f32be",
     /// 32-bit, IEEE-754 floating point formats (little-endian).
@@ -391,8 +507,16 @@ def_prims! {
     FormatRepeatLen32 => "repeat_len32",
     /// Repeat formats up to an unsigned 64-bit length.
     FormatRepeatLen64 => "repeat_len64",
+    /// Repeat a format an exact number of times, given as an unsigned 64-bit
+    /// count.
+    FormatRepeatCount => "repeat_count",
     /// Repeat a format until the length of the given parse scope is reached.
     FormatRepeatUntilEnd => "repeat_until_end",
+    /// Repeat a format up to a maximum number of times, given as an unsigned
+    /// 64-bit count, stopping early if the end of the parse scope is reached.
+    FormatRepeatUpTo => "repeat_up_to",
+    /// Repeat a format up to a length that is read using a length format.
+    FormatLengthPrefixed => "length_prefixed",
     /// Limit the format to an unsigned 8-bit byte length.
     FormatLimit8 => "limit8",
     /// Limit the format to an unsigned 16-bit byte length.
@@ -401,8 +525,19 @@ def_prims! {
     FormatLimit32 => "limit32",
     /// Limit the format to an unsigned 64-bit byte length.
     FormatLimit64 => "limit64",
+    /// A UTF-8 encoded string, given as an unsigned 64-bit byte length.
+    FormatUtf8 => "utf8",
+    /// Reserved bytes that must be zero, given as an unsigned 64-bit byte
+    /// length.
+    FormatReserved => "reserved",
     /// A format which returns the current position in the input stream.
     FormatStreamPos => "stream_pos",
+    /// A format that marks the current position in the input stream as the
+    /// base position for relative positions read by the format it wraps.
+    FormatWithBase => "with_base",
+    /// A format which returns the current position in the input stream,
+    /// relative to the innermost base position.
+    FormatRelPos => "rel_pos",
     /// A format that links to another location in the binary data stream,
     /// relative to a base position.
     FormatLink => "link",
@@ -414,6 +549,16 @@ def_prims! {
     FormatFail => "fail",
     /// Unwrap an option, or fail to parse.
     FormatUnwrap => "unwrap",
+    /// Parse a format if a condition holds, or succeed without parsing.
+    FormatOptional => "optional",
+    /// Parse a format, then log the parsed data with a label if debug logging
+    /// is enabled when reading.
+    FormatDebug => "debug",
+    /// Parse a format, then convert its representation using a function.
+    FormatMap => "map",
+    /// Parse a format, then parse the format returned by applying a function
+    /// to the parsed data.
+    FormatBind => "bind",
     /// Format representations.
     FormatRepr => "Repr",
 
@@ -426,6 +571,11 @@ def_prims! {
     BoolAnd => "bool_and",
     BoolOr  => "bool_or",
     BoolXor => "bool_xor",
+    /// Convert a boolean to `1` if it is `true`, or `0` if it is `false`.
+    BoolToU8 => "bool_to_u8",
+
+    StringEq  => "string_eq",
+    StringNeq => "string_neq",
 
     U8Eq  => "u8_eq",
     U8Neq => "u8_neq",
@@ -443,6 +593,9 @@ def_prims! {
     U8And => "u8_and",
     U8Or  => "u8_or",
     U8Xor => "u8_xor",
+    U8AsS8 => "u8_as_s8",
+    /// Convert a number to `false` if it is `0`, or `true` otherwise.
+    U8ToBool => "u8_to_bool",
 
     U16Eq  => "u16_eq",
     U16Neq => "u16_neq",
@@ -460,6 +613,7 @@ def_prims! {
     U16And => "u16_and",
     U16Or  => "u16_or",
     U16Xor => "u16_xor",
+    U16AsS16 => "u16_as_s16",
 
     U32Eq  => "u32_eq",
     U32Neq => "u32_neq",
@@ -477,6 +631,7 @@ def_prims! {
     U32And => "u32_and",
     U32Or  => "u32_or",
     U32Xor => "u32_xor",
+    U32AsS32 => "u32_as_s32",
 
     U64Eq  => "u64_eq",
     U64Neq => "u64_neq",
@@ -494,6 +649,25 @@ def_prims! {
     U64And => "u64_and",
     U64Or  => "u64_or",
     U64Xor => "u64_xor",
+    U64AsS64 => "u64_as_s64",
+
+    U128Eq  => "u128_eq",
+    U128Neq => "u128_neq",
+    U128Gt  => "u128_gt",
+    U128Lt  => "u128_lt",
+    U128Gte => "u128_gte",
+    U128Lte => "u128_lte",
+    U128Add => "u128_add",
+    U128Sub => "u128_sub",
+    U128Mul => "u128_mul",
+    U128Div => "u128_div",
+    U128Not => "u128_not",
+    U128Shl => "u128_shl",
+    U128Shr => "u128_shr",
+    U128And => "u128_and",
+    U128Or  => "u128_or",
+    U128Xor => "u128_xor",
+    U128AsS128 => "u128_as_s128",
 
     S8Eq  => "s8_eq",
     S8Neq => "s8_neq",
@@ -508,6 +682,7 @@ def_prims! {
     S8Div => "s8_div",
     S8Abs => "s8_abs",
     S8UAbs => "s8_unsigned_abs",
+    S8AsU8 => "s8_as_u8",
 
     S16Eq  => "s16_eq",
     S16Neq => "s16_neq",
@@ -522,6 +697,7 @@ def_prims! {
     S16Div => "s16_div",
     S16Abs => "s16_abs",
     S16UAbs => "s16_unsigned_abs",
+    S16AsU16 => "s16_as_u16",
 
     S32Eq  => "s32_eq",
     S32Neq => "s32_neq",
@@ -536,6 +712,7 @@ def_prims! {
     S32Div => "s32_div",
     S32Abs => "s32_abs",
     S32UAbs => "s32_unsigned_abs",
+    S32AsU32 => "s32_as_u32",
 
     S64Eq  => "s64_eq",
     S64Neq => "s64_neq",
@@ -550,6 +727,22 @@ def_prims! {
     S64Div => "s64_div",
     S64Abs => "s64_abs",
     S64UAbs => "s64_unsigned_abs",
+    S64AsU64 => "s64_as_u64",
+
+    S128Eq  => "s128_eq",
+    S128Neq => "s128_neq",
+    S128Gt  => "s128_gt",
+    S128Lt  => "s128_lt",
+    S128Gte => "s128_gte",
+    S128Lte => "s128_lte",
+    S128Neg => "s128_neg",
+    S128Add => "s128_add",
+    S128Sub => "s128_sub",
+    S128Mul => "s128_mul",
+    S128Div => "s128_div",
+    S128Abs => "s128_abs",
+    S128UAbs => "s128_unsigned_abs",
+    S128AsU128 => "s128_as_u128",
 
     OptionSome => "some",
     OptionNone => "none",
@@ -565,16 +758,30 @@ def_prims! {
     Array32Index => "array32_index",
     Array64Index => "array64_index",
 
+    Array8Fold => "array8_fold",
+    Array16Fold => "array16_fold",
+    Array32Fold => "array32_fold",
+    Array64Fold => "array64_fold",
+
+    ArrayLength => "array_length",
+    ArrayConcat => "array_concat",
+    ArraySlice => "array_slice",
+    ArrayMap => "array_map",
+
     PosAddU8  => "pos_add_u8",
     PosAddU16 => "pos_add_u16",
     PosAddU32 => "pos_add_u32",
     PosAddU64 => "pos_add_u64",
+    PosSub    => "pos_sub",
+    U64ToPos  => "u64_to_pos",
+    PosToU64  => "pos_to_u64",
 }
 
 /// Formatting style for integers
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd)]
 pub enum UIntStyle {
     Binary,
+    Octal,
     Decimal,
     Hexadecimal,
     /// A [four-character code](https://en.wikipedia.org/wiki/FourCC) (big-endian)
@@ -589,14 +796,29 @@ pub enum Const {
     U16(u16, UIntStyle),
     U32(u32, UIntStyle),
     U64(u64, UIntStyle),
+    U128(Packed<u128>, UIntStyle),
     S8(i8),
     S16(i16),
     S32(i32),
     S64(i64),
+    S128(Packed<i128>),
     F32(f32),
     F64(f64),
     Pos(usize),
     Ref(usize),
+    String(StringId),
+}
+
+impl Const {
+    /// Construct a 128-bit unsigned integer constant.
+    pub fn u128(num: u128, style: UIntStyle) -> Const {
+        Const::U128(Packed(num), style)
+    }
+
+    /// Construct a 128-bit signed integer constant.
+    pub fn s128(num: i128) -> Const {
+        Const::S128(Packed(num))
+    }
 }
 
 impl PartialEq for Const {
@@ -607,14 +829,17 @@ impl PartialEq for Const {
             (Const::U16(a, _), Const::U16(b, _)) => a == b,
             (Const::U32(a, _), Const::U32(b, _)) => a == b,
             (Const::U64(a, _), Const::U64(b, _)) => a == b,
+            (Const::U128(a, _), Const::U128(b, _)) => a == b,
             (Const::S8(a), Const::S8(b)) => a == b,
             (Const::S16(a), Const::S16(b)) => a == b,
             (Const::S32(a), Const::S32(b)) => a == b,
             (Const::S64(a), Const::S64(b)) => a == b,
+            (Const::S128(a), Const::S128(b)) => a == b,
             (Const::F32(a), Const::F32(b)) => a.total_cmp(&b).is_eq(),
             (Const::F64(a), Const::F64(b)) => a.total_cmp(&b).is_eq(),
             (Const::Pos(a), Const::Pos(b)) => a == b,
             (Const::Ref(a), Const::Ref(b)) => a == b,
+            (Const::String(a), Const::String(b)) => a == b,
             _ => false,
         }
     }
@@ -636,14 +861,17 @@ impl Ord for Const {
             (Const::U16(a, _), Const::U16(b, _)) => a.cmp(&b),
             (Const::U32(a, _), Const::U32(b, _)) => a.cmp(&b),
             (Const::U64(a, _), Const::U64(b, _)) => a.cmp(&b),
+            (Const::U128(a, _), Const::U128(b, _)) => a.cmp(&b),
             (Const::S8(a), Const::S8(b)) => a.cmp(&b),
             (Const::S16(a), Const::S16(b)) => a.cmp(&b),
             (Const::S32(a), Const::S32(b)) => a.cmp(&b),
             (Const::S64(a), Const::S64(b)) => a.cmp(&b),
+            (Const::S128(a), Const::S128(b)) => a.cmp(&b),
             (Const::F32(a), Const::F32(b)) => a.total_cmp(&b),
             (Const::F64(a), Const::F64(b)) => a.total_cmp(&b),
             (Const::Pos(a), Const::Pos(b)) => a.cmp(&b),
             (Const::Ref(a), Const::Ref(b)) => a.cmp(&b),
+            (Const::String(a), Const::String(b)) => a.cmp(&b),
             _ => {
                 fn discriminant(r#const: &Const) -> usize {
                     match r#const {
@@ -652,14 +880,17 @@ impl Ord for Const {
                         Const::U16(_, _) => 2,
                         Const::U32(_, _) => 3,
                         Const::U64(_, _) => 4,
-                        Const::S8(_) => 5,
-                        Const::S16(_) => 6,
-                        Const::S32(_) => 7,
-                        Const::S64(_) => 8,
-                        Const::F32(_) => 9,
-                        Const::F64(_) => 10,
-                        Const::Pos(_) => 11,
-                        Const::Ref(_) => 12,
+                        Const::U128(_, _) => 5,
+                        Const::S8(_) => 6,
+                        Const::S16(_) => 7,
+                        Const::S32(_) => 8,
+                        Const::S64(_) => 9,
+                        Const::S128(_) => 10,
+                        Const::F32(_) => 11,
+                        Const::F64(_) => 12,
+                        Const::Pos(_) => 13,
+                        Const::Ref(_) => 14,
+                        Const::String(_) => 15,
                     }
                 }
 
@@ -671,6 +902,106 @@ impl Ord for Const {
     }
 }
 
+/// A 128-bit integer, stored with the alignment of a 64-bit integer.
+///
+/// This prevents 128-bit integer constants from increasing the alignment of
+/// [`Const`], which would otherwise increase the size of every [`Term`] and
+/// [`Value`][semantics::Value].
+#[derive(Copy, Clone)]
+#[repr(packed(8))]
+pub struct Packed<T: Copy>(T);
+
+impl<T: Copy> Packed<T> {
+    pub const fn get(self) -> T {
+        self.0
+    }
+}
+
+impl<T: Copy> From<T> for Packed<T> {
+    fn from(value: T) -> Packed<T> {
+        Packed(value)
+    }
+}
+
+impl From<Packed<u128>> for u128 {
+    fn from(value: Packed<u128>) -> u128 {
+        value.get()
+    }
+}
+
+impl From<Packed<i128>> for i128 {
+    fn from(value: Packed<i128>) -> i128 {
+        value.get()
+    }
+}
+
+impl<T: Copy + PartialEq> PartialEq for Packed<T> {
+    fn eq(&self, other: &Packed<T>) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<T: Copy + Eq> Eq for Packed<T> {}
+
+impl<T: Copy + Ord> PartialOrd for Packed<T> {
+    fn partial_cmp(&self, other: &Packed<T>) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Copy + Ord> Ord for Packed<T> {
+    fn cmp(&self, other: &Packed<T>) -> std::cmp::Ordering {
+        self.get().cmp(&other.get())
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for Packed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+impl<T: Copy + fmt::Display> fmt::Display for Packed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// Patterns in [constant matches][Term::ConstMatch].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConstPattern {
+    /// Match a single constant.
+    Const(Const),
+    /// Match an inclusive range of integer constants.
+    Range(Const, Const),
+}
+
+impl ConstPattern {
+    /// The smallest constant matched by the pattern.
+    pub fn start(&self) -> Const {
+        match self {
+            ConstPattern::Const(r#const) | ConstPattern::Range(r#const, _) => *r#const,
+        }
+    }
+
+    /// The largest constant matched by the pattern.
+    pub fn end(&self) -> Const {
+        match self {
+            ConstPattern::Const(r#const) | ConstPattern::Range(_, r#const) => *r#const,
+        }
+    }
+
+    /// Returns `true` if the pattern matches the constant.
+    pub fn matches(&self, r#const: &Const) -> bool {
+        self.start() <= *r#const && *r#const <= self.end()
+    }
+
+    /// Returns `true` if both patterns match at least one common constant.
+    pub fn overlaps(&self, other: &ConstPattern) -> bool {
+        self.start() <= other.end() && other.start() <= self.end()
+    }
+}
+
 pub trait ToBeBytes<const N: usize> {
     fn to_be_bytes(self) -> [u8; N];
 }
@@ -689,10 +1020,10 @@ macro_rules! impl_styled_uint {
     };
 }
 
-impl_styled_uint!(u8, u16, u32, u64);
+impl_styled_uint!(u8, u16, u32, u64, u128);
 
 pub trait UIntStyled<const N: usize>:
-    std::fmt::Display + Copy + std::fmt::LowerHex + std::fmt::Binary + ToBeBytes<N>
+    std::fmt::Display + Copy + std::fmt::LowerHex + std::fmt::Octal + std::fmt::Binary + ToBeBytes<N>
 {
 }
 
@@ -700,6 +1031,7 @@ impl UIntStyle {
     pub fn format<T: UIntStyled<N>, const N: usize>(&self, number: T) -> String {
         match self {
             UIntStyle::Binary => format!("0b{number:b}"),
+            UIntStyle::Octal => format!("0o{number:o}"),
             UIntStyle::Decimal => number.to_string(),
             UIntStyle::Hexadecimal => format!("0x{number:x}"),
             UIntStyle::Ascii => {
@@ -722,6 +1054,7 @@ impl UIntStyle {
             (Decimal, style) | (style, Decimal) => style,
             // When both styles are the same. Note: (Decimal, Decimal) is handled above
             (Binary, Binary) => Binary,
+            (Octal, Octal) => Octal,
             (Hexadecimal, Hexadecimal) => Hexadecimal,
             (Ascii, Ascii) => Ascii,
             // Otherwise use the default style
@@ -732,6 +1065,8 @@ impl UIntStyle {
 
 #[cfg(test)]
 mod tests {
+    use scoped_arena::Scope;
+
     use super::*;
 
     #[test]
@@ -745,4 +1080,72 @@ mod tests {
     fn term_size() {
         assert_eq!(std::mem::size_of::<Term>(), 56);
     }
+
+    #[test]
+    fn free_locals() {
+        let scope = Scope::new();
+        let var = |index| Term::LocalVar(Span::Empty, index);
+        let fun_lit = |body_expr| {
+            Term::FunLit(
+                Span::Empty,
+                Plicity::Explicit,
+                None,
+                scope.to_scope(body_expr),
+            )
+        };
+        let fun_app = |head_expr, arg_expr| {
+            Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(head_expr),
+                scope.to_scope(arg_expr),
+            )
+        };
+        let index = |index| env::indices().nth(index).unwrap();
+
+        // `Type`
+        assert!(Term::Universe(Span::Empty).is_closed());
+        // `fun x => x`
+        assert!(fun_lit(var(index(0))).is_closed());
+        // `fun x => #2 x`
+        let term = fun_lit(fun_app(var(index(2)), var(index(0))));
+        assert!(!term.is_closed());
+        assert_eq!(term.max_free_local(), Some(index(1)));
+        assert_eq!(term.max_free_level(EnvLen::new()), None);
+        let mut local_len = EnvLen::new();
+        (0..3).for_each(|_| local_len.push());
+        assert_eq!(
+            term.max_free_level(local_len),
+            Some(env::levels().nth(1).unwrap()),
+        );
+        // `#0 (fun x => #2 x)`
+        let term = fun_app(var(index(0)), term);
+        assert_eq!(term.max_free_local(), Some(index(1)));
+        // `let x : Type = #4; x`
+        let term = Term::Let(
+            Span::Empty,
+            None,
+            scope.to_scope(Term::Universe(Span::Empty)),
+            scope.to_scope(var(index(4))),
+            scope.to_scope(var(index(0))),
+        );
+        assert_eq!(term.max_free_local(), Some(index(4)));
+        // `match #3 { 0 => #0, x => #1 }`
+        let const_match = |head_expr, body_expr, default_expr| {
+            Term::ConstMatch(
+                Span::Empty,
+                scope.to_scope(head_expr),
+                scope.to_scope_from_iter([(
+                    ConstPattern::Const(Const::U8(0, UIntStyle::Decimal)),
+                    body_expr,
+                )]),
+                Some((None, scope.to_scope(default_expr) as &_)),
+            )
+        };
+        let term = const_match(var(index(3)), var(index(0)), var(index(1)));
+        assert_eq!(term.max_free_local(), Some(index(3)));
+        // `match #0 { 0 => #1, x => #3 }`
+        let term = const_match(var(index(0)), var(index(1)), var(index(3)));
+        assert_eq!(term.max_free_local(), Some(index(2)));
+    }
 }
@@ -1,26 +1,67 @@
 //! Binary semantics of the data description language
 
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::Debug;
-use std::slice::SliceIndex;
+use std::io;
+use std::ops::Range;
 use std::sync::Arc;
 
-use crate::core::semantics::{self, ArcValue, Elim, Head, Value};
-use crate::core::{Const, Item, Module, Prim, Term, UIntStyle};
+use crate::core::semantics::{self, ArcValue, Elim, Fuel, Head, Value};
+use crate::core::{Const, Item, Module, Plicity, Prim, Term, UIntStyle};
 use crate::env::{EnvLen, SharedEnv, UniqueEnv};
-use crate::source::{Span, Spanned};
+use crate::source::{Span, Spanned, StringId, StringInterner};
 
 #[derive(Clone, Debug)]
 pub enum ReadError<'arena> {
     InvalidFormat(Span),
     InvalidValue(Span),
     UnknownItem,
-    UnwrappedNone(Span),
-    ReadFailFormat(Span),
-    CondFailure(Span, ArcValue<'arena>),
+    /// `unwrap` was applied to `none` while reading at the given offset.
+    UnwrappedNone(Span, usize),
+    /// A fail format was read at the given offset.
+    ReadFailFormat(Span, usize),
+    /// The predicate of a conditional format failed for the value read at
+    /// the given offset.
+    CondFailure(Span, usize, ArcValue<'arena>),
+    /// The bytes of a UTF-8 string read at the given offset were not valid
+    /// UTF-8.
+    InvalidUtf8(Span, usize),
+    /// The reserved byte at the given offset was not zero.
+    NonZeroReserved(Span, usize),
+    /// An array read at the given offset was longer than the maximum length
+    /// allowed by the reader, given as the requested and allowed number of
+    /// elements.
+    ArrayTooLong(Span, usize, u64, u64),
     BufferError(Span, BufferError),
+    OutOfFuel(Span),
+    /// A format needed to read data at the given offset, but the stream
+    /// could not seek to it.
+    SeekRequired(Span, usize),
+}
+
+impl<'arena> ReadError<'arena> {
+    /// The offset in the binary data that the error occurred at, if the error
+    /// was caused by the data.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ReadError::UnwrappedNone(_, offset)
+            | ReadError::ReadFailFormat(_, offset)
+            | ReadError::CondFailure(_, offset, _)
+            | ReadError::InvalidUtf8(_, offset)
+            | ReadError::NonZeroReserved(_, offset)
+            | ReadError::ArrayTooLong(_, offset, _, _)
+            | ReadError::SeekRequired(_, offset) => Some(*offset),
+            ReadError::BufferError(_, err) => err.offset(),
+            ReadError::InvalidFormat(_)
+            | ReadError::InvalidValue(_)
+            | ReadError::UnknownItem
+            | ReadError::OutOfFuel(_) => None,
+        }
+    }
 }
 
 impl<'arena> fmt::Display for ReadError<'arena> {
@@ -28,11 +69,16 @@ impl<'arena> fmt::Display for ReadError<'arena> {
         match self {
             ReadError::InvalidFormat(_) => f.write_str("invalid format"),
             ReadError::InvalidValue(_) => f.write_str("invalid value"),
-            ReadError::UnwrappedNone(_) => f.write_str("unwrapped none"),
+            ReadError::UnwrappedNone(_, _) => f.write_str("unwrapped none"),
             ReadError::UnknownItem => f.write_str("unknown item"),
-            ReadError::ReadFailFormat(_) => f.write_str("read a fail format"),
-            ReadError::CondFailure(_, _) => f.write_str("conditional format failed"),
+            ReadError::ReadFailFormat(_, _) => f.write_str("read a fail format"),
+            ReadError::CondFailure(_, _, _) => f.write_str("conditional format failed"),
+            ReadError::InvalidUtf8(_, _) => f.write_str("invalid UTF-8 string"),
+            ReadError::NonZeroReserved(_, _) => f.write_str("nonzero reserved byte"),
+            ReadError::ArrayTooLong(_, _, _, _) => f.write_str("array too long"),
             ReadError::BufferError(_, err) => fmt::Display::fmt(&err, f),
+            ReadError::OutOfFuel(_) => f.write_str("format nested too deeply"),
+            ReadError::SeekRequired(_, _) => f.write_str("format requires a seekable stream"),
         }
     }
 }
@@ -45,6 +91,38 @@ impl<'arena> From<BufferError> for ReadError<'arena> {
     }
 }
 
+#[derive(Debug)]
+pub enum WriteError<'arena> {
+    InvalidFormat(Span),
+    InvalidValue(Span),
+    WriteFailFormat(Span),
+    CondFailure(Span, ArcValue<'arena>),
+    /// The data to be written would not fit in memory.
+    TooLarge(Span),
+    IoError(io::Error),
+}
+
+impl<'arena> fmt::Display for WriteError<'arena> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::InvalidFormat(_) => f.write_str("invalid format"),
+            WriteError::InvalidValue(_) => f.write_str("invalid value"),
+            WriteError::WriteFailFormat(_) => f.write_str("wrote a fail format"),
+            WriteError::CondFailure(_, _) => f.write_str("conditional format failed"),
+            WriteError::TooLarge(_) => f.write_str("data too large to write"),
+            WriteError::IoError(err) => fmt::Display::fmt(&err, f),
+        }
+    }
+}
+
+impl<'arena> std::error::Error for WriteError<'arena> {}
+
+impl<'arena> From<io::Error> for WriteError<'arena> {
+    fn from(err: io::Error) -> WriteError<'arena> {
+        WriteError::IoError(err)
+    }
+}
+
 /// A buffer that starts at an offset into a larger buffer.
 ///
 /// ```text
@@ -66,15 +144,37 @@ impl<'arena> From<BufferError> for ReadError<'arena> {
 pub struct Buffer<'data> {
     /// Offset from the starting position.
     start_offset: usize,
-    /// A slice of data, starting from an offset from the start of a larger
-    /// buffer.
-    data: &'data [u8],
+    /// The data in the buffer, starting from an offset from the start of a
+    /// larger buffer.
+    data: BufferData<'data>,
+}
+
+/// The data backing a [`Buffer`].
+#[derive(Copy, Clone)]
+enum BufferData<'data> {
+    /// A slice of data that is already in memory.
+    Slice(&'data [u8]),
+    /// Data that is read from a stream as it is needed, ending at an optional
+    /// offset from the start of the stream.
+    Stream(&'data (dyn ByteStream + 'data), Option<usize>),
 }
 
 impl<'data> Buffer<'data> {
     /// Create a new buffer at an offset into a base buffer.
     pub fn new(start_offset: usize, data: &'data [u8]) -> Buffer<'data> {
-        Buffer { start_offset, data }
+        Buffer {
+            start_offset,
+            data: BufferData::Slice(data),
+        }
+    }
+
+    /// Create a buffer that reads data from a stream as it is needed, starting
+    /// at an offset from the start of the stream.
+    fn from_stream(start_offset: usize, stream: &'data (dyn ByteStream + 'data)) -> Buffer<'data> {
+        Buffer {
+            start_offset,
+            data: BufferData::Stream(stream, None),
+        }
     }
 
     /// The offset from the start of the base buffer.
@@ -84,28 +184,95 @@ impl<'data> Buffer<'data> {
 
     /// Total length of the buffer, including the base buffer.
     pub fn len(&self) -> Result<usize, BufferError> {
-        usize::checked_add(self.start_offset, self.data.len()).ok_or(BufferError::PositionOverflow)
+        usize::checked_add(self.start_offset, self.remaining_len()?)
+            .ok_or(BufferError::PositionOverflow)
     }
 
-    /// Remaining number of bytes in the buffer.
-    pub fn remaining_len(&self) -> usize {
-        self.data.len()
+    /// Remaining number of bytes in the buffer. Unless the buffer has been
+    /// limited, this reads the rest of a stream.
+    pub fn remaining_len(&self) -> Result<usize, BufferError> {
+        match self.data {
+            BufferData::Slice(data) => Ok(data.len()),
+            BufferData::Stream(_, Some(end)) => Ok(end - self.start_offset),
+            BufferData::Stream(stream, None) => {
+                Ok(stream.available(self.start_offset, usize::MAX)?)
+            }
+        }
     }
 
     /// Return a buffer limited to the supplied length.
     pub fn with_remaining_len(&self, len: usize) -> Result<Buffer<'data>, BufferError> {
+        let data = match self.data {
+            BufferData::Slice(data) => {
+                self.check_relative(0, len)?;
+                BufferData::Slice(&data[..len])
+            }
+            BufferData::Stream(stream, _) => {
+                self.check_relative(0, len)?;
+                BufferData::Stream(stream, Some(self.start_offset + len))
+            }
+        };
+
         Ok(Buffer {
             start_offset: self.start_offset,
-            data: self.get_relative(..len)?,
+            data,
         })
     }
 
-    /// Get a slice of the bytes in the buffer, relative to the start of the
-    /// buffer.
-    fn get_relative<I: SliceIndex<[u8]>>(&self, index: I) -> Result<&'data I::Output, BufferError> {
-        self.data
-            .get(index)
-            .ok_or(BufferError::UnexpectedEndOfBuffer)
+    /// Return a buffer of the data from an offset relative to the start of
+    /// the buffer.
+    fn with_relative_offset(&self, relative_offset: usize) -> Result<Buffer<'data>, BufferError> {
+        let start_offset = (self.start_offset.checked_add(relative_offset))
+            .ok_or(BufferError::PositionOverflow)?;
+        let data = match self.data {
+            BufferData::Slice(data) => BufferData::Slice(&data[relative_offset..]),
+            data @ BufferData::Stream(_, _) => data,
+        };
+
+        Ok(Buffer { start_offset, data })
+    }
+
+    /// Check that `len` bytes are available in the buffer, starting at an
+    /// offset relative to the start of the buffer.
+    fn check_relative(&self, relative_offset: usize, len: usize) -> Result<(), BufferError> {
+        let available = match self.data {
+            BufferData::Slice(data) => data.len().saturating_sub(relative_offset),
+            BufferData::Stream(stream, end) => {
+                let offset = self.start_offset.saturating_add(relative_offset);
+                let limit = end.map_or(usize::MAX, |end| end.saturating_sub(offset));
+                stream.available(offset, Ord::min(len, limit))?
+            }
+        };
+
+        match available < len {
+            true => Err(BufferError::UnexpectedEndOfBuffer {
+                offset: self.start_offset.saturating_add(relative_offset),
+                needed: len,
+                available,
+                format: None,
+                context: None,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Get `len` bytes in the buffer, starting at an offset relative to the
+    /// start of the buffer. Data in memory is borrowed, while data from
+    /// streams is copied.
+    fn get_relative(
+        &self,
+        relative_offset: usize,
+        len: usize,
+    ) -> Result<Cow<'data, [u8]>, BufferError> {
+        self.check_relative(relative_offset, len)?;
+
+        match self.data {
+            BufferData::Slice(data) => Ok(Cow::Borrowed(&data[relative_offset..][..len])),
+            BufferData::Stream(stream, _) => {
+                let offset = self.start_offset + relative_offset;
+                Ok(Cow::Owned(stream.read_at(offset, len)?))
+            }
+        }
     }
 
     /// Create a reader at the start of the buffer.
@@ -123,10 +290,127 @@ impl<'data> Buffer<'data> {
 
 impl<'data> From<&'data [u8]> for Buffer<'data> {
     fn from(data: &'data [u8]) -> Buffer<'data> {
-        Buffer {
-            start_offset: 0,
-            data,
+        Buffer::new(0, data)
+    }
+}
+
+/// A stream of bytes that can be read from at offsets from the start of the
+/// stream, for use as the data of a [`Buffer`].
+trait ByteStream {
+    /// The number of bytes available at an offset, up to `len`, reading more
+    /// of the stream if needed.
+    fn available(&self, offset: usize, len: usize) -> Result<usize, BufferError>;
+
+    /// Read `len` bytes at an offset, which must be [available].
+    ///
+    /// [available]: ByteStream::available
+    fn read_at(&self, offset: usize, len: usize) -> Result<Vec<u8>, BufferError>;
+}
+
+/// Data that is read incrementally from an [`io::Read`], as it is needed.
+///
+/// The data that has been read is retained in a window, so that it can be read
+/// again by formats like overlaps. If the reader can seek, reading from outside
+/// of the window moves the window to the new position, rather than reading all
+/// of the data in between.
+struct ReaderStream<R> {
+    state: RefCell<ReaderState<R>>,
+}
+
+struct ReaderState<R> {
+    reader: R,
+    /// Seek the reader to an offset, if the reader supports seeking.
+    seek: Option<fn(&mut R, usize) -> io::Result<()>>,
+    /// The offset of the first byte in the window.
+    window_start: usize,
+    /// The data that has been read, starting at `window_start`.
+    window: Vec<u8>,
+    /// Whether the end of the stream has been reached.
+    at_end: bool,
+}
+
+impl<R: io::Read> ReaderStream<R> {
+    /// Read from a stream sequentially, starting from its current position.
+    fn sequential(reader: R) -> ReaderStream<R> {
+        ReaderStream::new(reader, None, 0)
+    }
+
+    /// Read from a seekable stream, starting at the supplied offset.
+    fn seekable(reader: R, start_offset: usize) -> ReaderStream<R>
+    where
+        R: io::Seek,
+    {
+        let seek = |reader: &mut R, offset: usize| {
+            let offset = u64::try_from(offset).unwrap_or(u64::MAX);
+            reader.seek(io::SeekFrom::Start(offset)).map(|_| ())
+        };
+        ReaderStream::new(reader, Some(seek), start_offset)
+    }
+
+    fn new(
+        reader: R,
+        seek: Option<fn(&mut R, usize) -> io::Result<()>>,
+        start_offset: usize,
+    ) -> ReaderStream<R> {
+        ReaderStream {
+            state: RefCell::new(ReaderState {
+                reader,
+                seek,
+                window_start: start_offset,
+                window: Vec::new(),
+                at_end: false,
+            }),
+        }
+    }
+}
+
+impl<R: io::Read> ReaderState<R> {
+    /// Read data into the window so that it contains as much as possible of
+    /// the `len` bytes at `offset`.
+    fn fill(&mut self, offset: usize, len: usize) -> Result<(), BufferError> {
+        let window_end = self.window_start + self.window.len();
+
+        if offset < self.window_start || offset > window_end {
+            match self.seek {
+                Some(seek) => {
+                    seek(&mut self.reader, offset).map_err(BufferError::from)?;
+                    self.window_start = offset;
+                    self.window.clear();
+                    self.at_end = false;
+                }
+                None if offset < self.window_start => {
+                    return Err(BufferError::SetOffsetBeforeStartOfBuffer { offset });
+                }
+                // Read the data in between, as the reader can't skip it
+                None => {}
+            }
         }
+
+        let needed = (offset - self.window_start).saturating_add(len);
+        if self.window.len() < needed && !self.at_end {
+            let remaining = (needed - self.window.len()) as u64;
+            let mut reader = io::Read::take(&mut self.reader, remaining);
+            let read_len = io::Read::read_to_end(&mut reader, &mut self.window)?;
+            self.at_end = (read_len as u64) < remaining;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: io::Read> ByteStream for ReaderStream<R> {
+    fn available(&self, offset: usize, len: usize) -> Result<usize, BufferError> {
+        let mut state = self.state.borrow_mut();
+        state.fill(offset, len)?;
+        let window_end = state.window_start + state.window.len();
+        Ok(Ord::min(window_end.saturating_sub(offset), len))
+    }
+
+    fn read_at(&self, offset: usize, len: usize) -> Result<Vec<u8>, BufferError> {
+        let mut state = self.state.borrow_mut();
+        state.fill(offset, len)?;
+        let start = offset - state.window_start;
+        Ok(state.window[start..][..len].to_vec())
     }
 }
 
@@ -158,27 +442,32 @@ impl<'data> BufferReader<'data> {
     }
 
     /// Remaining number of bytes from the current position to the end of the
-    /// buffer.
-    pub fn remaining_len(&self) -> usize {
-        self.buffer.remaining_len() - self.relative_offset
+    /// buffer. Unless the buffer has been limited, this reads the rest of a
+    /// stream.
+    pub fn remaining_len(&self) -> Result<usize, BufferError> {
+        Ok(self.buffer.remaining_len()? - self.relative_offset)
     }
 
     /// Return a buffer of the remaining data from the current relative offset.
     pub fn remaining_buffer(&self) -> Result<Buffer<'data>, BufferError> {
-        Ok(Buffer::new(
-            self.offset()?,
-            self.buffer.get_relative(self.relative_offset..)?,
-        ))
+        self.buffer.with_relative_offset(self.relative_offset)
     }
 
     /// Set the offset of the reader relative to the start of the backing
     /// buffer.
     pub fn set_relative_offset(&mut self, relative_offset: usize) -> Result<(), BufferError> {
-        (relative_offset <= self.buffer.remaining_len())
-            .then(|| self.relative_offset = relative_offset)
-            .ok_or(BufferError::SetOffsetAfterEndOfBuffer {
-                offset: self.buffer.start_offset.checked_add(relative_offset),
-            })
+        match self.buffer.check_relative(0, relative_offset) {
+            Ok(()) => {
+                self.relative_offset = relative_offset;
+                Ok(())
+            }
+            Err(BufferError::UnexpectedEndOfBuffer { .. }) => {
+                Err(BufferError::SetOffsetAfterEndOfBuffer {
+                    offset: self.buffer.start_offset.checked_add(relative_offset),
+                })
+            }
+            Err(err) => Err(err),
+        }
     }
 
     /// Set the offset of the reader relative to the start position.
@@ -188,26 +477,30 @@ impl<'data> BufferReader<'data> {
             .and_then(|relative_offset| self.set_relative_offset(relative_offset))
     }
 
-    /// Get a slice of the bytes in the buffer, relative to the current offset
-    /// in the buffer.
-    fn get_relative<I: SliceIndex<[u8]>>(&self, index: I) -> Result<&'data I::Output, BufferError> {
-        let data = self.buffer.get_relative(self.relative_offset..)?;
-        data.get(index).ok_or(BufferError::UnexpectedEndOfBuffer)
+    /// Get `len` bytes in the buffer, starting from the current offset in the
+    /// buffer.
+    fn get_relative(&self, len: usize) -> Result<Cow<'data, [u8]>, BufferError> {
+        self.buffer.get_relative(self.relative_offset, len)
     }
 
     /// Read a byte and advance the reader.
     pub fn read_byte(&mut self) -> Result<u8, BufferError> {
-        let first = self.buffer.get_relative(self.relative_offset)?;
-        self.relative_offset += 1;
-        Ok(*first)
+        let [first] = self.read_byte_array()?;
+        Ok(first)
+    }
+
+    /// Read a slice of bytes and advance the offset into the buffer.
+    pub fn read_byte_slice(&mut self, len: usize) -> Result<Cow<'data, [u8]>, BufferError> {
+        let slice = self.get_relative(len)?;
+        self.relative_offset += len;
+        Ok(slice)
     }
 
     /// Read an array of bytes and advance the offset into the buffer.
-    pub fn read_byte_array<const N: usize>(&mut self) -> Result<&'data [u8; N], BufferError> {
-        let slice = self.get_relative(..N)?;
-        // SAFETY: slice points to [u8; N]? Yes it's [u8] of length N (checked by
-        // BufferReader::get_relative)
-        let array = unsafe { &*(slice.as_ptr() as *const [u8; N]) };
+    pub fn read_byte_array<const N: usize>(&mut self) -> Result<[u8; N], BufferError> {
+        let slice = self.get_relative(N)?;
+        // unwrap is safe because `get_relative` returns exactly `N` bytes
+        let array = <[u8; N]>::try_from(&slice[..]).unwrap();
         self.relative_offset += N;
         Ok(array)
     }
@@ -224,13 +517,45 @@ impl<'data> From<Buffer<'data>> for BufferReader<'data> {
 
 #[derive(Clone, Debug)]
 pub enum BufferError {
-    SetOffsetBeforeStartOfBuffer { offset: usize },
-    SetOffsetAfterEndOfBuffer { offset: Option<usize> },
-    UnexpectedEndOfBuffer,
+    SetOffsetBeforeStartOfBuffer {
+        offset: usize,
+    },
+    SetOffsetAfterEndOfBuffer {
+        offset: Option<usize>,
+    },
+    /// Fewer bytes were available at an offset than were needed.
+    UnexpectedEndOfBuffer {
+        offset: usize,
+        needed: usize,
+        available: usize,
+        /// The name of the primitive format being decoded, if known.
+        format: Option<&'static str>,
+        /// A description of where in the format the data was being read, if
+        /// known.
+        context: Option<String>,
+    },
     PositionOverflow,
+    /// An error occurred while reading from a stream.
+    IoError(Arc<io::Error>),
+}
+
+impl From<io::Error> for BufferError {
+    fn from(err: io::Error) -> BufferError {
+        BufferError::IoError(Arc::new(err))
+    }
 }
 
 impl BufferError {
+    /// The offset in the binary data that the error occurred at, if known.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            BufferError::SetOffsetBeforeStartOfBuffer { offset }
+            | BufferError::UnexpectedEndOfBuffer { offset, .. } => Some(*offset),
+            BufferError::SetOffsetAfterEndOfBuffer { offset } => *offset,
+            BufferError::PositionOverflow | BufferError::IoError(_) => None,
+        }
+    }
+
     fn with_span<'arena>(self, span: Span) -> ReadError<'arena> {
         ReadError::BufferError(span, self)
     }
@@ -245,8 +570,15 @@ impl fmt::Display for BufferError {
             BufferError::SetOffsetAfterEndOfBuffer { .. } => {
                 f.write_str("attempt to set buffer offset after the end of the buffer")
             }
-            BufferError::UnexpectedEndOfBuffer => f.write_str("unexpected end of buffer"),
+            BufferError::UnexpectedEndOfBuffer {
+                format: Some(format),
+                ..
+            } => write!(f, "unexpected end of buffer while reading `{format}`"),
+            BufferError::UnexpectedEndOfBuffer { format: None, .. } => {
+                f.write_str("unexpected end of buffer")
+            }
             BufferError::PositionOverflow => f.write_str("position overflow"),
+            BufferError::IoError(err) => fmt::Display::fmt(&err, f),
         }
     }
 }
@@ -259,6 +591,36 @@ pub struct Context<'arena, 'data> {
     initial_buffer: Buffer<'data>,
     pending_formats: Vec<(usize, ArcValue<'arena>)>,
     cached_refs: HashMap<usize, Vec<ParsedRef<'arena>>>,
+    fuel: Fuel,
+    /// The labels of the fields currently being read.
+    field_path: Vec<StringId>,
+    /// The byte ranges consumed by fields, if they are being recorded.
+    field_ranges: Option<Vec<FieldRange>>,
+    /// Whether data may be read from positions other than the current one.
+    seekable: bool,
+    /// The interner used to look up string constants that strings decoded
+    /// from the binary data are compared with.
+    interner: Option<&'data RefCell<StringInterner>>,
+    /// The maximum number of elements that may be read into a single array.
+    max_array_len: Option<u64>,
+    /// The base positions that relative positions are read from, with the
+    /// innermost base last.
+    bases: Vec<usize>,
+    /// Called with the label, position, and data read by each `debug` format.
+    debug_sink: Option<Box<DebugSink<'arena, 'data>>>,
+}
+
+/// A callback that receives the label, starting position, and data read by a
+/// `debug` format.
+pub type DebugSink<'arena, 'data> = dyn FnMut(StringId, usize, &ArcValue<'arena>) + 'data;
+
+/// The range of bytes consumed by a field of a record or overlap format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRange {
+    /// The labels of the enclosing fields, ending with the label of the field.
+    pub path: Vec<StringId>,
+    /// The offsets of the bytes consumed by the field.
+    pub range: Range<usize>,
 }
 
 pub struct ParsedRef<'arena> {
@@ -278,16 +640,59 @@ impl<'arena, 'data> Context<'arena, 'data> {
             initial_buffer,
             pending_formats: Vec::new(),
             cached_refs: HashMap::new(),
+            fuel: Fuel::default(),
+            field_path: Vec::new(),
+            field_ranges: None,
+            seekable: true,
+            interner: None,
+            max_array_len: None,
+            bases: Vec::new(),
+            debug_sink: None,
         }
     }
 
-    fn eval_env(&mut self) -> semantics::EvalEnv<'arena, '_> {
-        let elim_env = semantics::ElimEnv::new(&self.item_exprs, [][..].into());
+    /// Use the supplied interner to look up string constants that strings
+    /// decoded when reading `utf8` formats are compared with. Without an
+    /// interner these comparisons are stuck.
+    pub fn set_interner(&mut self, interner: &'data RefCell<StringInterner>) {
+        self.interner = Some(interner);
+    }
+
+    /// Limit how deeply formats may be nested while reading, including
+    /// through dereferenced links. Exceeding this results in a
+    /// [`ReadError::OutOfFuel`].
+    pub fn set_fuel(&mut self, fuel: Fuel) {
+        self.fuel = fuel;
+    }
+
+    /// Limit the number of elements that may be read into a single array,
+    /// guarding against length fields that would otherwise exhaust memory.
+    /// Exceeding this results in a [`ReadError::ArrayTooLong`].
+    pub fn set_max_array_len(&mut self, max_array_len: u64) {
+        self.max_array_len = Some(max_array_len);
+    }
+
+    /// Pass the data read by `debug` formats to `sink`, along with their labels
+    /// and the positions they were read from. Without a sink, `debug` formats
+    /// are read like the formats they wrap.
+    pub fn set_debug_sink(&mut self, sink: impl FnMut(StringId, usize, &ArcValue<'arena>) + 'data) {
+        self.debug_sink = Some(Box::new(sink));
+    }
+
+    pub fn eval_env(&mut self) -> semantics::EvalEnv<'arena, '_> {
+        let mut elim_env = semantics::ElimEnv::new(&self.item_exprs, [][..].into());
+        if let Some(interner) = self.interner {
+            elim_env = elim_env.with_interner(interner);
+        }
         semantics::EvalEnv::new(elim_env, &mut self.local_exprs)
     }
 
-    fn elim_env(&self) -> semantics::ElimEnv<'arena, '_> {
-        semantics::ElimEnv::new(&self.item_exprs, [][..].into())
+    pub fn elim_env(&self) -> semantics::ElimEnv<'arena, '_> {
+        let elim_env = semantics::ElimEnv::new(&self.item_exprs, [][..].into());
+        match self.interner {
+            Some(interner) => elim_env.with_interner(interner),
+            None => elim_env,
+        }
     }
 
     pub fn add_module(&mut self, module: &Module<'arena>) {
@@ -318,21 +723,159 @@ impl<'arena, 'data> Context<'arena, 'data> {
         Ok(self.cached_refs)
     }
 
-    fn read_format(
+    /// Read a value from the start of the buffer, according to the supplied
+    /// format description, returning the value alongside the position directly
+    /// after the last byte that was consumed.
+    ///
+    /// This is the same position that would be read by a subsequent
+    /// `stream_pos` format, allowing parsing to be continued afterwards.
+    pub fn read_format_with_end_pos(
+        &mut self,
+        buffer: Buffer<'data>,
+        format: &ArcValue<'arena>,
+    ) -> Result<(ArcValue<'arena>, usize), ReadError<'arena>> {
+        let mut reader = buffer.reader();
+        let expr = self.read_format(&mut reader, format)?;
+        let end_pos = reader.offset()?;
+
+        Ok((expr, end_pos))
+    }
+
+    /// Read a value from the start of the buffer, according to the supplied
+    /// format description, returning the value alongside the byte ranges
+    /// consumed by each field of the record and overlap formats encountered
+    /// along the way.
+    ///
+    /// Fields are recorded once they have been read, so nested fields appear
+    /// before the fields that contain them. Fields of overlap formats may
+    /// share the same bytes, and fields of repeated formats are recorded once
+    /// per repetition.
+    pub fn read_format_with_field_ranges(
+        &mut self,
+        buffer: Buffer<'data>,
+        format: &ArcValue<'arena>,
+    ) -> Result<(ArcValue<'arena>, Vec<FieldRange>), ReadError<'arena>> {
+        let prev_field_ranges = self.field_ranges.replace(Vec::new());
+        let expr = self.read_format(&mut buffer.reader(), format);
+        let field_ranges = std::mem::replace(&mut self.field_ranges, prev_field_ranges);
+
+        Ok((expr?, field_ranges.unwrap_or_default()))
+    }
+
+    /// Read a value from the reader, according to the supplied format
+    /// description.
+    ///
+    /// The reader is left positioned after the bytes consumed by the format.
+    /// Links encountered along the way are queued as pending formats, and will
+    /// only be resolved by [`Context::read_entrypoint`].
+    pub fn read_format(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        (self.fuel.burn()).map_err(|_| ReadError::OutOfFuel(format.span()))?;
+        let expr = self.read_format_step(reader, format);
+        self.fuel.refund();
+        expr
+    }
+
+    /// Read a field of a record or overlap format, recording the range of bytes
+    /// that it consumed if field ranges are being recorded.
+    fn read_field(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        label: StringId,
+        format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        self.field_path.push(label);
+        let expr = self.read_field_format(reader, format);
+        self.field_path.pop();
+        expr
+    }
+
+    /// Read the format of the field at the end of the current field path.
+    fn read_field_format(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        if self.field_ranges.is_none() {
+            return self.read_format(reader, format);
+        }
+
+        let start = reader.offset()?;
+        let expr = self.read_format(reader, format)?;
+        let end = reader.offset()?;
+
+        let path = self.field_path.clone();
+        if let Some(field_ranges) = &mut self.field_ranges {
+            field_ranges.push(FieldRange {
+                path,
+                range: start..end,
+            });
+        }
+
+        Ok(expr)
+    }
+
+    /// Record the primitive format and field being read when the end of the
+    /// buffer was reached, unless they were already recorded by a nested
+    /// format.
+    fn with_read_context(&self, err: ReadError<'arena>, prim: Prim) -> ReadError<'arena> {
+        match err {
+            ReadError::BufferError(
+                span,
+                BufferError::UnexpectedEndOfBuffer {
+                    offset,
+                    needed,
+                    available,
+                    format: None,
+                    context: _,
+                },
+            ) => ReadError::BufferError(
+                span,
+                BufferError::UnexpectedEndOfBuffer {
+                    offset,
+                    needed,
+                    available,
+                    format: Some(prim.name()),
+                    context: self.field_context(),
+                },
+            ),
+            err => err,
+        }
+    }
+
+    /// Describe the field currently being read, if there is one and its
+    /// labels can be resolved.
+    fn field_context(&self) -> Option<String> {
+        let interner = self.interner?.borrow();
+        let path = (self.field_path.iter())
+            .map(|label| interner.resolve(*label))
+            .collect::<Option<Vec<_>>>()?;
+
+        match path.as_slice() {
+            [] => None,
+            path => Some(format!("while reading the field `{}`", path.join("."))),
+        }
+    }
+
+    fn read_format_step(
         &mut self,
         reader: &mut BufferReader<'data>,
         format: &ArcValue<'arena>,
     ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
         match format.as_ref() {
             Value::Stuck(Head::Prim(prim), slice) => {
-                self.read_prim(reader, *prim, slice, format.span())
+                (self.read_prim(reader, *prim, slice, format.span()))
+                    .map_err(|err| self.with_read_context(err, *prim))
             }
             Value::FormatRecord(labels, formats) => {
                 let mut formats = formats.clone();
                 let mut exprs = Vec::with_capacity(formats.len());
 
                 while let Some((format, next_formats)) = self.elim_env().split_telescope(formats) {
-                    let expr = self.read_format(reader, &format)?;
+                    let expr = self.read_field(reader, labels[exprs.len()], &format)?;
                     exprs.push(expr.clone());
                     formats = next_formats(expr);
                 }
@@ -343,13 +886,14 @@ impl<'arena, 'data> Context<'arena, 'data> {
                 ))
             }
             Value::FormatCond(_label, format, cond) => {
+                let offset = reader.offset()?;
                 let value = self.read_format(reader, format)?;
                 let cond_res = self.elim_env().apply_closure(cond, value.clone());
 
                 match cond_res.as_ref() {
                     Value::ConstLit(Const::Bool(true)) => Ok(value),
                     Value::ConstLit(Const::Bool(false)) => {
-                        Err(ReadError::CondFailure(cond.span(), value))
+                        Err(ReadError::CondFailure(cond.span(), offset, value))
                     }
                     _ => {
                         // This shouldn't happen since we check that the cond type is Bool earlier
@@ -364,9 +908,12 @@ impl<'arena, 'data> Context<'arena, 'data> {
                 let mut exprs = Vec::with_capacity(formats.len());
 
                 while let Some((format, next_formats)) = self.elim_env().split_telescope(formats) {
+                    // Each field is read from the start of the overlap, so
+                    // `stream_pos` reports the same position at the start of
+                    // every field.
                     let mut reader = reader.clone();
 
-                    let expr = self.read_format(&mut reader, &format)?;
+                    let expr = self.read_field(&mut reader, labels[exprs.len()], &format)?;
                     exprs.push(expr.clone());
                     formats = next_formats(expr);
 
@@ -392,7 +939,9 @@ impl<'arena, 'data> Context<'arena, 'data> {
             | Value::RecordType(_, _)
             | Value::RecordLit(_, _)
             | Value::ArrayLit(_)
-            | Value::ConstLit(_) => Err(ReadError::InvalidFormat(format.span())),
+            | Value::ByteArrayLit(_)
+            | Value::ConstLit(_)
+            | Value::StringLit(_) => Err(ReadError::InvalidFormat(format.span())),
         }
     }
 
@@ -410,10 +959,14 @@ impl<'arena, 'data> Context<'arena, 'data> {
             (Prim::FormatU8, []) => read_const(reader, span, read_u8, |num| Const::U8(num, UIntStyle::Decimal)),
             (Prim::FormatU16Be, []) => read_const(reader, span, read_u16be, |num| Const::U16(num, UIntStyle::Decimal)),
             (Prim::FormatU16Le, []) => read_const(reader, span, read_u16le, |num| Const::U16(num, UIntStyle::Decimal)),
+            (Prim::FormatU24Be, []) => read_const(reader, span, read_u24be, |num| Const::U32(num, UIntStyle::Decimal)),
+            (Prim::FormatU24Le, []) => read_const(reader, span, read_u24le, |num| Const::U32(num, UIntStyle::Decimal)),
             (Prim::FormatU32Be, []) => read_const(reader, span, read_u32be, |num| Const::U32(num, UIntStyle::Decimal)),
             (Prim::FormatU32Le, []) => read_const(reader, span, read_u32le, |num| Const::U32(num, UIntStyle::Decimal)),
             (Prim::FormatU64Be, []) => read_const(reader, span, read_u64be, |num| Const::U64(num, UIntStyle::Decimal)),
             (Prim::FormatU64Le, []) => read_const(reader, span, read_u64le, |num| Const::U64(num, UIntStyle::Decimal)),
+            (Prim::FormatU128Be, []) => read_const(reader, span, read_u128be, |num| Const::u128(num, UIntStyle::Decimal)),
+            (Prim::FormatU128Le, []) => read_const(reader, span, read_u128le, |num| Const::u128(num, UIntStyle::Decimal)),
             (Prim::FormatS8, []) => read_const(reader, span, read_s8, Const::S8),
             (Prim::FormatS16Be, []) => read_const(reader, span, read_s16be, Const::S16),
             (Prim::FormatS16Le, []) => read_const(reader, span, read_s16le, Const::S16),
@@ -421,29 +974,42 @@ impl<'arena, 'data> Context<'arena, 'data> {
             (Prim::FormatS32Le, []) => read_const(reader, span, read_s32le, Const::S32),
             (Prim::FormatS64Be, []) => read_const(reader, span, read_s64be, Const::S64),
             (Prim::FormatS64Le, []) => read_const(reader, span, read_s64le, Const::S64),
+            (Prim::FormatS128Be, []) => read_const(reader, span, read_s128be, Const::s128),
+            (Prim::FormatS128Le, []) => read_const(reader, span, read_s128le, Const::s128),
             (Prim::FormatF32Be, []) => read_const(reader, span, read_f32be, Const::F32),
             (Prim::FormatF32Le, []) => read_const(reader, span, read_f32le, Const::F32),
             (Prim::FormatF64Be, []) => read_const(reader, span, read_f64be, Const::F64),
             (Prim::FormatF64Le, []) => read_const(reader, span, read_f64le, Const::F64),
-            (Prim::FormatRepeatLen8, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
-            (Prim::FormatRepeatLen16, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
-            (Prim::FormatRepeatLen32, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
-            (Prim::FormatRepeatLen64, [FunApp(_, len), FunApp(_, format)]) => self.read_repeat_len(reader, span, len, format),
-            (Prim::FormatRepeatUntilEnd, [FunApp(_,format)]) => self.read_repeat_until_end(reader, format),
-            (Prim::FormatLimit8, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
-            (Prim::FormatLimit16, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
-            (Prim::FormatLimit32, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
-            (Prim::FormatLimit64, [FunApp(_, limit), FunApp(_, format)]) => self.read_limit(reader, limit, format),
-            (Prim::FormatLink, [FunApp(_, pos), FunApp(_, format)]) => self.read_link(span, pos, format),
-            (Prim::FormatDeref, [FunApp(_, format), FunApp(_, r#ref)]) => self.read_deref(format, r#ref),
+            (Prim::FormatRepeatLen8, [FunApp(_, _, len), FunApp(_, _, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatLen16, [FunApp(_, _, len), FunApp(_, _, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatLen32, [FunApp(_, _, len), FunApp(_, _, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatLen64, [FunApp(_, _, len), FunApp(_, _, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatCount, [FunApp(_, _, len), FunApp(_, _, format)]) => self.read_repeat_len(reader, span, len, format),
+            (Prim::FormatRepeatUntilEnd, [FunApp(_, _, format)]) => self.read_repeat_until_end(reader, format),
+            (Prim::FormatRepeatUpTo, [FunApp(_, _, max_len), FunApp(_, _, format)]) => self.read_repeat_up_to(reader, span, max_len, format),
+            (Prim::FormatLengthPrefixed, [FunApp(_, _, len_format), FunApp(_, _, format)]) => self.read_length_prefixed(reader, span, len_format, format),
+            (Prim::FormatLimit8, [FunApp(_, _, limit), FunApp(_, _, format)]) => self.read_limit(reader, limit, format),
+            (Prim::FormatLimit16, [FunApp(_, _, limit), FunApp(_, _, format)]) => self.read_limit(reader, limit, format),
+            (Prim::FormatLimit32, [FunApp(_, _, limit), FunApp(_, _, format)]) => self.read_limit(reader, limit, format),
+            (Prim::FormatLimit64, [FunApp(_, _, limit), FunApp(_, _, format)]) => self.read_limit(reader, limit, format),
+            (Prim::FormatLink, [FunApp(_, _, pos), FunApp(_, _, format)]) => self.read_link(span, pos, format),
+            (Prim::FormatDeref, [FunApp(_, _, format), FunApp(_, _, r#ref)]) => self.read_deref(format, r#ref),
+            (Prim::FormatUtf8, [FunApp(_, _, len)]) => self.read_utf8(reader, span, len),
+            (Prim::FormatReserved, [FunApp(_, _, len)]) => read_reserved(reader, span, len),
             (Prim::FormatStreamPos, []) => read_stream_pos(reader, span),
-            (Prim::FormatSucceed, [_, FunApp(_, elem)]) => Ok(elem.clone()),
-            (Prim::FormatFail, []) => Err(ReadError::ReadFailFormat(span)),
-            (Prim::FormatUnwrap, [_, FunApp(_, option)]) => match option.match_prim_spine() {
-                Some((Prim::OptionSome, [_, FunApp(_, elem)])) => Ok(elem.clone()),
-                Some((Prim::OptionNone, [_])) => Err(ReadError::UnwrappedNone(span)),
+            (Prim::FormatWithBase, [FunApp(_, _, format)]) => self.read_with_base(reader, span, format),
+            (Prim::FormatRelPos, []) => self.read_rel_pos(reader, span),
+            (Prim::FormatSucceed, [_, FunApp(_, _, elem)]) => Ok(elem.clone()),
+            (Prim::FormatFail, []) => Err(ReadError::ReadFailFormat(span, reader.offset()?)),
+            (Prim::FormatUnwrap, [_, FunApp(_, _, option)]) => match option.match_prim_spine() {
+                Some((Prim::OptionSome, [_, FunApp(_, _, elem)])) => Ok(elem.clone()),
+                Some((Prim::OptionNone, [_])) => Err(ReadError::UnwrappedNone(span, reader.offset()?)),
                 _ => Err(ReadError::InvalidValue(span)),
             },
+            (Prim::FormatOptional, [FunApp(_, _, cond), FunApp(_, _, format)]) => self.read_optional(reader, span, cond, format),
+            (Prim::FormatDebug, [FunApp(_, _, label), FunApp(_, _, format)]) => self.read_debug(reader, label, format),
+            (Prim::FormatMap, [_, FunApp(_, _, format), FunApp(_, _, convert)]) => self.read_map(reader, format, convert),
+            (Prim::FormatBind, [FunApp(_, _, format), FunApp(_, _, next)]) => self.read_bind(reader, format, next),
             _ => Err(ReadError::InvalidFormat(span)),
         }
     }
@@ -462,6 +1028,31 @@ impl<'arena, 'data> Context<'arena, 'data> {
             Value::ConstLit(Const::U64(len, _)) => *len,
             _ => return Err(ReadError::InvalidValue(len.span())),
         };
+        let offset = reader.offset().map_err(|err| err.with_span(span))?;
+        self.check_array_len(span, offset, len)?;
+
+        // Retain the data of byte arrays, rather than allocating a value for
+        // each byte. This also applies to empty byte arrays, so that they are
+        // represented consistently.
+        if let Some((Prim::FormatU8, [])) = elem_format.match_prim_spine() {
+            let len = usize::try_from(len).unwrap_or(usize::MAX);
+            let bytes = (reader.read_byte_slice(len)).map_err(|err| err.with_span(span))?;
+            return Ok(Spanned::new(
+                span,
+                Arc::new(Value::ByteArrayLit(Arc::from(bytes))),
+            ));
+        }
+
+        // Empty arrays consume no data, so the element format is never read.
+        if len == 0 {
+            return Ok(Spanned::new(span, Arc::new(Value::ArrayLit(Vec::new()))));
+        }
+
+        // Avoid re-entering `read_format` for each element when reading arrays
+        // of primitive formats, which are common in practice.
+        if let Some(elem_exprs) = read_prim_array(reader, elem_format, len) {
+            return Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elem_exprs?))));
+        }
 
         let elem_exprs = (0..len)
             .map(|_| self.read_format(reader, elem_format))
@@ -470,21 +1061,73 @@ impl<'arena, 'data> Context<'arena, 'data> {
         Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elem_exprs))))
     }
 
+    fn read_utf8(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        len: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let len = match len.as_ref() {
+            Value::ConstLit(Const::U64(len, _)) => usize::try_from(*len).unwrap_or(usize::MAX),
+            _ => return Err(ReadError::InvalidValue(len.span())),
+        };
+
+        let offset = reader.offset().map_err(|err| err.with_span(span))?;
+        let bytes = (reader.read_byte_slice(len)).map_err(|err| err.with_span(span))?;
+        let string =
+            std::str::from_utf8(&bytes).map_err(|_| ReadError::InvalidUtf8(span, offset))?;
+
+        Ok(Spanned::new(
+            span,
+            Arc::new(Value::StringLit(Arc::from(string))),
+        ))
+    }
+
+    fn check_array_len(
+        &self,
+        span: Span,
+        offset: usize,
+        len: u64,
+    ) -> Result<(), ReadError<'arena>> {
+        match self.max_array_len {
+            Some(max_array_len) if len > max_array_len => {
+                Err(ReadError::ArrayTooLong(span, offset, len, max_array_len))
+            }
+            _ => Ok(()),
+        }
+    }
+
     fn read_repeat_until_end(
         &mut self,
         reader: &mut BufferReader<'data>,
         elem_format: &ArcValue<'arena>,
     ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let offset = reader
+            .offset()
+            .map_err(|err| err.with_span(elem_format.span()))?;
+
+        if let Some((Prim::FormatU8, [])) = elem_format.match_prim_spine() {
+            let len = (reader.remaining_len()).map_err(|err| err.with_span(elem_format.span()))?;
+            self.check_array_len(elem_format.span(), offset, len as u64)?;
+            let bytes =
+                (reader.read_byte_slice(len)).map_err(|err| err.with_span(elem_format.span()))?;
+            return Ok(Spanned::new(
+                elem_format.span(),
+                Arc::new(Value::ByteArrayLit(Arc::from(bytes))),
+            ));
+        }
+
         let mut current_offset = reader.relative_offset();
         let mut elems = Vec::new();
 
         loop {
             match self.read_format(reader, elem_format) {
                 Ok(elem) => {
+                    self.check_array_len(elem_format.span(), offset, elems.len() as u64 + 1)?;
                     elems.push(elem);
                     current_offset = reader.relative_offset();
                 }
-                Err(ReadError::BufferError(_, BufferError::UnexpectedEndOfBuffer)) => {
+                Err(ReadError::BufferError(_, BufferError::UnexpectedEndOfBuffer { .. })) => {
                     // unwrap shouldn't panic as we're rewinding to a known good offset
                     // Should this be set to the end of the current buffer?
                     reader.set_relative_offset(current_offset).unwrap();
@@ -498,6 +1141,107 @@ impl<'arena, 'data> Context<'arena, 'data> {
         }
     }
 
+    /// Read up to `max_len` elements, stopping early if an element would be
+    /// read past the end of the current buffer, for example the end of an
+    /// enclosing limit format. Any partially read element is discarded.
+    fn read_repeat_up_to(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        max_len: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let max_len = match max_len.as_ref() {
+            Value::ConstLit(Const::U64(len, _)) => *len,
+            _ => return Err(ReadError::InvalidValue(max_len.span())),
+        };
+
+        let offset = reader.offset().map_err(|err| err.with_span(span))?;
+        let mut current_offset = reader.relative_offset();
+        let mut elems = Vec::new();
+
+        while (elems.len() as u64) < max_len {
+            match self.read_format(reader, elem_format) {
+                Ok(elem) => {
+                    self.check_array_len(span, offset, elems.len() as u64 + 1)?;
+                    elems.push(elem);
+                    current_offset = reader.relative_offset();
+                }
+                Err(ReadError::BufferError(_, BufferError::UnexpectedEndOfBuffer { .. })) => {
+                    // unwrap shouldn't panic as we're rewinding to a known good offset
+                    reader.set_relative_offset(current_offset).unwrap();
+                    break;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elems))))
+    }
+
+    fn read_length_prefixed(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        len_format: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let len = self.read_format(reader, len_format)?;
+        self.read_repeat_len(reader, span, &len, elem_format)
+    }
+
+    fn read_optional(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+        cond: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let elem_type = Elim::FunApp(
+            Span::Empty,
+            Plicity::Implicit,
+            self.elim_env().format_repr(elem_format),
+        );
+        let option = match cond.as_ref() {
+            Value::ConstLit(Const::Bool(true)) => {
+                let elem = self.read_format(reader, elem_format)?;
+                let elem = Elim::FunApp(Span::Empty, Plicity::Explicit, elem);
+                Value::Stuck(Head::Prim(Prim::OptionSome), vec![elem_type, elem])
+            }
+            Value::ConstLit(Const::Bool(false)) => {
+                Value::Stuck(Head::Prim(Prim::OptionNone), vec![elem_type])
+            }
+            _ => return Err(ReadError::InvalidValue(cond.span())),
+        };
+
+        Ok(Spanned::new(span, Arc::new(option)))
+    }
+
+    fn read_map(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format: &ArcValue<'arena>,
+        convert: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let value = self.read_format(reader, format)?;
+        Ok(self
+            .elim_env()
+            .fun_app(convert.span(), Plicity::Explicit, convert.clone(), value))
+    }
+
+    fn read_bind(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        format: &ArcValue<'arena>,
+        next: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let value = self.read_format(reader, format)?;
+        let next_format =
+            self.elim_env()
+                .fun_app(next.span(), Plicity::Explicit, next.clone(), value);
+        self.read_format(reader, &next_format)
+    }
+
     fn read_limit(
         &mut self,
         reader: &BufferReader<'data>,
@@ -522,26 +1266,77 @@ impl<'arena, 'data> Context<'arena, 'data> {
         self.read_format(&mut buffer.reader(), elem_format)
     }
 
-    fn read_link(
+    fn read_with_base(
         &mut self,
+        reader: &mut BufferReader<'data>,
         span: Span,
-        pos_value: &ArcValue<'arena>,
-        elem_format: &ArcValue<'arena>,
+        format: &ArcValue<'arena>,
     ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
-        let pos = match pos_value.as_ref() {
-            Value::ConstLit(Const::Pos(pos)) => *pos,
-            _ => return Err(ReadError::InvalidValue(pos_value.span())),
-        };
+        let base = reader.offset().map_err(|err| err.with_span(span))?;
 
-        self.pending_formats.push((pos, elem_format.clone()));
+        self.bases.push(base);
+        let result = self.read_format(reader, format);
+        self.bases.pop();
 
-        Ok(Spanned::new(
-            span,
-            Arc::new(Value::ConstLit(Const::Ref(pos))),
-        ))
+        result
     }
 
-    fn read_deref(
+    fn read_debug(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        label: &ArcValue<'arena>,
+        format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let pos = reader
+            .offset()
+            .map_err(|err| err.with_span(format.span()))?;
+        let expr = self.read_format(reader, format)?;
+
+        if let Some(sink) = &mut self.debug_sink {
+            let label = match label.as_ref() {
+                Value::ConstLit(Const::String(label)) => *label,
+                _ => return Err(ReadError::InvalidValue(label.span())),
+            };
+            sink(label, pos, &expr);
+        }
+
+        Ok(expr)
+    }
+
+    fn read_rel_pos(
+        &mut self,
+        reader: &mut BufferReader<'data>,
+        span: Span,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let pos = reader.offset().map_err(|err| err.with_span(span))?;
+        let base = self.bases.last().copied().unwrap_or(0);
+
+        Ok(Spanned::new(
+            span,
+            Arc::new(Value::ConstLit(Const::Pos(pos - base))),
+        ))
+    }
+
+    fn read_link(
+        &mut self,
+        span: Span,
+        pos_value: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+        let pos = match pos_value.as_ref() {
+            Value::ConstLit(Const::Pos(pos)) => *pos,
+            _ => return Err(ReadError::InvalidValue(pos_value.span())),
+        };
+
+        self.pending_formats.push((pos, elem_format.clone()));
+
+        Ok(Spanned::new(
+            span,
+            Arc::new(Value::ConstLit(Const::Ref(pos))),
+        ))
+    }
+
+    fn read_deref(
         &mut self,
         format: &ArcValue<'arena>,
         r#ref: &ArcValue<'arena>,
@@ -581,9 +1376,18 @@ impl<'arena, 'data> Context<'arena, 'data> {
             return Ok(parsed_ref.expr.clone());
         }
 
+        if !self.seekable {
+            return Err(ReadError::SeekRequired(format.span(), pos));
+        }
+
         // Read the data at the ref location
         let mut reader = self.initial_buffer.reader_with_offset(pos)?;
-        let expr = self.read_format(&mut reader, format)?;
+        // Parsed references are shared between every format that refers to
+        // them, so positions in them are not relative to the current bases.
+        let bases = std::mem::take(&mut self.bases);
+        let expr = self.read_format(&mut reader, format);
+        self.bases = bases;
+        let expr = expr?;
 
         // We might have parsed the current reference during the above call to
         // `read_format`. It's unclear if this could ever happen in practice,
@@ -606,6 +1410,94 @@ impl<'arena, 'data> Context<'arena, 'data> {
     }
 }
 
+/// Read a value from a stream, starting from its current position, according
+/// to the supplied format description. Positions are measured from the
+/// starting position.
+///
+/// Data is read from the stream as the format needs it, so data after the end
+/// of the format is never read. The stream is only read sequentially, so
+/// formats that read data from other positions in the stream, like `deref`,
+/// result in a [`ReadError::SeekRequired`]. Links can still be read, but are
+/// not followed. Use [`read_format_from_seekable`] for formats that need to
+/// follow links.
+///
+/// The data that has been read is retained until reading has finished, as
+/// formats like overlaps may read it again.
+pub fn read_format_from_stream<'arena>(
+    module: &Module<'arena>,
+    format: &Term<'arena>,
+    reader: impl io::Read,
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    let stream = ReaderStream::sequential(reader);
+    let buffer = Buffer::from_stream(0, &stream);
+    let mut context = Context::new(buffer);
+    context.seekable = false;
+    context.add_module(module);
+    let format = context.eval_env().eval(format);
+
+    context.read_format(&mut buffer.reader(), &format)
+}
+
+/// Read a value from a seekable stream, starting from its current position,
+/// according to the supplied format description.
+///
+/// Positions are measured from the start of the stream, allowing formats like
+/// `deref` to read data from anywhere in the stream.
+///
+/// Data is read from the stream as the format needs it. Reading data from
+/// another position seeks the stream, discarding the data that was read from
+/// the previous position.
+pub fn read_format_from_seekable<'arena>(
+    module: &Module<'arena>,
+    format: &Term<'arena>,
+    mut reader: impl io::Read + io::Seek,
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    let mut context = Context::new(Buffer::from(&[][..]));
+    context.add_module(module);
+    let format = context.eval_env().eval(format);
+
+    read_format(context.elim_env(), &format, &mut reader)
+}
+
+/// Read a value from a seekable stream, starting from its current position,
+/// according to a format that has already been evaluated in `elim_env`.
+///
+/// Positions, including the offsets reported by [`ReadError::offset`], are
+/// measured from the start of the stream. Data is read from the stream as the
+/// format needs it, and the stream is left positioned directly after the last
+/// byte that was consumed by the format.
+pub fn read_format<'arena>(
+    elim_env: semantics::ElimEnv<'arena, '_>,
+    format: &ArcValue<'arena>,
+    reader: &mut (impl io::Read + io::Seek),
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    let start = reader.stream_position().map_err(BufferError::from)?;
+    let start = usize::try_from(start).map_err(|_| BufferError::PositionOverflow)?;
+
+    let (expr, end_pos) = {
+        let stream = ReaderStream::seekable(&mut *reader, start);
+        let buffer = Buffer::from_stream(0, &stream);
+        let mut context = Context::new(buffer);
+        for expr in elim_env.item_exprs().iter() {
+            context.item_exprs.push(expr.clone());
+        }
+        if let Some(interner) = elim_env.interner() {
+            context.set_interner(interner);
+        }
+
+        let mut buffer_reader = buffer.reader_with_offset(start)?;
+        let expr = context.read_format(&mut buffer_reader, format)?;
+        (expr, buffer_reader.offset()?)
+    };
+
+    let end_pos = u64::try_from(end_pos).map_err(|_| BufferError::PositionOverflow)?;
+    reader
+        .seek(io::SeekFrom::Start(end_pos))
+        .map_err(BufferError::from)?;
+
+    Ok(expr)
+}
+
 fn read_stream_pos<'arena>(
     reader: &mut BufferReader<'_>,
     span: Span,
@@ -618,6 +1510,28 @@ fn read_stream_pos<'arena>(
     ))
 }
 
+fn read_reserved<'arena>(
+    reader: &mut BufferReader<'_>,
+    span: Span,
+    len: &ArcValue<'arena>,
+) -> Result<ArcValue<'arena>, ReadError<'arena>> {
+    let len = match len.as_ref() {
+        Value::ConstLit(Const::U64(len, _)) => usize::try_from(*len).unwrap_or(usize::MAX),
+        _ => return Err(ReadError::InvalidValue(len.span())),
+    };
+
+    let offset = reader.offset().map_err(|err| err.with_span(span))?;
+    let bytes = (reader.read_byte_slice(len)).map_err(|err| err.with_span(span))?;
+    if let Some(index) = bytes.iter().position(|byte| *byte != 0) {
+        return Err(ReadError::NonZeroReserved(span, offset + index));
+    }
+
+    Ok(Spanned::new(
+        span,
+        Arc::new(Value::RecordLit(&[], Vec::new())),
+    ))
+}
+
 fn read_const<'arena, 'data, T>(
     reader: &mut BufferReader<'data>,
     span: Span,
@@ -631,6 +1545,70 @@ fn read_const<'arena, 'data, T>(
     ))
 }
 
+/// Read an array of elements of a primitive format in bulk, returning `None`
+/// if the format is not a primitive format.
+#[rustfmt::skip]
+fn read_prim_array<'arena>(
+    reader: &mut BufferReader<'_>,
+    format: &ArcValue<'arena>,
+    len: u64,
+) -> Option<Result<Vec<ArcValue<'arena>>, ReadError<'arena>>> {
+    let span = format.span();
+    match format.match_prim_spine()? {
+        (Prim::FormatU8, []) => Some(read_const_array(reader, span, len, 1, read_u8, |num| Const::U8(num, UIntStyle::Decimal))),
+        (Prim::FormatU16Be, []) => Some(read_const_array(reader, span, len, 2, read_u16be, |num| Const::U16(num, UIntStyle::Decimal))),
+        (Prim::FormatU16Le, []) => Some(read_const_array(reader, span, len, 2, read_u16le, |num| Const::U16(num, UIntStyle::Decimal))),
+        (Prim::FormatU24Be, []) => Some(read_const_array(reader, span, len, 3, read_u24be, |num| Const::U32(num, UIntStyle::Decimal))),
+        (Prim::FormatU24Le, []) => Some(read_const_array(reader, span, len, 3, read_u24le, |num| Const::U32(num, UIntStyle::Decimal))),
+        (Prim::FormatU32Be, []) => Some(read_const_array(reader, span, len, 4, read_u32be, |num| Const::U32(num, UIntStyle::Decimal))),
+        (Prim::FormatU32Le, []) => Some(read_const_array(reader, span, len, 4, read_u32le, |num| Const::U32(num, UIntStyle::Decimal))),
+        (Prim::FormatU64Be, []) => Some(read_const_array(reader, span, len, 8, read_u64be, |num| Const::U64(num, UIntStyle::Decimal))),
+        (Prim::FormatU64Le, []) => Some(read_const_array(reader, span, len, 8, read_u64le, |num| Const::U64(num, UIntStyle::Decimal))),
+        (Prim::FormatU128Be, []) => Some(read_const_array(reader, span, len, 16, read_u128be, |num| Const::u128(num, UIntStyle::Decimal))),
+        (Prim::FormatU128Le, []) => Some(read_const_array(reader, span, len, 16, read_u128le, |num| Const::u128(num, UIntStyle::Decimal))),
+        (Prim::FormatS8, []) => Some(read_const_array(reader, span, len, 1, read_s8, Const::S8)),
+        (Prim::FormatS16Be, []) => Some(read_const_array(reader, span, len, 2, read_s16be, Const::S16)),
+        (Prim::FormatS16Le, []) => Some(read_const_array(reader, span, len, 2, read_s16le, Const::S16)),
+        (Prim::FormatS32Be, []) => Some(read_const_array(reader, span, len, 4, read_s32be, Const::S32)),
+        (Prim::FormatS32Le, []) => Some(read_const_array(reader, span, len, 4, read_s32le, Const::S32)),
+        (Prim::FormatS64Be, []) => Some(read_const_array(reader, span, len, 8, read_s64be, Const::S64)),
+        (Prim::FormatS64Le, []) => Some(read_const_array(reader, span, len, 8, read_s64le, Const::S64)),
+        (Prim::FormatS128Be, []) => Some(read_const_array(reader, span, len, 16, read_s128be, Const::s128)),
+        (Prim::FormatS128Le, []) => Some(read_const_array(reader, span, len, 16, read_s128le, Const::s128)),
+        (Prim::FormatF32Be, []) => Some(read_const_array(reader, span, len, 4, read_f32be, Const::F32)),
+        (Prim::FormatF32Le, []) => Some(read_const_array(reader, span, len, 4, read_f32le, Const::F32)),
+        (Prim::FormatF64Be, []) => Some(read_const_array(reader, span, len, 8, read_f64be, Const::F64)),
+        (Prim::FormatF64Le, []) => Some(read_const_array(reader, span, len, 8, read_f64le, Const::F64)),
+        _ => None,
+    }
+}
+
+fn read_const_array<'arena, 'data, T>(
+    reader: &mut BufferReader<'data>,
+    span: Span,
+    len: u64,
+    elem_size: usize,
+    read: fn(&mut BufferReader<'_>) -> Result<T, BufferError>,
+    wrap_const: fn(T) -> Const,
+) -> Result<Vec<ArcValue<'arena>>, ReadError<'arena>> {
+    // Lengths that overflow will be reported as reading past the end of the
+    // buffer, as they would be if the elements were read one at a time.
+    let data_len = usize::try_from(len).unwrap_or(usize::MAX);
+    let data_len = data_len.saturating_mul(elem_size);
+    let data = reader
+        .read_byte_slice(data_len)
+        .map_err(|err| err.with_span(span))?;
+
+    let mut data_reader = Buffer::from(&data[..]).reader();
+    let elem_exprs = (0..len).map(|_| {
+        // unwrap is safe because the data was the length of the elements
+        let elem = read(&mut data_reader).unwrap();
+        Spanned::new(span, Arc::new(Value::ConstLit(wrap_const(elem))))
+    });
+
+    Ok(elem_exprs.collect())
+}
+
 fn read_u8(reader: &mut BufferReader<'_>) -> Result<u8, BufferError> {
     reader.read_byte()
 }
@@ -645,7 +1623,7 @@ macro_rules! read_multibyte_prim {
         fn $read_multibyte_prim<'data>(
             reader: &mut BufferReader<'data>,
         ) -> Result<$T, BufferError> {
-            Ok($T::$from_bytes(*reader.read_byte_array()?))
+            Ok($T::$from_bytes(reader.read_byte_array()?))
         }
     };
 }
@@ -656,13 +1634,1773 @@ read_multibyte_prim!(read_u32le, from_le_bytes, u32);
 read_multibyte_prim!(read_u32be, from_be_bytes, u32);
 read_multibyte_prim!(read_u64le, from_le_bytes, u64);
 read_multibyte_prim!(read_u64be, from_be_bytes, u64);
+read_multibyte_prim!(read_u128le, from_le_bytes, u128);
+read_multibyte_prim!(read_u128be, from_be_bytes, u128);
 read_multibyte_prim!(read_s16le, from_le_bytes, i16);
 read_multibyte_prim!(read_s16be, from_be_bytes, i16);
 read_multibyte_prim!(read_s32le, from_le_bytes, i32);
 read_multibyte_prim!(read_s32be, from_be_bytes, i32);
 read_multibyte_prim!(read_s64le, from_le_bytes, i64);
 read_multibyte_prim!(read_s64be, from_be_bytes, i64);
+read_multibyte_prim!(read_s128le, from_le_bytes, i128);
+read_multibyte_prim!(read_s128be, from_be_bytes, i128);
 read_multibyte_prim!(read_f32le, from_le_bytes, f32);
 read_multibyte_prim!(read_f32be, from_be_bytes, f32);
 read_multibyte_prim!(read_f64le, from_le_bytes, f64);
 read_multibyte_prim!(read_f64be, from_be_bytes, f64);
+
+fn read_u24le(reader: &mut BufferReader<'_>) -> Result<u32, BufferError> {
+    let [b0, b1, b2] = reader.read_byte_array()?;
+    Ok(u32::from_le_bytes([b0, b1, b2, 0]))
+}
+
+fn read_u24be(reader: &mut BufferReader<'_>) -> Result<u32, BufferError> {
+    let [b0, b1, b2] = reader.read_byte_array()?;
+    Ok(u32::from_be_bytes([0, b0, b1, b2]))
+}
+
+/// Serialize a value that was read from binary data as JSON.
+///
+/// Records become objects, arrays become arrays, and options become either
+/// `null` or their value. Integers become numbers, apart from 64-bit and
+/// 128-bit integers, which become strings to avoid losing precision in JSON
+/// parsers that represent numbers as doubles. Positions and references become
+/// numbers.
+///
+/// Returns `None` if the value has no representation in JSON, for example if
+/// it is a function or a type.
+pub fn value_to_json(interner: &StringInterner, value: &ArcValue<'_>) -> Option<String> {
+    let mut json = String::new();
+    write_json(interner, value, &mut json)?;
+    Some(json)
+}
+
+fn write_json(interner: &StringInterner, value: &ArcValue<'_>, json: &mut String) -> Option<()> {
+    use std::fmt::Write;
+
+    match value.as_ref() {
+        Value::RecordLit(labels, exprs) => {
+            json.push('{');
+            for (index, (label, expr)) in Iterator::zip(labels.iter(), exprs.iter()).enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_json_string(interner.resolve(*label)?, json);
+                json.push(':');
+                write_json(interner, expr, json)?;
+            }
+            json.push('}');
+        }
+        Value::ArrayLit(elems) => {
+            json.push('[');
+            for (index, elem) in elems.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write_json(interner, elem, json)?;
+            }
+            json.push(']');
+        }
+        Value::ByteArrayLit(bytes) => {
+            json.push('[');
+            for (index, byte) in bytes.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                write!(json, "{byte}").unwrap();
+            }
+            json.push(']');
+        }
+        Value::ConstLit(r#const) => match r#const {
+            Const::Bool(b) => write!(json, "{b}").unwrap(),
+            Const::U8(n, _) => write!(json, "{n}").unwrap(),
+            Const::U16(n, _) => write!(json, "{n}").unwrap(),
+            Const::U32(n, _) => write!(json, "{n}").unwrap(),
+            Const::U64(n, _) => write!(json, "\"{n}\"").unwrap(),
+            Const::U128(n, _) => write!(json, "\"{n}\"").unwrap(),
+            Const::S8(n) => write!(json, "{n}").unwrap(),
+            Const::S16(n) => write!(json, "{n}").unwrap(),
+            Const::S32(n) => write!(json, "{n}").unwrap(),
+            Const::S64(n) => write!(json, "\"{n}\"").unwrap(),
+            Const::S128(n) => write!(json, "\"{n}\"").unwrap(),
+            // JSON numbers can't represent infinities or NaNs
+            Const::F32(n) if n.is_finite() => write!(json, "{n}").unwrap(),
+            Const::F64(n) if n.is_finite() => write!(json, "{n}").unwrap(),
+            Const::F32(n) => write_json_string(&n.to_string(), json),
+            Const::F64(n) => write_json_string(&n.to_string(), json),
+            Const::Pos(pos) | Const::Ref(pos) => write!(json, "{pos}").unwrap(),
+            Const::String(string) => write_json_string(interner.resolve(*string)?, json),
+        },
+        Value::StringLit(string) => write_json_string(string, json),
+        Value::Stuck(Head::Prim(Prim::OptionSome), spine) => match spine.as_slice() {
+            [_, Elim::FunApp(_, _, elem)] => write_json(interner, elem, json)?,
+            _ => return None,
+        },
+        Value::Stuck(Head::Prim(Prim::OptionNone), _) => json.push_str("null"),
+        _ => return None,
+    }
+
+    Some(())
+}
+
+fn write_json_string(string: &str, json: &mut String) {
+    use std::fmt::Write;
+
+    json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+/// Write a value to the writer, according to the supplied format description.
+///
+/// This is the inverse of [`Context::read_format`]. Stream positions are
+/// recomputed from the number of bytes written so far, and are used in place
+/// of the supplied value when elaborating the rest of a record or overlap.
+///
+/// Formats whose data can't be recovered from the value alone, like links,
+/// dereferences, limits, maps and binds, can't be written.
+pub fn write_format<'arena>(
+    elim_env: semantics::ElimEnv<'arena, '_>,
+    format: &ArcValue<'arena>,
+    value: &ArcValue<'arena>,
+    writer: &mut impl io::Write,
+) -> Result<(), WriteError<'arena>> {
+    let mut context = WriteContext {
+        elim_env,
+        data: Vec::new(),
+        bases: Vec::new(),
+    };
+    context.write_format(format, value)?;
+    writer.write_all(&context.data)?;
+    Ok(())
+}
+
+struct WriteContext<'arena, 'env> {
+    elim_env: semantics::ElimEnv<'arena, 'env>,
+    data: Vec<u8>,
+    /// The base positions that relative positions are written from, with the
+    /// innermost base last.
+    bases: Vec<usize>,
+}
+
+impl<'arena, 'env> WriteContext<'arena, 'env> {
+    /// Write a value, returning the value that was actually written.
+    fn write_format(
+        &mut self,
+        format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        match format.as_ref() {
+            Value::Stuck(Head::Prim(prim), slice) => {
+                self.write_prim(*prim, slice, format.span(), value)
+            }
+            Value::FormatRecord(labels, formats) => {
+                let exprs = match value.as_ref() {
+                    Value::RecordLit(expr_labels, exprs) if expr_labels == labels => exprs,
+                    _ => return Err(WriteError::InvalidValue(value.span())),
+                };
+
+                let mut formats = formats.clone();
+                let mut exprs = exprs.iter();
+                let mut written_exprs = Vec::with_capacity(formats.len());
+
+                while let Some((format, next_formats)) = self.elim_env.split_telescope(formats) {
+                    let expr = exprs.next().ok_or(WriteError::InvalidValue(value.span()))?;
+                    let expr = self.write_format(&format, expr)?;
+                    written_exprs.push(expr.clone());
+                    formats = next_formats(expr);
+                }
+
+                Ok(Spanned::new(
+                    value.span(),
+                    Arc::new(Value::RecordLit(labels, written_exprs)),
+                ))
+            }
+            Value::FormatCond(_label, format, cond) => {
+                let value = self.write_format(format, value)?;
+                let cond_res = self.elim_env.apply_closure(cond, value.clone());
+
+                match cond_res.as_ref() {
+                    Value::ConstLit(Const::Bool(true)) => Ok(value),
+                    Value::ConstLit(Const::Bool(false)) => {
+                        Err(WriteError::CondFailure(cond.span(), value))
+                    }
+                    _ => Err(WriteError::InvalidValue(Span::Empty)),
+                }
+            }
+            Value::FormatOverlap(labels, formats) => {
+                let exprs = match value.as_ref() {
+                    Value::RecordLit(expr_labels, exprs) if expr_labels == labels => exprs,
+                    _ => return Err(WriteError::InvalidValue(value.span())),
+                };
+
+                let start = self.data.len();
+                let mut overlapped_data = Vec::new();
+
+                let mut formats = formats.clone();
+                let mut exprs = exprs.iter();
+                let mut written_exprs = Vec::with_capacity(formats.len());
+
+                while let Some((format, next_formats)) = self.elim_env.split_telescope(formats) {
+                    let expr = exprs.next().ok_or(WriteError::InvalidValue(value.span()))?;
+                    let expr = self.write_format(&format, expr)?;
+                    written_exprs.push(expr.clone());
+                    formats = next_formats(expr);
+
+                    // Rewind to the start of the overlap, layering the data
+                    // that was just written over the previously written data.
+                    let data = self.data.split_off(start);
+                    let common_len = std::cmp::min(data.len(), overlapped_data.len());
+                    overlapped_data[..common_len].copy_from_slice(&data[..common_len]);
+                    overlapped_data.extend_from_slice(&data[common_len..]);
+                }
+
+                self.data.extend_from_slice(&overlapped_data);
+
+                Ok(Spanned::new(
+                    value.span(),
+                    Arc::new(Value::RecordLit(labels, written_exprs)),
+                ))
+            }
+
+            Value::Stuck(Head::LocalVar(_), _)
+            | Value::Stuck(Head::MetaVar(_), _)
+            | Value::Universe
+            | Value::FunType(..)
+            | Value::FunLit(..)
+            | Value::RecordType(_, _)
+            | Value::RecordLit(_, _)
+            | Value::ArrayLit(_)
+            | Value::ByteArrayLit(_)
+            | Value::ConstLit(_)
+            | Value::StringLit(_) => Err(WriteError::InvalidFormat(format.span())),
+        }
+    }
+
+    #[rustfmt::skip]
+    fn write_prim(
+        &mut self,
+        prim: Prim,
+        slice: &[Elim<'arena>],
+        span: Span,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        use crate::core::semantics::Elim::FunApp;
+
+        match (prim, slice) {
+            (Prim::FormatU8, []) => self.write_const(value, u8_to_bytes),
+            (Prim::FormatU16Be, []) => self.write_const(value, u16be_to_bytes),
+            (Prim::FormatU16Le, []) => self.write_const(value, u16le_to_bytes),
+            (Prim::FormatU24Be, []) => self.write_const(value, u24be_to_bytes),
+            (Prim::FormatU24Le, []) => self.write_const(value, u24le_to_bytes),
+            (Prim::FormatU32Be, []) => self.write_const(value, u32be_to_bytes),
+            (Prim::FormatU32Le, []) => self.write_const(value, u32le_to_bytes),
+            (Prim::FormatU64Be, []) => self.write_const(value, u64be_to_bytes),
+            (Prim::FormatU64Le, []) => self.write_const(value, u64le_to_bytes),
+            (Prim::FormatU128Be, []) => self.write_const(value, u128be_to_bytes),
+            (Prim::FormatU128Le, []) => self.write_const(value, u128le_to_bytes),
+            (Prim::FormatS8, []) => self.write_const(value, s8_to_bytes),
+            (Prim::FormatS16Be, []) => self.write_const(value, s16be_to_bytes),
+            (Prim::FormatS16Le, []) => self.write_const(value, s16le_to_bytes),
+            (Prim::FormatS32Be, []) => self.write_const(value, s32be_to_bytes),
+            (Prim::FormatS32Le, []) => self.write_const(value, s32le_to_bytes),
+            (Prim::FormatS64Be, []) => self.write_const(value, s64be_to_bytes),
+            (Prim::FormatS64Le, []) => self.write_const(value, s64le_to_bytes),
+            (Prim::FormatS128Be, []) => self.write_const(value, s128be_to_bytes),
+            (Prim::FormatS128Le, []) => self.write_const(value, s128le_to_bytes),
+            (Prim::FormatF32Be, []) => self.write_const(value, f32be_to_bytes),
+            (Prim::FormatF32Le, []) => self.write_const(value, f32le_to_bytes),
+            (Prim::FormatF64Be, []) => self.write_const(value, f64be_to_bytes),
+            (Prim::FormatF64Le, []) => self.write_const(value, f64le_to_bytes),
+            (Prim::FormatRepeatLen8, [FunApp(_, _, len), FunApp(_, _, format)]) => self.write_repeat_len(span, len, format, value),
+            (Prim::FormatRepeatLen16, [FunApp(_, _, len), FunApp(_, _, format)]) => self.write_repeat_len(span, len, format, value),
+            (Prim::FormatRepeatLen32, [FunApp(_, _, len), FunApp(_, _, format)]) => self.write_repeat_len(span, len, format, value),
+            (Prim::FormatRepeatLen64, [FunApp(_, _, len), FunApp(_, _, format)]) => self.write_repeat_len(span, len, format, value),
+            (Prim::FormatRepeatCount, [FunApp(_, _, len), FunApp(_, _, format)]) => self.write_repeat_len(span, len, format, value),
+            (Prim::FormatRepeatUntilEnd, [FunApp(_, _, format)]) => self.write_repeat_until_end(span, format, value),
+            (Prim::FormatRepeatUpTo, [FunApp(_, _, max_len), FunApp(_, _, format)]) => self.write_repeat_up_to(span, max_len, format, value),
+            (Prim::FormatLengthPrefixed, [FunApp(_, _, len_format), FunApp(_, _, format)]) => self.write_length_prefixed(span, len_format, format, value),
+            // Limited formats are read without advancing past the data they
+            // consume, so the data following them would overlap it.
+            (Prim::FormatLimit8 | Prim::FormatLimit16 | Prim::FormatLimit32 | Prim::FormatLimit64, [FunApp(_, _, _), FunApp(_, _, _)]) => Err(WriteError::InvalidFormat(span)),
+            // The data of references is read from other positions in the
+            // stream, which are not known when writing.
+            (Prim::FormatLink, [FunApp(_, _, _), FunApp(_, _, _)]) => Err(WriteError::InvalidFormat(span)),
+            (Prim::FormatDeref, [FunApp(_, _, _), FunApp(_, _, _)]) => Err(WriteError::InvalidFormat(span)),
+            (Prim::FormatUtf8, [FunApp(_, _, len)]) => self.write_utf8(len, value),
+            (Prim::FormatReserved, [FunApp(_, _, len)]) => self.write_reserved(span, len, value),
+            (Prim::FormatStreamPos, []) => Ok(Spanned::new(span, Arc::new(Value::ConstLit(Const::Pos(self.data.len()))))),
+            (Prim::FormatWithBase, [FunApp(_, _, format)]) => self.write_with_base(format, value),
+            (Prim::FormatRelPos, []) => {
+                let base = self.bases.last().copied().unwrap_or(0);
+                Ok(Spanned::new(span, Arc::new(Value::ConstLit(Const::Pos(self.data.len() - base)))))
+            }
+            (Prim::FormatSucceed, [_, FunApp(_, _, elem)]) => self.write_succeed(elem, value),
+            (Prim::FormatFail, []) => Err(WriteError::WriteFailFormat(span)),
+            (Prim::FormatUnwrap, [_, FunApp(_, _, option)]) => match option.match_prim_spine() {
+                Some((Prim::OptionSome, [_, FunApp(_, _, elem)])) => self.write_succeed(elem, value),
+                _ => Err(WriteError::InvalidValue(span)),
+            },
+            (Prim::FormatOptional, [FunApp(_, _, cond), FunApp(_, _, format)]) => self.write_optional(cond, format, value),
+            (Prim::FormatDebug, [_, FunApp(_, _, format)]) => self.write_format(format, value),
+            // The conversion function can't be inverted to recover the value
+            // of the inner format, so mapped formats can't be written.
+            (Prim::FormatMap, [_, FunApp(_, _, _), FunApp(_, _, _)]) => Err(WriteError::InvalidFormat(span)),
+            // Only the data parsed by the continuation is retained, so the data
+            // for the first format is not available to be written.
+            (Prim::FormatBind, [FunApp(_, _, _), FunApp(_, _, _)]) => Err(WriteError::InvalidFormat(span)),
+            _ => Err(WriteError::InvalidFormat(span)),
+        }
+    }
+
+    fn write_const<const N: usize>(
+        &mut self,
+        value: &ArcValue<'arena>,
+        to_bytes: fn(&Const) -> Option<[u8; N]>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let bytes = match value.as_ref() {
+            Value::ConstLit(r#const) => to_bytes(r#const),
+            _ => None,
+        };
+        let bytes = bytes.ok_or(WriteError::InvalidValue(value.span()))?;
+        self.data.extend_from_slice(&bytes);
+        Ok(value.clone())
+    }
+
+    fn write_utf8(
+        &mut self,
+        len: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let len = match len.as_ref() {
+            Value::ConstLit(Const::U64(len, _)) => *len,
+            _ => return Err(WriteError::InvalidValue(len.span())),
+        };
+        let check_len = |string: &str| match string.len() as u64 == len {
+            true => Ok(()),
+            false => Err(WriteError::InvalidValue(value.span())),
+        };
+
+        match value.as_ref() {
+            Value::StringLit(string) => {
+                check_len(string)?;
+                self.data.extend_from_slice(string.as_bytes());
+            }
+            // String constants can only be written if they can be looked up
+            Value::ConstLit(Const::String(string)) => {
+                let interner = (self.elim_env.interner())
+                    .ok_or(WriteError::InvalidValue(value.span()))?
+                    .borrow();
+                let string =
+                    (interner.resolve(*string)).ok_or(WriteError::InvalidValue(value.span()))?;
+                check_len(string)?;
+                self.data.extend_from_slice(string.as_bytes());
+            }
+            _ => return Err(WriteError::InvalidValue(value.span())),
+        }
+
+        Ok(value.clone())
+    }
+
+    fn write_reserved(
+        &mut self,
+        span: Span,
+        len: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let len = match len.as_ref() {
+            Value::ConstLit(Const::U64(len, _)) => usize::try_from(*len).unwrap_or(usize::MAX),
+            _ => return Err(WriteError::InvalidValue(len.span())),
+        };
+        match value.as_ref() {
+            Value::RecordLit([], _) => {}
+            _ => return Err(WriteError::InvalidValue(value.span())),
+        }
+
+        let data_len = (self.data.len().checked_add(len)).ok_or(WriteError::TooLarge(span))?;
+        (self.data.try_reserve(len)).map_err(|_| WriteError::TooLarge(span))?;
+        self.data.resize(data_len, 0);
+        Ok(value.clone())
+    }
+
+    fn write_repeat_len(
+        &mut self,
+        span: Span,
+        len: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let len = match len.as_ref() {
+            Value::ConstLit(Const::U8(len, _)) => u64::from(*len),
+            Value::ConstLit(Const::U16(len, _)) => u64::from(*len),
+            Value::ConstLit(Const::U32(len, _)) => u64::from(*len),
+            Value::ConstLit(Const::U64(len, _)) => *len,
+            _ => return Err(WriteError::InvalidValue(len.span())),
+        };
+
+        match value.array_elems() {
+            Some(elem_exprs) if elem_exprs.len() as u64 == len => {
+                self.write_repeat_until_end(span, elem_format, value)
+            }
+            _ => Err(WriteError::InvalidValue(value.span())),
+        }
+    }
+
+    fn write_repeat_until_end(
+        &mut self,
+        span: Span,
+        elem_format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        if let (Value::ByteArrayLit(bytes), Some((Prim::FormatU8, []))) =
+            (value.as_ref(), elem_format.match_prim_spine())
+        {
+            self.data.extend_from_slice(bytes);
+            return Ok(value.clone());
+        }
+
+        let elem_exprs = match value.array_elems() {
+            Some(elem_exprs) => elem_exprs,
+            None => return Err(WriteError::InvalidValue(value.span())),
+        };
+
+        let elem_exprs = (elem_exprs.iter())
+            .map(|elem_expr| self.write_format(elem_format, elem_expr))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Spanned::new(span, Arc::new(Value::ArrayLit(elem_exprs))))
+    }
+
+    fn write_repeat_up_to(
+        &mut self,
+        span: Span,
+        max_len: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let max_len = match max_len.as_ref() {
+            Value::ConstLit(Const::U64(len, _)) => *len,
+            _ => return Err(WriteError::InvalidValue(max_len.span())),
+        };
+        match value.array_elems() {
+            Some(elem_exprs) if elem_exprs.len() as u64 <= max_len => {}
+            _ => return Err(WriteError::InvalidValue(value.span())),
+        }
+
+        self.write_repeat_until_end(span, elem_format, value)
+    }
+
+    fn write_length_prefixed(
+        &mut self,
+        span: Span,
+        len_format: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        let len = match value.array_elems() {
+            Some(elem_exprs) => elem_exprs.len(),
+            None => return Err(WriteError::InvalidValue(value.span())),
+        };
+        // Convert the length to the representation of the length format
+        let len_type = self.elim_env.format_repr(len_format);
+        let style = UIntStyle::Decimal;
+        let len = match len_type.match_prim_spine() {
+            Some((Prim::U8Type, [])) => u8::try_from(len).ok().map(|len| Const::U8(len, style)),
+            Some((Prim::U16Type, [])) => u16::try_from(len).ok().map(|len| Const::U16(len, style)),
+            Some((Prim::U32Type, [])) => u32::try_from(len).ok().map(|len| Const::U32(len, style)),
+            Some((Prim::U64Type, [])) => u64::try_from(len).ok().map(|len| Const::U64(len, style)),
+            _ => return Err(WriteError::InvalidFormat(len_format.span())),
+        };
+        let len = len.ok_or(WriteError::InvalidValue(value.span()))?;
+        let len = Spanned::empty(Arc::new(Value::ConstLit(len)));
+
+        self.write_format(len_format, &len)?;
+        self.write_repeat_until_end(span, elem_format, value)
+    }
+
+    fn write_optional(
+        &mut self,
+        cond: &ArcValue<'arena>,
+        elem_format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        use crate::core::semantics::Elim::FunApp;
+
+        match (cond.as_ref(), value.match_prim_spine()) {
+            (
+                Value::ConstLit(Const::Bool(true)),
+                Some((Prim::OptionSome, [_, FunApp(_, _, elem)])),
+            ) => {
+                self.write_format(elem_format, elem)?;
+                Ok(value.clone())
+            }
+            (Value::ConstLit(Const::Bool(false)), Some((Prim::OptionNone, [_]))) => {
+                Ok(value.clone())
+            }
+            (Value::ConstLit(Const::Bool(_)), _) => Err(WriteError::InvalidValue(value.span())),
+            (_, _) => Err(WriteError::InvalidValue(cond.span())),
+        }
+    }
+
+    fn write_with_base(
+        &mut self,
+        format: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        self.bases.push(self.data.len());
+        let result = self.write_format(format, value);
+        self.bases.pop();
+
+        result
+    }
+
+    /// Check that the value is the one produced when reading a format that
+    /// consumes no data.
+    fn write_succeed(
+        &mut self,
+        elem: &ArcValue<'arena>,
+        value: &ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, WriteError<'arena>> {
+        match self
+            .elim_env
+            .conversion_env(EnvLen::new())
+            .is_equal(elem, value)
+        {
+            true => Ok(value.clone()),
+            false => Err(WriteError::InvalidValue(value.span())),
+        }
+    }
+}
+
+/// Generates a function that converts a constant to its byte representation.
+macro_rules! const_to_bytes {
+    ($const_to_bytes:ident, $to_bytes:ident, $Const:ident, $T:ident) => {
+        fn $const_to_bytes(r#const: &Const) -> Option<[u8; std::mem::size_of::<$T>()]> {
+            match r#const {
+                Const::$Const(num, ..) => Some($T::$to_bytes(Into::into(*num))),
+                _ => None,
+            }
+        }
+    };
+}
+
+const_to_bytes!(u8_to_bytes, to_be_bytes, U8, u8);
+const_to_bytes!(u16le_to_bytes, to_le_bytes, U16, u16);
+const_to_bytes!(u16be_to_bytes, to_be_bytes, U16, u16);
+const_to_bytes!(u32le_to_bytes, to_le_bytes, U32, u32);
+const_to_bytes!(u32be_to_bytes, to_be_bytes, U32, u32);
+const_to_bytes!(u64le_to_bytes, to_le_bytes, U64, u64);
+const_to_bytes!(u64be_to_bytes, to_be_bytes, U64, u64);
+const_to_bytes!(u128le_to_bytes, to_le_bytes, U128, u128);
+const_to_bytes!(u128be_to_bytes, to_be_bytes, U128, u128);
+const_to_bytes!(s8_to_bytes, to_be_bytes, S8, i8);
+const_to_bytes!(s16le_to_bytes, to_le_bytes, S16, i16);
+const_to_bytes!(s16be_to_bytes, to_be_bytes, S16, i16);
+const_to_bytes!(s32le_to_bytes, to_le_bytes, S32, i32);
+const_to_bytes!(s32be_to_bytes, to_be_bytes, S32, i32);
+const_to_bytes!(s64le_to_bytes, to_le_bytes, S64, i64);
+const_to_bytes!(s64be_to_bytes, to_be_bytes, S64, i64);
+const_to_bytes!(s128le_to_bytes, to_le_bytes, S128, i128);
+const_to_bytes!(s128be_to_bytes, to_be_bytes, S128, i128);
+const_to_bytes!(f32le_to_bytes, to_le_bytes, F32, f32);
+const_to_bytes!(f32be_to_bytes, to_be_bytes, F32, f32);
+const_to_bytes!(f64le_to_bytes, to_le_bytes, F64, f64);
+const_to_bytes!(f64be_to_bytes, to_be_bytes, F64, f64);
+
+/// Convert a `U32` constant to three little-endian bytes, failing if it does
+/// not fit in 24 bits.
+fn u24le_to_bytes(r#const: &Const) -> Option<[u8; 3]> {
+    match u32le_to_bytes(r#const)? {
+        [b0, b1, b2, 0] => Some([b0, b1, b2]),
+        _ => None,
+    }
+}
+
+/// Convert a `U32` constant to three big-endian bytes, failing if it does not
+/// fit in 24 bits.
+fn u24be_to_bytes(r#const: &Const) -> Option<[u8; 3]> {
+    match u32be_to_bytes(r#const)? {
+        [0, b1, b2, b3] => Some([b1, b2, b3]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use scoped_arena::Scope;
+
+    use super::*;
+    use crate::env::{Index, Level};
+    use crate::source::StringInterner;
+    use crate::surface::elaboration;
+    use crate::testing::{self, assert_normalizes, elab_module, parse_term};
+
+    const EDID_MODULE: &str = include_str!("../../../formats/edid.fathom");
+    const EDID_DATA: &[u8] = include_bytes!("../../../formats/data/edid/dell-P2415Q.edid");
+
+    /// Elaborate a format in the context of a module, panicking on errors.
+    fn elab_format<'arena>(
+        interner: &RefCell<StringInterner>,
+        scope: &'arena Scope<'arena>,
+        module_source: &str,
+        format_source: &str,
+    ) -> (Module<'arena>, Term<'arena>) {
+        let (module, item_env) = elab_module(interner, scope, module_source);
+
+        let surface_format = parse_term(interner, scope, format_source);
+        let file_id = testing::file_id();
+        let mut context = elaboration::Context::new(file_id, interner, scope, item_env);
+        let format = context.elab_format(scope, &surface_format, &mut |m| panic!("{m:?}"));
+
+        (module, format)
+    }
+
+    /// The result of reading a format with [`read_test`].
+    struct ReadTest<'arena, 'data> {
+        context: Context<'arena, 'data>,
+        format: ArcValue<'arena>,
+        value: ArcValue<'arena>,
+        end_pos: usize,
+    }
+
+    impl<'arena, 'data> ReadTest<'arena, 'data> {
+        /// Write the value that was read back out, panicking on errors.
+        fn write(&self) -> Vec<u8> {
+            let mut data = Vec::new();
+            write_format(
+                self.context.elim_env(),
+                &self.format,
+                &self.value,
+                &mut data,
+            )
+            .unwrap();
+            data
+        }
+    }
+
+    /// Elaborate a format in the context of a module and read it from the
+    /// start of `data`, panicking on errors.
+    fn read_test<'arena, 'data>(
+        interner: &'data RefCell<StringInterner>,
+        scope: &'arena Scope<'arena>,
+        module_source: &str,
+        format_source: &str,
+        data: &'data [u8],
+    ) -> ReadTest<'arena, 'data> {
+        let (module, format) = elab_format(interner, scope, module_source, format_source);
+
+        let buffer = Buffer::from(data);
+        let mut context = Context::new(buffer);
+        context.set_interner(interner);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let (value, end_pos) = context.read_format_with_end_pos(buffer, &format).unwrap();
+        ReadTest {
+            context,
+            format,
+            value,
+            end_pos,
+        }
+    }
+
+    /// Project a field out of a record literal.
+    fn field<'a, 'arena>(value: &'a ArcValue<'arena>, index: usize) -> &'a ArcValue<'arena> {
+        match value.as_ref() {
+            Value::RecordLit(_, exprs) => &exprs[index],
+            _ => panic!("expected a record literal, found {value:?}"),
+        }
+    }
+
+    #[test]
+    fn edid_round_trip() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, EDID_MODULE, "main", EDID_DATA);
+
+        assert_eq!(test.write(), &EDID_DATA[..test.end_pos]);
+    }
+
+    #[test]
+    fn truncated_edid_reports_offset() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, EDID_MODULE, "main");
+
+        // Truncate the data in the middle of the `serial` field of the header
+        let buffer = Buffer::from(&EDID_DATA[..13]);
+        let mut context = Context::new(buffer);
+        context.set_interner(&interner);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let err = match context.read_format(&mut buffer.reader(), &format) {
+            Err(ReadError::BufferError(_, err)) => err,
+            result => panic!("expected a buffer error, found {result:?}"),
+        };
+        assert_eq!(
+            err.to_string(),
+            "unexpected end of buffer while reading `u32le`",
+        );
+        match err {
+            BufferError::UnexpectedEndOfBuffer {
+                offset,
+                needed,
+                available,
+                format,
+                context,
+            } => {
+                assert_eq!((offset, needed, available), (12, 4, 1));
+                assert_eq!(format, Some("u32le"));
+                assert_eq!(
+                    context.as_deref(),
+                    Some("while reading the field `header.serial`"),
+                );
+            }
+            err => panic!("expected an unexpected end of buffer, found {err:?}"),
+        }
+    }
+
+    #[test]
+    fn edid_from_buf_reader() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, EDID_MODULE, "main");
+
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../formats/data/edid/dell-P2415Q.edid",
+        );
+        let reader = io::BufReader::new(std::fs::File::open(path).unwrap());
+        let value = read_format_from_stream(&module, &format, reader).unwrap();
+
+        let mut context = Context::new(Buffer::from(EDID_DATA));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+        let mut data = Vec::new();
+        write_format(context.elim_env(), &format, &value, &mut data).unwrap();
+
+        assert_eq!(data, &EDID_DATA[..data.len()]);
+    }
+
+    #[test]
+    fn stream_is_read_as_needed() {
+        const MODULE: &str = r#"
+            def header = { magic <- u16be, len <- u8, data <- repeat_len8 len u8 };
+            def everything = { header <- header, rest <- repeat_until_end u8 };
+        "#;
+        const DATA: &[u8] = &[0xca, 0xfe, 0x02, 0xaa, 0xbb];
+
+        /// A stream that fails if it is read past the end of the data.
+        struct FailingReader;
+
+        impl io::Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "read past the end"))
+            }
+        }
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        let (module, format) = elab_format(&interner, &scope, MODULE, "header");
+        let reader = io::Read::chain(DATA, FailingReader);
+        let value = read_format_from_stream(&module, &format, reader).unwrap();
+        assert!(matches!(
+            value.as_ref(),
+            Value::RecordLit(_, exprs) if exprs[2].to_byte_array_lit().is_some(),
+        ));
+
+        let (module, format) = elab_format(&interner, &scope, MODULE, "everything");
+        let reader = io::Read::chain(DATA, FailingReader);
+        assert!(matches!(
+            read_format_from_stream(&module, &format, reader),
+            Err(ReadError::BufferError(_, BufferError::IoError(_))),
+        ));
+    }
+
+    #[test]
+    fn deref_requires_seekable_stream() {
+        const MODULE: &str = r#"
+            def main = {
+                start <- stream_pos,
+                link <- link start u16be,
+                len <- deref link,
+                _reserved <- u16be,
+                data <- repeat_len16 len u16be,
+            };
+        "#;
+        const DATA: &[u8] = &[0xff, 0xff, 0x00, 0x01, 0x12, 0x34];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, MODULE, "main");
+
+        let mut cursor = io::Cursor::new(DATA);
+        cursor.set_position(2);
+        assert!(matches!(
+            read_format_from_stream(&module, &format, cursor),
+            Err(ReadError::SeekRequired(_, 0)),
+        ));
+
+        let mut cursor = io::Cursor::new(DATA);
+        cursor.set_position(2);
+        let value = read_format_from_seekable(&module, &format, cursor).unwrap();
+        assert!(matches!(
+            field(&value, 0).as_ref(),
+            Value::ConstLit(Const::Pos(2))
+        ));
+        assert!(matches!(
+            field(&value, 2).as_ref(),
+            Value::ConstLit(Const::U16(1, _))
+        ));
+        assert!(matches!(
+            field(&value, 4).array_elems().as_deref(),
+            Some([elem]) if matches!(elem.as_ref(), Value::ConstLit(Const::U16(0x1234, _))),
+        ));
+    }
+
+    #[test]
+    fn read_format_from_evaluated_format() {
+        const MODULE: &str = r#"
+            def main = {
+                len <- u8,
+                data <- repeat_len8 len u8,
+                missing <- unwrap (none @U8),
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, EDID_MODULE, "header");
+
+        let mut context = Context::new(Buffer::from(&[][..]));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        // The stream is left directly after the header
+        let mut cursor = io::Cursor::new(EDID_DATA);
+        read_format(context.elim_env(), &format, &mut cursor).unwrap();
+        assert_eq!(cursor.position(), 20);
+
+        // Errors are reported at offsets from the start of the stream
+        let (module, format) = elab_format(&interner, &scope, MODULE, "main");
+        let mut context = Context::new(Buffer::from(&[][..]));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let mut cursor = io::Cursor::new(&[0xff, 0x02, 0xaa, 0xbb, 0xcc][..]);
+        cursor.set_position(1);
+        let err = read_format(context.elim_env(), &format, &mut cursor).unwrap_err();
+        assert!(matches!(err, ReadError::UnwrappedNone(_, 4)));
+        assert_eq!(err.offset(), Some(4));
+    }
+
+    #[test]
+    fn edid_header_end_pos() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, EDID_MODULE, "header", EDID_DATA);
+
+        assert!(EDID_DATA.len() > 20);
+        assert_eq!(test.end_pos, 20);
+    }
+
+    #[test]
+    fn length_prefixed_round_trip() {
+        const DATA: &[u8] = &[0x00, 0x03, 0x0a, 0x0b, 0x0c, 0xff];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, "", "length_prefixed u16be u8", DATA);
+
+        assert_eq!(test.end_pos, 5);
+        match test.value.as_ref() {
+            Value::ByteArrayLit(bytes) => assert_eq!(bytes[..], [0x0a, 0x0b, 0x0c]),
+            _ => panic!("expected a byte array literal"),
+        }
+        assert_eq!(test.write(), &DATA[..test.end_pos]);
+    }
+
+    #[test]
+    fn read_repeat_count() {
+        const MODULE: &str = r#"
+            def pairs = {
+                count <- u64be,
+                values <- repeat_count (count * (2 : U64)) u16be,
+            };
+        "#;
+        const DATA: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // count
+            0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, // values
+            0xff,
+        ];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "pairs", DATA);
+
+        assert_eq!(test.end_pos, 16);
+        let values = (field(&test.value, 1).array_elems().unwrap().iter())
+            .map(|elem| match elem.as_ref() {
+                Value::ConstLit(Const::U16(value, _)) => *value,
+                _ => panic!("expected a u16 literal"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn repeat_count_repr() {
+        assert_normalizes("Repr (repeat_count 3 u16be)", "Array U16");
+    }
+
+    #[test]
+    fn read_repeat_up_to() {
+        const MODULE: &str = r#"
+            def entries = {
+                by_limit <- limit8 7 (repeat_up_to 4 u16be),
+                by_count <- limit8 8 (repeat_up_to 2 u16be),
+            };
+        "#;
+        const DATA: &[u8] = &[0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "entries", DATA);
+        let values = |index| {
+            (field(&test.value, index).array_elems().unwrap().iter())
+                .map(|elem| match elem.as_ref() {
+                    Value::ConstLit(Const::U16(value, _)) => *value,
+                    _ => panic!("expected a u16 literal"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // The limit is reached part of the way through the fourth element
+        assert_eq!(values(0), [1, 2, 3]);
+        // The count is reached before the limit
+        assert_eq!(values(1), [1, 2]);
+    }
+
+    #[test]
+    fn read_debug_passes_data_through() {
+        const MODULE: &str = r#"
+            def entries = debug "entries" {
+                len <- debug "len" u8,
+                data <- debug "data" (repeat_len8 len u16be),
+            };
+        "#;
+        const DATA: &[u8] = &[0x02, 0x00, 0x01, 0x00, 0x02];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, MODULE, "entries");
+
+        let log = RefCell::new(Vec::new());
+        let buffer = Buffer::from(DATA);
+        let mut context = Context::new(buffer);
+        context.set_debug_sink(|label, pos, _: &ArcValue<'_>| log.borrow_mut().push((label, pos)));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let (value, end_pos) = context.read_format_with_end_pos(buffer, &format).unwrap();
+
+        assert_eq!(end_pos, 5);
+        assert!(matches!(
+            field(&value, 0).as_ref(),
+            Value::ConstLit(Const::U8(2, _))
+        ));
+        let data = (field(&value, 1).array_elems().unwrap().iter())
+            .map(|elem| match elem.as_ref() {
+                Value::ConstLit(Const::U16(value, _)) => *value,
+                _ => panic!("expected a u16 literal"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(data, [1, 2]);
+
+        // Each debug format is logged once it has been read
+        let [entries, len, data] =
+            ["entries", "len", "data"].map(|name| interner.borrow_mut().get_or_intern(name));
+        assert_eq!(*log.borrow(), [(len, 0), (data, 1), (entries, 0)]);
+    }
+
+    const UTF8_MODULE: &str = r#"
+        def name = {
+            len <- u64be,
+            name <- utf8 len,
+        };
+
+        def greeting = {
+            len <- u64be,
+            name <- utf8 len,
+            is_hello <- succeed (string_eq name "héllo"),
+        };
+    "#;
+
+    const UTF8_DATA: &[u8] = &[
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, // len
+        0x68, 0xc3, 0xa9, 0x6c, 0x6c, 0x6f, // name
+    ];
+
+    #[test]
+    fn read_utf8() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        // Decoded strings are not interned, so reading should leave the
+        // interner unchanged once the format has been elaborated
+        elab_format(&interner, &scope, UTF8_MODULE, "name");
+        let interned_len = interner.borrow().len();
+        let test = read_test(&interner, &scope, UTF8_MODULE, "name", UTF8_DATA);
+
+        assert_eq!(test.end_pos, 14);
+        match field(&test.value, 1).as_ref() {
+            Value::StringLit(string) => assert_eq!(&**string, "héllo"),
+            _ => panic!("expected a string literal"),
+        };
+        assert_eq!(interner.borrow().len(), interned_len);
+        assert_eq!(test.write(), UTF8_DATA);
+    }
+
+    #[test]
+    fn write_utf8_with_wrong_len() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, UTF8_MODULE, "name");
+
+        let buffer = Buffer::from(UTF8_DATA);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let [len, name] = ["len", "name"].map(|name| interner.borrow_mut().get_or_intern(name));
+        let labels = scope.to_scope_from_iter([len, name]);
+        let value = Spanned::empty(Arc::new(Value::RecordLit(
+            labels,
+            vec![
+                Spanned::empty(Arc::new(Value::ConstLit(Const::U64(5, UIntStyle::Decimal)))),
+                Spanned::empty(Arc::new(Value::StringLit(Arc::from("héllo")))),
+            ],
+        )));
+
+        let result = write_format(context.elim_env(), &format, &value, &mut Vec::new());
+        assert!(matches!(result, Err(WriteError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn write_formats_that_consume_no_data() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let u8_value = |num| {
+            Spanned::empty(Arc::new(Value::ConstLit(Const::U8(
+                num,
+                UIntStyle::Decimal,
+            ))))
+        };
+
+        for format in ["succeed (3 : U8)", "unwrap (some (3 : U8))"] {
+            let (module, format) = elab_format(&interner, &scope, "", format);
+            let mut context = Context::new(Buffer::from(&[][..]));
+            context.add_module(&module);
+            let format = context.eval_env().eval(&format);
+
+            let mut data = Vec::new();
+            write_format(context.elim_env(), &format, &u8_value(3), &mut data).unwrap();
+            assert!(data.is_empty());
+
+            let result = write_format(context.elim_env(), &format, &u8_value(4), &mut data);
+            assert!(matches!(result, Err(WriteError::InvalidValue(_))));
+        }
+
+        // Unwrapping `none` fails for every value
+        let (module, format) = elab_format(&interner, &scope, "", "unwrap (none @U8)");
+        let mut context = Context::new(Buffer::from(&[][..]));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let result = write_format(context.elim_env(), &format, &u8_value(3), &mut Vec::new());
+        assert!(matches!(result, Err(WriteError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn write_reserved() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let unit = Spanned::empty(Arc::new(Value::RecordLit(&[], Vec::new())));
+
+        let (module, format) = elab_format(&interner, &scope, "", "reserved 3");
+        let mut context = Context::new(Buffer::from(&[][..]));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let mut data = Vec::new();
+        write_format(context.elim_env(), &format, &unit, &mut data).unwrap();
+        assert_eq!(data, [0, 0, 0]);
+
+        let value = Spanned::empty(Arc::new(Value::ConstLit(Const::U8(3, UIntStyle::Decimal))));
+        let result = write_format(context.elim_env(), &format, &value, &mut Vec::new());
+        assert!(matches!(result, Err(WriteError::InvalidValue(_))));
+
+        let (module, format) = elab_format(&interner, &scope, "", "reserved 0xffffffffffffffff");
+        let mut context = Context::new(Buffer::from(&[][..]));
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let result = write_format(context.elim_env(), &format, &unit, &mut Vec::new());
+        assert!(matches!(result, Err(WriteError::TooLarge(_))));
+    }
+
+    #[test]
+    fn write_unwritable_formats() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (format, value) in [
+            ("limit8 1 u8", Const::U8(3, UIntStyle::Decimal)),
+            ("link (u64_to_pos 0) u8", Const::Ref(0)),
+        ] {
+            let (module, format) = elab_format(&interner, &scope, "", format);
+            let mut context = Context::new(Buffer::from(&[][..]));
+            context.add_module(&module);
+            let format = context.eval_env().eval(&format);
+            let value = Spanned::empty(Arc::new(Value::ConstLit(value)));
+
+            let result = write_format(context.elim_env(), &format, &value, &mut Vec::new());
+            assert!(matches!(result, Err(WriteError::InvalidFormat(_))));
+        }
+    }
+
+    #[test]
+    fn read_utf8_compared_with_string_constant() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, UTF8_MODULE, "greeting", UTF8_DATA);
+
+        assert!(matches!(
+            field(&test.value, 2).as_ref(),
+            Value::ConstLit(Const::Bool(true))
+        ));
+    }
+
+    #[test]
+    fn read_invalid_utf8() {
+        const DATA: &[u8] = &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, // len
+            0xff, 0xfe, // name
+        ];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, UTF8_MODULE, "name");
+
+        let buffer = Buffer::from(DATA);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        match context.read_format_with_end_pos(buffer, &format) {
+            Err(ReadError::InvalidUtf8(_, offset)) => assert_eq!(offset, 8),
+            _ => panic!("expected an invalid UTF-8 error"),
+        };
+    }
+
+    const RESERVED_MODULE: &str = r#"
+        def header = {
+            version <- u8,
+            _reserved <- reserved 4,
+            flags <- u8,
+        };
+    "#;
+
+    #[test]
+    fn read_reserved() {
+        const DATA: &[u8] = &[0x01, 0x00, 0x00, 0x00, 0x00, 0x02];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, RESERVED_MODULE, "header", DATA);
+
+        assert_eq!(test.end_pos, 6);
+    }
+
+    #[test]
+    fn read_nonzero_reserved() {
+        const DATA: &[u8] = &[0x01, 0x00, 0x00, 0x80, 0x00, 0x02];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, RESERVED_MODULE, "header");
+
+        let buffer = Buffer::from(DATA);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        match context.read_format_with_end_pos(buffer, &format) {
+            Err(ReadError::NonZeroReserved(_, offset)) => assert_eq!(offset, 3),
+            _ => panic!("expected a nonzero reserved byte error"),
+        };
+    }
+
+    #[test]
+    fn overlap_fields_share_stream_pos() {
+        const MODULE: &str = r#"
+            def main = {
+                header <- u16be,
+                body <- overlap {
+                    first <- { start <- stream_pos, value <- u32be },
+                    second <- { start <- stream_pos, high <- u16be, low <- u16be },
+                },
+                end <- stream_pos,
+            };
+        "#;
+        const DATA: &[u8] = &[0x00, 0x01, 0x00, 0x02, 0x00, 0x03];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "main", DATA);
+
+        let body = field(&test.value, 1);
+        let first_start = field(field(body, 0), 0);
+        let second_start = field(field(body, 1), 0);
+
+        assert!(matches!(
+            first_start.as_ref(),
+            Value::ConstLit(Const::Pos(2))
+        ));
+        assert!(matches!(
+            second_start.as_ref(),
+            Value::ConstLit(Const::Pos(2))
+        ));
+        assert!(matches!(
+            field(&test.value, 2).as_ref(),
+            Value::ConstLit(Const::Pos(6))
+        ));
+    }
+
+    #[test]
+    fn read_array_longer_than_max_len() {
+        const MODULE: &str = r#"
+            def table = {
+                len <- u32be,
+                entries <- repeat_len32 len u16be,
+            };
+        "#;
+        const DATA: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x00, 0x01];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, MODULE, "table");
+
+        let buffer = Buffer::from(DATA);
+        let mut context = Context::new(buffer);
+        context.set_max_array_len(1024);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        match context.read_format_with_end_pos(buffer, &format) {
+            Err(ReadError::ArrayTooLong(_, offset, len, max_len)) => {
+                assert_eq!(offset, 4);
+                assert_eq!(len, 0xffffffff);
+                assert_eq!(max_len, 1024);
+            }
+            _ => panic!("expected an array too long error"),
+        };
+    }
+
+    #[test]
+    fn optional_round_trip() {
+        const MODULE: &str = r#"
+            def flagged = {
+                has_extra <- u8,
+                extra <- optional (has_extra == (1 : U8)) u16be,
+                last <- u8,
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (data, extra) in [(&[1, 0x12, 0x34, 9][..], Some(0x1234)), (&[0, 9][..], None)] {
+            let test = read_test(&interner, &scope, MODULE, "flagged", data);
+            assert_eq!(test.end_pos, data.len());
+
+            match (field(&test.value, 1).match_prim_spine(), extra) {
+                (Some((Prim::OptionSome, [_, Elim::FunApp(_, _, elem)])), Some(extra)) => {
+                    assert!(
+                        matches!(elem.as_ref(), Value::ConstLit(Const::U16(n, _)) if *n == extra)
+                    );
+                }
+                (Some((Prim::OptionNone, [_])), None) => {}
+                _ => panic!("unexpected option value"),
+            }
+            assert_eq!(test.write(), data);
+        }
+    }
+
+    #[test]
+    fn pos_sub_measures_regions() {
+        const MODULE: &str = r#"
+            def region = {
+                padding <- repeat_len8 3 u8,
+                start <- stream_pos,
+                data <- repeat_len8 7 u8,
+                end <- stream_pos,
+                len <- succeed (pos_sub end start),
+                negative_len <- succeed (pos_sub start end),
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "region", &[0; 10]);
+
+        let value = &test.value;
+        assert!(matches!(
+            field(value, 1).as_ref(),
+            Value::ConstLit(Const::Pos(3))
+        ));
+        assert!(matches!(
+            field(value, 3).as_ref(),
+            Value::ConstLit(Const::Pos(10))
+        ));
+        assert!(matches!(
+            field(value, 4).as_ref(),
+            Value::ConstLit(Const::U64(7, _))
+        ));
+        assert!(matches!(
+            field(value, 5).match_prim_spine(),
+            Some((Prim::PosSub, [_, _])),
+        ));
+    }
+
+    #[test]
+    fn rel_pos_is_relative_to_innermost_base() {
+        const MODULE: &str = r#"
+            def table = {
+                padding <- repeat_len8 2 u8,
+                abs <- stream_pos,
+                rel <- rel_pos,
+            };
+            def file = {
+                header <- u8,
+                outer <- rel_pos,
+                first <- with_base table,
+                after <- rel_pos,
+                nested <- with_base {
+                    padding <- u8,
+                    inner <- with_base table,
+                },
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let data = [0; 12];
+        let test = read_test(&interner, &scope, MODULE, "file", &data);
+
+        let value = &test.value;
+        let pos = |value: &ArcValue<'_>| match value.as_ref() {
+            Value::ConstLit(Const::Pos(pos)) => *pos,
+            _ => panic!("expected a position"),
+        };
+
+        // Without a base, positions are relative to the start of the stream
+        assert_eq!(pos(field(value, 1)), 1);
+        // The table starts at position 1
+        assert_eq!(pos(field(field(value, 2), 1)), 3);
+        assert_eq!(pos(field(field(value, 2), 2)), 2);
+        // The base is restored after the table has been read
+        assert_eq!(pos(field(value, 3)), 3);
+        // The nested table starts at position 4
+        let nested_table = field(field(value, 4), 1);
+        assert_eq!(pos(field(nested_table, 1)), 6);
+        assert_eq!(pos(field(nested_table, 2)), 2);
+
+        assert_eq!(test.write(), data[..test.end_pos]);
+    }
+
+    #[test]
+    fn value_to_json_of_record() {
+        const MODULE: &str = r#"
+            def record = {
+                flag <- u8,
+                point <- { x <- u16be, y <- s8 },
+                size <- u64be,
+                data <- repeat_len8 3 u8,
+                extra <- optional (flag == (1 : U8)) u8,
+                missing <- optional false u8,
+                pos <- stream_pos,
+                empty <- succeed {},
+            };
+        "#;
+        const DATA: &[u8] = &[1, 0x01, 0x00, 0xff, 0, 0, 0, 0, 0, 0, 0, 42, 7, 8, 9, 10];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "record", DATA);
+
+        assert_eq!(
+            value_to_json(&interner.borrow(), &test.value).as_deref(),
+            Some(concat!(
+                r#"{"flag":1,"point":{"x":256,"y":-1},"size":"42","data":[7,8,9],"#,
+                r#""extra":10,"missing":null,"pos":16,"empty":{}}"#,
+            )),
+        );
+    }
+
+    #[test]
+    fn field_ranges_of_records() {
+        const MODULE: &str = r#"
+            def point = { x <- u16be, y <- u8 };
+            def header = {
+                magic <- u8,
+                point <- point,
+                tag <- overlap { word <- u16le, bytes <- repeat_len8 2 u8 },
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, MODULE, "header");
+
+        let buffer = Buffer::from(&[0; 8][..]);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        let (_, field_ranges) = context
+            .read_format_with_field_ranges(buffer, &format)
+            .unwrap();
+
+        let interner = interner.borrow();
+        let field_ranges = field_ranges
+            .iter()
+            .map(|field_range| {
+                let path = field_range.path.iter();
+                let path = path.map(|label| interner.resolve(*label).unwrap());
+                (
+                    path.collect::<Vec<_>>().join("."),
+                    field_range.range.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            field_ranges,
+            [
+                ("magic".to_owned(), 0..1),
+                ("point.x".to_owned(), 1..3),
+                ("point.y".to_owned(), 3..4),
+                ("point".to_owned(), 1..4),
+                ("tag.word".to_owned(), 4..6),
+                ("tag.bytes".to_owned(), 4..6),
+                ("tag".to_owned(), 4..6),
+            ]
+        );
+    }
+
+    #[test]
+    fn field_ranges_are_not_recorded_by_default() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, format) = elab_format(&interner, &scope, "", "{ x <- u8, y <- u8 }");
+
+        let buffer = Buffer::from(&[0; 2][..]);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        let format = context.eval_env().eval(&format);
+
+        context.read_format(&mut buffer.reader(), &format).unwrap();
+        assert!(context.field_ranges.is_none());
+        assert!(context.field_path.is_empty());
+    }
+
+    #[test]
+    fn read_zero_length_arrays() {
+        const MODULE: &str = r#"
+            def empty = {
+                bytes <- repeat_len8 0 u8,
+                words <- repeat_len16 0 u16be,
+                never <- repeat_len32 0 fail,
+                len <- u8,
+                records <- repeat_len8 len { x <- u8, y <- u8 },
+                after <- u8,
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "empty", &[0, 42]);
+        assert_eq!(test.end_pos, 2);
+
+        let bytes = field(&test.value, 0);
+        assert!(
+            matches!(bytes.as_ref(), Value::ByteArrayLit(bytes) if bytes.is_empty()),
+            "expected an empty byte array, found {bytes:?}",
+        );
+        for index in [1, 2, 4] {
+            let elems = field(&test.value, index);
+            assert!(
+                matches!(elems.as_ref(), Value::ArrayLit(elems) if elems.is_empty()),
+                "expected an empty array, found {elems:?}",
+            );
+        }
+        assert!(matches!(
+            field(&test.value, 5).as_ref(),
+            Value::ConstLit(Const::U8(42, _))
+        ));
+    }
+
+    #[test]
+    fn zero_length_array_repr() {
+        assert_normalizes("Repr (repeat_len8 0 u16be)", "Array8 0 U16");
+    }
+
+    #[test]
+    fn read_map_format() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let format = "map u8 (fun x => x != (0 : U8))";
+
+        for (data, expected) in [([0], false), ([1], true), ([0xff], true)] {
+            let test = read_test(&interner, &scope, "", format, &data);
+            assert_eq!(test.end_pos, 1);
+            assert!(
+                matches!(test.value.as_ref(), Value::ConstLit(Const::Bool(b)) if *b == expected),
+                "expected {expected}, found {:?}",
+                test.value,
+            );
+
+            let (context, format, value) = (&test.context, &test.format, &test.value);
+            let result = write_format(context.elim_env(), format, value, &mut Vec::new());
+            assert!(matches!(result, Err(WriteError::InvalidFormat(_))));
+        }
+    }
+
+    #[test]
+    fn read_bind_format() {
+        const DATA: &[u8] = &[0x03, 0x0a, 0x0b, 0x0c, 0xff];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let format = "bind u8 (fun len => repeat_len8 len u8)";
+        let test = read_test(&interner, &scope, "", format, DATA);
+        assert_eq!(test.end_pos, 4);
+
+        let elems = test.value.array_elems().expect("expected an array");
+        let bytes: Vec<_> = (elems.iter())
+            .map(|elem| match elem.as_ref() {
+                Value::ConstLit(Const::U8(byte, _)) => *byte,
+                _ => panic!("expected a byte, found {elem:?}"),
+            })
+            .collect();
+        assert_eq!(bytes, [0x0a, 0x0b, 0x0c]);
+    }
+
+    #[test]
+    fn u24_round_trip() {
+        const DATA: &[u8] = &[0x01, 0x02, 0x03];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (format, expected) in [("u24be", 0x010203), ("u24le", 0x030201)] {
+            let test = read_test(&interner, &scope, "", format, DATA);
+            assert_eq!(test.end_pos, 3);
+            assert!(
+                matches!(test.value.as_ref(), Value::ConstLit(Const::U32(n, _)) if *n == expected),
+                "expected {expected:#08x}, found {:?}",
+                test.value,
+            );
+            assert_eq!(test.write(), DATA);
+
+            let (context, format) = (&test.context, &test.format);
+            let too_large = Spanned::empty(Arc::new(Value::ConstLit(Const::U32(
+                0x01000000,
+                UIntStyle::Decimal,
+            ))));
+            let result = write_format(context.elim_env(), format, &too_large, &mut Vec::new());
+            assert!(matches!(result, Err(WriteError::InvalidValue(_))));
+        }
+    }
+
+    #[test]
+    fn read_u24_arrays() {
+        const DATA: &[u8] = &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0xff];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (elem_format, expected) in [
+            ("u24be", [0x010203, 0x040506]),
+            ("u24le", [0x030201, 0x060504]),
+        ] {
+            let source = format!("{{ xs <- repeat_len8 2 {elem_format}, y <- u8 }}");
+            let test = read_test(&interner, &scope, "", &source, DATA);
+            assert_eq!(test.end_pos, 7);
+
+            let xs: Vec<_> = (field(&test.value, 0).array_elems().unwrap().iter())
+                .map(|elem| match elem.as_ref() {
+                    Value::ConstLit(Const::U32(n, _)) => *n,
+                    _ => panic!("expected a u32, found {elem:?}"),
+                })
+                .collect();
+            assert_eq!(xs, expected);
+            assert!(matches!(
+                field(&test.value, 1).as_ref(),
+                Value::ConstLit(Const::U8(0xff, _))
+            ));
+        }
+    }
+
+    #[test]
+    fn u128be_round_trip() {
+        const MODULE: &str = r#"
+            def guid = {
+                value <- u128be where value != (0 : U128),
+                negated <- s128be,
+            };
+        "#;
+        const DATA: &[u8] = &[
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, // value
+            0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, // value
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // negated
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe, // negated
+        ];
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let test = read_test(&interner, &scope, MODULE, "guid", DATA);
+        assert_eq!(test.end_pos, 32);
+
+        match (
+            field(&test.value, 0).as_ref(),
+            field(&test.value, 1).as_ref(),
+        ) {
+            (Value::ConstLit(Const::U128(value, _)), Value::ConstLit(Const::S128(negated))) => {
+                assert_eq!(value.get(), 0x00112233_44556677_8899aabb_ccddeeff);
+                assert_eq!(negated.get(), -2);
+            }
+            _ => panic!("expected 128-bit integer literals"),
+        }
+        assert_eq!(test.write(), DATA);
+    }
+
+    #[test]
+    fn read_large_u8_array() {
+        const LEN: u32 = 1024 * 1024;
+        let data = Vec::from_iter((0..LEN).map(|i| i as u8));
+        let buffer = Buffer::from(&data[..]);
+        let mut context = Context::new(buffer);
+
+        let len = Value::ConstLit(Const::U32(LEN, UIntStyle::Decimal));
+        let len = Spanned::empty(Arc::new(len));
+        let elem_format = Spanned::empty(Arc::new(Value::prim(Prim::FormatU8, [])));
+        let format = Value::prim(Prim::FormatRepeatLen32, [len, elem_format]);
+        let format = Spanned::empty(Arc::new(format));
+
+        let (value, end_pos) = context.read_format_with_end_pos(buffer, &format).unwrap();
+
+        assert_eq!(end_pos, data.len());
+        match value.as_ref() {
+            Value::ByteArrayLit(bytes) => assert_eq!(bytes[..], data[..]),
+            _ => panic!("expected a byte array literal"),
+        }
+    }
+
+    /// Construct a format that links back to its own starting position, and
+    /// immediately dereferences that link:
+    ///
+    /// ```fathom
+    /// def cycle = { start <- stream_pos, self <- link start cycle, value <- deref cycle self };
+    /// ```
+    ///
+    /// This can't be defined in the surface language, but could still turn up
+    /// in a malicious or buggy core module.
+    fn cyclic_deref_module<'arena>(
+        interner: &mut StringInterner,
+        scope: &'arena Scope<'arena>,
+    ) -> Module<'arena> {
+        let app = |head, arg| {
+            Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(head),
+                scope.to_scope(arg),
+            )
+        };
+        let prim = |prim| Term::Prim(Span::Empty, prim);
+        let cycle = || Term::ItemVar(Span::Empty, Level::first());
+        let last_var = || Term::LocalVar(Span::Empty, Index::last());
+
+        let labels = ["start", "self", "value"].map(|label| interner.get_or_intern(label));
+        let formats = [
+            prim(Prim::FormatStreamPos),
+            app(app(prim(Prim::FormatLink), last_var()), cycle()),
+            app(app(prim(Prim::FormatDeref), cycle()), last_var()),
+        ];
+        let format = Term::FormatRecord(
+            Span::Empty,
+            scope.to_scope_from_iter(labels),
+            scope.to_scope_from_iter(formats),
+        );
+
+        Module {
+            items: scope.to_scope_from_iter([Item::Def {
+                doc: &[],
+                label: interner.get_or_intern("cycle"),
+                r#type: scope.to_scope(prim(Prim::FormatType)),
+                expr: scope.to_scope(format),
+            }]),
+        }
+    }
+
+    #[test]
+    fn cyclic_deref_runs_out_of_fuel() {
+        let mut interner = StringInterner::new();
+        let scope = Scope::new();
+        let module = cyclic_deref_module(&mut interner, &scope);
+
+        let buffer = Buffer::from(&[0; 4][..]);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+        context.set_fuel(Fuel::new(64));
+
+        let format = Term::ItemVar(Span::Empty, Level::first());
+        assert!(matches!(
+            context.read_entrypoint(&format),
+            Err(ReadError::OutOfFuel(_)),
+        ));
+    }
+
+    #[test]
+    fn cyclic_deref_runs_out_of_default_fuel() {
+        let mut interner = StringInterner::new();
+        let scope = Scope::new();
+        let module = cyclic_deref_module(&mut interner, &scope);
+
+        let buffer = Buffer::from(&[0; 4][..]);
+        let mut context = Context::new(buffer);
+        context.add_module(&module);
+
+        let format = Term::ItemVar(Span::Empty, Level::first());
+        assert!(matches!(
+            context.read_entrypoint(&format),
+            Err(ReadError::OutOfFuel(_)),
+        ));
+    }
+}
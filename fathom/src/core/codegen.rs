@@ -0,0 +1,1186 @@
+//! Generation of Rust code from format descriptions.
+//!
+//! Format records that are bound as top-level items are translated to Rust
+//! structs, with fields corresponding to the representation types of each of
+//! the field formats. Each struct is given an associated `read` function that
+//! reads the fields in order from an [`std::io::Read`] implementation, using a
+//! small runtime module that is included in the generated code.
+//!
+//! Tagged unions, where a field's format is chosen by matching on the value of
+//! an earlier field, are translated to Rust enums with one variant per branch,
+//! and are read by dispatching on that value.
+//!
+//! Overlap formats that are bound as top-level items are translated to Rust
+//! structs in the same way as format records, as each of their fields is
+//! present in the representation. The data read by each field is buffered, so
+//! that it can be replayed to the following fields, and the reader is left at
+//! the end of the longest field.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Write;
+
+use crate::core::{Const, ConstPattern, Item, Module, Prim, Term};
+use crate::env::UniqueEnv;
+use crate::source::{Span, StringId, StringInterner};
+
+#[derive(Clone, Debug)]
+pub enum CodegenError {
+    /// A format that could not be translated to Rust.
+    UnsupportedFormat(Span),
+    /// A type that could not be translated to Rust.
+    UnsupportedType(Span),
+    /// A term that could not be translated to Rust.
+    UnsupportedTerm(Span),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodegenError::UnsupportedFormat(_) => f.write_str("unsupported format"),
+            CodegenError::UnsupportedType(_) => f.write_str("unsupported type"),
+            CodegenError::UnsupportedTerm(_) => f.write_str("unsupported term"),
+        }
+    }
+}
+
+impl std::error::Error for CodegenError {}
+
+/// Code generation context.
+pub struct Context<'interner> {
+    interner: &'interner RefCell<StringInterner>,
+    /// The names of the Rust types that were generated for each item, if any.
+    item_names: UniqueEnv<Option<String>>,
+    /// The names of the Rust variables that are bound to each local variable.
+    local_names: UniqueEnv<String>,
+    /// The generated Rust code.
+    output: String,
+}
+
+impl<'interner> Context<'interner> {
+    pub fn new(interner: &'interner RefCell<StringInterner>) -> Context<'interner> {
+        Context {
+            interner,
+            item_names: UniqueEnv::new(),
+            local_names: UniqueEnv::new(),
+            output: String::new(),
+        }
+    }
+
+    /// Generate Rust code for the format records defined in a module.
+    pub fn module(mut self, module: &Module<'_>) -> Result<String, CodegenError> {
+        writeln!(
+            self.output,
+            "// This file was automatically generated by fathom."
+        )
+        .unwrap();
+        writeln!(self.output).unwrap();
+        writeln!(self.output, "use std::io::{{self, Read}};").unwrap();
+
+        self.item_names.reserve(module.items.len());
+        for item in module.items {
+            match item {
+                Item::Def {
+                    doc, label, expr, ..
+                } => {
+                    let name = match expr {
+                        Term::FormatRecord(_, labels, formats) => {
+                            let name = self.type_name(*label);
+                            self.format_record(doc, &name, labels, formats, false)?;
+                            Some(name)
+                        }
+                        Term::FormatOverlap(_, labels, formats) => {
+                            let name = self.type_name(*label);
+                            self.format_record(doc, &name, labels, formats, true)?;
+                            Some(name)
+                        }
+                        _ => None,
+                    };
+                    self.item_names.push(name);
+                }
+            }
+        }
+
+        writeln!(self.output).unwrap();
+        self.output.push_str(RUNTIME);
+
+        Ok(self.output)
+    }
+
+    fn type_name(&self, label: StringId) -> String {
+        let interner = self.interner.borrow();
+        let label = interner.resolve(label).unwrap();
+
+        (label.split('_'))
+            .flat_map(|word| {
+                let mut chars = word.chars();
+                let first = chars.next().map(|ch| ch.to_ascii_uppercase());
+                first.into_iter().chain(chars)
+            })
+            .collect()
+    }
+
+    fn field_name(&self, label: StringId) -> String {
+        let interner = self.interner.borrow();
+        let label = interner.resolve(label).unwrap();
+
+        match label {
+            "crate" | "self" | "super" | "Self" => format!("{label}_"),
+            _ if RUST_KEYWORDS.contains(&label) => format!("r#{label}"),
+            _ => label.to_owned(),
+        }
+    }
+
+    /// Returns the name of the Rust variable that a local variable is bound to
+    /// in the generated `read` functions.
+    fn local_name(&self, name: StringId) -> String {
+        match self.field_name(name) {
+            // Avoid shadowing the reader parameter
+            name if name == "reader" => "reader_".to_owned(),
+            name => name,
+        }
+    }
+
+    /// Returns the name of the Rust variable that a function parameter is
+    /// bound to, ignoring the value if the parameter is unnamed.
+    fn binder_name(&self, name: Option<StringId>) -> String {
+        match name {
+            Some(name) => self.local_name(name),
+            None => "_".to_owned(),
+        }
+    }
+
+    /// Generate a struct for a format record, or for an overlap format if
+    /// `overlap` is `true`, along with a function that reads it. The struct is
+    /// documented with the doc comments of the item that defined it.
+    fn format_record(
+        &mut self,
+        doc: &[StringId],
+        name: &str,
+        labels: &[StringId],
+        formats: &[Term<'_>],
+        overlap: bool,
+    ) -> Result<(), CodegenError> {
+        let initial_local_len = self.local_names.len();
+        let mut fields = Vec::with_capacity(labels.len());
+        for (label, format) in Iterator::zip(labels.iter(), formats.iter()) {
+            let type_name = format!("{name}{}", self.type_name(*label));
+            let field_type = self.format_repr(&type_name, format)?;
+            let read_expr = self.format_read(&type_name, format)?;
+            let local_name = self.local_name(*label);
+            self.local_names.push(local_name.clone());
+            fields.push((self.field_name(*label), field_type, local_name, read_expr));
+        }
+        self.local_names.truncate(initial_local_len);
+
+        writeln!(self.output).unwrap();
+        for line in doc {
+            let interner = self.interner.borrow();
+            writeln!(self.output, "///{}", interner.resolve(*line).unwrap()).unwrap();
+        }
+        writeln!(self.output, "#[derive(Debug)]").unwrap();
+        writeln!(self.output, "pub struct {name} {{").unwrap();
+        for (field_name, field_type, _, _) in &fields {
+            writeln!(self.output, "    pub {field_name}: {field_type},").unwrap();
+        }
+        writeln!(self.output, "}}").unwrap();
+
+        writeln!(self.output).unwrap();
+        writeln!(self.output, "impl {name} {{").unwrap();
+        writeln!(
+            self.output,
+            "    pub fn read(reader: &mut impl Read) -> io::Result<{name}> {{"
+        )
+        .unwrap();
+        if overlap {
+            writeln!(
+                self.output,
+                "        let mut reader = rt::Overlap::new(reader);"
+            )
+            .unwrap();
+        }
+        for (_, _, local_name, read_expr) in &fields {
+            match overlap {
+                true => writeln!(
+                    self.output,
+                    "        let {local_name} = {{ let reader = &mut reader.replay(); {read_expr} }};"
+                )
+                .unwrap(),
+                false => writeln!(self.output, "        let {local_name} = {read_expr};").unwrap(),
+            }
+        }
+        writeln!(self.output, "        Ok({name} {{").unwrap();
+        for (field_name, _, local_name, _) in &fields {
+            match field_name == local_name {
+                true => writeln!(self.output, "            {field_name},").unwrap(),
+                false => writeln!(self.output, "            {field_name}: {local_name},").unwrap(),
+            }
+        }
+        writeln!(self.output, "        }})").unwrap();
+        writeln!(self.output, "    }}").unwrap();
+        writeln!(self.output, "}}").unwrap();
+
+        Ok(())
+    }
+
+    /// Returns the Rust type that corresponds to the representation of a
+    /// format. Matches on formats are generated as enums with the given name.
+    fn format_repr(&mut self, name: &str, format: &Term<'_>) -> Result<String, CodegenError> {
+        match format {
+            Term::ItemVar(span, level) => match self.item_names.get_level(*level) {
+                Some(Some(name)) => Ok(name.clone()),
+                _ => Err(CodegenError::UnsupportedFormat(*span)),
+            },
+            Term::Ann(_, format, _) => self.format_repr(name, format),
+            Term::FormatCond(_, _, format, _) => self.format_repr(name, format),
+            Term::ConstMatch(_, _, branches, default_branch) => {
+                let variant_names = self.variant_names(branches, default_branch);
+                let formats = (branches.iter().map(|(_, format)| format))
+                    .chain(default_branch.iter().map(|(_, format)| *format));
+
+                let mut variants = Vec::with_capacity(variant_names.len());
+                for (variant_name, format) in Iterator::zip(variant_names.iter(), formats) {
+                    if let Some(variant_name) = variant_name {
+                        let variant_type =
+                            self.format_repr(&format!("{name}{variant_name}"), format)?;
+                        variants.push((variant_name, variant_type));
+                    }
+                }
+
+                writeln!(self.output).unwrap();
+                writeln!(self.output, "#[derive(Debug)]").unwrap();
+                writeln!(self.output, "pub enum {name} {{").unwrap();
+                for (variant_name, variant_type) in variants {
+                    writeln!(self.output, "    {variant_name}({variant_type}),").unwrap();
+                }
+                writeln!(self.output, "}}").unwrap();
+
+                Ok(name.to_owned())
+            }
+            Term::Prim(span, prim) => match prim {
+                Prim::FormatU8 => Ok("u8".to_owned()),
+                Prim::FormatU16Be | Prim::FormatU16Le => Ok("u16".to_owned()),
+                Prim::FormatU24Be | Prim::FormatU24Le | Prim::FormatU32Be | Prim::FormatU32Le => {
+                    Ok("u32".to_owned())
+                }
+                Prim::FormatU64Be | Prim::FormatU64Le => Ok("u64".to_owned()),
+                Prim::FormatU128Be | Prim::FormatU128Le => Ok("u128".to_owned()),
+                Prim::FormatS8 => Ok("i8".to_owned()),
+                Prim::FormatS16Be | Prim::FormatS16Le => Ok("i16".to_owned()),
+                Prim::FormatS32Be | Prim::FormatS32Le => Ok("i32".to_owned()),
+                Prim::FormatS64Be | Prim::FormatS64Le => Ok("i64".to_owned()),
+                Prim::FormatS128Be | Prim::FormatS128Le => Ok("i128".to_owned()),
+                Prim::FormatF32Be | Prim::FormatF32Le => Ok("f32".to_owned()),
+                Prim::FormatF64Be | Prim::FormatF64Le => Ok("f64".to_owned()),
+                _ => Err(CodegenError::UnsupportedFormat(*span)),
+            },
+            Term::FunApp(..) => {
+                let (prim, args) =
+                    prim_app(format).ok_or(CodegenError::UnsupportedFormat(format.span()))?;
+                match (prim, &args[..]) {
+                    (Prim::FormatRepeatLen8, [len, elem_format])
+                    | (Prim::FormatRepeatLen16, [len, elem_format])
+                    | (Prim::FormatRepeatLen32, [len, elem_format])
+                    | (Prim::FormatRepeatLen64, [len, elem_format]) => {
+                        let elem_type = self.format_repr(name, elem_format)?;
+                        match const_len(len) {
+                            Some(len) => Ok(format!("[{elem_type}; {len}]")),
+                            // Dependent lengths are only known when reading
+                            None => Ok(format!("Vec<{elem_type}>")),
+                        }
+                    }
+                    (Prim::FormatRepeatCount, [_, elem_format])
+                    | (Prim::FormatRepeatUntilEnd, [elem_format])
+                    | (Prim::FormatLengthPrefixed, [_, elem_format]) => {
+                        Ok(format!("Vec<{}>", self.format_repr(name, elem_format)?))
+                    }
+                    (Prim::FormatLimit8, [_, format])
+                    | (Prim::FormatLimit16, [_, format])
+                    | (Prim::FormatLimit32, [_, format])
+                    | (Prim::FormatLimit64, [_, format])
+                    | (Prim::FormatDebug, [_, format]) => self.format_repr(name, format),
+                    (Prim::FormatSucceed, [r#type, _]) => self.type_repr(r#type),
+                    (Prim::FormatOptional, [_, elem_format]) => {
+                        Ok(format!("Option<{}>", self.format_repr(name, elem_format)?))
+                    }
+                    // Dependent lengths in the next format are represented
+                    // with vectors, so its representation can be generated
+                    // without knowing the data read by the first format.
+                    (Prim::FormatBind, [_, Term::FunLit(_, _, binder, next_format)]) => {
+                        self.local_names.push(self.binder_name(*binder));
+                        let next_type = self.format_repr(name, next_format);
+                        self.local_names.pop();
+                        next_type
+                    }
+                    _ => Err(CodegenError::UnsupportedFormat(format.span())),
+                }
+            }
+            _ => Err(CodegenError::UnsupportedFormat(format.span())),
+        }
+    }
+
+    /// Returns a Rust expression that reads a format from `reader`, returning
+    /// early from the enclosing function if an error was encountered.
+    fn format_read(&mut self, name: &str, format: &Term<'_>) -> Result<String, CodegenError> {
+        match format {
+            Term::ItemVar(span, level) => match self.item_names.get_level(*level) {
+                Some(Some(name)) => Ok(format!("{name}::read(reader)?")),
+                _ => Err(CodegenError::UnsupportedFormat(*span)),
+            },
+            Term::Ann(_, format, _) => self.format_read(name, format),
+            Term::FormatCond(_, binder, format, pred) => {
+                let read_expr = self.format_read(name, format)?;
+                let binder = self.local_name(*binder);
+                self.local_names.push(binder.clone());
+                let pred = self.expr(pred);
+                self.local_names.pop();
+                let pred = pred?;
+
+                Ok(format!(
+                    "{{ let {binder} = {read_expr}; \
+                    if !{pred} {{ return Err(rt::cond_failure(\"{binder}\")); }} \
+                    {binder} }}"
+                ))
+            }
+            Term::ConstMatch(_, scrut, branches, default_branch) => {
+                let variant_names = self.variant_names(branches, default_branch);
+                let mut variant_names = variant_names.iter();
+                let scrut = self.expr(scrut)?;
+                let mut arms = String::new();
+
+                for (pattern, format) in branches.iter() {
+                    let pattern = const_pattern(pattern)
+                        .ok_or(CodegenError::UnsupportedTerm(format.span()))?;
+                    match variant_names.next() {
+                        Some(Some(variant_name)) => {
+                            let read_expr =
+                                self.format_read(&format!("{name}{variant_name}"), format)?;
+                            write!(arms, "{pattern} => {name}::{variant_name}({read_expr}), ")
+                                .unwrap();
+                        }
+                        _ => write!(arms, "{pattern} => return Err(rt::no_match()), ").unwrap(),
+                    }
+                }
+
+                match default_branch {
+                    Some((binder, format)) => {
+                        let binder = match binder {
+                            Some(binder) => self.local_name(*binder),
+                            None => "_".to_owned(),
+                        };
+                        self.local_names.push(binder.clone());
+                        let read_expr = match variant_names.next() {
+                            Some(Some(variant_name)) => {
+                                let read_expr =
+                                    self.format_read(&format!("{name}{variant_name}"), format);
+                                read_expr
+                                    .map(|read_expr| format!("{name}::{variant_name}({read_expr})"))
+                            }
+                            _ => Ok("return Err(rt::no_match())".to_owned()),
+                        };
+                        self.local_names.pop();
+                        write!(arms, "{binder} => {}, ", read_expr?).unwrap();
+                    }
+                    // Boolean matches are exhaustive without a default branch
+                    None if matches!(
+                        branches.first(),
+                        Some((ConstPattern::Const(Const::Bool(_)), _))
+                    ) => {}
+                    None => write!(arms, "_ => return Err(rt::no_match()), ").unwrap(),
+                }
+
+                Ok(format!("match {scrut} {{ {arms}}}"))
+            }
+            Term::Prim(span, prim) => match prim {
+                Prim::FormatU8
+                | Prim::FormatU16Be
+                | Prim::FormatU16Le
+                | Prim::FormatU24Be
+                | Prim::FormatU24Le
+                | Prim::FormatU32Be
+                | Prim::FormatU32Le
+                | Prim::FormatU64Be
+                | Prim::FormatU64Le
+                | Prim::FormatU128Be
+                | Prim::FormatU128Le
+                | Prim::FormatS8
+                | Prim::FormatS16Be
+                | Prim::FormatS16Le
+                | Prim::FormatS32Be
+                | Prim::FormatS32Le
+                | Prim::FormatS64Be
+                | Prim::FormatS64Le
+                | Prim::FormatS128Be
+                | Prim::FormatS128Le
+                | Prim::FormatF32Be
+                | Prim::FormatF32Le
+                | Prim::FormatF64Be
+                | Prim::FormatF64Le => Ok(format!("rt::read_{}(reader)?", prim.name())),
+                _ => Err(CodegenError::UnsupportedFormat(*span)),
+            },
+            Term::FunApp(..) => {
+                let (prim, args) =
+                    prim_app(format).ok_or(CodegenError::UnsupportedFormat(format.span()))?;
+                match (prim, &args[..]) {
+                    (Prim::FormatRepeatLen8, [len, elem_format])
+                    | (Prim::FormatRepeatLen16, [len, elem_format])
+                    | (Prim::FormatRepeatLen32, [len, elem_format])
+                    | (Prim::FormatRepeatLen64, [len, elem_format]) => {
+                        let read_elem = self.format_read(name, elem_format)?;
+                        let read_elem = format!("|reader| Ok({read_elem})");
+                        match const_len(len) {
+                            Some(_) => Ok(format!("rt::read_array(reader, {read_elem})?")),
+                            None => {
+                                let len = self.expr(len)?;
+                                Ok(format!(
+                                    "rt::read_vec(reader, u64::from({len}), {read_elem})?"
+                                ))
+                            }
+                        }
+                    }
+                    (Prim::FormatRepeatCount, [len, elem_format]) => {
+                        let len = self.expr(len)?;
+                        let read_elem = self.format_read(name, elem_format)?;
+                        Ok(format!(
+                            "rt::read_vec(reader, {len}, |reader| Ok({read_elem}))?"
+                        ))
+                    }
+                    (Prim::FormatRepeatUntilEnd, [elem_format]) => {
+                        let read_elem = self.format_read(name, elem_format)?;
+                        Ok(format!(
+                            "rt::read_until_end(reader, |reader| Ok({read_elem}))?"
+                        ))
+                    }
+                    (Prim::FormatLengthPrefixed, [len_format, elem_format]) => {
+                        let read_len = self.format_read(name, len_format)?;
+                        let read_elem = self.format_read(name, elem_format)?;
+                        Ok(format!(
+                            "{{ let len_prefix = {read_len}; \
+                            rt::read_vec(reader, u64::from(len_prefix), |reader| Ok({read_elem}))? }}"
+                        ))
+                    }
+                    (Prim::FormatSucceed, [_, expr]) => self.expr(expr),
+                    // Debug logging is only supported by the interpreter.
+                    (Prim::FormatDebug, [_, format]) => self.format_read(name, format),
+                    (Prim::FormatOptional, [cond, elem_format]) => {
+                        let cond = self.expr(cond)?;
+                        let read_elem = self.format_read(name, elem_format)?;
+                        Ok(format!("if {cond} {{ Some({read_elem}) }} else {{ None }}"))
+                    }
+                    (Prim::FormatBind, [format, Term::FunLit(_, _, binder, next_format)]) => {
+                        let read_expr = self.format_read(name, format)?;
+                        let binder = self.binder_name(*binder);
+                        self.local_names.push(binder.clone());
+                        let read_next = self.format_read(name, next_format);
+                        self.local_names.pop();
+                        let read_next = read_next?;
+
+                        Ok(format!("{{ let {binder} = {read_expr}; {read_next} }}"))
+                    }
+                    // Limits are not supported, as formats are read from a
+                    // stream that can not be rewound.
+                    _ => Err(CodegenError::UnsupportedFormat(format.span())),
+                }
+            }
+            _ => Err(CodegenError::UnsupportedFormat(format.span())),
+        }
+    }
+
+    /// Returns the names of the enum variants that are generated for each
+    /// branch of a match on formats, followed by the default branch, if one
+    /// is present. Branches that always fail are not given a variant.
+    ///
+    /// Variants are named after the format items that they contain, falling
+    /// back to numbered variants if this would result in duplicate names.
+    fn variant_names(
+        &self,
+        branches: &[(ConstPattern, Term<'_>)],
+        default_branch: &Option<(Option<StringId>, &Term<'_>)>,
+    ) -> Vec<Option<String>> {
+        let formats = (branches.iter().map(|(_, format)| format))
+            .chain(default_branch.iter().map(|(_, format)| *format));
+
+        let variant_names = formats
+            .clone()
+            .enumerate()
+            .map(|(index, format)| match format {
+                Term::Prim(_, Prim::FormatFail) => None,
+                Term::ItemVar(_, level) => match self.item_names.get_level(*level) {
+                    Some(Some(name)) => Some(name.clone()),
+                    _ => Some(format!("Variant{index}")),
+                },
+                _ => Some(format!("Variant{index}")),
+            })
+            .collect::<Vec<_>>();
+
+        let is_unique = (variant_names.iter().enumerate())
+            .all(|(index, name)| name.is_none() || !variant_names[..index].contains(name));
+
+        match is_unique {
+            true => variant_names,
+            false => (formats.enumerate())
+                .map(|(index, format)| match format {
+                    Term::Prim(_, Prim::FormatFail) => None,
+                    _ => Some(format!("Variant{index}")),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns a Rust expression that corresponds to a term.
+    fn expr(&self, term: &Term<'_>) -> Result<String, CodegenError> {
+        match term {
+            Term::LocalVar(span, var) => match self.local_names.get_index(*var) {
+                Some(name) => Ok(name.clone()),
+                None => Err(CodegenError::UnsupportedTerm(*span)),
+            },
+            Term::Ann(_, expr, _) => self.expr(expr),
+            Term::ConstLit(span, r#const) => match r#const {
+                Const::Bool(b) => Ok(b.to_string()),
+                Const::U8(num, _) => Ok(format!("{num}u8")),
+                Const::U16(num, _) => Ok(format!("{num}u16")),
+                Const::U32(num, _) => Ok(format!("{num}u32")),
+                Const::U64(num, _) => Ok(format!("{num}u64")),
+                Const::U128(num, _) => Ok(format!("{num}u128")),
+                Const::S8(num) => Ok(format!("({num}i8)")),
+                Const::S16(num) => Ok(format!("({num}i16)")),
+                Const::S32(num) => Ok(format!("({num}i32)")),
+                Const::S64(num) => Ok(format!("({num}i64)")),
+                Const::S128(num) => Ok(format!("({num}i128)")),
+                Const::F32(num) => Ok(format!("f32::from_bits({}u32)", num.to_bits())),
+                Const::F64(num) => Ok(format!("f64::from_bits({}u64)", num.to_bits())),
+                Const::Pos(pos) => Ok(format!("{pos}u64")),
+                Const::Ref(_) | Const::String(_) => Err(CodegenError::UnsupportedTerm(*span)),
+            },
+            Term::StringLit(span, _) => Err(CodegenError::UnsupportedTerm(*span)),
+            Term::FunApp(..) => {
+                use crate::core::Prim::*;
+
+                let (prim, args) =
+                    prim_app(term).ok_or(CodegenError::UnsupportedTerm(term.span()))?;
+                match (prim, &args[..]) {
+                    (BoolNot | U8Not | U16Not | U32Not | U64Not | U128Not, [x]) => {
+                        Ok(format!("(!{})", self.expr(x)?))
+                    }
+                    (S8Neg | S16Neg | S32Neg | S64Neg | S128Neg, [x]) => {
+                        Ok(format!("(-{})", self.expr(x)?))
+                    }
+                    (prim, [x, y]) => {
+                        let (x, y) = (self.expr(x)?, self.expr(y)?);
+                        let checked =
+                            |method| format!("{x}.{method}({y}).ok_or_else(rt::overflow)?");
+                        let checked_shift = |method| {
+                            format!("{x}.{method}(u32::from({y})).ok_or_else(rt::overflow)?")
+                        };
+                        match prim {
+                            BoolEq | StringEq | U8Eq | U16Eq | U32Eq | U64Eq | U128Eq | S8Eq
+                            | S16Eq | S32Eq | S64Eq | S128Eq => Ok(format!("({x} == {y})")),
+                            BoolNeq | StringNeq | U8Neq | U16Neq | U32Neq | U64Neq | U128Neq
+                            | S8Neq | S16Neq | S32Neq | S64Neq | S128Neq => {
+                                Ok(format!("({x} != {y})"))
+                            }
+                            U8Gt | U16Gt | U32Gt | U64Gt | U128Gt | S8Gt | S16Gt | S32Gt
+                            | S64Gt | S128Gt => Ok(format!("({x} > {y})")),
+                            U8Lt | U16Lt | U32Lt | U64Lt | U128Lt | S8Lt | S16Lt | S32Lt
+                            | S64Lt | S128Lt => Ok(format!("({x} < {y})")),
+                            U8Gte | U16Gte | U32Gte | U64Gte | U128Gte | S8Gte | S16Gte
+                            | S32Gte | S64Gte | S128Gte => Ok(format!("({x} >= {y})")),
+                            U8Lte | U16Lte | U32Lte | U64Lte | U128Lte | S8Lte | S16Lte
+                            | S32Lte | S64Lte | S128Lte => Ok(format!("({x} <= {y})")),
+                            BoolAnd | U8And | U16And | U32And | U64And | U128And => {
+                                Ok(format!("({x} & {y})"))
+                            }
+                            BoolOr | U8Or | U16Or | U32Or | U64Or | U128Or => {
+                                Ok(format!("({x} | {y})"))
+                            }
+                            BoolXor | U8Xor | U16Xor | U32Xor | U64Xor | U128Xor => {
+                                Ok(format!("({x} ^ {y})"))
+                            }
+                            U8Add | U16Add | U32Add | U64Add | U128Add | S8Add | S16Add
+                            | S32Add | S64Add | S128Add => Ok(checked("checked_add")),
+                            U8Sub | U16Sub | U32Sub | U64Sub | U128Sub | S8Sub | S16Sub
+                            | S32Sub | S64Sub | S128Sub | PosSub => Ok(checked("checked_sub")),
+                            U8Mul | U16Mul | U32Mul | U64Mul | U128Mul | S8Mul | S16Mul
+                            | S32Mul | S64Mul | S128Mul => Ok(checked("checked_mul")),
+                            U8Div | U16Div | U32Div | U64Div | U128Div | S8Div | S16Div
+                            | S32Div | S64Div | S128Div => Ok(checked("checked_div")),
+                            U8Shl | U16Shl | U32Shl | U64Shl | U128Shl => {
+                                Ok(checked_shift("checked_shl"))
+                            }
+                            U8Shr | U16Shr | U32Shr | U64Shr | U128Shr => {
+                                Ok(checked_shift("checked_shr"))
+                            }
+                            _ => Err(CodegenError::UnsupportedTerm(term.span())),
+                        }
+                    }
+                    _ => Err(CodegenError::UnsupportedTerm(term.span())),
+                }
+            }
+            _ => Err(CodegenError::UnsupportedTerm(term.span())),
+        }
+    }
+
+    /// Returns the Rust type that corresponds to a type.
+    fn type_repr(&self, r#type: &Term<'_>) -> Result<String, CodegenError> {
+        match r#type {
+            Term::Prim(span, prim) => match prim {
+                Prim::BoolType => Ok("bool".to_owned()),
+                Prim::U8Type => Ok("u8".to_owned()),
+                Prim::U16Type => Ok("u16".to_owned()),
+                Prim::U32Type => Ok("u32".to_owned()),
+                Prim::U64Type => Ok("u64".to_owned()),
+                Prim::U128Type => Ok("u128".to_owned()),
+                Prim::S8Type => Ok("i8".to_owned()),
+                Prim::S16Type => Ok("i16".to_owned()),
+                Prim::S32Type => Ok("i32".to_owned()),
+                Prim::S64Type => Ok("i64".to_owned()),
+                Prim::S128Type => Ok("i128".to_owned()),
+                Prim::F32Type => Ok("f32".to_owned()),
+                Prim::F64Type => Ok("f64".to_owned()),
+                Prim::PosType => Ok("u64".to_owned()),
+                _ => Err(CodegenError::UnsupportedType(*span)),
+            },
+            Term::FunApp(..) => {
+                let (prim, args) =
+                    prim_app(r#type).ok_or(CodegenError::UnsupportedType(r#type.span()))?;
+                match (prim, &args[..]) {
+                    (Prim::ArrayType, [elem_type]) => {
+                        Ok(format!("Vec<{}>", self.type_repr(elem_type)?))
+                    }
+                    (Prim::Array8Type, [len, elem_type])
+                    | (Prim::Array16Type, [len, elem_type])
+                    | (Prim::Array32Type, [len, elem_type])
+                    | (Prim::Array64Type, [len, elem_type]) => {
+                        let elem_type = self.type_repr(elem_type)?;
+                        match const_len(len) {
+                            Some(len) => Ok(format!("[{elem_type}; {len}]")),
+                            None => Ok(format!("Vec<{elem_type}>")),
+                        }
+                    }
+                    (Prim::OptionType, [elem_type]) => {
+                        Ok(format!("Option<{}>", self.type_repr(elem_type)?))
+                    }
+                    _ => Err(CodegenError::UnsupportedType(r#type.span())),
+                }
+            }
+            _ => Err(CodegenError::UnsupportedType(r#type.span())),
+        }
+    }
+}
+
+/// Split a term into a primitive applied to a series of arguments.
+fn prim_app<'term, 'arena>(term: &'term Term<'arena>) -> Option<(Prim, Vec<&'term Term<'arena>>)> {
+    fn go<'term, 'arena>(
+        term: &'term Term<'arena>,
+        args: &mut Vec<&'term Term<'arena>>,
+    ) -> Option<Prim> {
+        match term {
+            Term::Prim(_, prim) => Some(*prim),
+            Term::FunApp(_, _, head_expr, arg_expr) => {
+                let prim = go(head_expr, args)?;
+                args.push(arg_expr);
+                Some(prim)
+            }
+            _ => None,
+        }
+    }
+
+    let mut args = Vec::new();
+    let prim = go(term, &mut args)?;
+    Some((prim, args))
+}
+
+/// Returns a Rust pattern that matches a constant, if one exists.
+fn const_pattern(pattern: &ConstPattern) -> Option<String> {
+    match pattern {
+        ConstPattern::Const(r#const) => const_lit_pattern(r#const),
+        ConstPattern::Range(start, end) => Some(format!(
+            "{}..={}",
+            const_lit_pattern(start)?,
+            const_lit_pattern(end)?
+        )),
+    }
+}
+
+fn const_lit_pattern(r#const: &Const) -> Option<String> {
+    match r#const {
+        Const::Bool(b) => Some(b.to_string()),
+        Const::U8(num, _) => Some(format!("{num}u8")),
+        Const::U16(num, _) => Some(format!("{num}u16")),
+        Const::U32(num, _) => Some(format!("{num}u32")),
+        Const::U64(num, _) => Some(format!("{num}u64")),
+        Const::U128(num, _) => Some(format!("{num}u128")),
+        Const::S8(num) => Some(format!("{num}i8")),
+        Const::S16(num) => Some(format!("{num}i16")),
+        Const::S32(num) => Some(format!("{num}i32")),
+        Const::S64(num) => Some(format!("{num}i64")),
+        Const::S128(num) => Some(format!("{num}i128")),
+        // Floating point, position and string literals can not be matched on
+        Const::F32(_) | Const::F64(_) | Const::Pos(_) | Const::Ref(_) | Const::String(_) => None,
+    }
+}
+
+/// Returns the length of an array, if it is a constant.
+fn const_len(len: &Term<'_>) -> Option<u64> {
+    match len {
+        Term::ConstLit(_, Const::U8(len, _)) => Some(u64::from(*len)),
+        Term::ConstLit(_, Const::U16(len, _)) => Some(u64::from(*len)),
+        Term::ConstLit(_, Const::U32(len, _)) => Some(u64::from(*len)),
+        Term::ConstLit(_, Const::U64(len, _)) => Some(*len),
+        _ => None,
+    }
+}
+
+/// Runtime support functions that are included in the generated code.
+const RUNTIME: &str = r#"#[allow(dead_code)]
+mod rt {
+    use std::io::{self, Read};
+
+    macro_rules! read_num {
+        ($($read:ident: $T:ty = $from_bytes:ident),* $(,)?) => {
+            $(pub fn $read(reader: &mut impl Read) -> io::Result<$T> {
+                let mut bytes = [0; std::mem::size_of::<$T>()];
+                reader.read_exact(&mut bytes)?;
+                Ok(<$T>::$from_bytes(bytes))
+            })*
+        };
+    }
+
+    read_num! {
+        read_u8: u8 = from_be_bytes,
+        read_u16be: u16 = from_be_bytes,
+        read_u16le: u16 = from_le_bytes,
+        read_u32be: u32 = from_be_bytes,
+        read_u32le: u32 = from_le_bytes,
+        read_u64be: u64 = from_be_bytes,
+        read_u64le: u64 = from_le_bytes,
+        read_u128be: u128 = from_be_bytes,
+        read_u128le: u128 = from_le_bytes,
+        read_s8: i8 = from_be_bytes,
+        read_s16be: i16 = from_be_bytes,
+        read_s16le: i16 = from_le_bytes,
+        read_s32be: i32 = from_be_bytes,
+        read_s32le: i32 = from_le_bytes,
+        read_s64be: i64 = from_be_bytes,
+        read_s64le: i64 = from_le_bytes,
+        read_s128be: i128 = from_be_bytes,
+        read_s128le: i128 = from_le_bytes,
+        read_f32be: f32 = from_be_bytes,
+        read_f32le: f32 = from_le_bytes,
+        read_f64be: f64 = from_be_bytes,
+        read_f64le: f64 = from_le_bytes,
+    }
+
+    pub fn read_u24be(reader: &mut impl Read) -> io::Result<u32> {
+        let mut bytes = [0; 3];
+        reader.read_exact(&mut bytes)?;
+        Ok(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    pub fn read_u24le(reader: &mut impl Read) -> io::Result<u32> {
+        let mut bytes = [0; 3];
+        reader.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    }
+
+    pub fn read_vec<R: Read, T>(
+        reader: &mut R,
+        len: u64,
+        mut read_elem: impl FnMut(&mut R) -> io::Result<T>,
+    ) -> io::Result<Vec<T>> {
+        (0..len).map(|_| read_elem(reader)).collect()
+    }
+
+    pub fn read_array<R: Read, T, const N: usize>(
+        reader: &mut R,
+        read_elem: impl FnMut(&mut R) -> io::Result<T>,
+    ) -> io::Result<[T; N]> {
+        let elems = read_vec(reader, N as u64, read_elem)?;
+        Ok(elems.try_into().unwrap_or_else(|_| unreachable!()))
+    }
+
+    pub fn read_until_end<T>(
+        reader: &mut impl Read,
+        mut read_elem: impl FnMut(&mut &[u8]) -> io::Result<T>,
+    ) -> io::Result<Vec<T>> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut data = &data[..];
+        let mut elems = Vec::new();
+        while !data.is_empty() {
+            match read_elem(&mut data) {
+                Ok(elem) => elems.push(elem),
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(elems)
+    }
+
+    /// Buffers the data read by the fields of an overlap format, so that each
+    /// field can be read from the same position.
+    pub struct Overlap<R> {
+        reader: R,
+        data: Vec<u8>,
+    }
+
+    impl<R: Read> Overlap<R> {
+        pub fn new(reader: R) -> Overlap<R> {
+            Overlap {
+                reader,
+                data: Vec::new(),
+            }
+        }
+
+        /// Read from the start of the overlap, replaying the buffered data
+        /// before reading any more data from the underlying reader.
+        pub fn replay(&mut self) -> Replay<'_, R> {
+            Replay {
+                overlap: self,
+                pos: 0,
+            }
+        }
+    }
+
+    pub struct Replay<'a, R> {
+        overlap: &'a mut Overlap<R>,
+        pos: usize,
+    }
+
+    impl<R: Read> Read for Replay<'_, R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let overlap = &mut *self.overlap;
+            let len = match &overlap.data[self.pos..] {
+                [] => {
+                    let len = overlap.reader.read(buf)?;
+                    overlap.data.extend_from_slice(&buf[..len]);
+                    len
+                }
+                mut data => data.read(buf)?,
+            };
+            self.pos += len;
+            Ok(len)
+        }
+    }
+
+    pub fn cond_failure(name: &str) -> io::Error {
+        let message = format!("condition failed for `{name}`");
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
+    pub fn no_match() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, "no matching format")
+    }
+
+    pub fn overflow() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, "arithmetic overflow")
+    }
+}
+"#;
+
+/// Keywords that must be written as raw identifiers when used as field names.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use scoped_arena::Scope;
+
+    use super::*;
+    use crate::testing::elab_module;
+
+    const EDID_MODULE: &str = include_str!("../../../formats/edid.fathom");
+
+    fn generate(source: &str) -> String {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, _) = elab_module(&interner, &scope, source);
+        Context::new(&interner).module(&module).unwrap()
+    }
+
+    /// Compile some generated code with `rustc`, panicking on failure. Returns
+    /// the directory that the output was written to.
+    fn compile(name: &str, crate_type: &str, source: &str) -> PathBuf {
+        let dir = std::env::temp_dir();
+        let dir = dir.join(format!("fathom-codegen-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source_path = dir.join(format!("{name}.rs"));
+        std::fs::write(&source_path, source).unwrap();
+
+        let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+        let output = Command::new(rustc)
+            .args([
+                "--edition=2021",
+                "--crate-name",
+                name,
+                "--crate-type",
+                crate_type,
+            ])
+            .arg("--out-dir")
+            .arg(&dir)
+            .arg(&source_path)
+            .output()
+            .unwrap();
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(output.status.success(), "{source}\n{stderr}");
+
+        dir
+    }
+
+    /// Run a compiled binary, returning whether it exited successfully.
+    fn run(dir: &Path, name: &str, args: &[&Path]) -> bool {
+        let output = Command::new(dir.join(name)).args(args).output().unwrap();
+        output.status.success()
+    }
+
+    const POLYGON_MODULE: &str = r#"
+        def point = {
+            x <- u16le,
+            y <- u16le,
+        };
+
+        def polygon = {
+            type <- u8,
+            len <- u8 where u8_lte len 4,
+            points <- repeat_len8 len point,
+            color <- repeat_len8 3 u8,
+        };
+    "#;
+
+    #[test]
+    fn small_format_structs() {
+        let output = generate(POLYGON_MODULE);
+
+        assert!(output.contains("pub struct Point {"));
+        assert!(output.contains("pub r#type: u8,"));
+        assert!(output.contains("pub points: Vec<Point>,"));
+        assert!(output.contains("pub color: [u8; 3],"));
+
+        let dir = compile("small_format_structs", "lib", &output);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn doc_comments() {
+        let output = generate(
+            "/// A point on a plane.\n\
+             ///\n\
+             /// Coordinates are in pixels.\n\
+             def point = { x <- u16le, y <- u16le };\n",
+        );
+
+        assert!(output.contains(
+            "/// A point on a plane.\n\
+             ///\n\
+             /// Coordinates are in pixels.\n\
+             #[derive(Debug)]\n\
+             pub struct Point {"
+        ));
+    }
+
+    #[test]
+    fn small_format_read() {
+        let main = r#"
+            fn main() {
+                let data = [1, 2, 1, 0, 2, 0, 3, 0, 4, 0, 0xff, 0x80, 0x00];
+                let polygon = Polygon::read(&mut &data[..]).unwrap();
+                assert_eq!(polygon.r#type, 1);
+                assert_eq!(polygon.points.len(), 2);
+                assert_eq!((polygon.points[1].x, polygon.points[1].y), (3, 4));
+                assert_eq!(polygon.color, [0xff, 0x80, 0x00]);
+
+                // Too many points
+                let data = [1, 5, 1, 0, 2, 0, 3, 0, 4, 0, 0xff, 0x80, 0x00];
+                assert!(Polygon::read(&mut &data[..]).is_err());
+                // Unexpected end of input
+                assert!(Polygon::read(&mut &data[..4]).is_err());
+            }
+        "#;
+        let output = generate(POLYGON_MODULE) + main;
+
+        let dir = compile("small_format_read", "bin", &output);
+        assert!(run(&dir, "small_format_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn length_prefixed_read() {
+        let module = r#"
+            def name = {
+                bytes <- length_prefixed u16le u8,
+            };
+        "#;
+        let main = r#"
+            fn main() {
+                let data = [3, 0, b'a', b'b', b'c'];
+                let name = Name::read(&mut &data[..]).unwrap();
+                assert_eq!(name.bytes, b"abc");
+
+                // Unexpected end of input
+                assert!(Name::read(&mut &data[..4]).is_err());
+            }
+        "#;
+        let output = generate(module) + main;
+
+        let dir = compile("length_prefixed_read", "bin", &output);
+        assert!(run(&dir, "length_prefixed_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn optional_read() {
+        let module = r#"
+            def flagged = {
+                has_extra <- u8,
+                extra <- optional (has_extra == (1 : U8)) u16be,
+            };
+        "#;
+        let main = r#"
+            fn main() {
+                let flagged = Flagged::read(&mut &[1, 0x12, 0x34][..]).unwrap();
+                assert_eq!(flagged.extra, Some(0x1234));
+                let flagged = Flagged::read(&mut &[0][..]).unwrap();
+                assert_eq!(flagged.extra, None);
+            }
+        "#;
+        let output = generate(module) + main;
+
+        let dir = compile("optional_read", "bin", &output);
+        assert!(run(&dir, "optional_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn overlap_read() {
+        let module = r#"
+            def word = overlap {
+                value <- u32be,
+                halves <- repeat_len8 2 u16le,
+                bytes <- repeat_len8 4 u8,
+            };
+        "#;
+        let main = r#"
+            fn main() {
+                let data = [0x12, 0x34, 0x56, 0x78, 0x9a];
+                let mut reader = &data[..];
+                let word = Word::read(&mut reader).unwrap();
+                assert_eq!(word.value, 0x12345678);
+                assert_eq!(word.halves, [0x3412, 0x7856]);
+                assert_eq!(word.bytes, [0x12, 0x34, 0x56, 0x78]);
+                // The reader is left at the end of the overlap
+                assert_eq!(reader, [0x9a]);
+
+                // Unexpected end of input
+                assert!(Word::read(&mut &data[..2]).is_err());
+            }
+        "#;
+        let output = generate(module) + main;
+        assert!(output.contains("pub struct Word {"));
+        assert!(output.contains("pub bytes: [u8; 4],"));
+
+        let dir = compile("overlap_read", "bin", &output);
+        assert!(run(&dir, "overlap_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn bind_read() {
+        let module = r#"
+            def message = {
+                payload <- bind u8 (fun len => repeat_len8 len u8),
+                tag <- bind u8 (fun _ => u16be),
+            };
+        "#;
+        let main = r#"
+            fn main() {
+                let message = Message::read(&mut &[2, 0xa, 0xb, 0, 0x12, 0x34][..]).unwrap();
+                assert_eq!(message.payload, [0xa, 0xb]);
+                assert_eq!(message.tag, 0x1234);
+
+                // Unexpected end of input
+                assert!(Message::read(&mut &[3, 0xa, 0xb][..]).is_err());
+            }
+        "#;
+        let output = generate(module) + main;
+        assert!(output.contains("pub payload: Vec<u8>,"));
+        assert!(output.contains("pub tag: u16,"));
+
+        let dir = compile("bind_read", "bin", &output);
+        assert!(run(&dir, "bind_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tagged_union_read() {
+        let module = r#"
+            def circle = {
+                radius <- u8,
+            };
+
+            def rect = {
+                width <- u8,
+                height <- u8,
+            };
+
+            def shape = {
+                tag <- u8,
+                data <- match tag {
+                    0 => circle,
+                    1 => rect,
+                    _ => fail,
+                },
+            };
+        "#;
+        let main = r#"
+            fn main() {
+                let shape = Shape::read(&mut &[0, 3][..]).unwrap();
+                assert!(matches!(shape.data, ShapeData::Circle(Circle { radius: 3 })));
+                let shape = Shape::read(&mut &[1, 4, 5][..]).unwrap();
+                assert!(matches!(shape.data, ShapeData::Rect(Rect { width: 4, height: 5 })));
+                assert!(Shape::read(&mut &[2, 3][..]).is_err());
+            }
+        "#;
+        let output = generate(module);
+        assert!(output.contains("pub enum ShapeData {"));
+        let output = output + main;
+
+        let dir = compile("tagged_union_read", "bin", &output);
+        assert!(run(&dir, "tagged_union_read", &[]));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn edid_read() {
+        let main = r#"
+            fn main() {
+                let data = std::fs::read(std::env::args().nth(1).unwrap()).unwrap();
+                let main = Main::read(&mut &data[..]).unwrap();
+                assert_eq!(main.header.magic, 0x00ffffffffffff00);
+            }
+        "#;
+        let output = generate(EDID_MODULE) + main;
+
+        let data_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../formats/data/edid");
+        let dir = compile("edid_read", "bin", &output);
+        assert!(run(
+            &dir,
+            "edid_read",
+            &[&data_dir.join("dell-P2415Q.edid")]
+        ));
+        assert!(!run(
+            &dir,
+            "edid_read",
+            &[&data_dir.join("invalid/wrong-magic.edid")]
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -30,7 +30,7 @@ use std::cell::RefCell;
 
 use pretty::RcDoc;
 
-use crate::core::{Item, Module, Plicity, Term};
+use crate::core::{ConstPattern, Item, Module, Plicity, Term};
 use crate::source::{StringId, StringInterner};
 use crate::surface::lexer::is_keyword;
 
@@ -78,6 +78,7 @@ impl<'interner, 'arena> Context<'interner> {
                 label,
                 r#type,
                 expr,
+                ..
             } => RcDoc::concat([
                 RcDoc::text("def"),
                 RcDoc::space(),
@@ -268,7 +269,9 @@ impl<'interner, 'arena> Context<'interner> {
                 RcDoc::text(","),
                 RcDoc::text("]"),
             ),
+            Term::ElidedElems(_, len) => RcDoc::text(format!("... ({len} more)")),
             Term::ConstLit(_, const_) => RcDoc::text(format!("{const_:?}")),
+            Term::StringLit(_, string) => RcDoc::text(format!("{string:?}")),
             Term::FormatRecord(_, labels, formats) => self.sequence(
                 RcDoc::text("{"),
                 labels
@@ -315,7 +318,10 @@ impl<'interner, 'arena> Context<'interner> {
                     .iter()
                     .map(|(pattern, body_expr)| {
                         RcDoc::concat([
-                            RcDoc::text(format!("{pattern:?}")),
+                            RcDoc::text(match pattern {
+                                ConstPattern::Const(r#const) => format!("{:?}", r#const),
+                                ConstPattern::Range(start, end) => format!("{start:?}..={end:?}"),
+                            }),
                             RcDoc::space(),
                             RcDoc::text("=>"),
                             RcDoc::space(),
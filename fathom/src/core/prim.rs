@@ -4,7 +4,7 @@ use std::sync::Arc;
 use fxhash::FxHashMap;
 use scoped_arena::Scope;
 
-use crate::core::semantics::{ArcValue, Elim, ElimEnv, Head, Value};
+use crate::core::semantics::{ArcValue, Elim, ElimEnv, Head, Telescope, Value};
 use crate::core::{self, Const, Plicity, Prim, UIntStyle};
 use crate::env::{self, SharedEnv, UniqueEnv};
 use crate::source::{Span, Spanned, StringId, StringInterner};
@@ -14,6 +14,8 @@ pub struct Env<'arena> {
     // TODO: Provide a way to reflect these as top-level items in a module for
     //       improved documentation and error messages.
     entries: FxHashMap<StringId, (Prim, ArcValue<'arena>)>,
+    /// The types of the primitives in the default environment.
+    types: FxHashMap<Prim, ArcValue<'arena>>,
 }
 
 impl<'arena> Env<'arena> {
@@ -24,6 +26,36 @@ impl<'arena> Env<'arena> {
         Some((*prim, r#type))
     }
 
+    /// Define a name for a primitive, replacing any primitive that was
+    /// previously defined with the same name. This allows embedders to add
+    /// their own names for primitives, for example as aliases.
+    ///
+    /// The primitive is given the type from its [default] definition. Returns
+    /// `false` without defining the name if there is no such definition.
+    ///
+    /// [default]: Env::default
+    pub fn define_name(&mut self, name: StringId, prim: Prim) -> bool {
+        match self.types.get(&prim) {
+            Some(r#type) => {
+                self.entries.insert(name, (prim, r#type.clone()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The names of the primitives in the environment.
+    pub fn names(&self) -> impl Iterator<Item = (StringId, Prim)> + '_ {
+        self.entries.iter().map(|(name, (prim, _))| (*name, *prim))
+    }
+
+    /// Remove a primitive name, returning the primitive it referred to.
+    pub fn remove_name(&mut self, name: StringId) -> Option<(Prim, ArcValue<'arena>)> {
+        self.entries.remove(&name)
+    }
+
+    /// The primitive environment used by default, where each primitive is
+    /// named by [`Prim::name`].
     pub fn default(
         interner: &RefCell<StringInterner>,
         scope: &'arena Scope<'arena>,
@@ -37,6 +69,8 @@ impl<'arena> Env<'arena> {
         const VAR1: Term<'_> = Term::LocalVar(Span::Empty, env::Index::last().prev());
         const VAR2: Term<'_> = Term::LocalVar(Span::Empty, env::Index::last().prev().prev());
         const VAR3: Term<'_> = Term::LocalVar(Span::Empty, env::Index::last().prev().prev().prev());
+        const VAR4: Term<'_> =
+            Term::LocalVar(Span::Empty, env::Index::last().prev().prev().prev().prev());
         const UNIVERSE: Term<'_> = Term::Universe(Span::Empty);
         const VOID_TYPE: Term<'_> = Term::Prim(Span::Empty, VoidType);
         const FORMAT_TYPE: Term<'_> = Term::Prim(Span::Empty, FormatType);
@@ -45,15 +79,19 @@ impl<'arena> Env<'arena> {
         const U16_TYPE: Term<'_> = Term::Prim(Span::Empty, U16Type);
         const U32_TYPE: Term<'_> = Term::Prim(Span::Empty, U32Type);
         const U64_TYPE: Term<'_> = Term::Prim(Span::Empty, U64Type);
+        const U128_TYPE: Term<'_> = Term::Prim(Span::Empty, U128Type);
         const S8_TYPE: Term<'_> = Term::Prim(Span::Empty, S8Type);
         const S16_TYPE: Term<'_> = Term::Prim(Span::Empty, S16Type);
         const S32_TYPE: Term<'_> = Term::Prim(Span::Empty, S32Type);
         const S64_TYPE: Term<'_> = Term::Prim(Span::Empty, S64Type);
+        const S128_TYPE: Term<'_> = Term::Prim(Span::Empty, S128Type);
+        const ARRAY_TYPE: Term<'_> = Term::Prim(Span::Empty, ArrayType);
         const ARRAY8_TYPE: Term<'_> = Term::Prim(Span::Empty, Array8Type);
         const ARRAY16_TYPE: Term<'_> = Term::Prim(Span::Empty, Array16Type);
         const ARRAY32_TYPE: Term<'_> = Term::Prim(Span::Empty, Array32Type);
         const ARRAY64_TYPE: Term<'_> = Term::Prim(Span::Empty, Array64Type);
         const POS_TYPE: Term<'_> = Term::Prim(Span::Empty, PosType);
+        const STRING_TYPE: Term<'_> = Term::Prim(Span::Empty, StringType);
 
         let mut env = EnvBuilder::new(interner, scope);
 
@@ -63,12 +101,15 @@ impl<'arena> Env<'arena> {
         env.define_prim(U16Type, &UNIVERSE);
         env.define_prim(U32Type, &UNIVERSE);
         env.define_prim(U64Type, &UNIVERSE);
+        env.define_prim(U128Type, &UNIVERSE);
         env.define_prim(S8Type, &UNIVERSE);
         env.define_prim(S16Type, &UNIVERSE);
         env.define_prim(S32Type, &UNIVERSE);
         env.define_prim(S64Type, &UNIVERSE);
+        env.define_prim(S128Type, &UNIVERSE);
         env.define_prim(F32Type, &UNIVERSE);
         env.define_prim(F64Type, &UNIVERSE);
+        env.define_prim(StringType, &UNIVERSE);
         env.define_prim_fun(OptionType, [&UNIVERSE], &UNIVERSE);
         env.define_prim_fun(ArrayType, [&UNIVERSE], &UNIVERSE);
         env.define_prim_fun(Array8Type, [&U8_TYPE, &UNIVERSE], &UNIVERSE);
@@ -82,10 +123,14 @@ impl<'arena> Env<'arena> {
         env.define_prim(FormatU8, &FORMAT_TYPE);
         env.define_prim(FormatU16Be, &FORMAT_TYPE);
         env.define_prim(FormatU16Le, &FORMAT_TYPE);
+        env.define_prim(FormatU24Be, &FORMAT_TYPE);
+        env.define_prim(FormatU24Le, &FORMAT_TYPE);
         env.define_prim(FormatU32Be, &FORMAT_TYPE);
         env.define_prim(FormatU32Le, &FORMAT_TYPE);
         env.define_prim(FormatU64Be, &FORMAT_TYPE);
         env.define_prim(FormatU64Le, &FORMAT_TYPE);
+        env.define_prim(FormatU128Be, &FORMAT_TYPE);
+        env.define_prim(FormatU128Le, &FORMAT_TYPE);
         env.define_prim(FormatS8, &FORMAT_TYPE);
         env.define_prim(FormatS16Be, &FORMAT_TYPE);
         env.define_prim(FormatS16Le, &FORMAT_TYPE);
@@ -93,6 +138,8 @@ impl<'arena> Env<'arena> {
         env.define_prim(FormatS32Le, &FORMAT_TYPE);
         env.define_prim(FormatS64Be, &FORMAT_TYPE);
         env.define_prim(FormatS64Le, &FORMAT_TYPE);
+        env.define_prim(FormatS128Be, &FORMAT_TYPE);
+        env.define_prim(FormatS128Le, &FORMAT_TYPE);
         env.define_prim(FormatF32Be, &FORMAT_TYPE);
         env.define_prim(FormatF32Le, &FORMAT_TYPE);
         env.define_prim(FormatF64Be, &FORMAT_TYPE);
@@ -101,7 +148,14 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(FormatRepeatLen16, [&U16_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen32, [&U32_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatLen64, [&U64_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatCount, [&U64_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatRepeatUntilEnd, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatRepeatUpTo, [&U64_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(
+            FormatLengthPrefixed,
+            [&FORMAT_TYPE, &FORMAT_TYPE],
+            &FORMAT_TYPE,
+        );
         env.define_prim_fun(FormatLimit8, [&U8_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatLimit16, [&U16_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim_fun(FormatLimit32, [&U32_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
@@ -128,7 +182,12 @@ impl<'arena> Env<'arena> {
                 ),
             ),
         );
+        env.define_prim_fun(FormatUtf8, [&U64_TYPE], &FORMAT_TYPE);
+        env.define_prim_fun(FormatReserved, [&U64_TYPE], &FORMAT_TYPE);
         env.define_prim(FormatStreamPos, &FORMAT_TYPE);
+        env.define_prim_fun(FormatWithBase, [&FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim(FormatRelPos, &FORMAT_TYPE);
+        env.define_prim_fun(FormatDebug, [&STRING_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
         env.define_prim(
             FormatSucceed,
             &core::Term::FunType(
@@ -163,6 +222,72 @@ impl<'arena> Env<'arena> {
                 ),
             ),
         );
+        env.define_prim_fun(FormatOptional, [&BOOL_TYPE, &FORMAT_TYPE], &FORMAT_TYPE);
+        env.define_prim(
+            FormatMap,
+            // fun (@B : Type) (f : Format) -> (Repr f   -> B  ) -> Format
+            // fun (@B : Type) (f : Format) -> (Repr f@0 -> B@2) -> Format
+            scope.to_scope(core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("B"),
+                &UNIVERSE,
+                scope.to_scope(core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    env.name("f"),
+                    &FORMAT_TYPE,
+                    &Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        None,
+                        // Repr f@0 -> B@2
+                        &Term::FunType(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            None,
+                            &Term::FunApp(
+                                Span::Empty,
+                                Plicity::Explicit,
+                                &Term::Prim(Span::Empty, FormatRepr),
+                                &VAR0,
+                            ),
+                            &VAR2,
+                        ),
+                        &FORMAT_TYPE,
+                    ),
+                )),
+            )),
+        );
+        env.define_prim(
+            FormatBind,
+            // fun (f : Format) -> (Repr f   -> Format) -> Format
+            // fun (f : Format) -> (Repr f@0 -> Format) -> Format
+            scope.to_scope(core::Term::FunType(
+                Span::Empty,
+                Plicity::Explicit,
+                env.name("f"),
+                &FORMAT_TYPE,
+                &Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        None,
+                        &Term::FunApp(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            &Term::Prim(Span::Empty, FormatRepr),
+                            &VAR0,
+                        ),
+                        &FORMAT_TYPE,
+                    ),
+                    &FORMAT_TYPE,
+                ),
+            )),
+        );
         env.define_prim_fun(FormatRepr, [&FORMAT_TYPE], &UNIVERSE);
 
         // fun (@A : Type) -> Void -> A
@@ -183,6 +308,10 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(BoolAnd, [&BOOL_TYPE, &BOOL_TYPE], &BOOL_TYPE);
         env.define_prim_fun(BoolOr, [&BOOL_TYPE, &BOOL_TYPE], &BOOL_TYPE);
         env.define_prim_fun(BoolXor, [&BOOL_TYPE, &BOOL_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(BoolToU8, [&BOOL_TYPE], &U8_TYPE);
+
+        env.define_prim_fun(StringEq, [&STRING_TYPE, &STRING_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(StringNeq, [&STRING_TYPE, &STRING_TYPE], &BOOL_TYPE);
 
         env.define_prim_fun(U8Eq, [&U8_TYPE, &U8_TYPE], &BOOL_TYPE);
         env.define_prim_fun(U8Neq, [&U8_TYPE, &U8_TYPE], &BOOL_TYPE);
@@ -200,6 +329,8 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(U8And, [&U8_TYPE, &U8_TYPE], &U8_TYPE);
         env.define_prim_fun(U8Or, [&U8_TYPE, &U8_TYPE], &U8_TYPE);
         env.define_prim_fun(U8Xor, [&U8_TYPE, &U8_TYPE], &U8_TYPE);
+        env.define_prim_fun(U8AsS8, [&U8_TYPE], &S8_TYPE);
+        env.define_prim_fun(U8ToBool, [&U8_TYPE], &BOOL_TYPE);
 
         env.define_prim_fun(U16Eq, [&U16_TYPE, &U16_TYPE], &BOOL_TYPE);
         env.define_prim_fun(U16Neq, [&U16_TYPE, &U16_TYPE], &BOOL_TYPE);
@@ -217,6 +348,7 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(U16And, [&U16_TYPE, &U16_TYPE], &U16_TYPE);
         env.define_prim_fun(U16Or, [&U16_TYPE, &U16_TYPE], &U16_TYPE);
         env.define_prim_fun(U16Xor, [&U16_TYPE, &U16_TYPE], &U16_TYPE);
+        env.define_prim_fun(U16AsS16, [&U16_TYPE], &S16_TYPE);
 
         env.define_prim_fun(U32Eq, [&U32_TYPE, &U32_TYPE], &BOOL_TYPE);
         env.define_prim_fun(U32Neq, [&U32_TYPE, &U32_TYPE], &BOOL_TYPE);
@@ -234,6 +366,7 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(U32And, [&U32_TYPE, &U32_TYPE], &U32_TYPE);
         env.define_prim_fun(U32Or, [&U32_TYPE, &U32_TYPE], &U32_TYPE);
         env.define_prim_fun(U32Xor, [&U32_TYPE, &U32_TYPE], &U32_TYPE);
+        env.define_prim_fun(U32AsS32, [&U32_TYPE], &S32_TYPE);
 
         env.define_prim_fun(U64Eq, [&U64_TYPE, &U64_TYPE], &BOOL_TYPE);
         env.define_prim_fun(U64Neq, [&U64_TYPE, &U64_TYPE], &BOOL_TYPE);
@@ -251,6 +384,25 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(U64And, [&U64_TYPE, &U64_TYPE], &U64_TYPE);
         env.define_prim_fun(U64Or, [&U64_TYPE, &U64_TYPE], &U64_TYPE);
         env.define_prim_fun(U64Xor, [&U64_TYPE, &U64_TYPE], &U64_TYPE);
+        env.define_prim_fun(U64AsS64, [&U64_TYPE], &S64_TYPE);
+
+        env.define_prim_fun(U128Eq, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Neq, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Lt, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Gt, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Lte, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Gte, [&U128_TYPE, &U128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(U128Add, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Sub, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Mul, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Div, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Not, [&U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Shl, [&U128_TYPE, &U8_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Shr, [&U128_TYPE, &U8_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128And, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Or, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128Xor, [&U128_TYPE, &U128_TYPE], &U128_TYPE);
+        env.define_prim_fun(U128AsS128, [&U128_TYPE], &S128_TYPE);
 
         env.define_prim_fun(S8Eq, [&S8_TYPE, &S8_TYPE], &BOOL_TYPE);
         env.define_prim_fun(S8Neq, [&S8_TYPE, &S8_TYPE], &BOOL_TYPE);
@@ -265,6 +417,7 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(S8Div, [&S8_TYPE, &S8_TYPE], &S8_TYPE);
         env.define_prim_fun(S8Abs, [&S8_TYPE], &S8_TYPE);
         env.define_prim_fun(S8UAbs, [&S8_TYPE], &U8_TYPE);
+        env.define_prim_fun(S8AsU8, [&S8_TYPE], &U8_TYPE);
 
         env.define_prim_fun(S16Eq, [&S16_TYPE, &S16_TYPE], &BOOL_TYPE);
         env.define_prim_fun(S16Neq, [&S16_TYPE, &S16_TYPE], &BOOL_TYPE);
@@ -279,6 +432,7 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(S16Div, [&S16_TYPE, &S16_TYPE], &S16_TYPE);
         env.define_prim_fun(S16Abs, [&S16_TYPE], &S16_TYPE);
         env.define_prim_fun(S16UAbs, [&S16_TYPE], &U16_TYPE);
+        env.define_prim_fun(S16AsU16, [&S16_TYPE], &U16_TYPE);
 
         env.define_prim_fun(S32Eq, [&S32_TYPE, &S32_TYPE], &BOOL_TYPE);
         env.define_prim_fun(S32Neq, [&S32_TYPE, &S32_TYPE], &BOOL_TYPE);
@@ -293,6 +447,7 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(S32Div, [&S32_TYPE, &S32_TYPE], &S32_TYPE);
         env.define_prim_fun(S32Abs, [&S32_TYPE], &S32_TYPE);
         env.define_prim_fun(S32UAbs, [&S32_TYPE], &U32_TYPE);
+        env.define_prim_fun(S32AsU32, [&S32_TYPE], &U32_TYPE);
 
         env.define_prim_fun(S64Eq, [&S64_TYPE, &S64_TYPE], &BOOL_TYPE);
         env.define_prim_fun(S64Neq, [&S64_TYPE, &S64_TYPE], &BOOL_TYPE);
@@ -307,6 +462,22 @@ impl<'arena> Env<'arena> {
         env.define_prim_fun(S64Div, [&S64_TYPE, &S64_TYPE], &S64_TYPE);
         env.define_prim_fun(S64Abs, [&S64_TYPE], &S64_TYPE);
         env.define_prim_fun(S64UAbs, [&S64_TYPE], &U64_TYPE);
+        env.define_prim_fun(S64AsU64, [&S64_TYPE], &U64_TYPE);
+
+        env.define_prim_fun(S128Eq, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Neq, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Lt, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Gt, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Lte, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Gte, [&S128_TYPE, &S128_TYPE], &BOOL_TYPE);
+        env.define_prim_fun(S128Neg, [&S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128Add, [&S128_TYPE, &S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128Sub, [&S128_TYPE, &S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128Mul, [&S128_TYPE, &S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128Div, [&S128_TYPE, &S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128Abs, [&S128_TYPE], &S128_TYPE);
+        env.define_prim_fun(S128UAbs, [&S128_TYPE], &U128_TYPE);
+        env.define_prim_fun(S128AsU128, [&S128_TYPE], &U128_TYPE);
 
         env.define_prim(
             OptionSome,
@@ -498,10 +669,196 @@ impl<'arena> Env<'arena> {
         env.define_prim(Array32Index, array32_index_type);
         env.define_prim(Array64Index, array64_index_type);
 
+        // fun (@len : UN) (@A : Type) (@B : Type) -> B   -> (B   -> A   -> B  ) -> ArrayN len   A   -> B
+        // fun (@len : UN) (@A : Type) (@B : Type) -> B@0 -> (B@1 -> A@3 -> B@3) -> ArrayN len@4 A@3 -> B@3
+        let array_fold_type = |index_type, array_type| {
+            scope.to_scope(core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("len"),
+                index_type,
+                scope.to_scope(core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Implicit,
+                    env.name("A"),
+                    &UNIVERSE,
+                    scope.to_scope(core::Term::FunType(
+                        Span::Empty,
+                        Plicity::Implicit,
+                        env.name("B"),
+                        &UNIVERSE,
+                        scope.to_scope(core::Term::FunType(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            None,
+                            &VAR0, // B@0
+                            scope.to_scope(core::Term::FunType(
+                                Span::Empty,
+                                Plicity::Explicit,
+                                None,
+                                // B@1 -> A@3 -> B@3
+                                &Term::FunType(
+                                    Span::Empty,
+                                    Plicity::Explicit,
+                                    None,
+                                    &VAR1,
+                                    &Term::FunType(
+                                        Span::Empty,
+                                        Plicity::Explicit,
+                                        None,
+                                        &VAR3,
+                                        &VAR3,
+                                    ),
+                                ),
+                                scope.to_scope(core::Term::FunType(
+                                    Span::Empty,
+                                    Plicity::Explicit,
+                                    None,
+                                    // ArrayN len@4 A@3
+                                    scope.to_scope(Term::FunApp(
+                                        Span::Empty,
+                                        Plicity::Explicit,
+                                        scope.to_scope(Term::FunApp(
+                                            Span::Empty,
+                                            Plicity::Explicit,
+                                            array_type,
+                                            &VAR4,
+                                        )),
+                                        &VAR3,
+                                    )),
+                                    &VAR3, // B@3
+                                )),
+                            )),
+                        )),
+                    )),
+                )),
+            ))
+        };
+        let array8_fold_type = array_fold_type(&U8_TYPE, &ARRAY8_TYPE);
+        let array16_fold_type = array_fold_type(&U16_TYPE, &ARRAY16_TYPE);
+        let array32_fold_type = array_fold_type(&U32_TYPE, &ARRAY32_TYPE);
+        let array64_fold_type = array_fold_type(&U64_TYPE, &ARRAY64_TYPE);
+        env.define_prim(Array8Fold, array8_fold_type);
+        env.define_prim(Array16Fold, array16_fold_type);
+        env.define_prim(Array32Fold, array32_fold_type);
+        env.define_prim(Array64Fold, array64_fold_type);
+
+        env.define_prim(
+            ArrayLength,
+            // fun (@A : Type) -> Array A   -> U64
+            // fun (@A : Type) -> Array A@0 -> U64
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("A"),
+                &UNIVERSE,
+                &Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR0),
+                    &U64_TYPE,
+                ),
+            ),
+        );
+        env.define_prim(
+            ArrayConcat,
+            // fun (@A : Type) -> Array A   -> Array A   -> Array A
+            // fun (@A : Type) -> Array A@0 -> Array A@1 -> Array A@2
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("A"),
+                &UNIVERSE,
+                &Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    None,
+                    &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR0),
+                    &Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        None,
+                        &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR1),
+                        &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR2),
+                    ),
+                ),
+            ),
+        );
+        env.define_prim(
+            ArraySlice,
+            // fun (@A : Type) (start : U64) (len : U64) -> Array A   -> Option (Array A  )
+            // fun (@A : Type) (start : U64) (len : U64) -> Array A@2 -> Option (Array A@3)
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("A"),
+                &UNIVERSE,
+                scope.to_scope(core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    env.name("start"),
+                    &U64_TYPE,
+                    scope.to_scope(core::Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        env.name("len"),
+                        &U64_TYPE,
+                        &Term::FunType(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            None,
+                            &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR2),
+                            &Term::FunApp(
+                                Span::Empty,
+                                Plicity::Explicit,
+                                &Term::Prim(Span::Empty, OptionType),
+                                &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR3),
+                            ),
+                        ),
+                    )),
+                )),
+            ),
+        );
+        env.define_prim(
+            ArrayMap,
+            // fun (@A : Type) (@B : Type) -> (A   -> B  ) -> Array A   -> Array B
+            // fun (@A : Type) (@B : Type) -> (A@1 -> B@1) -> Array A@2 -> Array B@2
+            &core::Term::FunType(
+                Span::Empty,
+                Plicity::Implicit,
+                env.name("A"),
+                &UNIVERSE,
+                scope.to_scope(core::Term::FunType(
+                    Span::Empty,
+                    Plicity::Implicit,
+                    env.name("B"),
+                    &UNIVERSE,
+                    &Term::FunType(
+                        Span::Empty,
+                        Plicity::Explicit,
+                        None,
+                        // A@1 -> B@1
+                        &Term::FunType(Span::Empty, Plicity::Explicit, None, &VAR1, &VAR1),
+                        &Term::FunType(
+                            Span::Empty,
+                            Plicity::Explicit,
+                            None,
+                            &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR2),
+                            &Term::FunApp(Span::Empty, Plicity::Explicit, &ARRAY_TYPE, &VAR2),
+                        ),
+                    ),
+                )),
+            ),
+        );
+
         env.define_prim_fun(PosAddU8, [&POS_TYPE, &U8_TYPE], &POS_TYPE);
         env.define_prim_fun(PosAddU16, [&POS_TYPE, &U16_TYPE], &POS_TYPE);
         env.define_prim_fun(PosAddU32, [&POS_TYPE, &U32_TYPE], &POS_TYPE);
         env.define_prim_fun(PosAddU64, [&POS_TYPE, &U64_TYPE], &POS_TYPE);
+        env.define_prim_fun(PosSub, [&POS_TYPE, &POS_TYPE], &U64_TYPE);
+        env.define_prim_fun(U64ToPos, [&U64_TYPE], &POS_TYPE);
+        env.define_prim_fun(PosToU64, [&POS_TYPE], &U64_TYPE);
 
         env.build()
     }
@@ -509,6 +866,7 @@ impl<'arena> Env<'arena> {
 
 struct EnvBuilder<'interner, 'arena> {
     entries: FxHashMap<StringId, (Prim, ArcValue<'arena>)>,
+    types: FxHashMap<Prim, ArcValue<'arena>>,
     interner: &'interner RefCell<StringInterner>,
     scope: &'arena Scope<'arena>,
     meta_exprs: UniqueEnv<Option<ArcValue<'arena>>>,
@@ -523,6 +881,7 @@ impl<'interner, 'arena> EnvBuilder<'interner, 'arena> {
     ) -> EnvBuilder<'interner, 'arena> {
         EnvBuilder {
             entries: FxHashMap::with_hasher(fxhash::FxBuildHasher::default()),
+            types: FxHashMap::with_hasher(fxhash::FxBuildHasher::default()),
             interner,
             scope,
             meta_exprs: UniqueEnv::new(),
@@ -540,6 +899,7 @@ impl<'interner, 'arena> EnvBuilder<'interner, 'arena> {
         let r#type = ElimEnv::new(&self.item_exprs, &self.meta_exprs)
             .eval_env(&mut self.local_exprs)
             .eval(r#type);
+        self.types.insert(prim, r#type.clone());
         self.entries.insert(name, (prim, r#type));
     }
 
@@ -566,6 +926,51 @@ impl<'interner, 'arena> EnvBuilder<'interner, 'arena> {
     fn build(self) -> Env<'arena> {
         Env {
             entries: self.entries,
+            types: self.types,
+        }
+    }
+}
+
+/// The byte order used by number formats.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    /// Most significant byte first.
+    Big,
+    /// Least significant byte first.
+    Little,
+}
+
+impl Endianness {
+    /// Find the number format that corresponds to an endianness-agnostic
+    /// format name, like `u16`, when using this byte order.
+    pub fn number_format(self, name: &str) -> Option<Prim> {
+        use crate::core::Prim::*;
+        use Endianness::*;
+
+        match (name, self) {
+            ("u16", Big) => Some(FormatU16Be),
+            ("u16", Little) => Some(FormatU16Le),
+            ("u24", Big) => Some(FormatU24Be),
+            ("u24", Little) => Some(FormatU24Le),
+            ("u32", Big) => Some(FormatU32Be),
+            ("u32", Little) => Some(FormatU32Le),
+            ("u64", Big) => Some(FormatU64Be),
+            ("u64", Little) => Some(FormatU64Le),
+            ("u128", Big) => Some(FormatU128Be),
+            ("u128", Little) => Some(FormatU128Le),
+            ("s16", Big) => Some(FormatS16Be),
+            ("s16", Little) => Some(FormatS16Le),
+            ("s32", Big) => Some(FormatS32Be),
+            ("s32", Little) => Some(FormatS32Le),
+            ("s64", Big) => Some(FormatS64Be),
+            ("s64", Little) => Some(FormatS64Le),
+            ("s128", Big) => Some(FormatS128Be),
+            ("s128", Little) => Some(FormatS128Le),
+            ("f32", Big) => Some(FormatF32Be),
+            ("f32", Little) => Some(FormatF32Le),
+            ("f64", Big) => Some(FormatF64Be),
+            ("f64", Little) => Some(FormatF64Le),
+            (_, _) => None,
         }
     }
 }
@@ -576,7 +981,7 @@ pub type Step = for<'arena> fn(&ElimEnv<'arena, '_>, &[Elim<'arena>]) -> Option<
 macro_rules! step {
     ($env:pat, [$($param:pat),*] => $body:expr) => {
         |$env, spine| match spine {
-            [$(Elim::FunApp(_, $param)),*] => Some($body),
+            [$(Elim::FunApp(_, _, $param)),*] => Some($body),
             _ => return None,
         }
     };
@@ -598,16 +1003,32 @@ macro_rules! const_step {
     };
 }
 
+/// Compare two strings, which may be string constants or strings decoded from
+/// binary data.
+fn string_eq(env: &ElimEnv<'_, '_>, x: &ArcValue<'_>, y: &ArcValue<'_>) -> Option<bool> {
+    match (x.as_ref(), y.as_ref()) {
+        (Value::ConstLit(Const::String(x)), Value::ConstLit(Const::String(y))) => Some(x == y),
+        (Value::StringLit(x), Value::StringLit(y)) => Some(x == y),
+        (Value::StringLit(x), Value::ConstLit(Const::String(y)))
+        | (Value::ConstLit(Const::String(y)), Value::StringLit(x)) => env.string_eq(x, *y),
+        _ => None,
+    }
+}
+
 #[rustfmt::skip]
 pub fn repr(prim: Prim) -> Step {
     match prim {
         Prim::FormatU8 => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U8Type, [])))),
         Prim::FormatU16Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U16Type, [])))),
         Prim::FormatU16Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U16Type, [])))),
+        Prim::FormatU24Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U32Type, [])))),
+        Prim::FormatU24Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U32Type, [])))),
         Prim::FormatU32Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U32Type, [])))),
         Prim::FormatU32Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U32Type, [])))),
         Prim::FormatU64Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U64Type, [])))),
         Prim::FormatU64Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U64Type, [])))),
+        Prim::FormatU128Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U128Type, [])))),
+        Prim::FormatU128Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::U128Type, [])))),
         Prim::FormatS8 => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S8Type, [])))),
         Prim::FormatS16Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S16Type, [])))),
         Prim::FormatS16Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S16Type, [])))),
@@ -615,6 +1036,8 @@ pub fn repr(prim: Prim) -> Step {
         Prim::FormatS32Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S32Type, [])))),
         Prim::FormatS64Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S64Type, [])))),
         Prim::FormatS64Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S64Type, [])))),
+        Prim::FormatS128Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S128Type, [])))),
+        Prim::FormatS128Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::S128Type, [])))),
         Prim::FormatF32Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F32Type, [])))),
         Prim::FormatF32Le => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F32Type, [])))),
         Prim::FormatF64Be => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::F64Type, [])))),
@@ -627,13 +1050,24 @@ pub fn repr(prim: Prim) -> Step {
         Prim::FormatLimit16 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatLimit32 => step!(env, [_, elem] => env.format_repr(elem)),
         Prim::FormatLimit64 => step!(env, [_, elem] => env.format_repr(elem)),
+        Prim::FormatRepeatCount => step!(env, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
         Prim::FormatRepeatUntilEnd => step!(env, [elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatRepeatUpTo => step!(env, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
+        Prim::FormatLengthPrefixed => step!(env, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [env.format_repr(elem)])))),
         Prim::FormatLink => step!(_, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::RefType, [elem.clone()])))),
         Prim::FormatDeref => step!(env, [elem, _] => env.format_repr(elem)),
+        Prim::FormatUtf8 => step!(_, [_] => Spanned::empty(Arc::new(Value::prim(Prim::StringType, [])))),
+        Prim::FormatReserved => step!(_, [_] => Spanned::empty(Arc::new(Value::RecordType(&[], Telescope::new(SharedEnv::new(), &[]))))),
         Prim::FormatStreamPos => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::PosType, [])))),
+        Prim::FormatWithBase => step!(env, [elem] => env.format_repr(elem)),
+        Prim::FormatRelPos => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::PosType, [])))),
         Prim::FormatSucceed => step!(_, [elem, _] => elem.clone()),
         Prim::FormatFail => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::VoidType, [])))),
         Prim::FormatUnwrap => step!(_, [elem, _] => elem.clone()),
+        Prim::FormatDebug => step!(env, [_, elem] => env.format_repr(elem)),
+        Prim::FormatOptional => step!(env, [_, elem] => Spanned::empty(Arc::new(Value::prim(Prim::OptionType, [env.format_repr(elem)])))),
+        Prim::FormatMap => step!(_, [r#type, _, _] => r#type.clone()),
+        Prim::FormatBind => step!(env, [_, next] => env.format_repr(&env.bind_next_format(next)?)),
         Prim::ReportedError => step!(_, [] => Spanned::empty(Arc::new(Value::prim(Prim::ReportedError, [])))),
         _ => |_, _| None,
     }
@@ -657,6 +1091,16 @@ pub fn step(prim: Prim) -> Step {
         Prim::BoolAnd => const_step!([x: Bool, y: Bool] => Const::Bool(*x && *y)),
         Prim::BoolOr => const_step!([x: Bool, y: Bool] => Const::Bool(*x || *y)),
         Prim::BoolXor => const_step!([x: Bool, y: Bool] => Const::Bool(*x ^ *y)),
+        Prim::BoolToU8 => const_step!([x: Bool] => Const::U8(u8::from(*x), UIntStyle::Decimal)),
+
+        Prim::StringEq => step!(env, [x, y] => {
+            let eq = string_eq(env, x, y)?;
+            Spanned::empty(Arc::new(Value::ConstLit(Const::Bool(eq))))
+        }),
+        Prim::StringNeq => step!(env, [x, y] => {
+            let eq = string_eq(env, x, y)?;
+            Spanned::empty(Arc::new(Value::ConstLit(Const::Bool(!eq))))
+        }),
 
         Prim::U8Eq => const_step!([x: U8, y: U8] => Const::Bool(x == y)),
         Prim::U8Neq => const_step!([x: U8, y: U8] => Const::Bool(x != y)),
@@ -674,6 +1118,8 @@ pub fn step(prim: Prim) -> Step {
         Prim::U8And => const_step!([x, xst: U8, y, yst: U8] => Const::U8(u8::bitand(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U8Or => const_step!([x, xst: U8, y, yst: U8] => Const::U8(u8::bitor(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U8Xor => const_step!([x, xst: U8, y, yst: U8] => Const::U8(u8::bitxor(*x, *y), UIntStyle::merge(*xst, *yst))),
+        Prim::U8AsS8 => const_step!([x, _style: U8] => Const::S8(*x as i8)),
+        Prim::U8ToBool => const_step!([x, _style: U8] => Const::Bool(*x != 0)),
 
         Prim::U16Eq => const_step!([x: U16, y: U16] => Const::Bool(x == y)),
         Prim::U16Neq => const_step!([x: U16, y: U16] => Const::Bool(x != y)),
@@ -685,12 +1131,13 @@ pub fn step(prim: Prim) -> Step {
         Prim::U16Sub => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::checked_sub(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U16Mul => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::checked_mul(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U16Div => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::checked_div(*x, *y)?, UIntStyle::merge(*xst, *yst))),
-        Prim::U16Not => const_step!([x: U16] => Const::U16(u16::not(*x), UIntStyle::Decimal)),
+        Prim::U16Not => const_step!([x, style: U16] => Const::U16(u16::not(*x), *style)),
         Prim::U16Shl => const_step!([x, xst: U16, y, _yst: U8] => Const::U16(u16::checked_shl(*x, u32::from(*y))?, *xst)),
         Prim::U16Shr => const_step!([x, xst: U16, y, _yst: U8] => Const::U16(u16::checked_shr(*x, u32::from(*y))?, *xst)),
         Prim::U16And => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::bitand(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U16Or => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::bitor(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U16Xor => const_step!([x, xst: U16, y, yst: U16] => Const::U16(u16::bitxor(*x, *y), UIntStyle::merge(*xst, *yst))),
+        Prim::U16AsS16 => const_step!([x, _style: U16] => Const::S16(*x as i16)),
 
         Prim::U32Eq => const_step!([x: U32, y: U32] => Const::Bool(x == y)),
         Prim::U32Neq => const_step!([x: U32, y: U32] => Const::Bool(x != y)),
@@ -702,12 +1149,13 @@ pub fn step(prim: Prim) -> Step {
         Prim::U32Sub => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::checked_sub(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U32Mul => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::checked_mul(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U32Div => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::checked_div(*x, *y)?, UIntStyle::merge(*xst, *yst))),
-        Prim::U32Not => const_step!([x: U32] => Const::U32(u32::not(*x), UIntStyle::Decimal)),
+        Prim::U32Not => const_step!([x, style: U32] => Const::U32(u32::not(*x), *style)),
         Prim::U32Shl => const_step!([x, xst: U32, y, _yst: U8] => Const::U32(u32::checked_shl(*x, u32::from(*y))?, *xst)),
         Prim::U32Shr => const_step!([x, xst: U32, y, _yst: U8] => Const::U32(u32::checked_shr(*x, u32::from(*y))?, *xst)),
         Prim::U32And => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::bitand(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U32Or => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::bitor(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U32Xor => const_step!([x, xst: U32, y, yst: U32] => Const::U32(u32::bitxor(*x, *y), UIntStyle::merge(*xst, *yst))),
+        Prim::U32AsS32 => const_step!([x, _style: U32] => Const::S32(*x as i32)),
 
         Prim::U64Eq => const_step!([x: U64, y: U64] => Const::Bool(x == y)),
         Prim::U64Neq => const_step!([x: U64, y: U64] => Const::Bool(x != y)),
@@ -719,12 +1167,31 @@ pub fn step(prim: Prim) -> Step {
         Prim::U64Sub => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::checked_sub(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U64Mul => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::checked_mul(*x, *y)?, UIntStyle::merge(*xst, *yst))),
         Prim::U64Div => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::checked_div(*x, *y)?, UIntStyle::merge(*xst, *yst))),
-        Prim::U64Not => const_step!([x: U64] => Const::U64(u64::not(*x), UIntStyle::Decimal)),
+        Prim::U64Not => const_step!([x, style: U64] => Const::U64(u64::not(*x), *style)),
         Prim::U64Shl => const_step!([x, xst: U64, y, _yst: U8] => Const::U64(u64::checked_shl(*x, u32::from(*y))?, *xst)),
         Prim::U64Shr => const_step!([x, xst: U64, y, _yst: U8] => Const::U64(u64::checked_shr(*x, u32::from(*y))?, *xst)),
         Prim::U64And => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::bitand(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U64Or => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::bitor(*x, *y), UIntStyle::merge(*xst, *yst))),
         Prim::U64Xor => const_step!([x, xst: U64, y, yst: U64] => Const::U64(u64::bitxor(*x, *y), UIntStyle::merge(*xst, *yst))),
+        Prim::U64AsS64 => const_step!([x, _style: U64] => Const::S64(*x as i64)),
+
+        Prim::U128Eq => const_step!([x: U128, y: U128] => Const::Bool(x == y)),
+        Prim::U128Neq => const_step!([x: U128, y: U128] => Const::Bool(x != y)),
+        Prim::U128Gt => const_step!([x: U128, y: U128] => Const::Bool(x > y)),
+        Prim::U128Lt => const_step!([x: U128, y: U128] => Const::Bool(x < y)),
+        Prim::U128Gte => const_step!([x: U128, y: U128] => Const::Bool(x >= y)),
+        Prim::U128Lte => const_step!([x: U128, y: U128] => Const::Bool(x <= y)),
+        Prim::U128Add => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::checked_add(x.get(), y.get())?, UIntStyle::merge(*xst, *yst))),
+        Prim::U128Sub => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::checked_sub(x.get(), y.get())?, UIntStyle::merge(*xst, *yst))),
+        Prim::U128Mul => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::checked_mul(x.get(), y.get())?, UIntStyle::merge(*xst, *yst))),
+        Prim::U128Div => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::checked_div(x.get(), y.get())?, UIntStyle::merge(*xst, *yst))),
+        Prim::U128Not => const_step!([x, style: U128] => Const::u128(u128::not(x.get()), *style)),
+        Prim::U128Shl => const_step!([x, xst: U128, y, _yst: U8] => Const::u128(u128::checked_shl(x.get(), u32::from(*y))?, *xst)),
+        Prim::U128Shr => const_step!([x, xst: U128, y, _yst: U8] => Const::u128(u128::checked_shr(x.get(), u32::from(*y))?, *xst)),
+        Prim::U128And => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::bitand(x.get(), y.get()), UIntStyle::merge(*xst, *yst))),
+        Prim::U128Or => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::bitor(x.get(), y.get()), UIntStyle::merge(*xst, *yst))),
+        Prim::U128Xor => const_step!([x, xst: U128, y, yst: U128] => Const::u128(u128::bitxor(x.get(), y.get()), UIntStyle::merge(*xst, *yst))),
+        Prim::U128AsS128 => const_step!([x, _style: U128] => Const::s128(x.get() as i128)),
 
         Prim::S8Eq => const_step!([x: S8, y: S8] => Const::Bool(x == y)),
         Prim::S8Neq => const_step!([x: S8, y: S8] => Const::Bool(x != y)),
@@ -739,6 +1206,7 @@ pub fn step(prim: Prim) -> Step {
         Prim::S8Div => const_step!([x: S8, y: S8] => Const::S8(i8::checked_div(*x, *y)?)),
         Prim::S8Abs => const_step!([x: S8] => Const::S8(i8::abs(*x))),
         Prim::S8UAbs => const_step!([x: S8] => Const::U8(i8::unsigned_abs(*x), UIntStyle::Decimal)),
+        Prim::S8AsU8 => const_step!([x: S8] => Const::U8(*x as u8, UIntStyle::Hexadecimal)),
 
         Prim::S16Eq => const_step!([x: S16, y: S16] => Const::Bool(x == y)),
         Prim::S16Neq => const_step!([x: S16, y: S16] => Const::Bool(x != y)),
@@ -753,6 +1221,7 @@ pub fn step(prim: Prim) -> Step {
         Prim::S16Div => const_step!([x: S16, y: S16] => Const::S16(i16::checked_div(*x, *y)?)),
         Prim::S16Abs => const_step!([x: S16] => Const::S16(i16::abs(*x))),
         Prim::S16UAbs => const_step!([x: S16] => Const::U16(i16::unsigned_abs(*x), UIntStyle::Decimal)),
+        Prim::S16AsU16 => const_step!([x: S16] => Const::U16(*x as u16, UIntStyle::Hexadecimal)),
 
         Prim::S32Eq => const_step!([x: S32, y: S32] => Const::Bool(x == y)),
         Prim::S32Neq => const_step!([x: S32, y: S32] => Const::Bool(x != y)),
@@ -767,6 +1236,7 @@ pub fn step(prim: Prim) -> Step {
         Prim::S32Div => const_step!([x: S32, y: S32] => Const::S32(i32::checked_div(*x, *y)?)),
         Prim::S32Abs => const_step!([x: S32] => Const::S32(i32::abs(*x))),
         Prim::S32UAbs => const_step!([x: S32] => Const::U32(i32::unsigned_abs(*x), UIntStyle::Decimal)),
+        Prim::S32AsU32 => const_step!([x: S32] => Const::U32(*x as u32, UIntStyle::Hexadecimal)),
 
         Prim::S64Eq => const_step!([x: S64, y: S64] => Const::Bool(x == y)),
         Prim::S64Neq => const_step!([x: S64, y: S64] => Const::Bool(x != y)),
@@ -781,11 +1251,27 @@ pub fn step(prim: Prim) -> Step {
         Prim::S64Div => const_step!([x: S64, y: S64] => Const::S64(i64::checked_div(*x, *y)?)),
         Prim::S64Abs => const_step!([x: S64] => Const::S64(i64::abs(*x))),
         Prim::S64UAbs => const_step!([x: S64] => Const::U64(i64::unsigned_abs(*x), UIntStyle::Decimal)),
+        Prim::S64AsU64 => const_step!([x: S64] => Const::U64(*x as u64, UIntStyle::Hexadecimal)),
+
+        Prim::S128Eq => const_step!([x: S128, y: S128] => Const::Bool(x == y)),
+        Prim::S128Neq => const_step!([x: S128, y: S128] => Const::Bool(x != y)),
+        Prim::S128Gt => const_step!([x: S128, y: S128] => Const::Bool(x > y)),
+        Prim::S128Lt => const_step!([x: S128, y: S128] => Const::Bool(x < y)),
+        Prim::S128Gte => const_step!([x: S128, y: S128] => Const::Bool(x >= y)),
+        Prim::S128Lte => const_step!([x: S128, y: S128] => Const::Bool(x <= y)),
+        Prim::S128Neg => const_step!([x: S128] => Const::s128(i128::checked_neg(x.get())?)),
+        Prim::S128Add => const_step!([x: S128, y: S128] => Const::s128(i128::checked_add(x.get(), y.get())?)),
+        Prim::S128Sub => const_step!([x: S128, y: S128] => Const::s128(i128::checked_sub(x.get(), y.get())?)),
+        Prim::S128Mul => const_step!([x: S128, y: S128] => Const::s128(i128::checked_mul(x.get(), y.get())?)),
+        Prim::S128Div => const_step!([x: S128, y: S128] => Const::s128(i128::checked_div(x.get(), y.get())?)),
+        Prim::S128Abs => const_step!([x: S128] => Const::s128(i128::checked_abs(x.get())?)),
+        Prim::S128UAbs => const_step!([x: S128] => Const::u128(i128::unsigned_abs(x.get()), UIntStyle::Decimal)),
+        Prim::S128AsU128 => const_step!([x: S128] => Const::u128(x.get() as u128, UIntStyle::Hexadecimal)),
 
         Prim::OptionFold => step!(env, [_, _, on_none, on_some, option] => {
             match option.match_prim_spine()? {
-                (Prim::OptionSome, [_, Elim::FunApp(Plicity::Explicit, value)]) => {
-                    env.fun_app(Plicity::Explicit, on_some.clone(), value.clone())
+                (Prim::OptionSome, [_, Elim::FunApp(_, Plicity::Explicit, value)]) => {
+                    env.fun_app(Span::Empty, Plicity::Explicit, on_some.clone(), value.clone())
                 },
                 (Prim::OptionNone, [_]) => on_none.clone(),
                 _ => return None,
@@ -793,18 +1279,19 @@ pub fn step(prim: Prim) -> Step {
         }),
 
         Prim::Array8Find | Prim::Array16Find | Prim::Array32Find | Prim::Array64Find => {
-            step!(env, [_, elem_type, pred, array] => match array.as_ref() {
-                Value::ArrayLit(elems) => {
-                    for elem in elems {
+            step!(env, [_, elem_type, pred, array] => match array.array_len() {
+                Some(len) => {
+                    for elem in (0..len).filter_map(|index| array.array_elem(index)) {
                         match env.fun_app(
+                            Span::Empty,
                             Plicity::Explicit,
                             pred.clone(), elem.clone()).as_ref() {
                             Value::ConstLit(Const::Bool(true)) => {
                                 return Some(Spanned::empty(Arc::new(Value::Stuck(
                                     Head::Prim(Prim::OptionSome),
                                     vec![
-                                        Elim::FunApp(Plicity::Implicit, elem_type.clone()),
-                                        Elim::FunApp(Plicity::Explicit, elem.clone()),
+                                        Elim::FunApp(Span::Empty, Plicity::Implicit, elem_type.clone()),
+                                        Elim::FunApp(Span::Empty, Plicity::Explicit, elem.clone()),
                                     ],
                                 ))));
                             },
@@ -814,7 +1301,7 @@ pub fn step(prim: Prim) -> Step {
                     }
                     Spanned::empty(Arc::new(Value::Stuck(
                         Head::Prim(Prim::OptionNone),
-                        vec![Elim::FunApp(Plicity::Implicit, elem_type.clone())],
+                        vec![Elim::FunApp(Span::Empty, Plicity::Implicit, elem_type.clone())],
                     )))
                 }
                 _ => return None,
@@ -822,25 +1309,92 @@ pub fn step(prim: Prim) -> Step {
         }
 
         Prim::Array8Index | Prim::Array16Index | Prim::Array32Index | Prim::Array64Index => {
-            step!(_, [_, _, index, array] => match array.as_ref() {
-                Value::ArrayLit(elems) => {
-                    let index = match (index).as_ref() {
-                        Value::ConstLit(Const::U8(index, _)) => Some(usize::from(*index)),
-                        Value::ConstLit(Const::U16(index, _)) => Some(usize::from(*index)),
-                        Value::ConstLit(Const::U32(index, _)) => usize::try_from(*index).ok(),
-                        Value::ConstLit(Const::U64(index, _)) => usize::try_from(*index).ok(),
-                        _ => return None,
-                    }?;
-                    elems.get(index).cloned()?
-                }
-                _ => return None,
+            step!(_, [_, _, index, array] => {
+                let index = match (index).as_ref() {
+                    Value::ConstLit(Const::U8(index, _)) => Some(usize::from(*index)),
+                    Value::ConstLit(Const::U16(index, _)) => Some(usize::from(*index)),
+                    Value::ConstLit(Const::U32(index, _)) => usize::try_from(*index).ok(),
+                    Value::ConstLit(Const::U64(index, _)) => usize::try_from(*index).ok(),
+                    _ => return None,
+                }?;
+                array.array_elem(index)?
+            })
+        }
+
+        Prim::Array8Fold | Prim::Array16Fold | Prim::Array32Fold | Prim::Array64Fold => {
+            step!(env, [_, _, _, init, step, array] => {
+                let elems = (0..array.array_len()?).filter_map(|index| array.array_elem(index));
+                elems.fold(init.clone(), |acc, elem| {
+                    let step = env.fun_app(Span::Empty, Plicity::Explicit, step.clone(), acc);
+                    env.fun_app(Span::Empty, Plicity::Explicit, step, elem)
+                })
             })
         }
 
+        Prim::ArrayLength => step!(_, [_, array] => {
+            let len = u64::try_from(array.array_len()?).ok()?;
+            Spanned::empty(Arc::new(Value::ConstLit(Const::U64(len, UIntStyle::Decimal))))
+        }),
+        Prim::ArrayConcat => step!(_, [_, array0, array1] => {
+            match (array0.as_ref(), array1.as_ref()) {
+                (Value::ByteArrayLit(bytes0), Value::ByteArrayLit(bytes1)) => {
+                    let bytes = Iterator::chain(bytes0.iter(), bytes1.iter()).copied();
+                    Spanned::empty(Arc::new(Value::ByteArrayLit(bytes.collect())))
+                }
+                (_, _) => {
+                    let (elems0, elems1) = (array0.array_elems()?, array1.array_elems()?);
+                    let elems = Iterator::chain(elems0.iter(), elems1.iter()).cloned();
+                    Spanned::empty(Arc::new(Value::ArrayLit(elems.collect())))
+                }
+            }
+        }),
+        Prim::ArraySlice => step!(_, [elem_type, start, len, array] => {
+            let (start, len) = match (start.as_ref(), len.as_ref()) {
+                (Value::ConstLit(Const::U64(start, _)), Value::ConstLit(Const::U64(len, _))) => (*start, *len),
+                _ => return None,
+            };
+            // Slices that are out of range, or that overflow, result in `none`
+            let range = u64::checked_add(start, len)
+                .and_then(|end| Some(usize::try_from(start).ok()?..usize::try_from(end).ok()?));
+            let slice = match array.as_ref() {
+                Value::ByteArrayLit(bytes) => (range.and_then(|range| bytes.get(range)))
+                    .map(|bytes| Value::ByteArrayLit(bytes.into())),
+                _ => {
+                    let elems = array.array_elems()?;
+                    (range.and_then(|range| elems.get(range)))
+                        .map(|elems| Value::ArrayLit(elems.to_vec()))
+                }
+            };
+
+            let array_type = Spanned::empty(Arc::new(Value::prim(Prim::ArrayType, [elem_type.clone()])));
+            Spanned::empty(Arc::new(match slice {
+                Some(slice) => Value::Stuck(
+                    Head::Prim(Prim::OptionSome),
+                    vec![
+                        Elim::FunApp(Span::Empty, Plicity::Implicit, array_type),
+                        Elim::FunApp(Span::Empty, Plicity::Explicit, Spanned::empty(Arc::new(slice))),
+                    ],
+                ),
+                None => Value::Stuck(
+                    Head::Prim(Prim::OptionNone),
+                    vec![Elim::FunApp(Span::Empty, Plicity::Implicit, array_type)],
+                ),
+            }))
+        }),
+        Prim::ArrayMap => step!(env, [_, _, func, array] => {
+            let elems = (array.array_elems()?.iter())
+                .map(|elem| env.fun_app(Span::Empty, Plicity::Explicit, func.clone(), elem.clone()))
+                .collect();
+            Spanned::empty(Arc::new(Value::ArrayLit(elems)))
+        }),
+
         Prim::PosAddU8 => const_step!([x: Pos, y: U8] => Const::Pos(usize::checked_add(*x, usize::from(*y))?)),
         Prim::PosAddU16 => const_step!([x: Pos, y: U16] => Const::Pos(usize::checked_add(*x, usize::from(*y))?)),
         Prim::PosAddU32 => const_step!([x: Pos, y: U32] => Const::Pos(usize::checked_add(*x, usize::try_from(*y).ok()?)?)),
         Prim::PosAddU64 => const_step!([x: Pos, y: U64] => Const::Pos(usize::checked_add(*x, usize::try_from(*y).ok()?)?)),
+        Prim::PosSub => const_step!([x: Pos, y: Pos] => Const::U64(u64::try_from(usize::checked_sub(*x, *y)?).ok()?, UIntStyle::Decimal)),
+        Prim::U64ToPos => const_step!([x, _style: U64] => Const::Pos(usize::try_from(*x).ok()?)),
+        Prim::PosToU64 => const_step!([x: Pos] => Const::U64(u64::try_from(*x).ok()?, UIntStyle::Decimal)),
 
         _ => |_, _| None,
     }
@@ -1,15 +1,19 @@
 //! The semantics of the core language, implemented using [normalization by
 //! evaluation](https://en.wikipedia.org/wiki/Normalization_by_evaluation).
 
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::panic::panic_any;
 use std::sync::Arc;
 
 use scoped_arena::Scope;
 
 use crate::alloc::SliceVec;
-use crate::core::{prim, Const, LocalInfo, Plicity, Prim, Term};
+use crate::core::{
+    prim, to_scope_str, Const, ConstPattern, LocalInfo, Plicity, Prim, Term, UIntStyle,
+};
 use crate::env::{EnvLen, Index, Level, SharedEnv, SliceEnv};
-use crate::source::{Span, Spanned, StringId};
+use crate::source::{Span, Spanned, StringId, StringInterner};
 
 /// Atomically reference counted values. We use reference counting to increase
 /// the amount of sharing we can achieve during evaluation.
@@ -39,6 +43,9 @@ pub enum Value<'arena> {
 
     /// Array literals.
     ArrayLit(Vec<ArcValue<'arena>>),
+    /// Array literals of `U8` elements, retaining the bytes they were read
+    /// from. This avoids allocating a value for each element of large arrays.
+    ByteArrayLit(Arc<[u8]>),
 
     /// Record formats, consisting of a list of dependent formats.
     FormatRecord(&'arena [StringId], Telescope<'arena>),
@@ -50,13 +57,15 @@ pub enum Value<'arena> {
 
     /// Constant literals.
     ConstLit(Const),
+    /// String literals decoded from binary data.
+    StringLit(Arc<str>),
 }
 
 impl<'arena> Value<'arena> {
     pub fn prim(prim: Prim, params: impl IntoIterator<Item = ArcValue<'arena>>) -> Value<'arena> {
         let params = params
             .into_iter()
-            .map(|arg| Elim::FunApp(Plicity::Explicit, arg))
+            .map(|arg| Elim::FunApp(Span::Empty, Plicity::Explicit, arg))
             .collect();
         Value::Stuck(Head::Prim(prim), params)
     }
@@ -79,6 +88,79 @@ impl<'arena> Value<'arena> {
     pub fn is_error(&self) -> bool {
         matches!(self, Value::Stuck(Head::Prim(Prim::ReportedError), _))
     }
+
+    /// Returns `true` if the values have different top-level constructors,
+    /// and neither could become equal to the other. This excludes stuck
+    /// values, which might be unfolded by forcing, and function and record
+    /// literals, which are compared using eta-conversion.
+    pub fn has_distinct_rigid_constructor(&self, other: &Value<'_>) -> bool {
+        fn is_rigid(value: &Value<'_>) -> bool {
+            !matches!(
+                value,
+                Value::Stuck(..) | Value::FunLit(..) | Value::RecordLit(..),
+            )
+        }
+        fn is_array(value: &Value<'_>) -> bool {
+            matches!(value, Value::ArrayLit(_) | Value::ByteArrayLit(_))
+        }
+
+        std::mem::discriminant(self) != std::mem::discriminant(other)
+            && is_rigid(self)
+            && is_rigid(other)
+            && !(is_array(self) && is_array(other))
+    }
+
+    /// Returns the elements of an array literal, expanding byte array
+    /// literals into `U8` constants.
+    pub fn array_elems(&self) -> Option<Cow<'_, [ArcValue<'arena>]>> {
+        match self {
+            Value::ArrayLit(elems) => Some(Cow::Borrowed(elems)),
+            Value::ByteArrayLit(bytes) => Some(Cow::Owned(
+                (bytes.iter())
+                    .map(|byte| Value::ConstLit(Const::U8(*byte, UIntStyle::Decimal)))
+                    .map(|elem| Spanned::empty(Arc::new(elem)))
+                    .collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of elements in an array literal.
+    pub fn array_len(&self) -> Option<usize> {
+        match self {
+            Value::ArrayLit(elems) => Some(elems.len()),
+            Value::ByteArrayLit(bytes) => Some(bytes.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns the element of an array literal at `index`, without expanding
+    /// the rest of a byte array literal.
+    pub fn array_elem(&self, index: usize) -> Option<ArcValue<'arena>> {
+        match self {
+            Value::ArrayLit(elems) => elems.get(index).cloned(),
+            Value::ByteArrayLit(bytes) => {
+                let elem = Value::ConstLit(Const::U8(*bytes.get(index)?, UIntStyle::Decimal));
+                Some(Spanned::empty(Arc::new(elem)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts an array literal of `U8` constants into a byte array literal.
+    pub fn to_byte_array_lit(&self) -> Option<Value<'arena>> {
+        match self {
+            Value::ArrayLit(elems) => {
+                let bytes = elems.iter().map(|elem| match elem.as_ref() {
+                    Value::ConstLit(Const::U8(byte, _)) => Some(*byte),
+                    _ => None,
+                });
+                Some(Value::ByteArrayLit(bytes.collect::<Option<_>>()?))
+            }
+            Value::ByteArrayLit(bytes) => Some(Value::ByteArrayLit(bytes.clone())),
+            _ => None,
+        }
+    }
 }
 
 /// The head of a [stuck value][Value::Stuck].
@@ -94,14 +176,28 @@ pub enum Head {
 
 /// A pending elimination to be reduced if the [head][Head] of a [stuck
 /// value][Value::Stuck] becomes known.
+///
+/// Each elimination records the span of the term it originated from, allowing
+/// errors that arise when it is reduced to refer to the elimination.
 #[derive(Debug, Clone)]
 pub enum Elim<'arena> {
     /// Function applications.
-    FunApp(Plicity, ArcValue<'arena>),
+    FunApp(Span, Plicity, ArcValue<'arena>),
     /// Record projections.
-    RecordProj(StringId),
+    RecordProj(Span, StringId),
     /// Match on a constant.
-    ConstMatch(Branches<'arena, Const>),
+    ConstMatch(Span, Branches<'arena, ConstPattern>),
+}
+
+impl<'arena> Elim<'arena> {
+    /// The span of the term that the elimination originated from.
+    pub fn span(&self) -> Span {
+        match self {
+            Elim::FunApp(span, _, _) | Elim::RecordProj(span, _) | Elim::ConstMatch(span, _) => {
+                *span
+            }
+        }
+    }
 }
 
 /// A closure is a term that can later be instantiated with a value.
@@ -211,17 +307,42 @@ pub enum SplitBranches<'arena, P> {
 }
 
 /// Errors encountered while interpreting terms.
+///
+/// Apart from [`Error::OutOfFuel`], these should never be encountered when
+/// evaluating terms produced by the elaborator, and indicate a bug if they
+/// are. They can still be reached by terms constructed elsewhere, for example
+/// by an embedder, which can recover from them using [`EvalEnv::try_eval`].
 // TODO: include stack trace(??)
 #[derive(Clone, Debug)]
 pub enum Error {
+    /// An item variable was not bound in the environment. Shouldn't happen.
     UnboundItemVar,
+    /// A local variable was not bound in the environment. Shouldn't happen.
     UnboundLocalVar,
+    /// A metavariable was not bound in the environment. Shouldn't happen.
     UnboundMetaVar,
-    InvalidFunctionApp,
-    InvalidRecordProj,
-    InvalidConstMatch,
+    /// A non-function was applied to an argument, or a function was applied
+    /// with the wrong plicity. Shouldn't happen.
+    InvalidFunctionApp(Span),
+    /// A field was projected from a non-record, or from a record without that
+    /// field. Shouldn't happen.
+    InvalidRecordProj(Span),
+    /// A non-constant was matched on. Shouldn't happen.
+    InvalidConstMatch(Span),
+    /// The representation of a non-format was requested. Shouldn't happen.
     InvalidFormatRepr,
-    MissingConstDefault,
+    /// A constant match had no branch for the matched constant. Shouldn't
+    /// happen.
+    MissingConstDefault(Span),
+    /// The [fuel][Fuel] budget was exhausted. Reachable from well-typed terms.
+    OutOfFuel,
+    /// Array elements that were elided for display were evaluated. Shouldn't
+    /// happen.
+    InvalidElidedElems(Span),
+    /// A string decoded from binary data was matched against string constants
+    /// without an interner to look the constants up in. Reachable if the
+    /// environment was not given [an interner][ElimEnv::with_interner].
+    MissingInterner(Span),
 }
 
 impl Error {
@@ -230,15 +351,66 @@ impl Error {
             Error::UnboundItemVar => "unbound item variable",
             Error::UnboundLocalVar => "unbound local variable",
             Error::UnboundMetaVar => "unbound metavariable",
-            Error::InvalidFunctionApp => "invalid function application",
-            Error::InvalidRecordProj => "invalid record projection",
-            Error::InvalidConstMatch => "invalid constant match",
+            Error::InvalidFunctionApp(_) => "invalid function application",
+            Error::InvalidRecordProj(_) => "invalid record projection",
+            Error::InvalidConstMatch(_) => "invalid constant match",
             Error::InvalidFormatRepr => "invalid format repr",
-            Error::MissingConstDefault => "missing default expression",
+            Error::MissingConstDefault(_) => "missing default expression",
+            Error::OutOfFuel => "evaluation ran out of fuel",
+            Error::InvalidElidedElems(_) => "invalid elided array elements",
+            Error::MissingInterner(_) => "missing string interner",
         }
     }
 }
 
+/// A budget on how deeply computations may nest before being aborted with
+/// [`Error::OutOfFuel`], preventing runaway terms from overflowing the stack.
+///
+/// A unit of fuel is [burned][Fuel::burn] on entering each nested step, and
+/// [refunded][Fuel::refund] once that step has finished.
+#[derive(Debug)]
+pub struct Fuel {
+    remaining: Cell<usize>,
+}
+
+impl Fuel {
+    /// The default nesting limit. This is far deeper than any well-formed
+    /// format should need.
+    pub const DEFAULT_LIMIT: usize = 256;
+
+    /// Construct a fuel budget, allowing steps to be nested up to `limit`
+    /// levels deep.
+    pub fn new(limit: usize) -> Fuel {
+        Fuel {
+            remaining: Cell::new(limit),
+        }
+    }
+
+    /// The number of nested steps that can still be entered.
+    pub fn remaining(&self) -> usize {
+        self.remaining.get()
+    }
+
+    /// Burn a unit of fuel before entering a nested step.
+    pub fn burn(&self) -> Result<(), Error> {
+        let remaining = self.remaining.get().checked_sub(1);
+        self.remaining.set(remaining.ok_or(Error::OutOfFuel)?);
+        Ok(())
+    }
+
+    /// Refund the unit of fuel burned by the corresponding call to
+    /// [`Fuel::burn`].
+    pub fn refund(&self) {
+        self.remaining.set(self.remaining.get() + 1);
+    }
+}
+
+impl Default for Fuel {
+    fn default() -> Fuel {
+        Fuel::new(Fuel::DEFAULT_LIMIT)
+    }
+}
+
 /// Evaluation environment.
 ///
 /// Like the [`ElimEnv`], this allows for the running of computations, but
@@ -263,9 +435,13 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
         QuoteEnv::new(self.elim_env, self.local_exprs.len())
     }
 
-    fn get_local_expr<'this: 'env>(&'this self, var: Index) -> &'env ArcValue<'arena> {
-        let value = self.local_exprs.get_index(var);
-        value.unwrap_or_else(|| panic_any(Error::UnboundLocalVar))
+    fn get_local_expr<'this: 'env>(
+        &'this self,
+        var: Index,
+    ) -> Result<&'env ArcValue<'arena>, Error> {
+        self.local_exprs
+            .get_index(var)
+            .ok_or(Error::UnboundLocalVar)
     }
 
     /// Fully normalize a term by first [evaluating][EvalEnv::eval] it into
@@ -279,32 +455,82 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
         self.quote_env().quote(scope, &self.eval(term))
     }
 
+    /// Fully normalize a term like [`EvalEnv::normalize`], returning an
+    /// [`Error`] instead of panicking if evaluation goes wrong.
+    pub fn try_normalise<'out_arena>(
+        &mut self,
+        scope: &'out_arena Scope<'out_arena>,
+        term: &Term<'arena>,
+    ) -> Result<Term<'out_arena>, Error> {
+        let value = self.try_eval(term)?;
+        self.quote_env().try_quote(scope, &value)
+    }
+
+    /// Normalize a term for displaying to users, quoting at most
+    /// `max_array_elems` elements of each array literal. Any remaining
+    /// elements are replaced with a [`Term::ElidedElems`] marker, which keeps
+    /// the output readable when normalizing large arrays.
+    pub fn normalise_for_display<'out_arena>(
+        &mut self,
+        scope: &'out_arena Scope<'out_arena>,
+        term: &Term<'arena>,
+        max_array_elems: usize,
+    ) -> Term<'out_arena> {
+        (self.quote_env().limiting_array_elems(max_array_elems)).quote(scope, &self.eval(term))
+    }
+
     /// Evaluate a [term][Term] into a [value][Value].
     ///
     /// This could be loosely thought of as a just-in-time implementation of
     /// closure conversion + partial evaluation (for more discussion see [this
     /// twitter thread](https://twitter.com/brendanzab/status/1423536653658771457)).
+    ///
+    /// # Panics
+    ///
+    /// This panics with an [`Error`] if the term is ill-typed, or if the
+    /// [fuel][Fuel] budget of the [elimination environment][ElimEnv] is
+    /// exhausted. Use [`EvalEnv::try_eval`] to recover from these errors.
     pub fn eval(&mut self, term: &Term<'arena>) -> ArcValue<'arena> {
-        match term {
+        self.try_eval(term).unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Evaluate a [term][Term] into a [value][Value], returning an [`Error`]
+    /// instead of panicking if evaluation goes wrong.
+    ///
+    /// This is intended for embedders evaluating terms that might not have
+    /// been produced by the elaborator. Note that [primitive][Prim]
+    /// operations still assume that they are applied to well-typed
+    /// arguments.
+    pub fn try_eval(&mut self, term: &Term<'arena>) -> Result<ArcValue<'arena>, Error> {
+        self.elim_env.burn_fuel()?;
+        let value = self.eval_step(term);
+        self.elim_env.refund_fuel();
+        value
+    }
+
+    fn eval_step(&mut self, term: &Term<'arena>) -> Result<ArcValue<'arena>, Error> {
+        Ok(match term {
             Term::ItemVar(span, var) => {
-                Spanned::new(*span, Arc::clone(self.elim_env.get_item_expr(*var)))
+                Spanned::new(*span, Arc::clone(self.elim_env.try_get_item_expr(*var)?))
             }
-            Term::MetaVar(span, var) => match self.elim_env.get_meta_expr(*var) {
+            Term::MetaVar(span, var) => match self.elim_env.try_get_meta_expr(*var)? {
                 Some(value) => Spanned::new(*span, Arc::clone(value)),
                 None => Spanned::new(*span, Arc::new(Value::meta_var(*var))),
             },
-            Term::LocalVar(span, var) => Spanned::new(*span, Arc::clone(self.get_local_expr(*var))),
+            Term::LocalVar(span, var) => {
+                Spanned::new(*span, Arc::clone(self.get_local_expr(*var)?))
+            }
             Term::InsertedMeta(span, var, local_infos) => {
-                let head_expr = self.eval(&Term::MetaVar(*span, *var));
-                self.apply_local_infos(head_expr, local_infos)
+                let head_expr = self.try_eval(&Term::MetaVar(*span, *var))?;
+                self.try_apply_local_infos(head_expr, local_infos)?
             }
-            Term::Ann(span, expr, _) => Spanned::merge(*span, self.eval(expr)),
+            Term::Ann(span, expr, _) => Spanned::merge(*span, self.try_eval(expr)?),
             Term::Let(span, _, _, def_expr, body_expr) => {
-                let def_expr = self.eval(def_expr);
+                let def_expr = self.try_eval(def_expr)?;
                 self.local_exprs.push(def_expr);
-                let body_expr = self.eval(body_expr);
+                let body_expr = self.try_eval(body_expr);
                 self.local_exprs.pop();
-                Spanned::merge(*span, body_expr)
+                Spanned::merge(*span, body_expr?)
             }
 
             Term::Universe(span) => Spanned::new(*span, Arc::new(Value::Universe)),
@@ -314,7 +540,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                 Arc::new(Value::FunType(
                     *plicity,
                     *param_name,
-                    self.eval(param_type),
+                    self.try_eval(param_type)?,
                     Closure::new(self.local_exprs.clone(), body_type),
                 )),
             ),
@@ -327,9 +553,12 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                 )),
             ),
             Term::FunApp(span, plicity, head_expr, arg_expr) => {
-                let head_expr = self.eval(head_expr);
-                let arg_expr = self.eval(arg_expr);
-                Spanned::merge(*span, self.elim_env.fun_app(*plicity, head_expr, arg_expr))
+                let head_expr = self.try_eval(head_expr)?;
+                let arg_expr = self.try_eval(arg_expr)?;
+                let expr = self
+                    .elim_env
+                    .try_fun_app(*span, *plicity, head_expr, arg_expr)?;
+                Spanned::merge(*span, expr)
             }
 
             Term::RecordType(span, labels, types) => {
@@ -337,25 +566,31 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                 Spanned::new(*span, Arc::new(Value::RecordType(labels, types)))
             }
             Term::RecordLit(span, labels, exprs) => {
-                let exprs = exprs.iter().map(|expr| self.eval(expr)).collect();
+                let exprs = exprs.iter().map(|expr| self.try_eval(expr));
+                let exprs = exprs.collect::<Result<_, _>>()?;
                 Spanned::new(*span, Arc::new(Value::RecordLit(labels, exprs)))
             }
             Term::RecordProj(span, head_expr, label) => {
-                let head_expr = self.eval(head_expr);
-                Spanned::merge(*span, self.elim_env.record_proj(head_expr, *label))
+                let head_expr = self.try_eval(head_expr)?;
+                Spanned::merge(
+                    *span,
+                    self.elim_env.try_record_proj(*span, head_expr, *label)?,
+                )
             }
 
             Term::ArrayLit(span, exprs) => {
-                let exprs = exprs.iter().map(|expr| self.eval(expr)).collect();
+                let exprs = exprs.iter().map(|expr| self.try_eval(expr));
+                let exprs = exprs.collect::<Result<_, _>>()?;
                 Spanned::new(*span, Arc::new(Value::ArrayLit(exprs)))
             }
+            Term::ElidedElems(span, _) => return Err(Error::InvalidElidedElems(*span)),
 
             Term::FormatRecord(span, labels, formats) => {
                 let formats = Telescope::new(self.local_exprs.clone(), formats);
                 Spanned::new(*span, Arc::new(Value::FormatRecord(labels, formats)))
             }
             Term::FormatCond(span, name, format, cond) => {
-                let format = self.eval(format);
+                let format = self.try_eval(format)?;
                 let cond_expr = Closure::new(self.local_exprs.clone(), cond);
                 Spanned::new(*span, Arc::new(Value::FormatCond(*name, format, cond_expr)))
             }
@@ -369,29 +604,43 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
             Term::ConstLit(span, r#const) => {
                 Spanned::new(*span, Arc::new(Value::ConstLit(*r#const)))
             }
+            Term::StringLit(span, string) => {
+                Spanned::new(*span, Arc::new(Value::StringLit(Arc::from(*string))))
+            }
             Term::ConstMatch(span, head_expr, branches, default_expr) => {
-                let head_expr = self.eval(head_expr);
+                let head_expr = self.try_eval(head_expr)?;
                 let branches = Branches::new(self.local_exprs.clone(), branches, *default_expr);
-                Spanned::merge(*span, self.elim_env.const_match(head_expr, branches))
+                let expr = self.elim_env.try_const_match(*span, head_expr, branches)?;
+                Spanned::merge(*span, expr)
             }
-        }
+        })
     }
 
     fn apply_local_infos(
         &mut self,
-        mut head_expr: ArcValue<'arena>,
+        head_expr: ArcValue<'arena>,
         infos: &[LocalInfo],
     ) -> ArcValue<'arena> {
+        (self.try_apply_local_infos(head_expr, infos)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    fn try_apply_local_infos(
+        &mut self,
+        mut head_expr: ArcValue<'arena>,
+        infos: &[LocalInfo],
+    ) -> Result<ArcValue<'arena>, Error> {
         for (info, expr) in Iterator::zip(infos.iter(), self.local_exprs.iter()) {
             head_expr = match info {
                 LocalInfo::Def => head_expr,
-                LocalInfo::Param => {
-                    self.elim_env
-                        .fun_app(Plicity::Explicit, head_expr, expr.clone())
-                }
+                LocalInfo::Param => (self.elim_env).try_fun_app(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    head_expr,
+                    expr.clone(),
+                )?,
             };
         }
-        head_expr
+        Ok(head_expr)
     }
 }
 
@@ -403,6 +652,8 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
 pub struct ElimEnv<'arena, 'env> {
     item_exprs: &'env SliceEnv<ArcValue<'arena>>,
     meta_exprs: &'env SliceEnv<Option<ArcValue<'arena>>>,
+    fuel: Option<&'env Fuel>,
+    interner: Option<&'env RefCell<StringInterner>>,
 }
 
 impl<'arena, 'env> ElimEnv<'arena, 'env> {
@@ -413,6 +664,56 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
         ElimEnv {
             item_exprs,
             meta_exprs,
+            fuel: None,
+            interner: None,
+        }
+    }
+
+    /// Limit how deeply evaluation may nest, aborting runaway computations
+    /// with [`Error::OutOfFuel`]. Evaluation is unlimited by default.
+    pub fn with_fuel(self, fuel: &'env Fuel) -> ElimEnv<'arena, 'env> {
+        ElimEnv {
+            fuel: Some(fuel),
+            ..self
+        }
+    }
+
+    /// Use the supplied interner to compare strings decoded from binary data
+    /// with string constants. Without an interner these comparisons are stuck.
+    pub fn with_interner(self, interner: &'env RefCell<StringInterner>) -> ElimEnv<'arena, 'env> {
+        ElimEnv {
+            interner: Some(interner),
+            ..self
+        }
+    }
+
+    /// The expressions of the items that are in scope.
+    pub fn item_exprs(&self) -> &'env SliceEnv<ArcValue<'arena>> {
+        self.item_exprs
+    }
+
+    /// The interner used to look up string constants, if any.
+    pub fn interner(&self) -> Option<&'env RefCell<StringInterner>> {
+        self.interner
+    }
+
+    /// Compare a string decoded from binary data with a string constant,
+    /// returning `None` if there is no interner to look the constant up in.
+    pub fn string_eq(&self, string: &str, r#const: StringId) -> Option<bool> {
+        let interner = self.interner?.borrow();
+        Some(interner.resolve(r#const) == Some(string))
+    }
+
+    fn burn_fuel(&self) -> Result<(), Error> {
+        match self.fuel {
+            Some(fuel) => fuel.burn(),
+            None => Ok(()),
+        }
+    }
+
+    fn refund_fuel(&self) {
+        if let Some(fuel) = self.fuel {
+            fuel.refund();
         }
     }
 
@@ -427,32 +728,42 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
         ConversionEnv::new(*self, local_exprs)
     }
 
-    fn get_item_expr(&self, var: Level) -> &'env ArcValue<'arena> {
-        let value = self.item_exprs.get_level(var);
-        value.unwrap_or_else(|| panic_any(Error::UnboundItemVar))
+    fn try_get_item_expr(&self, var: Level) -> Result<&'env ArcValue<'arena>, Error> {
+        self.item_exprs.get_level(var).ok_or(Error::UnboundItemVar)
+    }
+
+    fn try_get_meta_expr(&self, var: Level) -> Result<&'env Option<ArcValue<'arena>>, Error> {
+        self.meta_exprs.get_level(var).ok_or(Error::UnboundMetaVar)
     }
 
     fn get_meta_expr(&self, var: Level) -> &'env Option<ArcValue<'arena>> {
-        let value = self.meta_exprs.get_level(var);
-        value.unwrap_or_else(|| panic_any(Error::UnboundMetaVar))
+        self.try_get_meta_expr(var)
+            .unwrap_or_else(|error| panic_any(error))
     }
 
     /// Bring a value up-to-date with any new unification solutions that
     /// might now be present at the head of in the given value.
     pub fn force(&self, value: &ArcValue<'arena>) -> ArcValue<'arena> {
+        self.try_force(value)
+            .unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Bring a value up-to-date with any new unification solutions, returning
+    /// an [`Error`] instead of panicking if a solution could not be applied.
+    pub fn try_force(&self, value: &ArcValue<'arena>) -> Result<ArcValue<'arena>, Error> {
         let mut forced_value = value.clone();
         // Attempt to force metavariables until we don't see any more.
         while let Value::Stuck(Head::MetaVar(var), spine) = forced_value.as_ref() {
             match self.get_meta_expr(*var) {
                 // Apply the spine to the solution. This might uncover another
                 // metavariable so we'll continue looping.
-                Some(expr) => forced_value = self.apply_spine(expr.clone(), spine),
+                Some(expr) => forced_value = self.try_apply_spine(expr.clone(), spine)?,
                 // There's no solution for this metavariable yet, meaning
                 // that we've forced the value as much as possible for now
                 None => break,
             }
         }
-        forced_value
+        Ok(forced_value)
     }
 
     /// Apply a closure to a value.
@@ -461,50 +772,90 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
         closure: &Closure<'arena>,
         value: ArcValue<'arena>,
     ) -> ArcValue<'arena> {
+        (self.try_apply_closure(closure, value)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Apply a closure to a value, returning an [`Error`] instead of
+    /// panicking if evaluation goes wrong.
+    pub fn try_apply_closure(
+        &self,
+        closure: &Closure<'arena>,
+        value: ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, Error> {
         let mut local_exprs = closure.local_exprs.clone();
         local_exprs.push(value);
-        self.eval_env(&mut local_exprs).eval(closure.term)
+        self.eval_env(&mut local_exprs).try_eval(closure.term)
     }
 
     /// Split a telescope into the first value, and a continuation that returns
     /// a telescope containing the rest of the values.
     pub fn split_telescope(
         &self,
-        mut telescope: Telescope<'arena>,
+        telescope: Telescope<'arena>,
     ) -> Option<(
         ArcValue<'arena>,
         impl FnOnce(ArcValue<'arena>) -> Telescope<'arena>,
     )> {
-        let (term, terms) = telescope.terms.split_first()?;
+        (self.try_split_telescope(telescope)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Split a telescope like [`ElimEnv::split_telescope`], returning an
+    /// [`Error`] instead of panicking if evaluating the first value goes
+    /// wrong.
+    pub fn try_split_telescope(
+        &self,
+        mut telescope: Telescope<'arena>,
+    ) -> Result<
+        Option<(
+            ArcValue<'arena>,
+            impl FnOnce(ArcValue<'arena>) -> Telescope<'arena>,
+        )>,
+        Error,
+    > {
+        let (term, terms) = match telescope.terms.split_first() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
         let mut env = self.eval_env(&mut telescope.local_exprs);
         let value = match telescope.apply_repr {
-            true => self.format_repr(&env.eval(term)),
-            false => env.eval(term),
+            true => self.try_format_repr(&env.try_eval(term)?)?,
+            false => env.try_eval(term)?,
         };
 
-        Some((value, move |previous_value| {
+        Ok(Some((value, move |previous_value| {
             telescope.local_exprs.push(previous_value);
             telescope.terms = terms;
             telescope
-        }))
+        })))
     }
 
     pub fn split_branches<P: Copy>(
         &self,
-        mut branches: Branches<'arena, P>,
+        branches: Branches<'arena, P>,
     ) -> SplitBranches<'arena, P> {
+        (self.try_split_branches(branches)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Split the branches of a constant match like
+    /// [`ElimEnv::split_branches`], returning an [`Error`] instead of
+    /// panicking if evaluating the first branch goes wrong.
+    pub fn try_split_branches<P: Copy>(
+        &self,
+        mut branches: Branches<'arena, P>,
+    ) -> Result<SplitBranches<'arena, P>, Error> {
         match branches.pattern_branches.split_first() {
             Some(((pattern, body_expr), pattern_branches)) => {
                 branches.pattern_branches = pattern_branches;
                 let mut context = self.eval_env(&mut branches.local_exprs);
-                SplitBranches::Branch((*pattern, context.eval(body_expr)), branches)
+                let body_expr = context.try_eval(body_expr)?;
+                Ok(SplitBranches::Branch((*pattern, body_expr), branches))
             }
             None => match branches.default_branch {
-                Some((default_name, default_expr)) => SplitBranches::Default(
+                Some((default_name, default_expr)) => Ok(SplitBranches::Default(
                     default_name,
                     Closure::new(branches.local_exprs, default_expr),
-                ),
-                None => SplitBranches::None,
+                )),
+                None => Ok(SplitBranches::None),
             },
         }
     }
@@ -515,26 +866,39 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
     /// [beta-reduction]: https://ncatlab.org/nlab/show/beta-reduction
     pub fn fun_app(
         &self,
+        span: Span,
         arg_plicity: Plicity,
-        mut head_expr: ArcValue<'arena>,
+        head_expr: ArcValue<'arena>,
         arg_expr: ArcValue<'arena>,
     ) -> ArcValue<'arena> {
+        (self.try_fun_app(span, arg_plicity, head_expr, arg_expr))
+            .unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Apply a function application to an expression, returning an [`Error`]
+    /// instead of panicking if the head is not a function.
+    pub fn try_fun_app(
+        &self,
+        span: Span,
+        arg_plicity: Plicity,
+        mut head_expr: ArcValue<'arena>,
+        arg_expr: ArcValue<'arena>,
+    ) -> Result<ArcValue<'arena>, Error> {
         match Arc::make_mut(&mut head_expr) {
             // Beta-reduction
-            Value::FunLit(fun_plicity, _, body_expr) => {
-                assert_eq!(arg_plicity, *fun_plicity, "Plicities must be equal");
+            Value::FunLit(fun_plicity, _, body_expr) if arg_plicity == *fun_plicity => {
                 // FIXME: use span from head/arg exprs?
-                self.apply_closure(body_expr, arg_expr)
+                self.try_apply_closure(body_expr, arg_expr)
             }
             // The computation is stuck, preventing further reduction
             Value::Stuck(head, spine) => {
-                spine.push(Elim::FunApp(arg_plicity, arg_expr));
+                spine.push(Elim::FunApp(span, arg_plicity, arg_expr));
                 match head {
-                    Head::Prim(prim) => prim::step(*prim)(self, spine).unwrap_or(head_expr),
-                    _ => head_expr,
+                    Head::Prim(prim) => Ok(prim::step(*prim)(self, spine).unwrap_or(head_expr)),
+                    _ => Ok(head_expr),
                 }
             }
-            _ => panic_any(Error::InvalidFunctionApp),
+            _ => Err(Error::InvalidFunctionApp(span)),
         }
     }
 
@@ -544,21 +908,33 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
     /// [beta-reduction]: https://ncatlab.org/nlab/show/beta-reduction
     pub fn record_proj(
         &self,
-        mut head_expr: ArcValue<'arena>,
+        span: Span,
+        head_expr: ArcValue<'arena>,
         label: StringId,
     ) -> ArcValue<'arena> {
+        (self.try_record_proj(span, head_expr, label)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Apply a record projection to an expression, returning an [`Error`]
+    /// instead of panicking if the head is not a record with the label.
+    pub fn try_record_proj(
+        &self,
+        span: Span,
+        mut head_expr: ArcValue<'arena>,
+        label: StringId,
+    ) -> Result<ArcValue<'arena>, Error> {
         match Arc::make_mut(&mut head_expr) {
             // Beta-reduction
             Value::RecordLit(labels, exprs) => (labels.iter())
                 .position(|current_label| *current_label == label)
                 .and_then(|expr_index| exprs.get(expr_index).cloned())
-                .unwrap_or_else(|| panic_any(Error::InvalidRecordProj)),
+                .ok_or(Error::InvalidRecordProj(span)),
             // The computation is stuck, preventing further reduction
             Value::Stuck(_, spine) => {
-                spine.push(Elim::RecordProj(label));
-                head_expr
+                spine.push(Elim::RecordProj(span, label));
+                Ok(head_expr)
             }
-            _ => panic_any(Error::InvalidRecordProj),
+            _ => Err(Error::InvalidRecordProj(span)),
         }
     }
 
@@ -568,61 +944,213 @@ impl<'arena, 'env> ElimEnv<'arena, 'env> {
     /// [beta-reduction]: https://ncatlab.org/nlab/show/beta-reduction
     fn const_match(
         &self,
-        mut head_expr: ArcValue<'arena>,
-        mut branches: Branches<'arena, Const>,
+        span: Span,
+        head_expr: ArcValue<'arena>,
+        branches: Branches<'arena, ConstPattern>,
     ) -> ArcValue<'arena> {
-        match Arc::make_mut(&mut head_expr) {
+        (self.try_const_match(span, head_expr, branches)).unwrap_or_else(|error| panic_any(error))
+    }
+
+    fn try_const_match(
+        &self,
+        span: Span,
+        mut head_expr: ArcValue<'arena>,
+        mut branches: Branches<'arena, ConstPattern>,
+    ) -> Result<ArcValue<'arena>, Error> {
+        // Try each branch
+        let branch = match Arc::make_mut(&mut head_expr) {
             Value::ConstLit(r#const) => {
-                // Try each branch
-                for (branch_const, body_expr) in branches.pattern_branches {
-                    if r#const == branch_const {
-                        return self.eval_env(&mut branches.local_exprs).eval(body_expr);
-                    }
-                }
-                // Otherwise call default with `head_expr`
-                let mut local_exprs = branches.local_exprs.clone();
-                match branches.default_branch {
-                    Some((_, default_expr)) => {
-                        local_exprs.push(head_expr);
-                        self.eval_env(&mut local_exprs).eval(default_expr)
+                (branches.pattern_branches.iter()).find(|(pattern, _)| pattern.matches(r#const))
+            }
+            Value::StringLit(string) => {
+                let mut matched = None;
+                for branch in branches.pattern_branches {
+                    if let (ConstPattern::Const(Const::String(r#const)), _) = branch {
+                        match self.string_eq(string, *r#const) {
+                            Some(true) => matched = Some(branch),
+                            Some(false) => continue,
+                            None => return Err(Error::MissingInterner(span)),
+                        }
+                        break;
                     }
-                    None => panic_any(Error::MissingConstDefault),
                 }
+                matched
             }
             // The computation is stuck, preventing further reduction
             Value::Stuck(_, spine) => {
-                spine.push(Elim::ConstMatch(branches));
-                head_expr
+                spine.push(Elim::ConstMatch(span, branches));
+                return Ok(head_expr);
             }
-            _ => panic_any(Error::InvalidConstMatch),
+            _ => return Err(Error::InvalidConstMatch(span)),
+        };
+        if let Some((_, body_expr)) = branch {
+            return self.eval_env(&mut branches.local_exprs).try_eval(body_expr);
+        }
+        // Otherwise call default with `head_expr`
+        let mut local_exprs = branches.local_exprs.clone();
+        match branches.default_branch {
+            Some((_, default_expr)) => {
+                local_exprs.push(head_expr);
+                self.eval_env(&mut local_exprs).try_eval(default_expr)
+            }
+            None => Err(Error::MissingConstDefault(span)),
         }
     }
 
-    /// Apply an expression to an elimination spine.
-    fn apply_spine(&self, head_expr: ArcValue<'arena>, spine: &[Elim<'arena>]) -> ArcValue<'arena> {
-        spine.iter().fold(head_expr, |head_expr, elim| match elim {
-            Elim::FunApp(plicity, arg_expr) => self.fun_app(*plicity, head_expr, arg_expr.clone()),
-            Elim::RecordProj(label) => self.record_proj(head_expr, *label),
-            Elim::ConstMatch(split) => self.const_match(head_expr, split.clone()),
-        })
+    /// Apply an expression to an elimination spine, returning an [`Error`]
+    /// that refers to the span of the failed elimination instead of
+    /// panicking.
+    fn try_apply_spine(
+        &self,
+        head_expr: ArcValue<'arena>,
+        spine: &[Elim<'arena>],
+    ) -> Result<ArcValue<'arena>, Error> {
+        spine
+            .iter()
+            .try_fold(head_expr, |head_expr, elim| match elim {
+                Elim::FunApp(span, plicity, arg_expr) => {
+                    self.try_fun_app(*span, *plicity, head_expr, arg_expr.clone())
+                }
+                Elim::RecordProj(span, label) => self.try_record_proj(*span, head_expr, *label),
+                Elim::ConstMatch(span, split) => {
+                    self.try_const_match(*span, head_expr, split.clone())
+                }
+            })
     }
 
     /// Find the representation type of a format description.
+    ///
+    /// The fields of record and overlap formats appear in the representation
+    /// type in the order that they were declared in the format.
     pub fn format_repr(&self, format: &ArcValue<'arena>) -> ArcValue<'arena> {
+        self.try_format_repr(format)
+            .unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Find the representation type of a format description, returning an
+    /// [`Error`] instead of panicking if the value is not a format.
+    pub fn try_format_repr(&self, format: &ArcValue<'arena>) -> Result<ArcValue<'arena>, Error> {
         let value = match format.as_ref() {
             Value::FormatRecord(labels, formats) | Value::FormatOverlap(labels, formats) => {
                 Value::RecordType(labels, formats.clone().apply_repr())
             }
-            Value::FormatCond(_, format, _) => return self.format_repr(format),
+            Value::FormatCond(_, format, _) => return self.try_format_repr(format),
             Value::Stuck(Head::Prim(prim), spine) => match prim::repr(*prim)(self, spine) {
-                Some(r#type) => return r#type,
+                Some(r#type) => return Ok(r#type),
                 None => Value::prim(Prim::FormatRepr, [format.clone()]),
             },
             Value::Stuck(_, _) => Value::prim(Prim::FormatRepr, [format.clone()]),
-            _ => panic_any(Error::InvalidFormatRepr),
+            _ => return Err(Error::InvalidFormatRepr),
         };
 
-        Spanned::new(format.span(), Arc::new(value))
+        Ok(Spanned::new(format.span(), Arc::new(value)))
+    }
+
+    /// Compute the number of bytes that a format consumes, returning `None` if
+    /// this depends on the data being read.
+    ///
+    /// Fields in record and overlap formats are visited in telescope order,
+    /// with later fields only seeing the earlier fields as variables. This
+    /// means that fields sized by earlier fields are treated as dynamic.
+    pub fn static_size(&self, format: &ArcValue<'arena>) -> Option<u64> {
+        match format.as_ref() {
+            Value::FormatRecord(_, formats) => {
+                let mut formats = formats.clone();
+                let mut size = 0_u64;
+                while let Some((format, next_formats)) = self.split_telescope(formats.clone()) {
+                    size = size.checked_add(self.static_size(&format)?)?;
+                    formats = next_formats(Self::telescope_var(&formats));
+                }
+                Some(size)
+            }
+            Value::FormatOverlap(_, formats) => {
+                let mut formats = formats.clone();
+                let mut size = 0_u64;
+                while let Some((format, next_formats)) = self.split_telescope(formats.clone()) {
+                    size = u64::max(size, self.static_size(&format)?);
+                    formats = next_formats(Self::telescope_var(&formats));
+                }
+                Some(size)
+            }
+            Value::FormatCond(_, format, _) => self.static_size(format),
+            Value::Stuck(Head::Prim(prim), spine) => match (prim, &spine[..]) {
+                (Prim::FormatU8 | Prim::FormatS8, []) => Some(1),
+                (Prim::FormatU16Be | Prim::FormatU16Le, [])
+                | (Prim::FormatS16Be | Prim::FormatS16Le, []) => Some(2),
+                (Prim::FormatU24Be | Prim::FormatU24Le, []) => Some(3),
+                (Prim::FormatU32Be | Prim::FormatU32Le, [])
+                | (Prim::FormatS32Be | Prim::FormatS32Le, [])
+                | (Prim::FormatF32Be | Prim::FormatF32Le, []) => Some(4),
+                (Prim::FormatU64Be | Prim::FormatU64Le, [])
+                | (Prim::FormatS64Be | Prim::FormatS64Le, [])
+                | (Prim::FormatF64Be | Prim::FormatF64Le, []) => Some(8),
+                (Prim::FormatU128Be | Prim::FormatU128Le, [])
+                | (Prim::FormatS128Be | Prim::FormatS128Le, []) => Some(16),
+                (
+                    Prim::FormatRepeatLen8
+                    | Prim::FormatRepeatLen16
+                    | Prim::FormatRepeatLen32
+                    | Prim::FormatRepeatLen64
+                    | Prim::FormatRepeatCount,
+                    [Elim::FunApp(_, _, len), Elim::FunApp(_, _, elem)],
+                ) => {
+                    let len = match len.as_ref() {
+                        Value::ConstLit(Const::U8(len, _)) => u64::from(*len),
+                        Value::ConstLit(Const::U16(len, _)) => u64::from(*len),
+                        Value::ConstLit(Const::U32(len, _)) => u64::from(*len),
+                        Value::ConstLit(Const::U64(len, _)) => *len,
+                        _ => return None,
+                    };
+                    len.checked_mul(self.static_size(elem)?)
+                }
+                (Prim::FormatUtf8 | Prim::FormatReserved, [Elim::FunApp(_, _, len)]) => {
+                    match len.as_ref() {
+                        Value::ConstLit(Const::U64(len, _)) => Some(*len),
+                        _ => None,
+                    }
+                }
+                (Prim::FormatStreamPos | Prim::FormatRelPos, []) => Some(0),
+                (Prim::FormatSucceed, [_, _]) => Some(0),
+                (Prim::FormatWithBase, [Elim::FunApp(_, _, format)]) => self.static_size(format),
+                (Prim::FormatDebug, [_, Elim::FunApp(_, _, format)]) => self.static_size(format),
+                (Prim::FormatMap, [_, Elim::FunApp(_, _, format), _]) => self.static_size(format),
+                (Prim::FormatBind, [Elim::FunApp(_, _, format), Elim::FunApp(_, _, next)]) => {
+                    let next_format = match next.as_ref() {
+                        Value::FunLit(_, _, next) => {
+                            self.apply_closure(next, Self::closure_var(next))
+                        }
+                        _ => return None,
+                    };
+                    (self.static_size(format)?).checked_add(self.static_size(&next_format)?)
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// A variable standing in for the next field of a telescope.
+    pub fn telescope_var(telescope: &Telescope<'arena>) -> ArcValue<'arena> {
+        let level = telescope.local_exprs.len().next_level();
+        Spanned::empty(Arc::new(Value::local_var(level)))
+    }
+
+    /// A variable standing in for the parameter of a closure.
+    fn closure_var(closure: &Closure<'arena>) -> ArcValue<'arena> {
+        let level = closure.local_exprs.len().next_level();
+        Spanned::empty(Arc::new(Value::local_var(level)))
+    }
+
+    /// The format that a bind format continues with, provided that it does not
+    /// depend on the data read by the first format. Returns `None` otherwise,
+    /// as the representation of the bind format then depends on that data.
+    pub fn bind_next_format(&self, next: &ArcValue<'arena>) -> Option<ArcValue<'arena>> {
+        match next.as_ref() {
+            Value::FunLit(_, _, next) if !next.term.binds_local(Index::last()) => {
+                Some(self.apply_closure(next, Self::closure_var(next)))
+            }
+            _ => None,
+        }
     }
 }
 
@@ -634,6 +1162,12 @@ pub struct QuoteEnv<'in_arena, 'env> {
     elim_env: ElimEnv<'in_arena, 'env>,
     local_exprs: EnvLen,
     unfold_metas: bool,
+    /// The number of nested metavariable solutions that may be unfolded, or
+    /// `None` if there is no limit.
+    meta_unfold_depth: Option<usize>,
+    /// The number of elements of each array that may be quoted, or `None` if
+    /// there is no limit.
+    max_array_elems: Option<usize>,
 }
 
 impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
@@ -645,6 +1179,8 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
             elim_env,
             local_exprs,
             unfold_metas: false,
+            meta_unfold_depth: None,
+            max_array_elems: None,
         }
     }
 
@@ -653,6 +1189,24 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
         self
     }
 
+    /// Unfold solved metavariables, but only up to `depth` levels deep.
+    /// Metavariables appearing in deeper solutions are left as
+    /// [`Term::MetaVar`]s, which is useful for keeping large solutions
+    /// readable when debugging.
+    pub fn unfolding_metas_to_depth(mut self, depth: usize) -> QuoteEnv<'in_arena, 'env> {
+        self.unfold_metas = true;
+        self.meta_unfold_depth = Some(depth);
+        self
+    }
+
+    /// Only quote the first `max_elems` elements of array literals, replacing
+    /// the rest with a [`Term::ElidedElems`] marker. The resulting terms are
+    /// only intended for display.
+    pub fn limiting_array_elems(mut self, max_elems: usize) -> QuoteEnv<'in_arena, 'env> {
+        self.max_array_elems = Some(max_elems);
+        self
+    }
+
     fn push_local(&mut self) {
         self.local_exprs.push();
     }
@@ -662,106 +1216,198 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
     }
 
     /// Quote a [value][Value] back into a [term][Term].
+    ///
+    /// # Panics
+    ///
+    /// This panics with an [`Error`] if evaluation goes wrong while quoting
+    /// under binders. Use [`QuoteEnv::try_quote`] to recover from these
+    /// errors.
     pub fn quote<'out_arena>(
         &mut self,
         scope: &'out_arena Scope<'out_arena>,
         value: &ArcValue<'in_arena>,
     ) -> Term<'out_arena> {
+        self.try_quote(scope, value)
+            .unwrap_or_else(|error| panic_any(error))
+    }
+
+    /// Quote a [value][Value] back into a [term][Term], returning an
+    /// [`Error`] instead of panicking if evaluation goes wrong while quoting
+    /// under binders.
+    pub fn try_quote<'out_arena>(
+        &mut self,
+        scope: &'out_arena Scope<'out_arena>,
+        value: &ArcValue<'in_arena>,
+    ) -> Result<Term<'out_arena>, Error> {
+        let depth = self.meta_unfold_depth;
+        let value = match depth {
+            None => self.elim_env.try_force(value),
+            Some(_) => self.force_to_depth(value),
+        };
+        let term = value.and_then(|value| self.quote_forced(scope, &value));
+        self.meta_unfold_depth = depth;
+        term
+    }
+
+    /// Force a value like [`ElimEnv::force`], but only unfolding as many
+    /// metavariable solutions as the remaining unfolding depth allows. The
+    /// depth is reduced for each solution that is unfolded, limiting the
+    /// unfolding of the metavariables within it.
+    fn force_to_depth(
+        &mut self,
+        value: &ArcValue<'in_arena>,
+    ) -> Result<ArcValue<'in_arena>, Error> {
+        let mut forced_value = value.clone();
+        while let Value::Stuck(Head::MetaVar(var), spine) = forced_value.as_ref() {
+            match (self.meta_unfold_depth, self.elim_env.get_meta_expr(*var)) {
+                (Some(depth @ 1..), Some(expr)) => {
+                    forced_value = self.elim_env.try_apply_spine(expr.clone(), spine)?;
+                    self.meta_unfold_depth = Some(depth - 1);
+                }
+                (_, _) => break,
+            }
+        }
+        Ok(forced_value)
+    }
+
+    fn quote_forced<'out_arena>(
+        &mut self,
+        scope: &'out_arena Scope<'out_arena>,
+        value: &ArcValue<'in_arena>,
+    ) -> Result<Term<'out_arena>, Error> {
         // NOTE: this copies more than is necessary when `'in_arena == 'out_arena`:
         // for example when copying label slices.
 
-        let value = self.elim_env.force(value);
         let span = value.span();
-        match value.as_ref() {
-            Value::Stuck(head, spine) => spine.iter().fold(
-                self.quote_head(scope, span, head),
-                |head_expr, elim| match elim {
-                    Elim::FunApp(plicity, arg_expr) => Term::FunApp(
-                        span,
-                        *plicity,
-                        scope.to_scope(head_expr),
-                        scope.to_scope(self.quote(scope, arg_expr)),
-                    ),
-                    Elim::RecordProj(label) => {
-                        Term::RecordProj(span, scope.to_scope(head_expr), *label)
-                    }
-                    Elim::ConstMatch(branches) => {
-                        let mut branches = branches.clone();
-                        let mut pattern_branches = SliceVec::new(scope, branches.num_patterns());
-
-                        let default_branch = loop {
-                            match self.elim_env.split_branches(branches) {
-                                SplitBranches::Branch((r#const, body_expr), next_branches) => {
-                                    pattern_branches.push((r#const, self.quote(scope, &body_expr)));
-                                    branches = next_branches;
-                                }
-                                SplitBranches::Default(default_name, default_expr) => {
-                                    break Some((default_name, default_expr))
-                                }
-                                SplitBranches::None => break None,
-                            }
-                        };
-
-                        Term::ConstMatch(
+        let term =
+            match value.as_ref() {
+                Value::Stuck(head, spine) => spine.iter().try_fold(
+                    self.quote_head(scope, span, head)?,
+                    |head_expr, elim| match elim {
+                        Elim::FunApp(_, plicity, arg_expr) => Ok(Term::FunApp(
                             span,
+                            *plicity,
                             scope.to_scope(head_expr),
-                            pattern_branches.into(),
-                            default_branch
-                                .map(|(name, expr)| (name, self.quote_closure(scope, &expr))),
-                        )
-                    }
-                },
-            ),
+                            scope.to_scope(self.try_quote(scope, arg_expr)?),
+                        )),
+                        Elim::RecordProj(_, label) => {
+                            Ok(Term::RecordProj(span, scope.to_scope(head_expr), *label))
+                        }
+                        Elim::ConstMatch(_, branches) => {
+                            let mut branches = branches.clone();
+                            let mut pattern_branches =
+                                SliceVec::new(scope, branches.num_patterns());
+
+                            let default_branch = loop {
+                                match self.elim_env.try_split_branches(branches)? {
+                                    SplitBranches::Branch((pattern, body_expr), next_branches) => {
+                                        let body_expr = self.try_quote(scope, &body_expr)?;
+                                        pattern_branches.push((pattern, body_expr));
+                                        branches = next_branches;
+                                    }
+                                    SplitBranches::Default(default_name, default_expr) => {
+                                        break Some((default_name, default_expr))
+                                    }
+                                    SplitBranches::None => break None,
+                                }
+                            };
+                            let default_branch = (default_branch
+                                .map(|(name, expr)| Ok((name, self.quote_closure(scope, &expr)?))))
+                            .transpose()?;
+
+                            Ok(Term::ConstMatch(
+                                span,
+                                scope.to_scope(head_expr),
+                                pattern_branches.into(),
+                                default_branch,
+                            ))
+                        }
+                    },
+                )?,
+
+                Value::Universe => Term::Universe(span),
+
+                Value::FunType(plicity, param_name, param_type, body_type) => Term::FunType(
+                    span,
+                    *plicity,
+                    *param_name,
+                    scope.to_scope(self.try_quote(scope, param_type)?),
+                    self.quote_closure(scope, body_type)?,
+                ),
+                Value::FunLit(plicity, param_name, body_expr) => Term::FunLit(
+                    span,
+                    *plicity,
+                    *param_name,
+                    self.quote_closure(scope, body_expr)?,
+                ),
 
-            Value::Universe => Term::Universe(span),
+                Value::RecordType(labels, types) => Term::RecordType(
+                    span,
+                    scope.to_scope_from_iter(labels.iter().copied()),
+                    self.quote_telescope(scope, types)?,
+                ),
+                Value::RecordLit(labels, exprs) => {
+                    let exprs = exprs.iter().map(|expr| self.try_quote(scope, expr));
+                    let exprs = exprs.collect::<Result<Vec<_>, _>>()?;
+                    Term::RecordLit(
+                        span,
+                        scope.to_scope_from_iter(labels.iter().copied()),
+                        scope.to_scope_from_iter(exprs),
+                    )
+                }
+                Value::ArrayLit(exprs) => {
+                    let (exprs, elided) = self.limit_array_elems(span, exprs);
+                    let exprs = exprs.iter().map(|expr| self.try_quote(scope, expr));
+                    let exprs = exprs.collect::<Result<Vec<_>, _>>()?;
+                    Term::ArrayLit(
+                        span,
+                        scope.to_scope_from_iter(exprs.into_iter().chain(elided)),
+                    )
+                }
+                Value::ByteArrayLit(bytes) => {
+                    let (bytes, elided) = self.limit_array_elems(span, bytes);
+                    let exprs = (bytes.iter())
+                        .map(|byte| Term::ConstLit(span, Const::U8(*byte, UIntStyle::Decimal)));
+                    Term::ArrayLit(span, scope.to_scope_from_iter(exprs.chain(elided)))
+                }
 
-            Value::FunType(plicity, param_name, param_type, body_type) => Term::FunType(
-                span,
-                *plicity,
-                *param_name,
-                scope.to_scope(self.quote(scope, param_type)),
-                self.quote_closure(scope, body_type),
-            ),
-            Value::FunLit(plicity, param_name, body_expr) => Term::FunLit(
-                span,
-                *plicity,
-                *param_name,
-                self.quote_closure(scope, body_expr),
-            ),
+                Value::FormatRecord(labels, formats) => Term::FormatRecord(
+                    span,
+                    scope.to_scope_from_iter(labels.iter().copied()),
+                    self.quote_telescope(scope, formats)?,
+                ),
+                Value::FormatCond(label, format, cond) => Term::FormatCond(
+                    span,
+                    *label,
+                    scope.to_scope(self.try_quote(scope, format)?),
+                    self.quote_closure(scope, cond)?,
+                ),
+                Value::FormatOverlap(labels, formats) => Term::FormatOverlap(
+                    span,
+                    scope.to_scope_from_iter(labels.iter().copied()),
+                    self.quote_telescope(scope, formats)?,
+                ),
 
-            Value::RecordType(labels, types) => Term::RecordType(
-                span,
-                scope.to_scope_from_iter(labels.iter().copied()),
-                self.quote_telescope(scope, types),
-            ),
-            Value::RecordLit(labels, exprs) => Term::RecordLit(
-                span,
-                scope.to_scope_from_iter(labels.iter().copied()),
-                scope.to_scope_from_iter(exprs.iter().map(|expr| self.quote(scope, expr))),
-            ),
-            Value::ArrayLit(exprs) => Term::ArrayLit(
-                span,
-                scope.to_scope_from_iter(exprs.iter().map(|expr| self.quote(scope, expr))),
-            ),
+                Value::ConstLit(r#const) => Term::ConstLit(span, *r#const),
+                Value::StringLit(string) => Term::StringLit(span, to_scope_str(scope, string)),
+            };
 
-            Value::FormatRecord(labels, formats) => Term::FormatRecord(
-                span,
-                scope.to_scope_from_iter(labels.iter().copied()),
-                self.quote_telescope(scope, formats),
-            ),
-            Value::FormatCond(label, format, cond) => Term::FormatCond(
-                span,
-                *label,
-                scope.to_scope(self.quote(scope, format)),
-                self.quote_closure(scope, cond),
-            ),
-            Value::FormatOverlap(labels, formats) => Term::FormatOverlap(
-                span,
-                scope.to_scope_from_iter(labels.iter().copied()),
-                self.quote_telescope(scope, formats),
-            ),
+        Ok(term)
+    }
 
-            Value::ConstLit(r#const) => Term::ConstLit(span, *r#const),
+    /// Split the elements of an array into those that should be quoted, and
+    /// a marker for any elements that were elided.
+    fn limit_array_elems<'elems, 'out_arena, T>(
+        &self,
+        span: Span,
+        elems: &'elems [T],
+    ) -> (&'elems [T], Option<Term<'out_arena>>) {
+        match self.max_array_elems {
+            Some(max_elems) if max_elems < elems.len() => (
+                &elems[..max_elems],
+                Some(Term::ElidedElems(span, elems.len() - max_elems)),
+            ),
+            _ => (elems, None),
         }
     }
 
@@ -771,22 +1417,22 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
         scope: &'out_arena Scope<'out_arena>,
         span: Span,
         head: &Head,
-    ) -> Term<'out_arena> {
+    ) -> Result<Term<'out_arena>, Error> {
         match head {
-            Head::Prim(prim) => Term::Prim(span, *prim),
+            Head::Prim(prim) => Ok(Term::Prim(span, *prim)),
             Head::LocalVar(var) => match self.local_exprs.level_to_index(*var) {
-                Some(var) => Term::LocalVar(span, var),
-                None => panic_any(Error::UnboundLocalVar),
+                Some(var) => Ok(Term::LocalVar(span, var)),
+                None => Err(Error::UnboundLocalVar),
             },
-            Head::MetaVar(var) if self.unfold_metas => {
+            Head::MetaVar(var) if self.unfold_metas && self.meta_unfold_depth != Some(0) => {
                 match self.elim_env.get_meta_expr(*var) {
                     // The metavariable has a solution, so unfold it.
-                    Some(value) => self.quote(scope, value),
+                    Some(value) => self.try_quote(scope, value),
                     // NOTE: We might want to replace this with `ReportedError`.
-                    None => Term::MetaVar(span, *var),
+                    None => Ok(Term::MetaVar(span, *var)),
                 }
             }
-            Head::MetaVar(var) => Term::MetaVar(span, *var),
+            Head::MetaVar(var) => Ok(Term::MetaVar(span, *var)),
         }
     }
 
@@ -795,15 +1441,17 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
         &mut self,
         scope: &'out_arena Scope<'out_arena>,
         closure: &Closure<'in_arena>,
-    ) -> &'out_arena Term<'out_arena> {
+    ) -> Result<&'out_arena Term<'out_arena>, Error> {
         let var = Arc::new(Value::local_var(self.local_exprs.next_level()));
-        let value = self.elim_env.apply_closure(closure, Spanned::empty(var));
+        let value = self
+            .elim_env
+            .try_apply_closure(closure, Spanned::empty(var))?;
 
         self.push_local();
-        let term = self.quote(scope, &value);
+        let term = self.try_quote(scope, &value);
         self.pop_local();
 
-        scope.to_scope(term)
+        Ok(scope.to_scope(term?))
     }
 
     /// Quote a [telescope][Telescope] back into a slice of [terms][Term].
@@ -811,20 +1459,25 @@ impl<'in_arena, 'env> QuoteEnv<'in_arena, 'env> {
         &mut self,
         scope: &'out_arena Scope<'out_arena>,
         telescope: &Telescope<'in_arena>,
-    ) -> &'out_arena [Term<'out_arena>] {
+    ) -> Result<&'out_arena [Term<'out_arena>], Error> {
         let initial_local_len = self.local_exprs;
         let mut telescope = telescope.clone();
         let mut terms = SliceVec::new(scope, telescope.len());
 
-        while let Some((value, next_telescope)) = self.elim_env.split_telescope(telescope) {
-            let var = Arc::new(Value::local_var(self.local_exprs.next_level()));
-            telescope = next_telescope(Spanned::empty(var));
-            terms.push(self.quote(scope, &value));
-            self.local_exprs.push();
-        }
+        let result = (|| {
+            while let Some((value, next_telescope)) =
+                self.elim_env.try_split_telescope(telescope)?
+            {
+                let var = Arc::new(Value::local_var(self.local_exprs.next_level()));
+                telescope = next_telescope(Spanned::empty(var));
+                terms.push(self.try_quote(scope, &value)?);
+                self.local_exprs.push();
+            }
+            Ok(())
+        })();
 
         self.local_exprs.truncate(initial_local_len);
-        terms.into()
+        result.map(|()| terms.into())
     }
 }
 
@@ -918,6 +1571,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                 *span,
                 scope.to_scope_from_iter(exprs.iter().map(|expr| self.unfold_metas(scope, expr))),
             ),
+            Term::ElidedElems(span, len) => Term::ElidedElems(*span, *len),
 
             Term::FormatRecord(span, labels, formats) => Term::FormatRecord(
                 *span,
@@ -939,6 +1593,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
             Term::Prim(span, prim) => Term::Prim(*span, *prim),
 
             Term::ConstLit(span, r#const) => Term::ConstLit(*span, *r#const),
+            Term::StringLit(span, string) => Term::StringLit(*span, to_scope_str(scope, string)),
         }
     }
 
@@ -982,7 +1637,9 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                     )),
                     TermOrValue::Value(head_expr) => {
                         let arg_expr = self.eval(arg_expr);
-                        TermOrValue::Value(self.elim_env.fun_app(*plicity, head_expr, arg_expr))
+                        TermOrValue::Value(
+                            self.elim_env.fun_app(*span, *plicity, head_expr, arg_expr),
+                        )
                     }
                 }
             }
@@ -994,7 +1651,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                         *label,
                     )),
                     TermOrValue::Value(head_expr) => {
-                        TermOrValue::Value(self.elim_env.record_proj(head_expr, *label))
+                        TermOrValue::Value(self.elim_env.record_proj(*span, head_expr, *label))
                     }
                 }
             }
@@ -1005,7 +1662,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                         scope.to_scope(head_expr),
                         scope.to_scope_from_iter(
                             (branches.iter())
-                                .map(|(r#const, expr)| (*r#const, self.unfold_metas(scope, expr))),
+                                .map(|(pattern, expr)| (*pattern, self.unfold_metas(scope, expr))),
                         ),
                         default_branch
                             .map(|(name, expr)| (name, self.unfold_bound_metas(scope, expr))),
@@ -1013,7 +1670,7 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
                     TermOrValue::Value(head_expr) => {
                         let branches =
                             Branches::new(self.local_exprs.clone(), branches, *default_branch);
-                        TermOrValue::Value(self.elim_env.const_match(head_expr, branches))
+                        TermOrValue::Value(self.elim_env.const_match(*span, head_expr, branches))
                     }
                 }
             }
@@ -1057,6 +1714,34 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
     }
 }
 
+/// A step into the structure of a value, used to describe where two values
+/// differ.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MismatchStep {
+    /// The parameter type of a function type.
+    FunParamType,
+    /// The body type of a function type.
+    FunBodyType,
+    /// The body expression of a function literal.
+    FunBodyExpr,
+    /// A field of a record type, record literal, or format.
+    Field(StringId),
+    /// An element of an array literal.
+    ArrayElem(usize),
+    /// An argument in the spine of a stuck value.
+    Arg(usize),
+}
+
+/// The location of the first difference found between two values.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    /// Steps taken from the compared values to the point of difference,
+    /// starting from the outermost value.
+    pub path: Vec<MismatchStep>,
+    /// The spans of the differing parts of the first and second values.
+    pub spans: (Span, Span),
+}
+
 /// Conversion environment.
 ///
 /// This environment keeps track of the length of the local environment,
@@ -1064,6 +1749,9 @@ impl<'arena, 'env> EvalEnv<'arena, 'env> {
 pub struct ConversionEnv<'arena, 'env> {
     elim_env: ElimEnv<'arena, 'env>,
     local_exprs: EnvLen,
+    /// Whether the fields of record types and record literals are matched
+    /// by label, rather than by position.
+    order_insensitive_records: bool,
 }
 
 impl<'arena, 'env> ConversionEnv<'arena, 'env> {
@@ -1074,9 +1762,20 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
         ConversionEnv {
             elim_env,
             local_exprs,
+            order_insensitive_records: false,
         }
     }
 
+    /// Treat record types and record literals as equal if their fields are
+    /// equal when matched by label, regardless of the order they appear in.
+    ///
+    /// The types of dependent fields must still line up, so a field may only
+    /// depend on fields that appear before it in both records. Record formats
+    /// are always compared in order, as this determines their binary layout.
+    pub fn set_order_insensitive_records(&mut self, order_insensitive_records: bool) {
+        self.order_insensitive_records = order_insensitive_records;
+    }
+
     fn push_local(&mut self) {
         self.local_exprs.push();
     }
@@ -1092,9 +1791,30 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
     ///
     /// We perform [eta-conversion] here, if possible.
     ///
+    /// Record types, record literals, and record formats are only equal if
+    /// their fields appear in the same order. Field order determines the
+    /// binary layout of record formats, and [`ElimEnv::format_repr`] preserves
+    /// this order in the representation type, so reordering the fields of a
+    /// record is not a definitional equality by default. See
+    /// [`ConversionEnv::set_order_insensitive_records`] for relaxing this for
+    /// record types and record literals.
+    ///
     /// [computationally equal]: https://ncatlab.org/nlab/show/equality#computational_equality
     /// [eta-conversion]: https://ncatlab.org/nlab/show/eta-conversion
     pub fn is_equal(&mut self, value0: &ArcValue<'_>, value1: &ArcValue<'_>) -> bool {
+        // Values are always equal to themselves, so there's no need to force or
+        // traverse them if they are the same allocation. This is common when
+        // unifying a value with itself.
+        if Arc::ptr_eq(value0, value1) {
+            return true;
+        }
+        // Values with different constructors are never equal, so there's no
+        // need to force them, unless they could be unfolded by forcing or
+        // compared using eta-conversion.
+        if value0.has_distinct_rigid_constructor(value1) {
+            return false;
+        }
+
         let value0 = self.elim_env.force(value0);
         let value1 = self.elim_env.force(value1);
 
@@ -1128,12 +1848,27 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
             }
 
             (Value::RecordType(labels0, types0), Value::RecordType(labels1, types1)) => {
-                labels0 == labels1 && self.is_equal_telescopes(types0, types1)
+                if labels0 == labels1 {
+                    self.is_equal_telescopes(types0, types1)
+                } else {
+                    self.order_insensitive_records
+                        && self.is_equal_reordered_telescopes(labels0, types0, labels1, types1)
+                }
             }
             (Value::RecordLit(labels0, exprs0), Value::RecordLit(labels1, exprs1)) => {
-                labels0 == labels1
-                    && Iterator::zip(exprs0.iter(), exprs1.iter())
+                if labels0 == labels1 {
+                    Iterator::zip(exprs0.iter(), exprs1.iter())
                         .all(|(expr0, expr1)| self.is_equal(expr0, expr1))
+                } else {
+                    self.order_insensitive_records
+                        && labels0.len() == labels1.len()
+                        && Iterator::zip(labels1.iter(), exprs1.iter()).all(|(label, expr1)| {
+                            match labels0.iter().position(|label0| label0 == label) {
+                                Some(index) => self.is_equal(&exprs0[index], expr1),
+                                None => false,
+                            }
+                        })
+                }
             }
             (Value::RecordLit(labels, exprs), _) => {
                 self.is_equal_record_lit(labels, exprs, &value1)
@@ -1146,6 +1881,18 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
                 Iterator::zip(exprs0.iter(), exprs1.iter())
                     .all(|(expr0, expr1)| self.is_equal(expr0, expr1))
             }
+            (Value::ByteArrayLit(bytes0), Value::ByteArrayLit(bytes1)) => bytes0 == bytes1,
+            (Value::ByteArrayLit(_), Value::ArrayLit(_))
+            | (Value::ArrayLit(_), Value::ByteArrayLit(_)) => {
+                let len = value0.array_len();
+                len == value1.array_len()
+                    && (0..len.unwrap_or(0)).all(|index| {
+                        match Option::zip(value0.array_elem(index), value1.array_elem(index)) {
+                            Some((expr0, expr1)) => self.is_equal(&expr0, &expr1),
+                            None => false,
+                        }
+                    })
+            }
 
             (Value::FormatRecord(labels0, formats0), Value::FormatRecord(labels1, formats1))
             | (Value::FormatOverlap(labels0, formats0), Value::FormatOverlap(labels1, formats1)) => {
@@ -1162,6 +1909,11 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
             }
 
             (Value::ConstLit(const0), Value::ConstLit(const1)) => const0 == const1,
+            (Value::StringLit(string0), Value::StringLit(string1)) => string0 == string1,
+            (Value::StringLit(string), Value::ConstLit(Const::String(r#const)))
+            | (Value::ConstLit(Const::String(r#const)), Value::StringLit(string)) => {
+                self.elim_env.string_eq(string, *r#const) == Some(true)
+            }
 
             (_, _) => false,
         }
@@ -1172,11 +1924,11 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
         spine0.len() == spine1.len()
             && Iterator::zip(spine0.iter(), spine1.iter()).all(|(elim0, elim1)| {
                 match (elim0, elim1) {
-                    (Elim::FunApp(plicity0, expr0), Elim::FunApp(plicity1, expr1)) => {
+                    (Elim::FunApp(_, plicity0, expr0), Elim::FunApp(_, plicity1, expr1)) => {
                         plicity0 == plicity1 && self.is_equal(expr0, expr1)
                     }
-                    (Elim::RecordProj(label0), Elim::RecordProj(label1)) => label0 == label1,
-                    (Elim::ConstMatch(branches0), Elim::ConstMatch(branches1)) => {
+                    (Elim::RecordProj(_, label0), Elim::RecordProj(_, label1)) => label0 == label1,
+                    (Elim::ConstMatch(_, branches0), Elim::ConstMatch(_, branches1)) => {
                         self.is_equal_branches(branches0, branches1)
                     }
                     (_, _) => false,
@@ -1230,6 +1982,65 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
         true
     }
 
+    /// Check that two [telescopes][Telescope] are equal, matching their
+    /// entries by label rather than by position.
+    ///
+    /// Each field is bound to the same variable in both telescopes, so the
+    /// type of a field will only be equal if it depends on fields that occur
+    /// earlier in both telescopes.
+    fn is_equal_reordered_telescopes(
+        &mut self,
+        labels0: &[StringId],
+        telescope0: &Telescope<'_>,
+        labels1: &[StringId],
+        telescope1: &Telescope<'_>,
+    ) -> bool {
+        if labels0.len() != labels1.len() || telescope0.len() != telescope1.len() {
+            return false;
+        }
+
+        // The positions of the fields of the second telescope in the first
+        let indices1 = (labels1.iter())
+            .map(|label| labels0.iter().position(|label0| label0 == label))
+            .collect::<Option<Vec<_>>>();
+        let indices1 = match indices1 {
+            Some(indices1) => indices1,
+            None => return false,
+        };
+
+        // Bind a variable for each field, in the order of the first telescope
+        let initial_local_len = self.local_exprs;
+        let vars = (labels0.iter())
+            .map(|_| {
+                let var = Value::local_var(self.local_exprs.next_level());
+                self.local_exprs.push();
+                Spanned::empty(Arc::new(var))
+            })
+            .collect::<Vec<_>>();
+
+        let mut types0 = Vec::with_capacity(labels0.len());
+        let mut telescope0 = telescope0.clone();
+        while let Some((r#type, next_telescope)) = self.elim_env.split_telescope(telescope0) {
+            telescope0 = next_telescope(vars[types0.len()].clone());
+            types0.push(r#type);
+        }
+
+        let mut telescope1 = telescope1.clone();
+        let mut indices = indices1.iter();
+        let mut result = true;
+        while let Some((type1, next_telescope)) = self.elim_env.split_telescope(telescope1) {
+            let index = *indices.next().unwrap();
+            if !self.is_equal(&types0[index], &type1) {
+                result = false;
+                break;
+            }
+            telescope1 = next_telescope(vars[index].clone());
+        }
+
+        self.local_exprs.truncate(initial_local_len);
+        result
+    }
+
     /// Check that two [constant branches][Branches] are equal.
     fn is_equal_branches<P: PartialEq + Copy>(
         &mut self,
@@ -1247,9 +2058,9 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
                 self.elim_env.split_branches(branches1),
             ) {
                 (
-                    Branch((const0, body_expr0), next_branches0),
-                    Branch((const1, body_expr1), next_branches1),
-                ) if const0 == const1 && self.is_equal(&body_expr0, &body_expr1) => {
+                    Branch((pattern0, body_expr0), next_branches0),
+                    Branch((pattern1, body_expr1), next_branches1),
+                ) if pattern0 == pattern1 && self.is_equal(&body_expr0, &body_expr1) => {
                     branches0 = next_branches0;
                     branches1 = next_branches1;
                 }
@@ -1274,7 +2085,7 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
         value: &ArcValue<'_>,
     ) -> bool {
         let var = Spanned::empty(Arc::new(Value::local_var(self.local_exprs.next_level())));
-        let value = self.elim_env.fun_app(plicity, value.clone(), var.clone());
+        let value = (self.elim_env).fun_app(Span::Empty, plicity, value.clone(), var.clone());
         let body_expr = self.elim_env.apply_closure(body_expr, var);
 
         self.push_local();
@@ -1296,15 +2107,158 @@ impl<'arena, 'env> ConversionEnv<'arena, 'env> {
         value: &ArcValue<'_>,
     ) -> bool {
         Iterator::zip(labels.iter(), exprs.iter()).all(|(label, expr)| {
-            let field_value = self.elim_env.record_proj(value.clone(), *label);
+            let field_value = (self.elim_env).record_proj(Span::Empty, value.clone(), *label);
             self.is_equal(expr, &field_value)
         })
     }
+
+    /// Find where one value differs from another, returning `None` if they
+    /// are equal.
+    ///
+    /// This is slower than [`ConversionEnv::is_equal`], and is intended for
+    /// explaining type mismatches after they have been detected.
+    pub fn mismatch(
+        &mut self,
+        value0: &ArcValue<'arena>,
+        value1: &ArcValue<'arena>,
+    ) -> Option<Mismatch> {
+        if self.is_equal(value0, value1) {
+            return None;
+        }
+
+        let value0 = self.elim_env.force(value0);
+        let value1 = self.elim_env.force(value1);
+        let inner = match (value0.as_ref(), value1.as_ref()) {
+            (Value::Stuck(head0, spine0), Value::Stuck(head1, spine1))
+                if head0 == head1 && spine0.len() == spine1.len() =>
+            {
+                let mut args = Iterator::zip(spine0.iter(), spine1.iter()).enumerate();
+                args.find_map(|(index, elims)| match elims {
+                    (Elim::FunApp(_, plicity0, expr0), Elim::FunApp(_, plicity1, expr1))
+                        if plicity0 == plicity1 =>
+                    {
+                        Some((MismatchStep::Arg(index), self.mismatch(expr0, expr1)?))
+                    }
+                    _ => None,
+                })
+            }
+            (
+                Value::FunType(plicity0, _, param_type0, body_type0),
+                Value::FunType(plicity1, _, param_type1, body_type1),
+            ) if plicity0 == plicity1 => match self.mismatch(param_type0, param_type1) {
+                Some(mismatch) => Some((MismatchStep::FunParamType, mismatch)),
+                None => (self.mismatch_closures(body_type0, body_type1))
+                    .map(|mismatch| (MismatchStep::FunBodyType, mismatch)),
+            },
+            (Value::FunLit(plicity0, _, body_expr0), Value::FunLit(plicity1, _, body_expr1))
+                if plicity0 == plicity1 =>
+            {
+                (self.mismatch_closures(body_expr0, body_expr1))
+                    .map(|mismatch| (MismatchStep::FunBodyExpr, mismatch))
+            }
+            (Value::RecordType(labels0, types0), Value::RecordType(labels1, types1))
+            | (Value::FormatRecord(labels0, types0), Value::FormatRecord(labels1, types1))
+            | (Value::FormatOverlap(labels0, types0), Value::FormatOverlap(labels1, types1))
+                if labels0 == labels1 =>
+            {
+                self.mismatch_telescopes(labels0, types0, types1)
+            }
+            (Value::RecordLit(labels0, exprs0), Value::RecordLit(labels1, exprs1))
+                if labels0 == labels1 =>
+            {
+                let mut fields = Iterator::zip(labels0.iter(), exprs0.iter().zip(exprs1.iter()));
+                fields.find_map(|(label, (expr0, expr1))| {
+                    Some((MismatchStep::Field(*label), self.mismatch(expr0, expr1)?))
+                })
+            }
+            (
+                Value::ArrayLit(_) | Value::ByteArrayLit(_),
+                Value::ArrayLit(_) | Value::ByteArrayLit(_),
+            ) => match Option::zip(value0.array_elems(), value1.array_elems()) {
+                Some((exprs0, exprs1)) if exprs0.len() == exprs1.len() => {
+                    let mut elems = Iterator::zip(exprs0.iter(), exprs1.iter()).enumerate();
+                    elems.find_map(|(index, (expr0, expr1))| {
+                        Some((MismatchStep::ArrayElem(index), self.mismatch(expr0, expr1)?))
+                    })
+                }
+                _ => None,
+            },
+            (_, _) => None,
+        };
+
+        Some(match inner {
+            Some((step, mut mismatch)) => {
+                mismatch.path.insert(0, step);
+                mismatch
+            }
+            None => Mismatch {
+                path: Vec::new(),
+                spans: (value0.span(), value1.span()),
+            },
+        })
+    }
+
+    /// Find where the bodies of two [closures][Closure] differ.
+    fn mismatch_closures(
+        &mut self,
+        closure0: &Closure<'arena>,
+        closure1: &Closure<'arena>,
+    ) -> Option<Mismatch> {
+        let var = Spanned::empty(Arc::new(Value::local_var(self.local_exprs.next_level())));
+        let value0 = self.elim_env.apply_closure(closure0, var.clone());
+        let value1 = self.elim_env.apply_closure(closure1, var);
+
+        self.push_local();
+        let result = self.mismatch(&value0, &value1);
+        self.pop_local();
+
+        result
+    }
+
+    /// Find the first field where two [telescopes][Telescope] differ.
+    fn mismatch_telescopes(
+        &mut self,
+        labels: &[StringId],
+        telescope0: &Telescope<'arena>,
+        telescope1: &Telescope<'arena>,
+    ) -> Option<(MismatchStep, Mismatch)> {
+        let initial_local_len = self.local_exprs;
+        let mut telescope0 = telescope0.clone();
+        let mut telescope1 = telescope1.clone();
+
+        let mut result = None;
+        for label in labels {
+            let ((value0, next_telescope0), (value1, next_telescope1)) = match Option::zip(
+                self.elim_env.split_telescope(telescope0),
+                self.elim_env.split_telescope(telescope1),
+            ) {
+                Some(splits) => splits,
+                None => break,
+            };
+
+            if let Some(mismatch) = self.mismatch(&value0, &value1) {
+                result = Some((MismatchStep::Field(*label), mismatch));
+                break;
+            }
+
+            let var = Spanned::empty(Arc::new(Value::local_var(self.local_exprs.next_level())));
+            telescope0 = next_telescope0(var.clone());
+            telescope1 = next_telescope1(var);
+            self.local_exprs.push();
+        }
+
+        self.local_exprs.truncate(initial_local_len);
+        result
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::env::UniqueEnv;
+    use crate::files::FileId;
+    use crate::source::{ByteRange, FileRange, StringInterner};
+    use crate::testing::assert_normalizes;
 
     #[allow(dead_code)]
     fn value_has_unify_and_is_equal_impls(value: Value<'_>) {
@@ -1324,10 +2278,12 @@ mod tests {
             Value::RecordType(..) => {}
             Value::RecordLit(..) => {}
             Value::ArrayLit(..) => {}
+            Value::ByteArrayLit(..) => {}
             Value::FormatRecord(..) => {}
             Value::FormatCond(..) => {}
             Value::FormatOverlap(..) => {}
             Value::ConstLit(..) => {}
+            Value::StringLit(..) => {}
         }
     }
 
@@ -1353,4 +2309,625 @@ mod tests {
     fn value_size() {
         assert_eq!(std::mem::size_of::<Value>(), 72);
     }
+
+    fn format_prim<'arena>(prim: Prim, params: Vec<ArcValue<'arena>>) -> ArcValue<'arena> {
+        Spanned::empty(Arc::new(Value::prim(prim, params)))
+    }
+
+    fn const_lit<'arena>(r#const: Const) -> ArcValue<'arena> {
+        Spanned::empty(Arc::new(Value::ConstLit(r#const)))
+    }
+
+    #[test]
+    fn static_size_of_fixed_formats() {
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        assert_eq!(
+            env.static_size(&format_prim(Prim::FormatU8, vec![])),
+            Some(1)
+        );
+        assert_eq!(
+            env.static_size(&format_prim(Prim::FormatS16Le, vec![])),
+            Some(2)
+        );
+        assert_eq!(
+            env.static_size(&format_prim(Prim::FormatF64Be, vec![])),
+            Some(8)
+        );
+
+        let array = format_prim(
+            Prim::FormatRepeatLen16,
+            vec![
+                const_lit(Const::U16(3, UIntStyle::Decimal)),
+                format_prim(Prim::FormatU32Be, vec![]),
+            ],
+        );
+        assert_eq!(env.static_size(&array), Some(12));
+
+        let until_end = format_prim(
+            Prim::FormatRepeatUntilEnd,
+            vec![format_prim(Prim::FormatU8, vec![])],
+        );
+        assert_eq!(env.static_size(&until_end), None);
+    }
+
+    fn format_record<'arena>(
+        scope: &'arena Scope<'arena>,
+        labels: &'arena [StringId],
+        formats: Vec<Term<'arena>>,
+    ) -> ArcValue<'arena> {
+        let formats = scope.to_scope_from_iter(formats);
+        let telescope = Telescope::new(SharedEnv::new(), formats);
+        Spanned::empty(Arc::new(Value::FormatRecord(labels, telescope)))
+    }
+
+    #[test]
+    fn static_size_of_records() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let labels =
+            scope.to_scope_from_iter(["len", "data"].map(|label| interner.get_or_intern(label)));
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let u8_format = Term::Prim(Span::Empty, Prim::FormatU8);
+        let repeat_len8 = |len| {
+            Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(Term::FunApp(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    scope.to_scope(Term::Prim(Span::Empty, Prim::FormatRepeatLen8)),
+                    scope.to_scope(len),
+                )),
+                scope.to_scope(Term::Prim(Span::Empty, Prim::FormatU16Le)),
+            )
+        };
+
+        // `{ len <- u8, data <- repeat_len8 4 u16le }`
+        let fixed = format_record(
+            &scope,
+            labels,
+            vec![
+                u8_format.clone(),
+                repeat_len8(Term::ConstLit(
+                    Span::Empty,
+                    Const::U8(4, UIntStyle::Decimal),
+                )),
+            ],
+        );
+        assert_eq!(env.static_size(&fixed), Some(9));
+
+        // `{ len <- u8, data <- repeat_len8 len u16le }`
+        let dependent = format_record(
+            &scope,
+            labels,
+            vec![
+                u8_format,
+                repeat_len8(Term::LocalVar(Span::Empty, Index::last())),
+            ],
+        );
+        assert_eq!(env.static_size(&dependent), None);
+    }
+
+    #[test]
+    fn static_size_of_binds() {
+        let scope = Scope::new();
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let bind = |next| {
+            let bind = Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(Term::Prim(Span::Empty, Prim::FormatBind)),
+                scope.to_scope(Term::Prim(Span::Empty, Prim::FormatU8)),
+            );
+            let next = Term::FunLit(Span::Empty, Plicity::Explicit, None, scope.to_scope(next));
+            let term = Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(bind),
+                scope.to_scope(next),
+            );
+            env.eval_env(&mut SharedEnv::new())
+                .eval(scope.to_scope(term))
+        };
+        let repeat_len8 = |len| {
+            Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(Term::FunApp(
+                    Span::Empty,
+                    Plicity::Explicit,
+                    scope.to_scope(Term::Prim(Span::Empty, Prim::FormatRepeatLen8)),
+                    scope.to_scope(len),
+                )),
+                scope.to_scope(Term::Prim(Span::Empty, Prim::FormatU8)),
+            )
+        };
+
+        // `bind u8 (fun _ => u16le)`
+        let fixed = bind(Term::Prim(Span::Empty, Prim::FormatU16Le));
+        assert_eq!(env.static_size(&fixed), Some(3));
+
+        // `bind u8 (fun len => repeat_len8 len u8)`
+        let dependent = bind(repeat_len8(Term::LocalVar(Span::Empty, Index::last())));
+        assert_eq!(env.static_size(&dependent), None);
+    }
+
+    #[test]
+    fn record_field_order_is_significant() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let [x, y] = ["x", "y"].map(|label| interner.get_or_intern(label));
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let u8_format = Term::Prim(Span::Empty, Prim::FormatU8);
+        let u16_format = Term::Prim(Span::Empty, Prim::FormatU16Be);
+
+        // `{ x <- u8, y <- u16be }` and `{ y <- u16be, x <- u8 }`
+        let format_xy = format_record(
+            &scope,
+            scope.to_scope_from_iter([x, y]),
+            vec![u8_format.clone(), u16_format.clone()],
+        );
+        let format_yx = format_record(
+            &scope,
+            scope.to_scope_from_iter([y, x]),
+            vec![u16_format, u8_format],
+        );
+
+        let repr_xy = env.format_repr(&format_xy);
+        let repr_yx = env.format_repr(&format_yx);
+        match (repr_xy.as_ref(), repr_yx.as_ref()) {
+            (Value::RecordType(labels_xy, _), Value::RecordType(labels_yx, _)) => {
+                assert_eq!(*labels_xy, [x, y]);
+                assert_eq!(*labels_yx, [y, x]);
+            }
+            _ => panic!("expected record types"),
+        }
+
+        let mut conversion_env = env.conversion_env(EnvLen::new());
+        assert!(conversion_env.is_equal(&format_xy, &format_xy));
+        assert!(conversion_env.is_equal(&repr_xy, &repr_xy));
+        assert!(!conversion_env.is_equal(&format_xy, &format_yx));
+        assert!(!conversion_env.is_equal(&repr_xy, &repr_yx));
+    }
+
+    fn record_type<'arena>(
+        scope: &'arena Scope<'arena>,
+        labels: &'arena [StringId],
+        types: Vec<Term<'arena>>,
+    ) -> ArcValue<'arena> {
+        let types = scope.to_scope_from_iter(types);
+        let telescope = Telescope::new(SharedEnv::new(), types);
+        Spanned::empty(Arc::new(Value::RecordType(labels, telescope)))
+    }
+
+    #[test]
+    fn record_field_order_is_optionally_insignificant() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let [n, x, xs] = ["n", "x", "xs"].map(|label| interner.get_or_intern(label));
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let u8_type = Term::Prim(Span::Empty, Prim::U8Type);
+        let u16_type = Term::Prim(Span::Empty, Prim::U16Type);
+        let array_type = |len| {
+            let array8 = scope.to_scope(Term::Prim(Span::Empty, Prim::Array8Type));
+            let len = scope.to_scope(Term::LocalVar(Span::Empty, len));
+            let array8_len = Term::FunApp(Span::Empty, Plicity::Explicit, array8, len);
+            let elem = scope.to_scope(Term::Prim(Span::Empty, Prim::U8Type));
+            Term::FunApp(
+                Span::Empty,
+                Plicity::Explicit,
+                scope.to_scope(array8_len),
+                elem,
+            )
+        };
+
+        // `{ n : U8, x : U16, xs : Array8 n U8 }`
+        let type_nxxs = record_type(
+            &scope,
+            scope.to_scope_from_iter([n, x, xs]),
+            vec![
+                u8_type.clone(),
+                u16_type.clone(),
+                array_type(Index::last().prev()),
+            ],
+        );
+        // `{ x : U16, n : U8, xs : Array8 n U8 }`
+        let type_xnxs = record_type(
+            &scope,
+            scope.to_scope_from_iter([x, n, xs]),
+            vec![u16_type.clone(), u8_type.clone(), array_type(Index::last())],
+        );
+        // `{ n : U16, x : U8, xs : Array8 x U8 }`
+        let type_mismatched = record_type(
+            &scope,
+            scope.to_scope_from_iter([n, x, xs]),
+            vec![u16_type, u8_type, array_type(Index::last())],
+        );
+
+        // `{ n = 1, x = 2 }` and `{ x = 2, n = 1 }`
+        let lit = |value| const_lit(Const::U8(value, UIntStyle::Decimal));
+        let lit_nx = Spanned::empty(Arc::new(Value::RecordLit(
+            scope.to_scope_from_iter([n, x]),
+            vec![lit(1), lit(2)],
+        )));
+        let lit_xn = Spanned::empty(Arc::new(Value::RecordLit(
+            scope.to_scope_from_iter([x, n]),
+            vec![lit(2), lit(1)],
+        )));
+        let lit_swapped = Spanned::empty(Arc::new(Value::RecordLit(
+            scope.to_scope_from_iter([x, n]),
+            vec![lit(1), lit(2)],
+        )));
+
+        let mut conversion_env = env.conversion_env(EnvLen::new());
+        assert!(!conversion_env.is_equal(&type_nxxs, &type_xnxs));
+        assert!(!conversion_env.is_equal(&lit_nx, &lit_xn));
+
+        conversion_env.set_order_insensitive_records(true);
+        assert!(conversion_env.is_equal(&type_nxxs, &type_xnxs));
+        assert!(conversion_env.is_equal(&type_xnxs, &type_nxxs));
+        assert!(!conversion_env.is_equal(&type_nxxs, &type_mismatched));
+        assert!(conversion_env.is_equal(&lit_nx, &lit_xn));
+        assert!(!conversion_env.is_equal(&lit_nx, &lit_swapped));
+    }
+
+    #[test]
+    fn quote_unfolds_metas_to_depth() {
+        let scope = Scope::new();
+        let item_exprs = UniqueEnv::new();
+        let mut meta_exprs = UniqueEnv::new();
+
+        // `?0 := U8`, `?1 := Array8 3 ?0`, and `?2 := fun x => x`
+        let mut meta_len = EnvLen::new();
+        let meta0 = meta_len.next_level();
+        meta_len.push();
+        let meta1 = meta_len.next_level();
+        meta_len.push();
+        let meta2 = meta_len.next_level();
+        let u8_type = Spanned::empty(Arc::new(Value::prim(Prim::U8Type, [])));
+        let array_type = Value::prim(
+            Prim::Array8Type,
+            [
+                const_lit(Const::U8(3, UIntStyle::Decimal)),
+                Spanned::empty(Arc::new(Value::meta_var(meta0))),
+            ],
+        );
+        meta_exprs.push(Some(u8_type));
+        meta_exprs.push(Some(Spanned::empty(Arc::new(array_type))));
+        let id_body = scope.to_scope(Term::LocalVar(Span::Empty, Index::last()));
+        let id_fun = Value::FunLit(
+            Plicity::Explicit,
+            None,
+            Closure::new(SharedEnv::new(), id_body),
+        );
+        meta_exprs.push(Some(Spanned::empty(Arc::new(id_fun))));
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let value = Spanned::empty(Arc::new(Value::meta_var(meta1)));
+        let quote_env = || QuoteEnv::new(env, EnvLen::new());
+
+        let term = quote_env()
+            .unfolding_metas_to_depth(0)
+            .quote(&scope, &value);
+        assert!(matches!(term, Term::MetaVar(_, var) if var == meta1));
+
+        let term = quote_env()
+            .unfolding_metas_to_depth(1)
+            .quote(&scope, &value);
+        match term {
+            Term::FunApp(_, _, _, Term::MetaVar(_, var)) => assert_eq!(*var, meta0),
+            _ => panic!("expected `Array8 3 ?0`, found {term:?}"),
+        }
+
+        let term = quote_env().unfolding_metas().quote(&scope, &value);
+        match term {
+            Term::FunApp(_, _, _, Term::Prim(_, prim)) => assert_eq!(*prim, Prim::U8Type),
+            _ => panic!("expected `Array8 3 U8`, found {term:?}"),
+        }
+
+        // `?2 ?1` is forced when `?2` is unfolded, so each level of unfolding
+        // applies the solution to its arguments
+        let value = Spanned::empty(Arc::new(Value::Stuck(
+            Head::MetaVar(meta2),
+            vec![Elim::FunApp(Span::Empty, Plicity::Explicit, value)],
+        )));
+        let term = quote_env()
+            .unfolding_metas_to_depth(1)
+            .quote(&scope, &value);
+        assert!(matches!(term, Term::MetaVar(_, var) if var == meta1));
+
+        let term = quote_env()
+            .unfolding_metas_to_depth(usize::MAX)
+            .quote(&scope, &value);
+        let unfolded_term = quote_env().unfolding_metas().quote(&scope, &value);
+        assert_eq!(format!("{term:?}"), format!("{unfolded_term:?}"));
+    }
+
+    #[test]
+    fn distinct_rigid_constructors() {
+        let universe = Value::Universe;
+        let bytes = Value::ByteArrayLit(Arc::from([1].as_slice()));
+        let array = Value::ArrayLit(vec![const_lit(Const::U8(1, UIntStyle::Decimal))]);
+        let bool_lit = Value::ConstLit(Const::Bool(true));
+        let meta = Value::meta_var(Level::first());
+        let record = Value::RecordLit(&[], Vec::new());
+
+        assert!(universe.has_distinct_rigid_constructor(&bool_lit));
+        assert!(bytes.has_distinct_rigid_constructor(&bool_lit));
+        assert!(!universe.has_distinct_rigid_constructor(&Value::Universe));
+        // Byte arrays can be equal to array literals
+        assert!(!bytes.has_distinct_rigid_constructor(&array));
+        // Metavariables might be solved with a constant
+        assert!(!meta.has_distinct_rigid_constructor(&bool_lit));
+        // Record literals are compared with eta-conversion
+        assert!(!record.has_distinct_rigid_constructor(&universe));
+    }
+
+    #[test]
+    fn byte_array_lits_convert_to_array_lits() {
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let bytes = Value::ByteArrayLit(Arc::from([1, 2, 3].as_slice()));
+        let elems = (bytes.array_elems().unwrap().iter())
+            .map(|elem| match elem.as_ref() {
+                Value::ConstLit(Const::U8(byte, _)) => *byte,
+                _ => panic!("expected a byte"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(elems, [1, 2, 3]);
+
+        let array = Value::ArrayLit(vec![const_lit(Const::U8(1, UIntStyle::Hexadecimal))]);
+        assert!(
+            matches!(array.to_byte_array_lit(), Some(Value::ByteArrayLit(bytes)) if bytes[..] == [1])
+        );
+        let array = Value::ArrayLit(vec![const_lit(Const::Bool(true))]);
+        assert!(array.to_byte_array_lit().is_none());
+
+        let array = Value::ArrayLit(vec![
+            const_lit(Const::U8(1, UIntStyle::Decimal)),
+            const_lit(Const::U8(2, UIntStyle::Decimal)),
+            const_lit(Const::U8(3, UIntStyle::Decimal)),
+        ]);
+        assert!(matches!(
+            bytes.array_elem(2).unwrap().as_ref(),
+            Value::ConstLit(Const::U8(3, _))
+        ));
+        assert!(bytes.array_elem(3).is_none());
+
+        let (bytes, array) = (
+            Spanned::empty(Arc::new(bytes)),
+            Spanned::empty(Arc::new(array)),
+        );
+        let prefix = Spanned::empty(Arc::new(Value::ArrayLit(vec![
+            const_lit(Const::U8(1, UIntStyle::Decimal)),
+            const_lit(Const::U8(2, UIntStyle::Decimal)),
+        ])));
+        let mut conversion_env = env.conversion_env(EnvLen::new());
+        assert!(conversion_env.is_equal(&bytes, &array));
+        // Arrays of different lengths are never equal, even if one is a prefix
+        // of the other
+        assert!(!conversion_env.is_equal(&bytes, &prefix));
+        assert!(!conversion_env.is_equal(&prefix, &bytes));
+    }
+
+    #[test]
+    fn eval_runs_out_of_fuel() {
+        let scope = Scope::new();
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let fuel = Fuel::new(64);
+        let env = ElimEnv::new(&item_exprs, &meta_exprs).with_fuel(&fuel);
+
+        // `(fun x => x x) (fun x => x x)`
+        let var = scope.to_scope(Term::LocalVar(Span::Empty, Index::last()));
+        let self_app = scope.to_scope(Term::FunLit(
+            Span::Empty,
+            Plicity::Explicit,
+            None,
+            scope.to_scope(Term::FunApp(Span::Empty, Plicity::Explicit, var, var)),
+        ));
+        let omega = Term::FunApp(Span::Empty, Plicity::Explicit, self_app, self_app);
+
+        let mut local_exprs = SharedEnv::new();
+        let result = env.eval_env(&mut local_exprs).try_eval(&omega);
+        assert!(matches!(result, Err(Error::OutOfFuel)));
+        assert_eq!(fuel.remaining(), 64);
+    }
+
+    #[test]
+    fn invalid_record_proj_returns_error() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        // `{ x = true }.y`
+        let labels = scope.to_scope_from_iter([interner.get_or_intern("x")]);
+        let exprs = scope.to_scope_from_iter([Term::ConstLit(Span::Empty, Const::Bool(true))]);
+        let record = Term::RecordLit(Span::Empty, labels, exprs);
+        let proj = Term::RecordProj(Span::Empty, &record, interner.get_or_intern("y"));
+
+        let mut local_exprs = SharedEnv::new();
+        let result = env.eval_env(&mut local_exprs).try_eval(&proj);
+        assert!(matches!(result, Err(Error::InvalidRecordProj(_))));
+    }
+
+    #[test]
+    fn try_normalise_invalid_record_proj_under_binder_returns_error() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        // `fun _ => { x = true }.y`, which only fails once the body is quoted
+        let labels = scope.to_scope_from_iter([interner.get_or_intern("x")]);
+        let exprs = scope.to_scope_from_iter([Term::ConstLit(Span::Empty, Const::Bool(true))]);
+        let record = Term::RecordLit(Span::Empty, labels, exprs);
+        let proj = Term::RecordProj(Span::Empty, &record, interner.get_or_intern("y"));
+        let fun = Term::FunLit(Span::Empty, Plicity::Explicit, None, &proj);
+
+        let mut local_exprs = SharedEnv::new();
+        let result = env.eval_env(&mut local_exprs).try_normalise(&scope, &fun);
+        assert!(matches!(result, Err(Error::InvalidRecordProj(_))));
+    }
+
+    #[test]
+    fn normalise_for_display_elides_array_elems() {
+        let scope = Scope::new();
+        let item_exprs = UniqueEnv::new();
+        let meta_exprs = UniqueEnv::new();
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+
+        let elems =
+            (0..1000).map(|n| Term::ConstLit(Span::Empty, Const::U32(n, UIntStyle::Decimal)));
+        let array = Term::ArrayLit(Span::Empty, scope.to_scope_from_iter(elems));
+
+        let mut local_exprs = SharedEnv::new();
+        let term = env
+            .eval_env(&mut local_exprs)
+            .normalise_for_display(&scope, &array, 10);
+        match term {
+            Term::ArrayLit(_, [elems @ .., Term::ElidedElems(_, len)]) => {
+                assert_eq!(elems.len(), 10);
+                assert!(matches!(elems[9], Term::ConstLit(_, Const::U32(9, _))));
+                assert_eq!(*len, 990);
+            }
+            _ => panic!("expected an array with elided elements, found {term:?}"),
+        }
+
+        let mut local_exprs = SharedEnv::new();
+        let term = env
+            .eval_env(&mut local_exprs)
+            .normalise_for_display(&scope, &array, 1000);
+        assert!(matches!(term, Term::ArrayLit(_, elems) if elems.len() == 1000));
+    }
+
+    #[test]
+    fn invalid_record_proj_in_spine_returns_span() {
+        let scope = Scope::new();
+        let mut interner = StringInterner::new();
+        let item_exprs = UniqueEnv::new();
+        let mut meta_exprs = UniqueEnv::new();
+        meta_exprs.push(None);
+        let mut local_exprs = SharedEnv::new();
+        let file_id = FileId::try_from(1).unwrap();
+        let app_span = Span::Range(FileRange::new(file_id, ByteRange::new(0, 7)));
+        let proj_span = Span::Range(FileRange::new(file_id, ByteRange::new(0, 9)));
+
+        // `?0 true .y`, where `?0` is unsolved
+        let meta_var = scope.to_scope(Term::MetaVar(Span::Empty, Level::first()));
+        let r#true = scope.to_scope(Term::ConstLit(Span::Empty, Const::Bool(true)));
+        let app = scope.to_scope(Term::FunApp(app_span, Plicity::Explicit, meta_var, r#true));
+        let proj = Term::RecordProj(proj_span, app, interner.get_or_intern("y"));
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+        let stuck_expr = env.eval_env(&mut local_exprs).eval(&proj);
+
+        // `fun _ => { x = true }`
+        let labels = scope.to_scope_from_iter([interner.get_or_intern("x")]);
+        let exprs = scope.to_scope_from_iter([r#true.clone()]);
+        let record = scope.to_scope(Term::RecordLit(Span::Empty, labels, exprs));
+        let fun_lit = Term::FunLit(Span::Empty, Plicity::Explicit, None, record);
+        let solution = env.eval_env(&mut local_exprs).eval(&fun_lit);
+        meta_exprs.pop();
+        meta_exprs.push(Some(solution.clone()));
+
+        let env = ElimEnv::new(&item_exprs, &meta_exprs);
+        match stuck_expr.as_ref() {
+            Value::Stuck(Head::MetaVar(_), spine) => {
+                let spans = spine.iter().map(Elim::span).collect::<Vec<_>>();
+                assert_eq!(spans, [app_span, proj_span]);
+
+                let result = env.try_apply_spine(solution, spine);
+                assert!(matches!(result, Err(Error::InvalidRecordProj(span)) if span == proj_span));
+            }
+            _ => panic!("expected a stuck metavariable"),
+        }
+    }
+
+    #[test]
+    fn prim_ops_normalize() {
+        assert_normalizes("u8_add 1 2", "3");
+        assert_normalizes("u16_sub 3 1", "2");
+        assert_normalizes("u32_mul 6 7", "42");
+        assert_normalizes("s8_neg 5", "-5");
+        assert_normalizes("bool_and true (bool_not false)", "true");
+        assert_normalizes("u64_lt 3 2", "false");
+        assert_normalizes(r#"string_eq "OK" "NO""#, "false");
+    }
+
+    #[test]
+    fn s128_abs_normalizes() {
+        assert_normalizes("s128_abs (-5 : S128)", "5");
+        // The absolute value of the minimum integer overflows, so it does not
+        // evaluate any further
+        assert_normalizes(
+            "s128_abs (-170141183460469231731687303715884105728 : S128)",
+            "s128_abs -170141183460469231731687303715884105728",
+        );
+    }
+
+    #[test]
+    fn pos_conversions_normalize() {
+        assert_normalizes("pos_to_u64 (u64_to_pos 42)", "42");
+        assert_normalizes("pos_to_u64 (pos_add_u8 (u64_to_pos 3) 4)", "7");
+        assert_normalizes("pos_sub (u64_to_pos 10) (u64_to_pos 4)", "6");
+    }
+
+    #[test]
+    fn binops_normalize() {
+        assert_normalizes("(1 : U8) + (2 : U8) * (3 : U8)", "7");
+        assert_normalizes("(2 : S16) - (5 : S16)", "-3");
+        assert_normalizes("(1 : U32) == (1 : U32)", "true");
+    }
+
+    #[test]
+    fn eliminations_normalize() {
+        assert_normalizes("(fun (x : U8) => u8_mul x 2) 21", "42");
+        assert_normalizes("{ x = 1 : U8, y = true }.y", "true");
+        assert_normalizes("if u8_lt 1 2 then (3 : U8) else 4", "3");
+        assert_normalizes("let x : U16 = 1; u16_add x x", "2");
+        assert_normalizes("match (5 : U8) { 0 => false, _ => true }", "true");
+    }
+
+    #[test]
+    fn stuck_terms_normalize() {
+        assert_normalizes("fun (x : U8) => u8_add x 0", "fun x => x + (0 : U8)");
+        assert_normalizes(
+            "fun (x : Bool) => if x then (1 : U8) else 0",
+            "fun x => if x then 1 else 0",
+        );
+    }
+
+    #[test]
+    fn empty_array_ops_normalize() {
+        assert_normalizes(
+            "array8_find (fun (x : U8) => true) ([] : Array8 0 U8)",
+            "none @U8",
+        );
+        assert_normalizes("array16_fold (7 : U8) u8_add ([] : Array16 0 U8)", "7");
+        // Indexing out of bounds does not evaluate any further
+        assert_normalizes(
+            "array8_index 0 ([] : Array8 0 U8)",
+            "array8_index @0 @U8 0 []",
+        );
+    }
 }
@@ -0,0 +1,304 @@
+//! Traversals over [core terms][Term].
+//!
+//! Passes that only care about a few kinds of terms can implement
+//! [`TermVisitor`] or [`TermFolder`], overriding [`TermVisitor::visit_term`]
+//! or [`TermFolder::fold_term`] to handle those terms, and falling back to
+//! [`visit_subterms`] or [`fold_subterms`] for the rest. This means that passes
+//! don't need to be updated when new variants are added to [`Term`].
+
+use scoped_arena::Scope;
+
+use crate::core::{to_scope_str, Term};
+use crate::source::Span;
+
+/// A traversal over a term that doesn't build a new term, for example to
+/// collect information about the variables it contains.
+pub trait TermVisitor<'arena> {
+    /// Visit a term. By default this visits each of its subterms.
+    fn visit_term(&mut self, term: &Term<'arena>) {
+        visit_subterms(self, term);
+    }
+
+    /// Called before visiting a subterm that is under a local binder.
+    fn push_local(&mut self) {}
+
+    /// Called after visiting a subterm that is under a local binder.
+    fn pop_local(&mut self) {}
+}
+
+/// Visit each of the immediate subterms of a term, in the order that they
+/// appear in the term.
+pub fn visit_subterms<'arena, V>(visitor: &mut V, term: &Term<'arena>)
+where
+    V: TermVisitor<'arena> + ?Sized,
+{
+    match term {
+        Term::ItemVar(_, _)
+        | Term::LocalVar(_, _)
+        | Term::MetaVar(_, _)
+        | Term::InsertedMeta(_, _, _)
+        | Term::Universe(_)
+        | Term::ElidedElems(_, _)
+        | Term::Prim(_, _)
+        | Term::ConstLit(_, _)
+        | Term::StringLit(_, _) => {}
+
+        Term::Ann(_, expr, r#type) => {
+            visitor.visit_term(expr);
+            visitor.visit_term(r#type);
+        }
+        Term::Let(_, _, def_type, def_expr, body_expr) => {
+            visitor.visit_term(def_type);
+            visitor.visit_term(def_expr);
+            visit_under_local(visitor, body_expr);
+        }
+        Term::FunType(.., param_type, body_type) => {
+            visitor.visit_term(param_type);
+            visit_under_local(visitor, body_type);
+        }
+        Term::FunLit(.., body_expr) => visit_under_local(visitor, body_expr),
+        Term::FunApp(.., head_expr, arg_expr) => {
+            visitor.visit_term(head_expr);
+            visitor.visit_term(arg_expr);
+        }
+        Term::RecordType(_, _, terms)
+        | Term::FormatRecord(_, _, terms)
+        | Term::FormatOverlap(_, _, terms) => {
+            for term in terms.iter() {
+                visitor.visit_term(term);
+                visitor.push_local();
+            }
+            terms.iter().for_each(|_| visitor.pop_local());
+        }
+        Term::RecordLit(_, _, exprs) | Term::ArrayLit(_, exprs) => {
+            exprs.iter().for_each(|expr| visitor.visit_term(expr));
+        }
+        Term::RecordProj(_, head_expr, _) => visitor.visit_term(head_expr),
+        Term::FormatCond(_, _, format, pred) => {
+            visitor.visit_term(format);
+            visit_under_local(visitor, pred);
+        }
+        Term::ConstMatch(_, head_expr, branches, default_branch) => {
+            visitor.visit_term(head_expr);
+            for (_, body_expr) in branches.iter() {
+                visitor.visit_term(body_expr);
+            }
+            if let Some((_, default_expr)) = default_branch {
+                visit_under_local(visitor, default_expr);
+            }
+        }
+    }
+}
+
+fn visit_under_local<'arena, V>(visitor: &mut V, term: &Term<'arena>)
+where
+    V: TermVisitor<'arena> + ?Sized,
+{
+    visitor.push_local();
+    visitor.visit_term(term);
+    visitor.pop_local();
+}
+
+/// A traversal that builds a new term from an existing one, for example to
+/// replace some of its subterms.
+pub trait TermFolder<'in_arena, 'out_arena> {
+    /// The arena that the new terms are allocated in.
+    fn scope(&self) -> &'out_arena Scope<'out_arena>;
+
+    /// Fold a term. By default this folds each of its subterms, leaving the
+    /// rest of the term unchanged.
+    fn fold_term(&mut self, term: &Term<'in_arena>) -> Term<'out_arena> {
+        fold_subterms(self, term)
+    }
+
+    /// Fold the span of a term. By default this leaves the span unchanged.
+    fn fold_span(&mut self, span: Span) -> Span {
+        span
+    }
+
+    /// Called before folding a subterm that is under a local binder.
+    fn push_local(&mut self) {}
+
+    /// Called after folding a subterm that is under a local binder.
+    fn pop_local(&mut self) {}
+}
+
+/// Rebuild a term in the folder's arena, folding each of its immediate
+/// subterms and its span.
+pub fn fold_subterms<'in_arena, 'out_arena, F>(
+    folder: &mut F,
+    term: &Term<'in_arena>,
+) -> Term<'out_arena>
+where
+    F: TermFolder<'in_arena, 'out_arena> + ?Sized,
+{
+    let scope = folder.scope();
+    let span = folder.fold_span(term.span());
+
+    match term {
+        Term::ItemVar(_, var) => Term::ItemVar(span, *var),
+        Term::LocalVar(_, var) => Term::LocalVar(span, *var),
+        Term::MetaVar(_, var) => Term::MetaVar(span, *var),
+        Term::InsertedMeta(_, var, infos) => {
+            Term::InsertedMeta(span, *var, scope.to_scope_from_iter(infos.iter().copied()))
+        }
+        Term::Ann(_, expr, r#type) => Term::Ann(
+            span,
+            scope.to_scope(folder.fold_term(expr)),
+            scope.to_scope(folder.fold_term(r#type)),
+        ),
+        Term::Let(_, name, def_type, def_expr, body_expr) => Term::Let(
+            span,
+            *name,
+            scope.to_scope(folder.fold_term(def_type)),
+            scope.to_scope(folder.fold_term(def_expr)),
+            scope.to_scope(fold_under_local(folder, body_expr)),
+        ),
+
+        Term::Universe(_) => Term::Universe(span),
+
+        Term::FunType(_, plicity, name, param_type, body_type) => Term::FunType(
+            span,
+            *plicity,
+            *name,
+            scope.to_scope(folder.fold_term(param_type)),
+            scope.to_scope(fold_under_local(folder, body_type)),
+        ),
+        Term::FunLit(_, plicity, name, body_expr) => Term::FunLit(
+            span,
+            *plicity,
+            *name,
+            scope.to_scope(fold_under_local(folder, body_expr)),
+        ),
+        Term::FunApp(_, plicity, head_expr, arg_expr) => Term::FunApp(
+            span,
+            *plicity,
+            scope.to_scope(folder.fold_term(head_expr)),
+            scope.to_scope(folder.fold_term(arg_expr)),
+        ),
+
+        Term::RecordType(_, labels, types) => Term::RecordType(
+            span,
+            scope.to_scope_from_iter(labels.iter().copied()),
+            fold_telescope(folder, types),
+        ),
+        Term::RecordLit(_, labels, exprs) => Term::RecordLit(
+            span,
+            scope.to_scope_from_iter(labels.iter().copied()),
+            scope.to_scope_from_iter(exprs.iter().map(|expr| folder.fold_term(expr))),
+        ),
+        Term::RecordProj(_, head_expr, label) => {
+            Term::RecordProj(span, scope.to_scope(folder.fold_term(head_expr)), *label)
+        }
+
+        Term::ArrayLit(_, exprs) => Term::ArrayLit(
+            span,
+            scope.to_scope_from_iter(exprs.iter().map(|expr| folder.fold_term(expr))),
+        ),
+        Term::ElidedElems(_, len) => Term::ElidedElems(span, *len),
+
+        Term::FormatRecord(_, labels, formats) => Term::FormatRecord(
+            span,
+            scope.to_scope_from_iter(labels.iter().copied()),
+            fold_telescope(folder, formats),
+        ),
+        Term::FormatCond(_, name, format, pred) => Term::FormatCond(
+            span,
+            *name,
+            scope.to_scope(folder.fold_term(format)),
+            scope.to_scope(fold_under_local(folder, pred)),
+        ),
+        Term::FormatOverlap(_, labels, formats) => Term::FormatOverlap(
+            span,
+            scope.to_scope_from_iter(labels.iter().copied()),
+            fold_telescope(folder, formats),
+        ),
+
+        Term::Prim(_, prim) => Term::Prim(span, *prim),
+
+        Term::ConstLit(_, r#const) => Term::ConstLit(span, *r#const),
+        Term::StringLit(_, string) => Term::StringLit(span, to_scope_str(scope, string)),
+        Term::ConstMatch(_, head_expr, branches, default_branch) => Term::ConstMatch(
+            span,
+            scope.to_scope(folder.fold_term(head_expr)),
+            scope.to_scope_from_iter(
+                (branches.iter())
+                    .map(|(pattern, body_expr)| (*pattern, folder.fold_term(body_expr))),
+            ),
+            default_branch.map(|(name, default_expr)| {
+                let default_expr = fold_under_local(folder, default_expr);
+                (name, scope.to_scope(default_expr) as &_)
+            }),
+        ),
+    }
+}
+
+fn fold_under_local<'in_arena, 'out_arena, F>(
+    folder: &mut F,
+    term: &Term<'in_arena>,
+) -> Term<'out_arena>
+where
+    F: TermFolder<'in_arena, 'out_arena> + ?Sized,
+{
+    folder.push_local();
+    let term = folder.fold_term(term);
+    folder.pop_local();
+    term
+}
+
+fn fold_telescope<'in_arena, 'out_arena, F>(
+    folder: &mut F,
+    terms: &[Term<'in_arena>],
+) -> &'out_arena [Term<'out_arena>]
+where
+    F: TermFolder<'in_arena, 'out_arena> + ?Sized,
+{
+    let scope = folder.scope();
+    let terms = scope.to_scope_from_iter(terms.iter().map(|term| {
+        let term = folder.fold_term(term);
+        folder.push_local();
+        term
+    }));
+    terms.iter().for_each(|_| folder.pop_local());
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::source::StringInterner;
+    use crate::testing::elab_term;
+
+    struct Identity<'out_arena> {
+        scope: &'out_arena Scope<'out_arena>,
+    }
+
+    impl<'in_arena, 'out_arena> TermFolder<'in_arena, 'out_arena> for Identity<'out_arena> {
+        fn scope(&self) -> &'out_arena Scope<'out_arena> {
+            self.scope
+        }
+    }
+
+    #[test]
+    fn identity_fold() {
+        const SOURCE: &str = r#"
+            let x : U8 = 1;
+            fun (f : U8 -> Bool) => {
+                lit = { a = f x, b = [x, x] : Array8 2 U8 },
+                matched = match x { 0 => true, n => f n },
+                format = { len <- u8, data <- repeat_len8 len u8 },
+                cond = { y <- u8 where y == x },
+            }
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let term = elab_term(&interner, &scope, SOURCE);
+
+        let fold_scope = Scope::new();
+        let folded = Identity { scope: &fold_scope }.fold_term(&term);
+        assert_eq!(format!("{folded:?}"), format!("{term:?}"));
+    }
+}
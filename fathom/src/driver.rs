@@ -1,18 +1,27 @@
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 use std::path::Path;
 
-use codespan_reporting::diagnostic::{Diagnostic, Label, Severity};
+use codespan_reporting::diagnostic::{Diagnostic, Label, LabelStyle, Severity};
 use codespan_reporting::files::SimpleFiles;
 use codespan_reporting::term::termcolor::{BufferedStandardStream, ColorChoice, WriteColor};
 
 use crate::core::binary::{self, BufferError, ReadError};
+use crate::core::prim::Endianness;
+use crate::core::semantics::{self, ArcValue, Elim, Head, Value};
+use crate::core::Prim;
+use crate::env::SharedEnv;
 use crate::files::{FileId, Files};
-use crate::source::{ByteRange, ProgramSource, SourceTooBig, Span, StringInterner, MAX_SOURCE_LEN};
+use crate::source::{
+    ByteRange, ProgramSource, SourceTooBig, Span, StringId, StringInterner, MAX_SOURCE_LEN,
+};
 use crate::surface::elaboration::ItemEnv;
 use crate::surface::{self, elaboration};
 use crate::{core, BUG_REPORT_URL};
 
+type DocBuilder<'doc> = pretty::DocBuilder<'doc, surface::pretty::Context<'doc, 'doc>>;
+
 #[derive(Debug, Copy, Clone)]
 pub enum Status {
     Ok,
@@ -35,6 +44,12 @@ pub struct Driver<'surface, 'core> {
     core_scope: scoped_arena::Scope<'core>,
 
     allow_errors: bool,
+    default_endianness: Option<Endianness>,
+    emit_core: bool,
+    warn_unused_fields: bool,
+    hexdump: bool,
+    emit_json: bool,
+    debug_log: bool,
     seen_errors: RefCell<bool>,
     codespan_config: codespan_reporting::term::Config,
     diagnostic_writer: RefCell<Box<dyn WriteColor>>,
@@ -52,6 +67,12 @@ impl<'surface, 'core> Driver<'surface, 'core> {
             files: Files::new(),
 
             allow_errors: false,
+            default_endianness: None,
+            emit_core: false,
+            warn_unused_fields: false,
+            hexdump: false,
+            emit_json: false,
+            debug_log: false,
             seen_errors: RefCell::new(false),
             codespan_config: codespan_reporting::term::Config::default(),
             diagnostic_writer: RefCell::new(Box::new(BufferedStandardStream::stderr(
@@ -75,8 +96,6 @@ impl<'surface, 'core> Driver<'surface, 'core> {
 
     /// Setup a global panic hook
     pub fn install_panic_hook(&self) {
-        use crate::core::semantics;
-
         // Use the currently set codespan configuration
         let term_config = self.codespan_config.clone();
         // Fetch the default hook (which prints the panic message and an optional
@@ -126,6 +145,41 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.allow_errors = allow_errors;
     }
 
+    /// Set the byte order to use for endianness-agnostic number formats, like
+    /// `u16`.
+    pub fn set_default_endianness(&mut self, default_endianness: Option<Endianness>) {
+        self.default_endianness = default_endianness;
+    }
+
+    /// Set to true if elaborated terms should be emitted explicitly, in a form
+    /// that can be elaborated again to the same core terms
+    pub fn set_emit_core(&mut self, emit_core: bool) {
+        self.emit_core = emit_core;
+    }
+
+    /// Set to true if fixed-size fields of record formats that are never
+    /// referenced by later fields should be reported
+    pub fn set_warn_unused_fields(&mut self, warn_unused_fields: bool) {
+        self.warn_unused_fields = warn_unused_fields;
+    }
+
+    /// Set to true if byte arrays should be emitted as hexdumps when rendering
+    /// binary data
+    pub fn set_hexdump(&mut self, hexdump: bool) {
+        self.hexdump = hexdump;
+    }
+
+    /// Set to true if binary data should be emitted as JSON
+    pub fn set_emit_json(&mut self, emit_json: bool) {
+        self.emit_json = emit_json;
+    }
+
+    /// Set to true if the data read by `debug` formats should be logged to
+    /// stderr
+    pub fn set_debug_log(&mut self, debug_log: bool) {
+        self.debug_log = debug_log;
+    }
+
     /// Set the writer to use when rendering diagnostics
     pub fn set_diagnostic_writer(&mut self, stream: impl 'static + WriteColor) {
         self.diagnostic_writer = RefCell::new(Box::new(stream) as Box<dyn WriteColor>);
@@ -206,11 +260,15 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     pub fn elaborate_and_emit_module(&mut self, file_id: FileId, pretty_core: bool) -> Status {
         let mut context =
             elaboration::Context::new(file_id, &self.interner, &self.core_scope, ItemEnv::new());
+        context.set_default_endianness(self.default_endianness);
+        context.set_warn_unused_fields(self.warn_unused_fields);
 
         let surface_module = self.parse_module(file_id);
+        let mut diagnostics = Vec::new();
         let module = context.elab_module(&self.core_scope, &surface_module, &mut |m| {
-            self.emit_diagnostic(m.to_diagnostic(&self.interner));
+            diagnostics.push(m.to_diagnostic(&self.interner));
         });
+        self.emit_sorted_diagnostics(diagnostics);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
@@ -221,7 +279,8 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         }
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
-        let context = context.distillation_context(&self.surface_scope);
+        let mut context = context.distillation_context(&self.surface_scope);
+        context.set_explicit(self.emit_core);
         let module = context.distill_module(&module);
 
         self.emit_module(&module);
@@ -232,12 +291,16 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     pub fn elaborate_and_emit_term(&mut self, file_id: FileId) -> Status {
         let mut context =
             elaboration::Context::new(file_id, &self.interner, &self.core_scope, ItemEnv::new());
+        context.set_default_endianness(self.default_endianness);
+        context.set_warn_unused_fields(self.warn_unused_fields);
 
         // Parse and elaborate the term
         let surface_term = self.parse_term(file_id);
+        let mut diagnostics = Vec::new();
         let (term, r#type) = context.elab_term(&self.core_scope, &surface_term, &mut |m| {
-            self.emit_diagnostic(m.to_diagnostic(&self.interner));
+            diagnostics.push(m.to_diagnostic(&self.interner));
         });
+        self.emit_sorted_diagnostics(diagnostics);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
@@ -246,6 +309,7 @@ impl<'surface, 'core> Driver<'surface, 'core> {
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
         let mut context = context.distillation_context(&self.surface_scope);
+        context.set_explicit(self.emit_core);
         let term = context.check(&term);
         let r#type = context.check(&r#type);
 
@@ -257,20 +321,46 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     pub fn normalize_and_emit_term(&mut self, file_id: FileId) -> Status {
         let mut context =
             elaboration::Context::new(file_id, &self.interner, &self.core_scope, ItemEnv::new());
+        context.set_default_endianness(self.default_endianness);
 
         // Parse and elaborate the term
         let surface_term = self.parse_term(file_id);
+        let mut diagnostics = Vec::new();
         let (term, r#type) = context.elab_term(&self.core_scope, &surface_term, &mut |m| {
-            self.emit_diagnostic(m.to_diagnostic(&self.interner));
+            diagnostics.push(m.to_diagnostic(&self.interner));
         });
+        self.emit_sorted_diagnostics(diagnostics);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
             return Status::Error;
         }
 
-        let term = context.eval_env().normalize(&self.core_scope, &term);
-        let r#type = context.eval_env().normalize(&self.core_scope, &r#type);
+        // Normalize with a limited amount of fuel, so that runaway terms are
+        // reported as errors rather than overflowing the stack
+        let fuel = semantics::Fuel::default();
+        let mut local_exprs = SharedEnv::new();
+        let mut eval_env = context
+            .elim_env()
+            .with_fuel(&fuel)
+            .eval_env(&mut local_exprs);
+        let terms = (eval_env.try_normalise(&self.core_scope, &term))
+            .and_then(|term| Ok((term, eval_env.try_normalise(&self.core_scope, &r#type)?)));
+        let (term, r#type) = match terms {
+            Ok(terms) => terms,
+            Err(err @ semantics::Error::OutOfFuel) => {
+                self.emit_diagnostic(
+                    Diagnostic::error()
+                        .with_message(err.description())
+                        .with_notes(vec![format!(
+                            "Evaluation nested more than {} levels deep.",
+                            semantics::Fuel::DEFAULT_LIMIT,
+                        )]),
+                );
+                return Status::Error;
+            }
+            Err(err) => std::panic::panic_any(err),
+        };
 
         self.surface_scope.reset(); // Reuse the surface scope for distillation
         let mut context = context.distillation_context(&self.surface_scope);
@@ -290,18 +380,28 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     ) -> Status {
         use itertools::Itertools;
 
+        let debug_log = RefCell::new(Vec::new());
         let initial_buffer = binary::Buffer::from(buffer_data);
         let mut binary_context = binary::Context::new(initial_buffer);
+        binary_context.set_interner(&self.interner);
+        if self.debug_log {
+            binary_context.set_debug_sink(|label, pos, expr: &ArcValue<'_>| {
+                debug_log.borrow_mut().push((label, pos, expr.clone()));
+            });
+        }
         let mut item_env = ItemEnv::new();
 
         // Parse and elaborate a module if one was provided
         if let Some(file_id) = module_file_id {
             let mut elab_context =
                 elaboration::Context::new(file_id, &self.interner, &self.core_scope, item_env);
+            elab_context.set_default_endianness(self.default_endianness);
             let surface_module = self.parse_module(file_id);
+            let mut diagnostics = Vec::new();
             let module = elab_context.elab_module(&self.core_scope, &surface_module, &mut |m| {
-                self.emit_diagnostic(m.to_diagnostic(&self.interner));
+                diagnostics.push(m.to_diagnostic(&self.interner));
             });
+            self.emit_sorted_diagnostics(diagnostics);
             // Add it to the binary context
             binary_context.add_module(&module);
             item_env = elab_context.finish();
@@ -313,17 +413,23 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         // it works for now!
         let mut elab_context =
             elaboration::Context::new(format_file_id, &self.interner, &self.core_scope, item_env);
+        elab_context.set_default_endianness(self.default_endianness);
         let surface_format = self.parse_term(format_file_id);
+        let mut diagnostics = Vec::new();
         let format = elab_context.elab_format(&self.core_scope, &surface_format, &mut |m| {
-            self.emit_diagnostic(m.to_diagnostic(&self.interner));
+            diagnostics.push(m.to_diagnostic(&self.interner));
         });
+        self.emit_sorted_diagnostics(diagnostics);
 
         // Return early if we’ve seen any errors, unless `allow_errors` is enabled
         if *self.seen_errors.borrow() && !self.allow_errors {
             return Status::Error;
         }
 
-        let refs = match binary_context.read_entrypoint(&format) {
+        let result = binary_context.read_entrypoint(&format);
+        self.emit_debug_log(&mut elab_context, debug_log.take());
+
+        let refs = match result {
             Ok(refs) => refs,
             Err(err) => {
                 self.emit_diagnostic(self.read_error_to_diagnostic(err, &mut elab_context));
@@ -331,20 +437,25 @@ impl<'surface, 'core> Driver<'surface, 'core> {
             }
         };
 
+        if self.emit_json {
+            return self.emit_json_refs(refs);
+        }
+
         // Render the data we have read
         for (pos, parsed_refs) in refs.into_iter().sorted_by_key(|(pos, _)| *pos) {
             self.surface_scope.reset(); // Reuse the surface scope for distillation
 
-            let exprs = parsed_refs.iter().map(|parsed_ref| {
-                let core_scope = &self.core_scope;
-                let surface_scope = &self.surface_scope;
-                let expr = elab_context.quote_env().quote(core_scope, &parsed_ref.expr);
-                elab_context
-                    .distillation_context(surface_scope)
-                    .check(&expr)
+            let context = surface::pretty::Context::new(&self.interner, &self.surface_scope);
+            let exprs = parsed_refs.iter().map(|parsed_ref| match self.hexdump {
+                // The representation type is only needed to find the byte arrays
+                true => {
+                    let r#type = elab_context.elim_env().format_repr(&parsed_ref.format);
+                    self.hexdump_value_doc(&context, &mut elab_context, &parsed_ref.expr, &r#type)
+                }
+                false => self.value_doc(&context, &mut elab_context, &parsed_ref.expr),
             });
 
-            self.emit_ref(pos, exprs.collect());
+            self.emit_ref(&context, pos, exprs.collect());
         }
 
         Status::Ok
@@ -386,10 +497,117 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.emit_doc(context.term(term).into_doc());
     }
 
-    fn emit_ref(&self, pos: usize, exprs: Vec<surface::Term<'_, ()>>) {
+    /// Log the data read by `debug` formats to stderr, eliding long arrays.
+    fn emit_debug_log<'arena>(
+        &self,
+        elab_context: &mut elaboration::Context<'_, 'arena>,
+        debug_log: Vec<(StringId, usize, ArcValue<'arena>)>,
+    ) {
+        /// The number of array elements to log before eliding the rest.
+        const MAX_ARRAY_ELEMS: usize = 16;
+
+        let mut writer = self.diagnostic_writer.borrow_mut();
+        for (label, pos, expr) in debug_log {
+            let surface_scope = scoped_arena::Scope::new();
+            let expr = (elab_context.quote_env())
+                .limiting_array_elems(MAX_ARRAY_ELEMS)
+                .quote(&self.core_scope, &expr);
+            let term = elab_context
+                .distillation_context(&surface_scope)
+                .check(&expr);
+            let context = surface::pretty::Context::new(&self.interner, &surface_scope);
+            let doc = context.term(&term).into_doc();
+            let doc = doc.pretty(usize::MAX);
+            let label = self
+                .interner
+                .borrow()
+                .resolve(label)
+                .unwrap_or("")
+                .to_owned();
+
+            writeln!(writer, "debug: {label} at {pos}: {doc}").unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    /// Pretty print a value that was read from binary data, with the given
+    /// type. Byte arrays are rendered as hexdumps, and the rest of the value is
+    /// distilled back to surface terms.
+    fn hexdump_value_doc<'doc>(
+        &'doc self,
+        context: &'doc surface::pretty::Context<'doc, 'doc>,
+        elab_context: &mut elaboration::Context<'_, 'core>,
+        value: &ArcValue<'core>,
+        r#type: &ArcValue<'core>,
+    ) -> DocBuilder<'doc> {
+        let r#type = elab_context.elim_env().force(r#type);
+        match (value.as_ref(), r#type.as_ref()) {
+            (Value::RecordLit(labels, exprs), Value::RecordType(_, types)) => {
+                let mut types = types.clone();
+                let mut fields = Vec::with_capacity(exprs.len());
+                for (label, expr) in Iterator::zip(labels.iter(), exprs.iter()) {
+                    let split = elab_context.elim_env().split_telescope(types);
+                    let (r#type, next_types) = match split {
+                        Some(split) => split,
+                        None => break,
+                    };
+                    fields.push((
+                        *label,
+                        self.hexdump_value_doc(context, elab_context, expr, &r#type),
+                    ));
+                    types = next_types(expr.clone());
+                }
+                if !fields.is_empty() && fields.len() == exprs.len() {
+                    return context.record_literal(fields.into_iter());
+                }
+            }
+            (_, Value::Stuck(Head::Prim(prim), spine)) if is_array_type(*prim) => {
+                if let Some(Elim::FunApp(_, _, elem_type)) = spine.last() {
+                    let elem_type = elab_context.elim_env().force(elem_type);
+                    if let Value::Stuck(Head::Prim(Prim::U8Type), _) = elem_type.as_ref() {
+                        if let Some(Value::ByteArrayLit(bytes)) = value.to_byte_array_lit() {
+                            return context.hexdump(&bytes);
+                        }
+                    }
+                    if let Some(elems) = value.array_elems() {
+                        let elems = (elems.iter())
+                            .map(|elem| {
+                                self.hexdump_value_doc(context, elab_context, elem, &elem_type)
+                            })
+                            .collect::<Vec<_>>();
+                        return context.array_literal(elems.into_iter());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.value_doc(context, elab_context, value)
+    }
+
+    /// Pretty print a value that was read from binary data, by distilling it
+    /// back to a surface term.
+    fn value_doc<'doc>(
+        &'doc self,
+        context: &'doc surface::pretty::Context<'doc, 'doc>,
+        elab_context: &mut elaboration::Context<'_, 'core>,
+        value: &ArcValue<'core>,
+    ) -> DocBuilder<'doc> {
+        let expr = elab_context.quote_env().quote(&self.core_scope, value);
+        let term = elab_context
+            .distillation_context(&self.surface_scope)
+            .check(&expr);
+        context.term(&term)
+    }
+
+    fn emit_ref<'doc>(
+        &'doc self,
+        context: &'doc surface::pretty::Context<'doc, 'doc>,
+        pos: usize,
+        exprs: Vec<DocBuilder<'doc>>,
+    ) {
         use pretty::DocAllocator;
 
-        let context = surface::pretty::Context::new(&self.interner, &self.surface_scope);
         let pos = pos.to_string();
         let doc = context
             .concat([
@@ -400,7 +618,7 @@ impl<'surface, 'core> Driver<'surface, 'core> {
                 context.sequence(
                     true,
                     context.text("["),
-                    exprs.iter().map(|expr| context.term(expr)),
+                    exprs.into_iter(),
                     context.text(","),
                     context.text("]"),
                 ),
@@ -410,6 +628,43 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         self.emit_doc(doc);
     }
 
+    /// Emit the data that was read as a JSON object, mapping the positions of
+    /// the parsed references to arrays of their values.
+    fn emit_json_refs(&self, refs: HashMap<usize, Vec<binary::ParsedRef<'core>>>) -> Status {
+        use itertools::Itertools;
+
+        let interner = self.interner.borrow();
+        let mut json = String::from("{");
+        for (index, (pos, parsed_refs)) in refs.iter().sorted_by_key(|(pos, _)| **pos).enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!("\"{pos}\":["));
+            for (index, parsed_ref) in parsed_refs.iter().enumerate() {
+                if index > 0 {
+                    json.push(',');
+                }
+                match binary::value_to_json(&interner, &parsed_ref.expr) {
+                    Some(value) => json.push_str(&value),
+                    None => {
+                        self.emit_diagnostic(Diagnostic::error().with_message(format!(
+                            "data read at position {pos} can't be represented as JSON"
+                        )));
+                        return Status::Error;
+                    }
+                }
+            }
+            json.push(']');
+        }
+        json.push('}');
+
+        let mut emit_writer = self.emit_writer.borrow_mut();
+        writeln!(emit_writer, "{json}").unwrap();
+        emit_writer.flush().unwrap();
+
+        Status::Ok
+    }
+
     fn emit_doc(&self, doc: pretty::RefDoc) {
         let mut emit_writer = self.emit_writer.borrow_mut();
         writeln!(emit_writer, "{}", doc.pretty(self.emit_width)).unwrap();
@@ -428,6 +683,12 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         }
     }
 
+    /// Emit diagnostics in source order, skipping repeated diagnostics.
+    fn emit_sorted_diagnostics(&self, mut diagnostics: Vec<Diagnostic<FileId>>) {
+        sort_diagnostics(&mut diagnostics);
+        self.emit_diagnostics(diagnostics.into_iter());
+    }
+
     fn emit_diagnostics(&self, diagnostics: impl Iterator<Item = Diagnostic<FileId>>) {
         for diagnostic in diagnostics {
             self.emit_diagnostic(diagnostic);
@@ -454,13 +715,13 @@ impl<'surface, 'core> Driver<'surface, 'core> {
         context: &mut elaboration::Context,
     ) -> Diagnostic<FileId> {
         match err {
-            ReadError::ReadFailFormat(span) => Diagnostic::error()
+            ReadError::ReadFailFormat(span, offset) => Diagnostic::error()
                 .with_message(err.to_string())
                 .with_labels(label_for_span(&span).into_iter().collect())
                 .with_notes(vec![format!(
-                    "A fail format was encountered when reading this file."
+                    "A fail format was encountered at offset {offset} when reading this file."
                 )]),
-            ReadError::CondFailure(span, ref value) => {
+            ReadError::CondFailure(span, offset, ref value) => {
                 let core_scope = &self.core_scope;
                 let surface_scope = &self.surface_scope;
                 let expr = context.quote_env().quote(core_scope, value);
@@ -474,12 +735,47 @@ impl<'surface, 'core> Driver<'surface, 'core> {
                     .with_notes(vec![
                         "The predicate on a conditional format did not succeed.".to_string(),
                         format!("failed value: {}", doc.pretty(self.emit_width)),
+                        format!("The value was read at offset {offset}."),
                     ])
             }
-            ReadError::UnwrappedNone(_) => Diagnostic::error()
+            ReadError::InvalidUtf8(span, offset) => Diagnostic::error()
                 .with_message(err.to_string())
-                .with_notes(vec![format!("option_unwrap was called on a none value.")]),
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "The string read at offset {offset} was not valid UTF-8."
+                )]),
+            ReadError::NonZeroReserved(span, offset) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "The reserved byte at offset {offset} must be zero."
+                )]),
+            ReadError::ArrayTooLong(span, offset, len, max_len) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "The array at offset {offset} has {len} elements, but at most {max_len} elements may be read."
+                )]),
+            ReadError::UnwrappedNone(_, offset) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_notes(vec![format!(
+                    "option_unwrap was called on a none value at offset {offset}."
+                )]),
             ReadError::BufferError(span, err) => self.buffer_error_to_diagnostic(err, span),
+            ReadError::OutOfFuel(span) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![
+                    "Reading this file required more nested formats than the limit allows."
+                        .to_string(),
+                    "This is usually caused by a link that refers back to itself.".to_string(),
+                ]),
+            ReadError::SeekRequired(span, offset) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect())
+                .with_notes(vec![format!(
+                    "This format reads data from offset {offset}, which is at another position in the stream."
+                )]),
             ReadError::InvalidFormat(span) | ReadError::InvalidValue(span) => Diagnostic::bug()
                 .with_message(format!("unexpected error '{err}'"))
                 .with_labels(label_for_span(&span).into_iter().collect())
@@ -496,12 +792,31 @@ impl<'surface, 'core> Driver<'surface, 'core> {
 
     fn buffer_error_to_diagnostic(&self, err: BufferError, span: Span) -> Diagnostic<FileId> {
         match err {
-            BufferError::UnexpectedEndOfBuffer => Diagnostic::error()
-                .with_message(err.to_string())
-                .with_labels(label_for_span(&span).into_iter().collect())
-                .with_notes(vec![format!(
-                    "The end of the buffer was reached before all data could be read."
-                )]),
+            BufferError::UnexpectedEndOfBuffer {
+                offset,
+                needed,
+                available,
+                format: _,
+                ref context,
+            } => {
+                let mut notes = vec![match context {
+                    Some(context) => format!(
+                        "The end of the buffer was reached {context}, \
+                        before all data could be read."
+                    ),
+                    None => "The end of the buffer was reached before all data could be read."
+                        .to_owned(),
+                }];
+                notes.push(format!(
+                    "{needed} bytes were needed at offset {offset}, \
+                    but only {available} were available."
+                ));
+
+                Diagnostic::error()
+                    .with_message(err.to_string())
+                    .with_labels(label_for_span(&span).into_iter().collect())
+                    .with_notes(notes)
+            }
             BufferError::SetOffsetBeforeStartOfBuffer { offset } => Diagnostic::error()
                 .with_message(err.to_string())
                 .with_labels(label_for_span(&span).into_iter().collect())
@@ -522,6 +837,9 @@ impl<'surface, 'core> Driver<'surface, 'core> {
                 .with_notes(vec![format!(
                     "The offset is beyond the end of the buffer (overflow).",
                 )]),
+            BufferError::IoError(_) => Diagnostic::error()
+                .with_message(err.to_string())
+                .with_labels(label_for_span(&span).into_iter().collect()),
             BufferError::PositionOverflow => Diagnostic::bug()
                 .with_message(format!("unexpected error '{err}'"))
                 .with_labels(label_for_span(&span).into_iter().collect())
@@ -532,9 +850,123 @@ impl<'surface, 'core> Driver<'surface, 'core> {
     }
 }
 
+/// Returns `true` if the primitive is one of the array types.
+fn is_array_type(prim: Prim) -> bool {
+    matches!(
+        prim,
+        Prim::ArrayType
+            | Prim::Array8Type
+            | Prim::Array16Type
+            | Prim::Array32Type
+            | Prim::Array64Type
+    )
+}
+
+/// Sort diagnostics by the position of their primary labels in the source,
+/// removing any diagnostics with the same message, primary label, and notes
+/// as an earlier one. This avoids flooding the output with errors that have the same
+/// root cause.
+fn sort_diagnostics(diagnostics: &mut Vec<Diagnostic<FileId>>) {
+    fn primary_label(diagnostic: &Diagnostic<FileId>) -> Option<&Label<FileId>> {
+        (diagnostic.labels.iter())
+            .find(|label| label.style == LabelStyle::Primary)
+            .or_else(|| diagnostic.labels.first())
+    }
+
+    // Diagnostics without labels are sorted last. The sort is stable, so
+    // diagnostics at the same position stay in the order they were found.
+    diagnostics.sort_by_key(|diagnostic| match primary_label(diagnostic) {
+        Some(label) => (false, u32::from(label.file_id), label.range.start),
+        None => (true, 0, 0),
+    });
+
+    let mut seen = HashSet::new();
+    diagnostics.retain(|diagnostic| {
+        seen.insert((
+            diagnostic.message.clone(),
+            primary_label(diagnostic).map(|label| (label.file_id, label.range.clone())),
+            diagnostic.notes.clone(),
+        ))
+    });
+}
+
 fn label_for_span(span: &Span) -> Option<Label<FileId>> {
     match span {
         Span::Range(range) => Some(Label::primary(range.file_id(), *range)),
         Span::Empty => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sort_diagnostics_removes_repeated_errors() {
+        let mut files = Files::new();
+        let source = "let x : U8 = foo; let y : U8 = foo; x".to_owned();
+        let file_id = files.add("test", ProgramSource::try_from(source).unwrap());
+
+        let not_found = |start, end| {
+            Diagnostic::error()
+                .with_message("cannot find `foo` in scope")
+                .with_labels(vec![Label::primary(file_id, start..end)])
+        };
+        let mut diagnostics = vec![
+            not_found(31, 34),
+            not_found(13, 16),
+            not_found(31, 34),
+            Diagnostic::error().with_message("unlabelled"),
+            not_found(13, 16),
+        ];
+        sort_diagnostics(&mut diagnostics);
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                not_found(13, 16),
+                not_found(31, 34),
+                Diagnostic::error().with_message("unlabelled"),
+            ],
+        );
+    }
+
+    #[test]
+    fn sort_diagnostics_orders_module_errors() {
+        let mut driver = Driver::new();
+        let source = "def a : U8 = b + foo;\ndef b : U8 = bar;\n".to_owned();
+        let file_id = driver
+            .load_source_string("test".to_owned(), source)
+            .unwrap();
+
+        // `b` is elaborated before `a`, so its error is reported first
+        let mut context = elaboration::Context::new(
+            file_id,
+            &driver.interner,
+            &driver.core_scope,
+            ItemEnv::new(),
+        );
+        let surface_module = driver.parse_module(file_id);
+        let mut diagnostics = Vec::new();
+        context.elab_module(&driver.core_scope, &surface_module, &mut |m| {
+            diagnostics.push(m.to_diagnostic(&driver.interner));
+        });
+        let messages = |diagnostics: &[Diagnostic<FileId>]| {
+            (diagnostics.iter())
+                .map(|diagnostic| diagnostic.message.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            messages(&diagnostics),
+            ["cannot find `bar` in scope", "cannot find `foo` in scope"],
+        );
+
+        diagnostics.extend(diagnostics.clone());
+        sort_diagnostics(&mut diagnostics);
+
+        assert_eq!(
+            messages(&diagnostics),
+            ["cannot find `foo` in scope", "cannot find `bar` in scope"],
+        );
+    }
+}
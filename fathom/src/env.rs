@@ -41,7 +41,7 @@ type RawVar = u16;
 ///
 /// [de Bruijn index]: https://en.wikipedia.org/wiki/De_Bruijn_index
 /// [alpha-equivalence]: https://ncatlab.org/nlab/show/alpha-equivalence
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Index(RawVar);
 
 impl Index {
@@ -88,7 +88,7 @@ pub fn indices() -> impl Iterator<Item = Index> {
 /// are not tied to a specific binding depth, unlike [indices][Index].
 /// Because of this, we're able to sidestep the need for expensive variable
 /// shifting during [normalization][crate::core::semantics::EvalEnv::normalize].
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Level(RawVar);
 
 impl Level {
@@ -147,6 +147,13 @@ impl EnvLen {
         Some(Index(self.0.checked_sub(level.0)?.checked_sub(1)?))
     }
 
+    /// Convert an index that occurs under the binders counted by this length
+    /// to an index referring to the same variable outside of those binders,
+    /// returning `None` if the index refers to one of the binders.
+    pub fn unbind_index(self, index: Index) -> Option<Index> {
+        Some(Index(index.0.checked_sub(self.0)?))
+    }
+
     /// The next level that will be bound in this environment.
     pub fn next_level(self) -> Level {
         Level(self.0)
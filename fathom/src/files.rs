@@ -11,7 +11,7 @@ use codespan_reporting::files::{Error, SimpleFile};
 // - Use `u32` over `usize` because 4 billion files should be enough for anyone
 // - `u16` doesn't save any size in `ByteRange` or `Span` compared to `u32`
 // - `NonZeroU32` saves 4 bytes on the size of `Span` compared to `u32`
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileId(NonZeroU32);
 
 impl fmt::Display for FileId {
@@ -8,6 +8,8 @@ mod alloc;
 pub mod env;
 pub mod files;
 pub mod source;
+#[cfg(test)]
+mod testing;
 
 // Intermediate languages
 pub mod core;
@@ -29,9 +29,20 @@ enum Cli {
         /// Continue even if errors were encountered
         #[clap(long = "allow-errors")]
         allow_errors: bool,
+        /// Endianness of number formats like `u16`
+        #[clap(long = "endianness", name = "ENDIANNESS", value_enum)]
+        default_endianness: Option<Endianness>,
         /// Pretty print core module
         #[clap(long = "pretty-core", conflicts_with("TERM_FILE"))]
         pretty_core: bool,
+        /// Print the elaborated core explicitly, in a form that can be
+        /// elaborated again
+        #[clap(long = "emit-core")]
+        emit_core: bool,
+        /// Report fixed-size fields of record formats that are never referenced
+        /// by later fields
+        #[clap(long = "warn-unused-fields")]
+        warn_unused_fields: bool,
     },
     /// Normalize a Fathom term, printing its normal form and type
     Norm {
@@ -41,6 +52,9 @@ enum Cli {
         /// Continue even if errors were encountered
         #[clap(long = "allow-errors")]
         allow_errors: bool,
+        /// Endianness of number formats like `u16`
+        #[clap(long = "endianness", name = "ENDIANNESS", value_enum)]
+        default_endianness: Option<Endianness>,
     },
     /// Manipulate binary data based on a Fathom format
     #[clap(after_help = DATA_COMMAND_AFTER_HELP)]
@@ -68,6 +82,18 @@ enum Cli {
         /// Continue even if errors were encountered
         #[clap(long = "allow-errors")]
         allow_errors: bool,
+        /// Endianness of number formats like `u16`
+        #[clap(long = "endianness", name = "ENDIANNESS", value_enum)]
+        default_endianness: Option<Endianness>,
+        /// Render arrays of bytes as hexdumps
+        #[clap(long = "hexdump")]
+        hexdump: bool,
+        /// Log the data read by `debug` formats to stderr
+        #[clap(long = "debug-log")]
+        debug_log: bool,
+        /// How the binary data is rendered
+        #[clap(long = "output", name = "OUTPUT", value_enum, default_value = "text")]
+        output: DataOutput,
     },
 }
 
@@ -95,6 +121,29 @@ When a module is specified the binary data is read assuming that it contains a
   $ fathom data --module formats/icns.fathom --format header AppIcon.icns
 ";
 
+#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+enum Endianness {
+    Big,
+    Little,
+}
+
+impl From<Endianness> for fathom::core::prim::Endianness {
+    fn from(endianness: Endianness) -> fathom::core::prim::Endianness {
+        match endianness {
+            Endianness::Big => fathom::core::prim::Endianness::Big,
+            Endianness::Little => fathom::core::prim::Endianness::Little,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum DataOutput {
+    /// Render the data as Fathom terms
+    Text,
+    /// Render the data as JSON
+    Json,
+}
+
 #[derive(Clone, Debug)]
 enum PathOrStdin {
     StdIn,
@@ -151,11 +200,17 @@ fn main() -> ! {
             module_file,
             term_file,
             allow_errors,
+            default_endianness,
             pretty_core,
+            emit_core,
+            warn_unused_fields,
         } => {
             let mut driver = fathom::Driver::new();
             driver.install_panic_hook();
             driver.set_allow_errors(allow_errors);
+            driver.set_emit_core(emit_core);
+            driver.set_warn_unused_fields(warn_unused_fields);
+            driver.set_default_endianness(default_endianness.map(Into::into));
             driver.set_emit_width(get_pretty_width());
 
             let status = match (module_file, term_file) {
@@ -177,10 +232,12 @@ fn main() -> ! {
         Cli::Norm {
             term_file,
             allow_errors,
+            default_endianness,
         } => {
             let mut driver = fathom::Driver::new();
             driver.install_panic_hook();
             driver.set_allow_errors(allow_errors);
+            driver.set_default_endianness(default_endianness.map(Into::into));
             driver.set_emit_width(get_pretty_width());
 
             let file_id = load_file_or_exit(&mut driver, term_file);
@@ -193,10 +250,18 @@ fn main() -> ! {
             format,
             binary_file,
             allow_errors,
+            default_endianness,
+            hexdump,
+            debug_log,
+            output,
         } => {
             let mut driver = fathom::Driver::new();
             driver.install_panic_hook();
             driver.set_allow_errors(allow_errors);
+            driver.set_default_endianness(default_endianness.map(Into::into));
+            driver.set_hexdump(hexdump);
+            driver.set_debug_log(debug_log);
+            driver.set_emit_json(output == DataOutput::Json);
             driver.set_emit_width(get_pretty_width());
 
             let module_file_id = module_file.map(|input| load_file_or_exit(&mut driver, input));
@@ -194,7 +194,7 @@ impl<T> DerefMut for Spanned<T> {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Span {
     Range(FileRange),
     Empty,
@@ -231,7 +231,7 @@ impl From<Option<FileRange>> for Span {
 pub type BytePos = u32;
 
 /// Byte ranges in source files.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct FileRange {
     file_id: FileId,
     byte_range: ByteRange,
@@ -289,7 +289,7 @@ impl From<FileRange> for Range<usize> {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct ByteRange {
     start: BytePos,
     end: BytePos,
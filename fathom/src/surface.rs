@@ -62,7 +62,10 @@ pub enum Item<'arena, Range> {
 /// Top-level definitions
 #[derive(Debug, Clone)]
 pub struct ItemDef<'arena, Range> {
-    /// The full range of the definition
+    /// Lines of the doc comments attached to the definition, without their
+    /// leading `///`
+    doc: &'arena [StringId],
+    /// The full range of the definition, not including its doc comments
     range: Range,
     /// The label that identifies this definition
     label: (Range, StringId),
@@ -74,6 +77,14 @@ pub struct ItemDef<'arena, Range> {
     expr: &'arena Term<'arena, Range>,
 }
 
+impl<'arena, Range> ItemDef<'arena, Range> {
+    /// Lines of the doc comments attached to the definition, without their
+    /// leading `///`
+    pub fn doc(&self) -> &'arena [StringId] {
+        self.doc
+    }
+}
+
 /// Surface patterns.
 #[derive(Debug, Clone)]
 pub enum Pattern<Range> {
@@ -91,6 +102,11 @@ pub enum Pattern<Range> {
     /// As with [term literals][Term::NumberLiteral], these will be parsed fully
     /// during [elaboration].
     NumberLiteral(Range, StringId),
+    /// Inclusive number range patterns, eg. `0..=9`
+    ///
+    /// The bounds are stored as they were written in the source, and will be
+    /// parsed fully during [elaboration].
+    NumberRange(Range, StringId, StringId),
     /// Boolean literal patterns
     BooleanLiteral(Range, bool),
     // TODO: Record literal patterns
@@ -130,6 +146,21 @@ impl<Range> BinOp<Range> {
         }
     }
 
+    fn range_mut(&mut self) -> &mut Range {
+        match self {
+            BinOp::Add(range)
+            | BinOp::Sub(range)
+            | BinOp::Mul(range)
+            | BinOp::Div(range)
+            | BinOp::Eq(range)
+            | BinOp::Neq(range)
+            | BinOp::Lt(range)
+            | BinOp::Lte(range)
+            | BinOp::Gt(range)
+            | BinOp::Gte(range) => range,
+        }
+    }
+
     fn as_str(&self) -> &'static str {
         match self {
             BinOp::Add(_) => "+",
@@ -174,6 +205,7 @@ impl<Range: Clone> Pattern<Range> {
             | Pattern::Placeholder(range)
             | Pattern::StringLiteral(range, _)
             | Pattern::NumberLiteral(range, _)
+            | Pattern::NumberRange(range, _, _)
             | Pattern::BooleanLiteral(range, _) => range.clone(),
         }
     }
@@ -204,6 +236,12 @@ pub enum Term<'arena, Range> {
         &'arena Term<'arena, Range>,
         &'arena Term<'arena, Range>,
     ),
+    /// Record destructuring let expressions, eg. `let { x, y } = r; body`.
+    LetRecord(
+        Range,
+        &'arena LetRecordDef<'arena, Range>,
+        &'arena Term<'arena, Range>,
+    ),
     /// If expressions
     If(
         Range,
@@ -249,6 +287,12 @@ pub enum Term<'arena, Range> {
     /// Record literals.
     RecordLiteral(Range, &'arena [ExprField<'arena, Range>]),
     Tuple(Range, &'arena [Term<'arena, Range>]),
+    /// Record update expressions.
+    RecordUpdate(
+        Range,
+        &'arena Term<'arena, Range>,
+        &'arena [ExprField<'arena, Range>],
+    ),
     /// Projections.
     Proj(
         Range,
@@ -257,6 +301,12 @@ pub enum Term<'arena, Range> {
     ),
     /// Array literals.
     ArrayLiteral(Range, &'arena [Term<'arena, Range>]),
+    /// The number of array elements that were left out when distilling a term
+    /// that was [normalized for display]. These are never produced by the
+    /// parser.
+    ///
+    /// [normalized for display]: crate::core::semantics::EvalEnv::normalise_for_display
+    ElidedElems(Range, usize),
     /// String literal.
     ///
     /// These are stored as strings, and will be parsed during [elaboration]
@@ -301,6 +351,7 @@ impl<'arena, Range: Clone> Term<'arena, Range> {
             | Term::Placeholder(range)
             | Term::Ann(range, _, _)
             | Term::Let(range, _, _, _, _)
+            | Term::LetRecord(range, _, _)
             | Term::If(range, _, _, _)
             | Term::Match(range, _, _)
             | Term::Universe(range)
@@ -311,8 +362,10 @@ impl<'arena, Range: Clone> Term<'arena, Range> {
             | Term::RecordType(range, _)
             | Term::RecordLiteral(range, _)
             | Term::Tuple(range, _)
+            | Term::RecordUpdate(range, _, _)
             | Term::Proj(range, _, _)
             | Term::ArrayLiteral(range, _)
+            | Term::ElidedElems(range, _)
             | Term::StringLiteral(range, _)
             | Term::NumberLiteral(range, _)
             | Term::BooleanLiteral(range, _)
@@ -349,6 +402,59 @@ impl<'arena> Term<'arena, FileRange> {
     }
 }
 
+/// A local definition in a `where` expression or a `let { .. } in` block.
+#[derive(Debug, Clone)]
+pub struct LetDef<'arena, Range> {
+    /// The pattern that the definition is bound to
+    pub pattern: Pattern<Range>,
+    /// An optional type annotation for the defined expression
+    pub r#type: Option<Term<'arena, Range>>,
+    /// The defined expression
+    pub expr: Term<'arena, Range>,
+}
+
+/// Desugar the definitions of a `where` expression or a `let { .. } in`
+/// block into nested let expressions, so that each definition is bound in
+/// the definitions that follow it and in the body.
+fn desugar_let_defs<'arena>(
+    scope: &'arena Scope<'arena>,
+    start: BytePos,
+    end: BytePos,
+    defs: &'arena [LetDef<'arena, ByteRange>],
+    body_expr: Term<'arena, ByteRange>,
+) -> Term<'arena, ByteRange> {
+    let (first, rest) = defs.split_first().unwrap();
+    let body_expr = rest.iter().rev().fold(body_expr, |body_expr, def| {
+        Term::Let(
+            ByteRange::new(def.pattern.range().start(), end),
+            def.pattern.clone(),
+            def.r#type.as_ref(),
+            &def.expr,
+            scope.to_scope(body_expr),
+        )
+    });
+
+    Term::Let(
+        ByteRange::new(start, end),
+        first.pattern.clone(),
+        first.r#type.as_ref(),
+        &first.expr,
+        scope.to_scope(body_expr),
+    )
+}
+
+/// A definition that destructures a record, binding each of its fields to a
+/// definition with the same name as the field's label.
+#[derive(Debug, Clone)]
+pub struct LetRecordDef<'arena, Range> {
+    /// The labels of the fields bound by the definition
+    pub labels: &'arena [(Range, StringId)],
+    /// An optional type annotation for the defined expression
+    pub r#type: Option<Term<'arena, Range>>,
+    /// The defined expression
+    pub expr: Term<'arena, Range>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Param<'arena, Range> {
     pub plicity: Plicity,
@@ -423,6 +529,9 @@ pub enum ParseMessage {
         range: ByteRange,
         token: &'static str,
     },
+    WhitespaceInNegativeLiteral {
+        range: ByteRange,
+    },
 }
 
 impl ParseMessage {
@@ -432,7 +541,8 @@ impl ParseMessage {
             ParseMessage::InvalidToken { range }
             | ParseMessage::UnrecognizedEof { range, .. }
             | ParseMessage::UnrecognizedToken { range, .. }
-            | ParseMessage::ExtraToken { range, .. } => *range,
+            | ParseMessage::ExtraToken { range, .. }
+            | ParseMessage::WhitespaceInNegativeLiteral { range } => *range,
         }
     }
 
@@ -495,6 +605,15 @@ impl ParseMessage {
             ParseMessage::ExtraToken { range, token } => Diagnostic::error()
                 .with_message(format!("extra token {token}"))
                 .with_labels(vec![primary_label(range).with_message("extra token")]),
+            ParseMessage::WhitespaceInNegativeLiteral { range } => Diagnostic::error()
+                .with_message("whitespace in negative number literal")
+                .with_labels(vec![
+                    primary_label(range).with_message("unexpected whitespace")
+                ])
+                .with_notes(vec![
+                    "the `-` of a negative number literal must directly precede the number"
+                        .to_owned(),
+                ]),
         }
     }
 }
@@ -519,6 +638,242 @@ mod tests {
     use super::*;
     use crate::source::ByteRange;
 
+    /// Parse a term, panicking on errors.
+    fn parse_term<'arena>(
+        interner: &RefCell<StringInterner>,
+        scope: &'arena Scope<'arena>,
+        source: &str,
+    ) -> Term<'arena, ByteRange> {
+        let source = ProgramSource::try_from(source.to_owned()).unwrap();
+        let (term, messages) = Term::parse(interner, scope, &source);
+        assert!(messages.is_empty(), "{messages:?}");
+        term
+    }
+
+    fn number_literal(interner: &RefCell<StringInterner>, term: &Term<'_, ByteRange>) -> String {
+        match term {
+            Term::NumberLiteral(_, number) => {
+                interner.borrow().resolve(*number).unwrap().to_owned()
+            }
+            term => panic!("expected number literal, found {term:?}"),
+        }
+    }
+
+    #[test]
+    fn minus_without_spaces_is_subtraction() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for source in ["a-1", "a - 1", "a -1"] {
+            match parse_term(&interner, &scope, source) {
+                Term::BinOp(_, Term::Name(..), BinOp::Sub(_), rhs) => {
+                    assert_eq!(number_literal(&interner, rhs), "1", "{source}");
+                }
+                term => panic!("expected subtraction for `{source}`, found {term:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn negative_literal_in_argument_position() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        match parse_term(&interner, &scope, "f (-1)") {
+            Term::App(
+                _,
+                Term::Name(..),
+                [Arg {
+                    term: Term::Paren(_, arg),
+                    ..
+                }],
+            ) => {
+                assert_eq!(number_literal(&interner, arg), "-1");
+            }
+            term => panic!("expected application, found {term:?}"),
+        }
+    }
+
+    #[test]
+    fn negative_literal_operands() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        let term = parse_term(&interner, &scope, "-1");
+        assert_eq!(number_literal(&interner, &term), "-1");
+        assert_eq!((term.range().start(), term.range().end()), (0, 2));
+
+        match parse_term(&interner, &scope, "a - -1") {
+            Term::BinOp(_, Term::Name(..), BinOp::Sub(_), rhs) => {
+                assert_eq!(number_literal(&interner, rhs), "-1");
+            }
+            term => panic!("expected subtraction, found {term:?}"),
+        }
+    }
+
+    #[test]
+    fn whitespace_in_negative_literal() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (source, range) in [("- 1", (1, 2)), ("a - - 1", (5, 6)), ("f (-\n1)", (4, 5))] {
+            let source = ProgramSource::try_from(source.to_owned()).unwrap();
+            let (_, messages) = Term::parse(&interner, &scope, &source);
+            match messages.as_slice() {
+                [message @ ParseMessage::WhitespaceInNegativeLiteral { .. }] => {
+                    let found = message.range();
+                    assert_eq!((found.start(), found.end()), range);
+                }
+                messages => panic!("unexpected messages {messages:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn doc_comments_attach_to_items() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let source = ProgramSource::try_from(
+            "// not a doc comment\n\
+             def a = 1;\n\
+             /// The second item.\n\
+             ///\n\
+             /// More about the second item.\n\
+             def b = { x <- u8 };\n\
+             def c = {\n    /// A field.\n    x <- u8,\n};\n\
+             //// Not a doc comment.\n\
+             /// The fourth item.\r\n\
+             def d = 4;\n"
+                .to_owned(),
+        )
+        .unwrap();
+
+        let (module, messages) = Module::parse(&interner, &scope, &source);
+        assert!(messages.is_empty(), "{messages:?}");
+
+        let docs = (module.items.iter())
+            .map(|item| match item {
+                Item::Def(item) => (item.doc.iter())
+                    .map(|line| interner.borrow().resolve(*line).unwrap().to_owned())
+                    .collect::<Vec<_>>(),
+                Item::ReportedError(_) => panic!("unexpected error item"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            docs,
+            [
+                vec![],
+                vec![" The second item.", "", " More about the second item."],
+                vec![],
+                vec![" The fourth item."],
+            ],
+        );
+    }
+
+    #[test]
+    fn trailing_separators() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for (source, len) in [
+            ("[]", 0),
+            ("[1]", 1),
+            ("[1,]", 1),
+            ("[1, 2]", 2),
+            ("[1, 2,]", 2),
+        ] {
+            match parse_term(&interner, &scope, source) {
+                Term::ArrayLiteral(_, exprs) => assert_eq!(exprs.len(), len, "{source}"),
+                term => panic!("expected array literal for `{source}`, found {term:?}"),
+            }
+        }
+
+        for (source, len) in [
+            ("{ x : A }", 1),
+            ("{ x : A, }", 1),
+            ("{ x : A, y : B, }", 2),
+        ] {
+            match parse_term(&interner, &scope, source) {
+                Term::RecordType(_, fields) => assert_eq!(fields.len(), len, "{source}"),
+                term => panic!("expected record type for `{source}`, found {term:?}"),
+            }
+        }
+
+        for (source, len) in [
+            ("{ x = 1 }", 1),
+            ("{ x = 1, }", 1),
+            ("{ x = 1, y = 2, }", 2),
+        ] {
+            match parse_term(&interner, &scope, source) {
+                Term::RecordLiteral(_, fields) => assert_eq!(fields.len(), len, "{source}"),
+                term => panic!("expected record literal for `{source}`, found {term:?}"),
+            }
+        }
+
+        for (source, len) in [
+            ("{ x <- a }", 1),
+            ("{ x <- a, }", 1),
+            ("{ x <- a, y <- b, }", 2),
+        ] {
+            match parse_term(&interner, &scope, source) {
+                Term::FormatRecord(_, fields) => assert_eq!(fields.len(), len, "{source}"),
+                term => panic!("expected format record for `{source}`, found {term:?}"),
+            }
+        }
+
+        for (source, len) in [
+            ("match x {}", 0),
+            ("match x { _ => 1 }", 1),
+            ("match x { _ => 1, }", 1),
+            ("match x { 0 => 1, _ => 2 }", 2),
+            ("match x { 0 => 1, _ => 2, }", 2),
+        ] {
+            match parse_term(&interner, &scope, source) {
+                Term::Match(_, _, equations) => assert_eq!(equations.len(), len, "{source}"),
+                term => panic!("expected match expression for `{source}`, found {term:?}"),
+            }
+        }
+
+        for source in ["[,]", "[1,,]", "{ , }", "{ x = 1,, }", "match x { , }"] {
+            let source = ProgramSource::try_from(source.to_owned()).unwrap();
+            let (_, messages) = Term::parse(&interner, &scope, &source);
+            assert!(!messages.is_empty(), "expected `{source:?}` to be rejected");
+        }
+    }
+
+    #[test]
+    fn let_blocks_desugar_to_nested_lets() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let pretty_context = pretty::Context::new(&interner, &scope);
+        let pretty = |source| {
+            let term = parse_term(&interner, &scope, source);
+            pretty_context.term(&term).into_doc().pretty(80).to_string()
+        };
+
+        assert_eq!(
+            pretty("let { x : U8 = 1; y = x; z = y; } in z"),
+            pretty("let x : U8 = 1; let y = x; let z = y; z"),
+        );
+        assert_eq!(pretty("let { x = 1 } in x"), pretty("let x = 1; x"));
+    }
+
+    #[test]
+    fn let_blocks_and_record_bindings_share_prefix() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        for source in ["let { x } = r; x", "let { x, y } : R = r; x"] {
+            let term = parse_term(&interner, &scope, source);
+            assert!(matches!(term, Term::LetRecord(..)), "{source}: {term:?}");
+        }
+        for source in ["let { x = r } in x", "let { x : R = r } in x"] {
+            let term = parse_term(&interner, &scope, source);
+            assert!(matches!(term, Term::Let(..)), "{source}: {term:?}");
+        }
+    }
+
     #[test]
     fn no_drop() {
         assert!(!std::mem::needs_drop::<Term<'_, ()>>());
@@ -529,14 +884,14 @@ mod tests {
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn term_size() {
-        assert_eq!(std::mem::size_of::<Term<()>>(), 32);
-        assert_eq!(std::mem::size_of::<Term<ByteRange>>(), 48);
+        assert_eq!(std::mem::size_of::<Term<()>>(), 40);
+        assert_eq!(std::mem::size_of::<Term<ByteRange>>(), 56);
     }
 
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn pattern_size() {
-        assert_eq!(std::mem::size_of::<Pattern<()>>(), 8);
-        assert_eq!(std::mem::size_of::<Pattern<ByteRange>>(), 16);
+        assert_eq!(std::mem::size_of::<Pattern<()>>(), 12);
+        assert_eq!(std::mem::size_of::<Pattern<ByteRange>>(), 20);
     }
 }
@@ -6,7 +6,7 @@ use scoped_arena::Scope;
 
 use crate::alloc::SliceVec;
 use crate::core;
-use crate::core::{Const, Plicity, UIntStyle};
+use crate::core::{Const, ConstPattern, Plicity, UIntStyle};
 use crate::env::{self, EnvLen, Index, Level, UniqueEnv};
 use crate::source::{Span, StringId, StringInterner};
 use crate::surface::elaboration::MetaSource;
@@ -40,6 +40,8 @@ pub struct Context<'interner, 'arena, 'env> {
     local_names: &'env mut UniqueEnv<Option<StringId>>,
     /// Metavariable sources.
     meta_sources: &'env UniqueEnv<MetaSource>,
+    /// Whether terms should be distilled [explicitly][Context::set_explicit].
+    explicit: bool,
 }
 
 impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
@@ -57,9 +59,18 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
             item_names,
             local_names,
             meta_sources,
+            explicit: false,
         }
     }
 
+    /// Distill terms explicitly, so that they can be elaborated again to the
+    /// same core terms. Binders are renamed to avoid capturing other
+    /// variables, type annotations are retained, and primitive operations are
+    /// not distilled to binary operators.
+    pub fn set_explicit(&mut self, explicit: bool) {
+        self.explicit = explicit;
+    }
+
     fn is_bound(&self, name: StringId) -> bool {
         (self.local_names.iter()).any(|local_name| *local_name == Some(name))
             || self.item_names.iter().any(|item_name| *item_name == name)
@@ -111,9 +122,31 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
         }
     }
 
-    /// Replace `name` with a fresh name if it is `_` and occurs in `body`
+    /// Generate a fresh name by appending a number to `name`
+    fn gen_fresh_name_like(&mut self, name: StringId) -> StringId {
+        let base_name = self
+            .interner
+            .borrow()
+            .resolve(name)
+            .unwrap_or("x")
+            .to_owned();
+        let mut counter = 1;
+        loop {
+            let name = (self.interner.borrow_mut()).get_or_intern(format!("{base_name}{counter}"));
+            match self.is_bound(name) {
+                true => counter += 1,
+                false => return name,
+            }
+        }
+    }
+
+    /// Replace `name` with a fresh name if it is `_` and occurs in `body`, or
+    /// if it is already bound when distilling explicitly
     fn freshen_name(&mut self, name: Option<StringId>, body: &core::Term<'_>) -> Option<StringId> {
         match name {
+            Some(name) if self.explicit && self.is_bound(name) => {
+                Some(self.gen_fresh_name_like(name))
+            }
             Some(name) => Some(name),
             None => body
                 .binds_local(Index::last())
@@ -139,11 +172,13 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 label,
                 r#type,
                 expr,
+                ..
             } => {
                 let r#type = scope.to_scope(self.check_prec(Prec::Top, r#type));
                 let expr = scope.to_scope(self.check_prec(Prec::Let, expr));
 
                 Item::Def(ItemDef {
+                    doc: &[],
                     range: (),
                     label: ((), *label),
                     params: &[],
@@ -194,14 +229,30 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
             Const::U16(number, style) => self.check_number_pattern_styled(number, *style),
             Const::U32(number, style) => self.check_number_pattern_styled(number, *style),
             Const::U64(number, style) => self.check_number_pattern_styled(number, *style),
+            Const::U128(number, style) => self.check_number_pattern_styled(&number.get(), *style),
             Const::S8(number) => self.check_number_pattern(number),
             Const::S16(number) => self.check_number_pattern(number),
             Const::S32(number) => self.check_number_pattern(number),
             Const::S64(number) => self.check_number_pattern(number),
+            Const::S128(number) => self.check_number_pattern(number),
             Const::F32(number) => self.check_number_pattern(number),
             Const::F64(number) => self.check_number_pattern(number),
             Const::Pos(number) => self.check_number_pattern(number),
             Const::Ref(number) => self.check_number_pattern(number),
+            Const::String(string) => Pattern::StringLiteral((), *string),
+        }
+    }
+
+    fn check_const_pattern(&mut self, pattern: &ConstPattern) -> Pattern<()> {
+        match pattern {
+            ConstPattern::Const(r#const) => self.check_constant_pattern(r#const),
+            ConstPattern::Range(start, end) => {
+                // Ranges are only elaborated for integer types, but distil the
+                // bounds of other constants as-is rather than giving up.
+                let start = self.check_constant_pattern(start);
+                let end = self.check_constant_pattern(end);
+                Pattern::NumberRange((), pattern_string_id(&start), pattern_string_id(&end))
+            }
         }
     }
 
@@ -242,6 +293,16 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
         )
     }
 
+    fn synth_string_literal(&mut self, prec: Prec, string: StringId) -> Term<'arena, ()> {
+        let expr = Term::StringLiteral((), string);
+        let r#type = self.synth_prim(core::Prim::StringType);
+
+        self.paren(
+            prec > Prec::Top,
+            Term::Ann((), self.scope.to_scope(expr), self.scope.to_scope(r#type)),
+        )
+    }
+
     fn check_dependent_tuple(
         &mut self,
         labels: &[StringId],
@@ -276,7 +337,7 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
 
     fn check_prec(&mut self, prec: Prec, core_term: &core::Term<'_>) -> Term<'arena, ()> {
         match core_term {
-            core::Term::Ann(_span, expr, _) => {
+            core::Term::Ann(_span, expr, _) if !self.explicit => {
                 // Avoid adding extraneous type annotations!
                 self.check_prec(prec, expr)
             }
@@ -370,15 +431,23 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 core::Const::U16(number, style) => self.check_number_literal_styled(number, *style),
                 core::Const::U32(number, style) => self.check_number_literal_styled(number, *style),
                 core::Const::U64(number, style) => self.check_number_literal_styled(number, *style),
+                core::Const::U128(number, style) => {
+                    self.check_number_literal_styled(&number.get(), *style)
+                }
                 core::Const::S8(number) => self.check_number_literal(number),
                 core::Const::S16(number) => self.check_number_literal(number),
                 core::Const::S32(number) => self.check_number_literal(number),
                 core::Const::S64(number) => self.check_number_literal(number),
+                core::Const::S128(number) => self.check_number_literal(number),
                 core::Const::F32(number) => self.check_number_literal(number),
                 core::Const::F64(number) => self.check_number_literal(number),
                 core::Const::Pos(number) => self.check_number_literal(number),
                 core::Const::Ref(number) => self.check_number_literal(number),
+                core::Const::String(string) => Term::StringLiteral((), *string),
             },
+            core::Term::StringLit(_span, string) => {
+                Term::StringLiteral((), self.interner.borrow_mut().get_or_intern(string))
+            }
             core::Term::ConstMatch(_span, head_expr, branches, default_branch) => {
                 if let Some((then_expr, else_expr)) = match_if_then_else(branches, *default_branch)
                 {
@@ -414,8 +483,8 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                             self.scope.to_scope_from_iter(
                                 branches
                                     .iter()
-                                    .map(|(r#const, body_expr)| {
-                                        let pattern = self.check_constant_pattern(r#const);
+                                    .map(|(pattern, body_expr)| {
+                                        let pattern = self.check_const_pattern(pattern);
                                         let body_expr = self.check_prec(Prec::Top, body_expr);
                                         (pattern, body_expr)
                                     })
@@ -427,8 +496,8 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                         (),
                         self.scope.to_scope(head_expr),
                         self.scope.to_scope_from_iter(branches.iter().map(
-                            |(r#const, body_expr)| {
-                                let pattern = self.check_constant_pattern(r#const);
+                            |(pattern, body_expr)| {
+                                let pattern = self.check_const_pattern(pattern);
                                 let body_expr = self.check_prec(Prec::Top, body_expr);
                                 (pattern, body_expr)
                             },
@@ -620,7 +689,7 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
 
                 // Distill appropriate primitives to binary operator expressions
                 if let (core::Term::Prim(_, prim), [(_, rhs), (_, lhs)]) = (head_expr, &args[..]) {
-                    if let Some(op) = prim_to_bin_op(prim) {
+                    if let Some(op) = prim_to_bin_op(prim).filter(|_| !self.explicit) {
                         let lhs = (self.scope).to_scope(self.synth_prec(op.lhs_prec(), lhs));
                         let rhs = (self.scope).to_scope(self.synth_prec(op.rhs_prec(), rhs));
                         return self.paren(prec > op.precedence(), Term::BinOp((), lhs, op, rhs));
@@ -709,6 +778,7 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 // FIXME: Type annotations
                 Term::ArrayLiteral((), scope.to_scope_from_iter(elem_exprs))
             }
+            core::Term::ElidedElems(_span, len) => Term::ElidedElems((), *len),
 
             core::Term::FormatRecord(_span, labels, formats) => {
                 Term::FormatRecord((), self.synth_format_fields(labels, formats))
@@ -743,6 +813,12 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 core::Const::U64(number, style) => {
                     self.synth_number_literal_styled(prec, number, *style, core::Prim::U64Type)
                 }
+                core::Const::U128(number, style) => self.synth_number_literal_styled(
+                    prec,
+                    &number.get(),
+                    *style,
+                    core::Prim::U128Type,
+                ),
                 core::Const::S8(number) => {
                     self.synth_number_literal(prec, number, core::Prim::S8Type)
                 }
@@ -755,6 +831,9 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 core::Const::S64(number) => {
                     self.synth_number_literal(prec, number, core::Prim::S64Type)
                 }
+                core::Const::S128(number) => {
+                    self.synth_number_literal(prec, number, core::Prim::S128Type)
+                }
                 core::Const::F32(number) => {
                     self.synth_number_literal(prec, number, core::Prim::F32Type)
                 }
@@ -767,7 +846,12 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                 core::Const::Ref(number) => {
                     self.synth_number_literal(prec, number, core::Prim::RefType)
                 }
+                core::Const::String(string) => self.synth_string_literal(prec, *string),
             },
+            core::Term::StringLit(_span, string) => {
+                let string = self.interner.borrow_mut().get_or_intern(string);
+                self.synth_string_literal(prec, string)
+            }
             core::Term::ConstMatch(_span, head_expr, branches, default_expr) => {
                 if let Some((then_expr, else_expr)) = match_if_then_else(branches, *default_expr) {
                     let cond_expr = self.check_prec(Prec::Fun, head_expr);
@@ -799,8 +883,8 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                             self.scope.to_scope_from_iter(
                                 branches
                                     .iter()
-                                    .map(|(r#const, body_expr)| {
-                                        let pattern = self.check_constant_pattern(r#const);
+                                    .map(|(pattern, body_expr)| {
+                                        let pattern = self.check_const_pattern(pattern);
                                         let body_expr = self.synth_prec(Prec::Top, body_expr);
                                         (pattern, body_expr)
                                     })
@@ -812,8 +896,8 @@ impl<'interner, 'arena, 'env> Context<'interner, 'arena, 'env> {
                         (),
                         self.scope.to_scope(head_expr),
                         self.scope.to_scope_from_iter(branches.iter().map(
-                            |(r#const, body_expr)| {
-                                let pattern = self.check_constant_pattern(r#const);
+                            |(pattern, body_expr)| {
+                                let pattern = self.check_const_pattern(pattern);
                                 let body_expr = self.synth_prec(Prec::Top, body_expr);
                                 (pattern, body_expr)
                             },
@@ -891,14 +975,16 @@ fn name_to_pattern(name: Option<StringId>) -> Pattern<()> {
 }
 
 fn match_if_then_else<'arena>(
-    branches: &'arena [(Const, core::Term<'arena>)],
+    branches: &'arena [(ConstPattern, core::Term<'arena>)],
     default_branch: Option<(Option<StringId>, &'arena core::Term<'arena>)>,
 ) -> Option<(&'arena core::Term<'arena>, &'arena core::Term<'arena>)> {
+    use ConstPattern::Const as Pat;
+
     match (branches, default_branch) {
-        ([(Const::Bool(false), else_expr), (Const::Bool(true), then_expr)], None)
+        ([(Pat(Const::Bool(false)), else_expr), (Pat(Const::Bool(true)), then_expr)], None)
         // TODO: Normalize boolean branches when elaborating patterns
-        | ([(Const::Bool(true), then_expr)], Some((_, else_expr)))
-        | ([(Const::Bool(false), else_expr)], Some((_, then_expr))) => Some((then_expr, else_expr)),
+        | ([(Pat(Const::Bool(true)), then_expr)], Some((_, else_expr)))
+        | ([(Pat(Const::Bool(false)), else_expr)], Some((_, then_expr))) => Some((then_expr, else_expr)),
         _ => None,
     }
 }
@@ -919,23 +1005,48 @@ fn is_tuple_type(
         })
 }
 
+/// The source string of a distilled constant pattern.
+fn pattern_string_id(pattern: &Pattern<()>) -> StringId {
+    match pattern {
+        Pattern::Name((), string_id)
+        | Pattern::StringLiteral((), string_id)
+        | Pattern::NumberLiteral((), string_id) => *string_id,
+        _ => unreachable!("constant patterns distil to literals or names"),
+    }
+}
+
 fn prim_to_bin_op(prim: &core::Prim) -> Option<BinOp<()>> {
     use crate::core::Prim::*;
 
     match prim {
-        U8Mul | U16Mul | U32Mul | U64Mul | S8Mul | S16Mul | S32Mul | S64Mul => Some(BinOp::Mul(())),
-        U8Div | U16Div | U32Div | U64Div | S8Div | S16Div | S32Div | S64Div => Some(BinOp::Div(())),
-        U8Add | U16Add | U32Add | U64Add | S8Add | S16Add | S32Add | S64Add | PosAddU8
-        | PosAddU16 | PosAddU32 | PosAddU64 => Some(BinOp::Add(())),
-        U8Sub | U16Sub | U32Sub | U64Sub | S8Sub | S16Sub | S32Sub | S64Sub => Some(BinOp::Sub(())),
-        BoolEq | U8Eq | U16Eq | U32Eq | U64Eq | S8Eq | S16Eq | S32Eq | S64Eq => Some(BinOp::Eq(())),
-        BoolNeq | U8Neq | U16Neq | U32Neq | U64Neq | S8Neq | S16Neq | S32Neq | S64Neq => {
-            Some(BinOp::Neq(()))
+        U8Mul | U16Mul | U32Mul | U64Mul | U128Mul | S8Mul | S16Mul | S32Mul | S64Mul | S128Mul => {
+            Some(BinOp::Mul(()))
+        }
+        U8Div | U16Div | U32Div | U64Div | U128Div | S8Div | S16Div | S32Div | S64Div | S128Div => {
+            Some(BinOp::Div(()))
+        }
+        U8Add | U16Add | U32Add | U64Add | U128Add | S8Add | S16Add | S32Add | S64Add | S128Add
+        | PosAddU8 | PosAddU16 | PosAddU32 | PosAddU64 => Some(BinOp::Add(())),
+        U8Sub | U16Sub | U32Sub | U64Sub | U128Sub | S8Sub | S16Sub | S32Sub | S64Sub | S128Sub => {
+            Some(BinOp::Sub(()))
+        }
+        BoolEq | U8Eq | U16Eq | U32Eq | U64Eq | U128Eq | S8Eq | S16Eq | S32Eq | S64Eq | S128Eq => {
+            Some(BinOp::Eq(()))
+        }
+        BoolNeq | U8Neq | U16Neq | U32Neq | U64Neq | U128Neq | S8Neq | S16Neq | S32Neq | S64Neq
+        | S128Neq => Some(BinOp::Neq(())),
+        U8Lt | U16Lt | U32Lt | U64Lt | U128Lt | S8Lt | S16Lt | S32Lt | S64Lt | S128Lt => {
+            Some(BinOp::Lt(()))
+        }
+        U8Lte | U16Lte | U32Lte | U64Lte | U128Lte | S8Lte | S16Lte | S32Lte | S64Lte | S128Lte => {
+            Some(BinOp::Lte(()))
+        }
+        U8Gt | U16Gt | U32Gt | U64Gt | U128Gt | S8Gt | S16Gt | S32Gt | S64Gt | S128Gt => {
+            Some(BinOp::Gt(()))
+        }
+        U8Gte | U16Gte | U32Gte | U64Gte | U128Gte | S8Gte | S16Gte | S32Gte | S64Gte | S128Gte => {
+            Some(BinOp::Gte(()))
         }
-        U8Lt | U16Lt | U32Lt | U64Lt | S8Lt | S16Lt | S32Lt | S64Lt => Some(BinOp::Lt(())),
-        U8Lte | U16Lte | U32Lte | U64Lte | S8Lte | S16Lte | S32Lte | S64Lte => Some(BinOp::Lte(())),
-        U8Gt | U16Gt | U32Gt | U64Gt | S8Gt | S16Gt | S32Gt | S64Gt => Some(BinOp::Gt(())),
-        U8Gte | U16Gte | U32Gte | U64Gte | S8Gte | S16Gte | S32Gte | S64Gte => Some(BinOp::Gte(())),
 
         _ => None,
     }
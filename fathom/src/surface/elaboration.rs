@@ -21,27 +21,33 @@
 //! - [elaboration-zoo](https://github.com/AndrasKovacs/elaboration-zoo/)
 
 use std::cell::RefCell;
+use std::num::{IntErrorKind, ParseIntError};
 use std::str::FromStr;
 use std::sync::Arc;
 
+use fxhash::FxHashSet;
 use scoped_arena::Scope;
 
 use super::ExprField;
 use crate::alloc::SliceVec;
+use crate::core::prim::Endianness;
 use crate::core::semantics::{self, ArcValue, Head, Telescope, Value};
-use crate::core::{self, prim, Const, Plicity, Prim, UIntStyle};
+use crate::core::{self, prim, Const, ConstPattern, Plicity, Prim, UIntStyle};
 use crate::env::{self, EnvLen, Level, SharedEnv, UniqueEnv};
 use crate::files::FileId;
 use crate::source::{BytePos, ByteRange, FileRange, Span, Spanned, StringId, StringInterner};
 use crate::surface::elaboration::reporting::Message;
 use crate::surface::{
-    distillation, pretty, BinOp, FormatField, Item, Module, Param, Pattern, Term,
+    distillation, pretty, BinOp, FormatField, Item, LetRecordDef, Module, Param, Pattern, Term,
 };
 
+mod incremental;
 mod order;
 mod reporting;
 mod unification;
 
+pub use self::incremental::ModuleCache;
+
 /// Top-level item environment.
 pub struct ItemEnv<'arena> {
     /// Names of items.
@@ -104,6 +110,12 @@ struct LocalEnv<'arena> {
     /// Expressions that will be substituted for local variables during
     /// [evaluation][semantics::EvalEnv::eval].
     exprs: SharedEnv<ArcValue<'arena>>,
+    /// Whether local variables have been referenced. Used when reporting
+    /// [unused let bindings][Message::UnusedLetBinding].
+    uses: UniqueEnv<bool>,
+    /// Source ranges of the local binders, if they were bound by a pattern.
+    /// Used when reporting [shadowed bindings][Message::ShadowedBinding].
+    ranges: UniqueEnv<Option<FileRange>>,
 }
 
 impl<'arena> LocalEnv<'arena> {
@@ -114,6 +126,8 @@ impl<'arena> LocalEnv<'arena> {
             types: UniqueEnv::new(),
             infos: UniqueEnv::new(),
             exprs: SharedEnv::new(),
+            uses: UniqueEnv::new(),
+            ranges: UniqueEnv::new(),
         }
     }
 
@@ -127,12 +141,15 @@ impl<'arena> LocalEnv<'arena> {
         self.types.reserve(additional);
         self.infos.reserve(additional);
         self.exprs.reserve(additional);
+        self.uses.reserve(additional);
+        self.ranges.reserve(additional);
     }
 
     /// Push a local definition onto the context.
     fn push_def(
         &mut self,
         name: Option<StringId>,
+        range: Option<FileRange>,
         expr: ArcValue<'arena>,
         r#type: ArcValue<'arena>,
     ) {
@@ -140,10 +157,17 @@ impl<'arena> LocalEnv<'arena> {
         self.types.push(r#type);
         self.infos.push(core::LocalInfo::Def);
         self.exprs.push(expr);
+        self.uses.push(false);
+        self.ranges.push(range);
     }
 
     /// Push a local parameter onto the context.
-    fn push_param(&mut self, name: Option<StringId>, r#type: ArcValue<'arena>) -> ArcValue<'arena> {
+    fn push_param(
+        &mut self,
+        name: Option<StringId>,
+        range: Option<FileRange>,
+        r#type: ArcValue<'arena>,
+    ) -> ArcValue<'arena> {
         // An expression that refers to itself once it is pushed onto the local
         // expression environment.
         let expr = Spanned::empty(Arc::new(Value::local_var(self.exprs.len().next_level())));
@@ -152,16 +176,27 @@ impl<'arena> LocalEnv<'arena> {
         self.types.push(r#type);
         self.infos.push(core::LocalInfo::Param);
         self.exprs.push(expr.clone());
+        self.uses.push(false);
+        self.ranges.push(range);
 
         expr
     }
 
+    /// Record that a local variable has been referenced.
+    fn mark_used(&mut self, var: env::Index) {
+        if let Some(var) = self.uses.len().index_to_level(var) {
+            self.uses.set_level(var, true);
+        }
+    }
+
     /// Pop a local binder off the context.
     fn pop(&mut self) {
         self.names.pop();
         self.types.pop();
         self.infos.pop();
         self.exprs.pop();
+        self.uses.pop();
+        self.ranges.pop();
     }
 
     /// Truncate the local environment.
@@ -170,6 +205,8 @@ impl<'arena> LocalEnv<'arena> {
         self.types.truncate(len);
         self.infos.truncate(len);
         self.exprs.truncate(len);
+        self.uses.truncate(len);
+        self.ranges.truncate(len);
     }
 }
 
@@ -209,6 +246,20 @@ impl MetaSource {
             | MetaSource::ReportedErrorType(range) => *range,
         }
     }
+
+    pub fn range_mut(&mut self) -> &mut FileRange {
+        match self {
+            MetaSource::ImplicitArg(range, _)
+            | MetaSource::HoleType(range, _)
+            | MetaSource::HoleExpr(range, _)
+            | MetaSource::PlaceholderType(range)
+            | MetaSource::PlaceholderExpr(range)
+            | MetaSource::PlaceholderPatternType(range)
+            | MetaSource::NamedPatternType(range, _)
+            | MetaSource::MatchExprType(range)
+            | MetaSource::ReportedErrorType(range) => range,
+        }
+    }
 }
 
 /// Metavariable environment.
@@ -221,8 +272,12 @@ struct MetaEnv<'arena> {
     /// The source of inserted metavariables, used when reporting [unsolved
     /// metavariables][Message::UnsolvedMetaVar].
     sources: UniqueEnv<MetaSource>,
-    /// Types of metavariables.
+    /// Types of metavariables, used when reporting [unsolved
+    /// metavariables][Message::UnsolvedMetaVar].
     types: UniqueEnv</* TODO: lazy value */ ArcValue<'arena>>,
+    /// Names of the local bindings that were in scope when each metavariable
+    /// was inserted, which may be referenced by its type.
+    local_names: UniqueEnv<Vec<Option<StringId>>>,
     /// Expressions that will be substituted for metavariables during
     /// [evaluation][semantics::EvalEnv::eval].
     ///
@@ -238,23 +293,41 @@ impl<'arena> MetaEnv<'arena> {
         MetaEnv {
             sources: UniqueEnv::new(),
             types: UniqueEnv::new(),
+            local_names: UniqueEnv::new(),
             exprs: UniqueEnv::new(),
         }
     }
 
     /// Push an unsolved metavariable onto the context.
-    fn push(&mut self, source: MetaSource, r#type: ArcValue<'arena>) -> Level {
+    fn push(
+        &mut self,
+        source: MetaSource,
+        r#type: ArcValue<'arena>,
+        local_names: Vec<Option<StringId>>,
+    ) -> Level {
         // TODO: check that hole name is not already in use
         let var = self.exprs.len().next_level();
 
         self.sources.push(source);
         self.types.push(r#type);
+        self.local_names.push(local_names);
         self.exprs.push(None);
 
         var
     }
 }
 
+/// A hole that was encountered during elaboration, along with the local
+/// bindings that were in scope at that point. These are recorded so that the
+/// type of the hole can be reported once elaboration has finished.
+struct Hole<'arena> {
+    range: FileRange,
+    name: StringId,
+    r#type: ArcValue<'arena>,
+    local_names: Vec<Option<StringId>>,
+    local_types: Vec<ArcValue<'arena>>,
+}
+
 /// Elaboration context.
 pub struct Context<'interner, 'arena> {
     file_id: FileId,
@@ -282,8 +355,21 @@ pub struct Context<'interner, 'arena> {
     local_env: LocalEnv<'arena>,
     /// A partial renaming to be used during [`unification`].
     renaming: unification::PartialRenaming,
+    /// Holes encountered during elaboration.
+    holes: Vec<Hole<'arena>>,
+    /// The ranges of the types in type annotations. These are used to point
+    /// back to the annotation when an expression fails to check against it.
+    annotation_ranges: FxHashSet<FileRange>,
+    /// The byte order used for endianness-agnostic number formats, like `u16`.
+    default_endianness: Option<Endianness>,
+    /// Whether to report [unused format fields][Message::UnusedFormatField].
+    warn_unused_fields: bool,
     /// Diagnostic messages encountered during elaboration.
     messages: Vec<Message>,
+    /// The number of messages that had been reported when each of the local
+    /// definitions in scope was pushed. Used to report [unused let
+    /// bindings][Message::UnusedLetBinding] in source order.
+    local_def_message_lens: Vec<usize>,
 }
 
 fn suggest_name(
@@ -320,14 +406,46 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             meta_env: MetaEnv::new(),
             local_env: LocalEnv::new(),
             renaming: unification::PartialRenaming::new(),
+            holes: Vec::new(),
+            annotation_ranges: FxHashSet::default(),
+            default_endianness: None,
+            warn_unused_fields: false,
             messages: Vec::new(),
+            local_def_message_lens: Vec::new(),
         }
     }
 
+    /// Set the byte order used for endianness-agnostic number formats, like
+    /// `u16`. If this is `None`, these formats are reported as errors.
+    pub fn set_default_endianness(&mut self, default_endianness: Option<Endianness>) {
+        self.default_endianness = default_endianness;
+    }
+
+    /// Set to true to report fixed-size fields of record formats that are
+    /// never referenced by later fields.
+    pub fn set_warn_unused_fields(&mut self, warn_unused_fields: bool) {
+        self.warn_unused_fields = warn_unused_fields;
+    }
+
+    /// The primitives that names refer to when they are not bound by a local
+    /// binding or an item. This can be used to add or remove primitive names
+    /// before elaborating.
+    pub fn prim_env_mut(&mut self) -> &mut prim::Env<'arena> {
+        &mut self.prim_env
+    }
+
     pub fn finish(self) -> ItemEnv<'arena> {
         self.item_env
     }
 
+    /// The labels of a tuple with `len` elements. The interner is released
+    /// before returning, as elaborating the elements might need to borrow it.
+    fn tuple_labels(&self, len: usize) -> &'arena [StringId] {
+        let mut interner = self.interner.borrow_mut();
+        let labels = interner.get_tuple_labels(0..len);
+        self.scope.to_scope_from_iter(labels.iter().copied())
+    }
+
     fn file_range(&self, byte_range: ByteRange) -> FileRange {
         FileRange::new(self.file_id, byte_range)
     }
@@ -340,6 +458,16 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         Some((item_var, item_type))
     }
 
+    /// Lookup an endianness-agnostic number format name, like `u16`, returning
+    /// the format for the default endianness if one has been set.
+    fn get_endian_format_name(&self, name: StringId) -> Option<Option<Prim>> {
+        let interner = self.interner.borrow();
+        let name = interner.resolve(name)?;
+        Endianness::Big.number_format(name)?;
+
+        Some(self.default_endianness.and_then(|e| e.number_format(name)))
+    }
+
     /// Lookup a local name in the context.
     fn get_local_name(&self, name: StringId) -> Option<(env::Index, &ArcValue<'arena>)> {
         let local_var = self.local_env.names.elem_index(&Some(name))?;
@@ -355,9 +483,10 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         source: MetaSource,
         r#type: ArcValue<'arena>,
     ) -> core::Term<'arena> {
+        let local_names = self.local_env.names.iter().copied().collect();
         core::Term::InsertedMeta(
             source.range().into(),
-            self.meta_env.push(source, r#type),
+            self.meta_env.push(source, r#type, local_names),
             (self.scope).to_scope_from_iter(self.local_env.infos.iter().copied()),
         )
     }
@@ -378,8 +507,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             on_message(message);
         }
 
-        let meta_env = &self.meta_env;
-        for (expr, source) in Iterator::zip(meta_env.exprs.iter(), meta_env.sources.iter()) {
+        let meta_sources = self.meta_env.sources.clone();
+        for (var, source) in Iterator::zip(env::levels(), meta_sources.iter()) {
+            let expr = self.meta_env.exprs.get_level(var).cloned().flatten();
             match (expr, *source) {
                 // Avoid producing messages for some unsolved metavariable sources:
                 // Should have an unsolved hole expression
@@ -390,10 +520,18 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (None, MetaSource::ReportedErrorType(_)) => {}
 
                 // For other sources, report an unsolved problem message
-                (None, source) => on_message(Message::UnsolvedMetaVar { source }),
+                (None, source) => {
+                    let expr = self.pretty_print_meta_var(var);
+                    let r#type = self.pretty_print_meta_type(var);
+                    on_message(Message::UnsolvedMetaVar {
+                        source,
+                        expr,
+                        r#type,
+                    });
+                }
                 // Yield messages of solved named holes
                 (Some(expr), MetaSource::HoleExpr(range, name)) => {
-                    let term = self.quote_env().quote(self.scope, expr);
+                    let term = self.quote_env().quote(self.scope, &expr);
                     let surface_term = distillation::Context::new(
                         self.interner,
                         self.scope,
@@ -413,6 +551,26 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (Some(_), _) => {}
             }
         }
+
+        // Report the types of holes, along with the local bindings in scope
+        for hole in std::mem::take(&mut self.holes) {
+            let initial_local_len = self.local_env.len();
+            let mut locals = Vec::with_capacity(hole.local_names.len());
+
+            for (name, r#type) in Iterator::zip(hole.local_names.into_iter(), hole.local_types) {
+                locals.push((name, self.pretty_print_value(&r#type)));
+                self.local_env.push_param(name, None, r#type);
+            }
+            let r#type = self.pretty_print_value(&hole.r#type);
+            self.local_env.truncate(initial_local_len);
+
+            on_message(Message::HoleType {
+                range: hole.range,
+                name: hole.name,
+                r#type,
+                locals,
+            });
+        }
     }
 
     pub fn eval_env(&mut self) -> semantics::EvalEnv<'arena, '_> {
@@ -428,6 +586,64 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         semantics::QuoteEnv::new(self.elim_env(), self.local_env.len())
     }
 
+    /// Construct a message describing why `found` failed to unify with
+    /// `expected`.
+    fn unification_error(
+        &mut self,
+        range: FileRange,
+        found: &ArcValue<'arena>,
+        expected: &ArcValue<'arena>,
+        error: unification::Error,
+    ) -> Message {
+        let mismatch = match error {
+            unification::Error::Mismatch => {
+                let mut conversion_env = self.elim_env().conversion_env(self.local_env.len());
+                conversion_env.mismatch(found, expected)
+            }
+            _ => None,
+        };
+        let annotation = self.annotation_range(expected.span());
+        let found = self.pretty_print_value(found);
+        let expected = self.pretty_print_value(expected);
+
+        match error {
+            unification::Error::Rename(unification::RenameError::InfiniteSolution(var)) => {
+                Message::InfiniteSolution {
+                    range,
+                    source: *self.meta_env.sources.get_level(var).unwrap(),
+                    found,
+                    expected,
+                }
+            }
+            _ => Message::FailedToUnify {
+                range,
+                found,
+                expected,
+                error,
+                mismatch,
+                annotation: annotation.filter(|annotation| {
+                    (annotation.start(), annotation.end()) != (range.start(), range.end())
+                }),
+            },
+        }
+    }
+
+    /// Check the type of a type annotation, remembering its range so that
+    /// failures to check against it can point back to the annotation.
+    fn check_ann_type(&mut self, r#type: &Term<'_, ByteRange>) -> core::Term<'arena> {
+        self.annotation_ranges
+            .insert(self.file_range(r#type.range()));
+        self.check(r#type, &self.universe.clone())
+    }
+
+    /// Find the type annotation that a value with the supplied span came from.
+    fn annotation_range(&self, span: Span) -> Option<FileRange> {
+        match span {
+            Span::Range(range) => self.annotation_ranges.get(&range).copied(),
+            Span::Empty => None,
+        }
+    }
+
     fn unification_context(&mut self) -> unification::Context<'arena, '_> {
         unification::Context::new(
             self.scope,
@@ -463,6 +679,28 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             .to_string()
     }
 
+    /// Pretty print a metavariable, as it would appear in a term.
+    fn pretty_print_meta_var(&mut self, var: Level) -> String {
+        let expr = Spanned::empty(Arc::new(Value::meta_var(var)));
+        self.pretty_print_value(&expr)
+    }
+
+    /// Pretty print the type of a metavariable, in the scope of the local
+    /// bindings that were present when it was inserted.
+    fn pretty_print_meta_type(&mut self, var: Level) -> String {
+        let initial_local_len = self.local_env.len();
+        let r#type = self.meta_env.types.get_level(var).unwrap().clone();
+        let local_names = self.meta_env.local_names.get_level(var).unwrap().clone();
+
+        for name in local_names {
+            self.local_env.push_param(name, None, self.universe.clone());
+        }
+        let r#type = self.pretty_print_value(&r#type);
+        self.local_env.truncate(initial_local_len);
+
+        r#type
+    }
+
     /// Reports an error if there are duplicate fields found, returning a slice
     /// of the labels unique labels and an iterator over the unique fields.
     fn report_duplicate_labels<'fields, F>(
@@ -551,7 +789,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         num.map(|num| make(num, UIntStyle::Ascii))
     }
 
-    /// Parse a source string into a number.
+    /// Parse a source string into a floating point number.
     fn parse_number<T: FromStr>(
         &mut self,
         range: ByteRange,
@@ -575,7 +813,29 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         }
     }
 
-    /// Parse a source string into a number.
+    /// Parse a decimal source string into a signed integer.
+    fn parse_signed_number<T: FromStrRadix>(
+        &mut self,
+        range: ByteRange,
+        string_id: StringId,
+        make: fn(T) -> Const,
+    ) -> Option<Const> {
+        let interner = self.interner.borrow();
+        let s = interner.resolve(string_id).unwrap();
+        match T::from_str_radix(s, 10) {
+            Ok(data) => Some(make(data)),
+            Err(error) => {
+                let message = Self::invalid_number_message::<T>(s, &error);
+                self.push_message(Message::InvalidNumericLiteral {
+                    range: self.file_range(range),
+                    message,
+                });
+                None
+            }
+        }
+    }
+
+    /// Parse a source string into an unsigned integer.
     fn parse_number_radix<T: FromStrRadix>(
         &mut self,
         range: ByteRange,
@@ -584,18 +844,36 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
     ) -> Option<Const> {
         // TODO: Custom parsing and improved errors
         let interner = self.interner.borrow();
-        let s = interner.resolve(string_id).unwrap();
-        let (s, radix, style) = if let Some(s) = s.strip_prefix("0x") {
+        let literal = interner.resolve(string_id).unwrap();
+        if literal.starts_with('-') {
+            let message = format!(
+                "negative literal {literal} used for unsigned type {}",
+                T::NAME
+            );
+            self.push_message(Message::InvalidNumericLiteral {
+                range: self.file_range(range),
+                message,
+            });
+            return None;
+        }
+        let (s, radix, style) = if let Some(s) = literal.strip_prefix("0x") {
             (s, 16, UIntStyle::Hexadecimal)
-        } else if let Some(s) = s.strip_prefix("0b") {
+        } else if let Some(s) = literal.strip_prefix("0b") {
             (s, 2, UIntStyle::Binary)
+        } else if let Some(s) = literal.strip_prefix("0o") {
+            (s, 8, UIntStyle::Octal)
         } else {
-            (s, 10, UIntStyle::Decimal)
+            (literal, 10, UIntStyle::Decimal)
         };
         match T::from_str_radix(s, radix) {
             Ok(data) => Some(make(data, style)),
             Err(error) => {
-                let message = error.to_string();
+                let message = match radix {
+                    8 if s.contains(['8', '9']) => {
+                        "octal literals can only contain the digits `0` to `7`".to_owned()
+                    }
+                    _ => Self::invalid_number_message::<T>(literal, &error),
+                };
                 self.push_message(Message::InvalidNumericLiteral {
                     range: self.file_range(range),
                     message,
@@ -605,6 +883,17 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         }
     }
 
+    /// Describe why an integer literal could not be parsed, including the
+    /// bounds of the target type if the literal was out of range.
+    fn invalid_number_message<T: FromStrRadix>(literal: &str, error: &ParseIntError) -> String {
+        let bound = match error.kind() {
+            IntErrorKind::PosOverflow => format!("max {}", T::MAX),
+            IntErrorKind::NegOverflow => format!("min {}", T::MIN),
+            _ => return error.to_string(),
+        };
+        format!("literal {literal} out of range for {} ({bound})", T::NAME)
+    }
+
     /// Coerce an expression from one type to another type. This will trigger
     /// unification, recording a unification error on failure.
     fn coerce(
@@ -647,14 +936,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                         }
                     };
 
-                    let from = self.pretty_print_value(&from);
-                    let to = self.pretty_print_value(&to);
-                    self.push_message(Message::FailedToUnify {
-                        range,
-                        found: from,
-                        expected: to,
-                        error,
-                    });
+                    let message = self.unification_error(range, &from, &to, error);
+                    self.push_message(message);
                     core::Term::Prim(span, Prim::ReportedError)
                 }
             },
@@ -684,6 +967,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                         .push_definition(item.label.1, type_value, expr_value);
 
                     items.push(core::Item::Def {
+                        doc: self.scope.to_scope_from_iter(item.doc.iter().copied()),
                         label: item.label.1,
                         r#type: self.scope.to_scope(r#type),
                         expr: self.scope.to_scope(expr),
@@ -696,6 +980,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         // Unfold all unification solutions
         let items = scope.to_scope_from_iter(items.into_iter().map(|item| match item {
             core::Item::Def {
+                doc,
                 label,
                 r#type,
                 expr,
@@ -705,6 +990,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 let expr = self.eval_env().unfold_metas(scope, expr);
 
                 core::Item::Def {
+                    doc: scope.to_scope_from_iter(doc.iter().copied()),
                     label,
                     r#type: scope.to_scope(r#type),
                     expr: scope.to_scope(expr),
@@ -720,6 +1006,31 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         core::Module { items }
     }
 
+    /// Return the top-level items elaborated so far, in elaboration order, with
+    /// their types and expressions quoted into `scope`.
+    ///
+    /// Unlike the module returned by [`Context::elab_module`], the expressions
+    /// of these items are in normal form.
+    pub fn items<'out_arena>(
+        &self,
+        scope: &'out_arena Scope<'out_arena>,
+    ) -> &'out_arena [core::Item<'out_arena>] {
+        let item_env = &self.item_env;
+        let quote_env = semantics::QuoteEnv::new(self.elim_env(), EnvLen::new());
+        let mut quote_env = quote_env.unfolding_metas();
+
+        scope.to_scope_from_iter(
+            Iterator::zip(item_env.names.iter(), item_env.types.iter())
+                .zip(item_env.exprs.iter())
+                .map(|((label, r#type), expr)| core::Item::Def {
+                    doc: &[],
+                    label: *label,
+                    r#type: scope.to_scope(quote_env.quote(scope, r#type)),
+                    expr: scope.to_scope(quote_env.quote(scope, expr)),
+                }),
+        )
+    }
+
     /// Elaborate a term, returning its synthesized type.
     pub fn elab_term<'out_arena>(
         &mut self,
@@ -773,6 +1084,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Some((Prim::U16Type, [])) => self.parse_ascii(*range, *lit, Const::U16),
                     Some((Prim::U32Type, [])) => self.parse_ascii(*range, *lit, Const::U32),
                     Some((Prim::U64Type, [])) => self.parse_ascii(*range, *lit, Const::U64),
+                    Some((Prim::U128Type, [])) => self.parse_ascii(*range, *lit, Const::u128),
+                    Some((Prim::StringType, [])) => Some(Const::String(*lit)),
                     // Some((Prim::Array8Type, [len, _])) => todo!(),
                     // Some((Prim::Array16Type, [len, _])) => todo!(),
                     // Some((Prim::Array32Type, [len, _])) => todo!(),
@@ -799,10 +1112,16 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Some((Prim::U16Type, [])) => self.parse_number_radix(*range, *lit, Const::U16),
                     Some((Prim::U32Type, [])) => self.parse_number_radix(*range, *lit, Const::U32),
                     Some((Prim::U64Type, [])) => self.parse_number_radix(*range, *lit, Const::U64),
-                    Some((Prim::S8Type, [])) => self.parse_number(*range, *lit, Const::S8),
-                    Some((Prim::S16Type, [])) => self.parse_number(*range, *lit, Const::S16),
-                    Some((Prim::S32Type, [])) => self.parse_number(*range, *lit, Const::S32),
-                    Some((Prim::S64Type, [])) => self.parse_number(*range, *lit, Const::S64),
+                    Some((Prim::U128Type, [])) => {
+                        self.parse_number_radix(*range, *lit, Const::u128)
+                    }
+                    Some((Prim::S8Type, [])) => self.parse_signed_number(*range, *lit, Const::S8),
+                    Some((Prim::S16Type, [])) => self.parse_signed_number(*range, *lit, Const::S16),
+                    Some((Prim::S32Type, [])) => self.parse_signed_number(*range, *lit, Const::S32),
+                    Some((Prim::S64Type, [])) => self.parse_signed_number(*range, *lit, Const::S64),
+                    Some((Prim::S128Type, [])) => {
+                        self.parse_signed_number(*range, *lit, Const::s128)
+                    }
                     Some((Prim::F32Type, [])) => self.parse_number(*range, *lit, Const::F32),
                     Some((Prim::F64Type, [])) => self.parse_number(*range, *lit, Const::F64),
                     Some((Prim::ReportedError, _)) => None,
@@ -821,6 +1140,71 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     None => CheckedPattern::ReportedError(file_range),
                 }
             }
+            Pattern::NumberRange(range, start, end) => {
+                let (start, end) = (*start, *end);
+                let bounds = match expected_type.match_prim_spine() {
+                    Some((Prim::U8Type, [])) => Option::zip(
+                        self.parse_number_radix(*range, start, Const::U8),
+                        self.parse_number_radix(*range, end, Const::U8),
+                    ),
+                    Some((Prim::U16Type, [])) => Option::zip(
+                        self.parse_number_radix(*range, start, Const::U16),
+                        self.parse_number_radix(*range, end, Const::U16),
+                    ),
+                    Some((Prim::U32Type, [])) => Option::zip(
+                        self.parse_number_radix(*range, start, Const::U32),
+                        self.parse_number_radix(*range, end, Const::U32),
+                    ),
+                    Some((Prim::U64Type, [])) => Option::zip(
+                        self.parse_number_radix(*range, start, Const::U64),
+                        self.parse_number_radix(*range, end, Const::U64),
+                    ),
+                    Some((Prim::U128Type, [])) => Option::zip(
+                        self.parse_number_radix(*range, start, Const::u128),
+                        self.parse_number_radix(*range, end, Const::u128),
+                    ),
+                    Some((Prim::S8Type, [])) => Option::zip(
+                        self.parse_signed_number(*range, start, Const::S8),
+                        self.parse_signed_number(*range, end, Const::S8),
+                    ),
+                    Some((Prim::S16Type, [])) => Option::zip(
+                        self.parse_signed_number(*range, start, Const::S16),
+                        self.parse_signed_number(*range, end, Const::S16),
+                    ),
+                    Some((Prim::S32Type, [])) => Option::zip(
+                        self.parse_signed_number(*range, start, Const::S32),
+                        self.parse_signed_number(*range, end, Const::S32),
+                    ),
+                    Some((Prim::S64Type, [])) => Option::zip(
+                        self.parse_signed_number(*range, start, Const::S64),
+                        self.parse_signed_number(*range, end, Const::S64),
+                    ),
+                    Some((Prim::S128Type, [])) => Option::zip(
+                        self.parse_signed_number(*range, start, Const::s128),
+                        self.parse_signed_number(*range, end, Const::s128),
+                    ),
+                    Some((Prim::ReportedError, _)) => None,
+                    _ => {
+                        let expected_type = self.pretty_print_value(expected_type);
+                        self.push_message(Message::RangePatternNotSupported {
+                            range: file_range,
+                            expected_type,
+                        });
+                        None
+                    }
+                };
+
+                match bounds {
+                    Some((start, end)) if start <= end => {
+                        CheckedPattern::ConstRange(file_range, Box::new((start, end)))
+                    }
+                    Some(_) => {
+                        self.push_message(Message::EmptyRangePattern { range: file_range });
+                        CheckedPattern::ReportedError(file_range)
+                    }
+                    None => CheckedPattern::ReportedError(file_range),
+                }
+            }
             Pattern::BooleanLiteral(_, boolean) => {
                 let constant = match expected_type.match_prim_spine() {
                     Some((Prim::BoolType, [])) => match *boolean {
@@ -866,7 +1250,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 let r#type = self.push_unsolved_type(source);
                 (CheckedPattern::ReportedError(file_range), r#type)
             }
-            Pattern::NumberLiteral(_, _) => {
+            Pattern::NumberLiteral(_, _) | Pattern::NumberRange(_, _, _) => {
                 self.push_message(Message::AmbiguousNumericLiteral { range: file_range });
                 let source = MetaSource::ReportedErrorType(file_range);
                 let r#type = self.push_unsolved_type(source);
@@ -897,14 +1281,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 match self.unification_context().unify(&r#type, expected_type) {
                     Ok(()) => self.check_pattern(pattern, &r#type),
                     Err(error) => {
-                        let lhs = self.pretty_print_value(&r#type);
-                        let rhs = self.pretty_print_value(expected_type);
-                        self.push_message(Message::FailedToUnify {
-                            range: file_range,
-                            found: lhs,
-                            expected: rhs,
-                            error,
-                        });
+                        let message =
+                            self.unification_error(file_range, &r#type, expected_type, error);
+                        self.push_message(message);
                         CheckedPattern::ReportedError(file_range)
                     }
                 }
@@ -925,7 +1304,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (pattern, r#type, type_value)
             }
             Some(r#type) => {
-                let r#type = self.check(r#type, &self.universe.clone());
+                let r#type = self.check_ann_type(r#type);
                 let type_value = self.eval_env().eval(&r#type);
                 (self.check_pattern(pattern, &type_value), r#type, type_value)
             }
@@ -940,24 +1319,80 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         expr: ArcValue<'arena>,
         r#type: ArcValue<'arena>,
     ) -> Option<StringId> {
-        let name = match pattern {
-            CheckedPattern::Binder(_, name) => Some(name),
-            CheckedPattern::Placeholder(_) => None,
+        let (name, range) = match pattern {
+            CheckedPattern::Binder(range, name) => {
+                self.report_shadowed_binding(range, name);
+                (Some(name), Some(range))
+            }
+            CheckedPattern::Placeholder(_) => (None, None),
             // FIXME: generate failing parameter expressions?
-            CheckedPattern::ConstLit(range, _) => {
+            CheckedPattern::ConstLit(range, _) | CheckedPattern::ConstRange(range, _) => {
                 self.push_message(Message::RefutablePattern {
                     pattern_range: range,
                 });
-                None
+                (None, None)
             }
-            CheckedPattern::ReportedError(_) => None,
+            CheckedPattern::ReportedError(_) => (None, None),
         };
 
-        self.local_env.push_def(name, expr, r#type);
+        self.local_env.push_def(name, range, expr, r#type);
+        self.local_def_message_lens.push(self.messages.len());
 
         name
     }
 
+    /// Report a warning if a let binding shadows a name that is already in
+    /// scope. Function parameters commonly reuse the names of enclosing
+    /// parameters, so they are not reported. Names starting with an underscore
+    /// are exempt.
+    fn report_shadowed_binding(&mut self, range: FileRange, name: StringId) {
+        if self
+            .interner
+            .borrow()
+            .resolve(name)
+            .unwrap()
+            .starts_with('_')
+        {
+            return;
+        }
+        if let Some(var) = self.local_env.names.elem_index(&Some(name)) {
+            let prev_range = self.local_env.ranges.get_index(var).copied().flatten();
+            self.push_message(Message::ShadowedBinding {
+                range,
+                prev_range,
+                name,
+            });
+        }
+    }
+
+    /// Pop a local definition off the context, reporting a warning if it was
+    /// bound to a name that was never referenced. Names starting with an
+    /// underscore are exempt.
+    fn pop_local_def(&mut self, pattern_range: ByteRange) {
+        let name = self.local_env.names.get_index(env::Index::last()).copied();
+        let used = self.local_env.uses.get_index(env::Index::last()).copied();
+        let message_len = self.local_def_message_lens.pop().unwrap_or(0);
+        self.local_env.pop();
+
+        if let (Some(Some(name)), Some(false)) = (name, used) {
+            if !self
+                .interner
+                .borrow()
+                .resolve(name)
+                .unwrap()
+                .starts_with('_')
+            {
+                // Report the binding before any messages from its body
+                let message = Message::UnusedLetBinding {
+                    range: self.file_range(pattern_range),
+                    name,
+                };
+                let index = message_len.min(self.messages.len());
+                self.messages.insert(index, message);
+            }
+        }
+    }
+
     /// Push a local parameter onto the context.
     /// The supplied `pattern` is expected to be irrefutable.
     fn push_local_param(
@@ -965,20 +1400,20 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         pattern: CheckedPattern,
         r#type: ArcValue<'arena>,
     ) -> (Option<StringId>, ArcValue<'arena>) {
-        let name = match pattern {
-            CheckedPattern::Binder(_, name) => Some(name),
-            CheckedPattern::Placeholder(_) => None,
+        let (name, range) = match pattern {
+            CheckedPattern::Binder(range, name) => (Some(name), Some(range)),
+            CheckedPattern::Placeholder(_) => (None, None),
             // FIXME: generate failing parameter expressions?
-            CheckedPattern::ConstLit(range, _) => {
+            CheckedPattern::ConstLit(range, _) | CheckedPattern::ConstRange(range, _) => {
                 self.push_message(Message::RefutablePattern {
                     pattern_range: range,
                 });
-                None
+                (None, None)
             }
-            CheckedPattern::ReportedError(_) => None,
+            CheckedPattern::ReportedError(_) => (None, None),
         };
 
-        let expr = self.local_env.push_param(name, r#type);
+        let expr = self.local_env.push_param(name, range, r#type);
 
         (name, expr)
     }
@@ -1014,6 +1449,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         match (surface_term, expected_type.as_ref()) {
             (Term::Paren(_, term), _) => self.check(term, &expected_type),
             (Term::Let(_, def_pattern, def_type, def_expr, body_expr), _) => {
+                let def_range = def_pattern.range();
                 let (def_pattern, def_type, def_type_value) =
                     self.synth_ann_pattern(def_pattern, *def_type);
                 let def_expr = self.check(def_expr, &def_type_value);
@@ -1021,7 +1457,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
                 let def_name = self.push_local_def(def_pattern, def_expr_value, def_type_value); // TODO: split on constants
                 let body_expr = self.check(body_expr, &expected_type);
-                self.local_env.pop();
+                self.pop_local_def(def_range);
 
                 core::Term::Let(
                     file_range.into(),
@@ -1031,6 +1467,11 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     self.scope.to_scope(body_expr),
                 )
             }
+            (Term::LetRecord(range, def, body_expr), _) => {
+                let defs = self.push_let_record(*range, def);
+                let body_expr = self.check(body_expr, &expected_type);
+                self.pop_let_record(*range, def.labels, defs, body_expr)
+            }
             (Term::If(_, cond_expr, then_expr, else_expr), _) => {
                 let cond_expr = self.check(cond_expr, &self.bool_type.clone());
                 let then_expr = self.check(then_expr, &expected_type);
@@ -1041,8 +1482,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     self.scope.to_scope(cond_expr),
                     // NOTE: in lexicographic order: in Rust, `false < true`
                     self.scope.to_scope_from_iter([
-                        (Const::Bool(false), else_expr),
-                        (Const::Bool(true), then_expr),
+                        (ConstPattern::Const(Const::Bool(false)), else_expr),
+                        (ConstPattern::Const(Const::Bool(true)), then_expr),
                     ]),
                     None,
                 )
@@ -1092,9 +1533,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             }
             (Term::Tuple(_, elem_exprs), Value::Universe) => {
                 self.local_env.reserve(elem_exprs.len());
-                let mut interner = self.interner.borrow_mut();
-                let labels = interner.get_tuple_labels(0..elem_exprs.len());
-                let labels = self.scope.to_scope_from_iter(labels.iter().copied());
+                let labels = self.tuple_labels(elem_exprs.len());
 
                 let initial_local_len = self.local_env.len();
                 let universe = &self.universe.clone();
@@ -1102,7 +1541,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Iterator::zip(labels.iter(), elem_exprs.iter()).map(|(label, elem_expr)| {
                         let r#type = self.check(elem_expr, universe);
                         let type_value = self.eval_env().eval(&r#type);
-                        self.local_env.push_param(Some(*label), type_value);
+                        self.local_env.push_param(Some(*label), None, type_value);
                         r#type
                     }),
                 );
@@ -1115,9 +1554,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 if args.is_empty() =>
             {
                 self.local_env.reserve(elem_exprs.len());
-                let mut interner = self.interner.borrow_mut();
-                let labels = interner.get_tuple_labels(0..elem_exprs.len());
-                let labels = self.scope.to_scope_from_iter(labels.iter().copied());
+                let labels = self.tuple_labels(elem_exprs.len());
 
                 let initial_local_len = self.local_env.len();
                 let format_type = self.format_type.clone();
@@ -1126,7 +1563,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                         let format = self.check(elem_expr, &format_type);
                         let format_value = self.eval_env().eval(&format);
                         let r#type = self.elim_env().format_repr(&format_value);
-                        self.local_env.push_param(Some(*label), r#type);
+                        self.local_env.push_param(Some(*label), None, r#type);
                         format
                     }),
                 );
@@ -1183,17 +1620,17 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 use crate::core::semantics::Elim::FunApp as App;
 
                 let (len_value, elem_type) = match expected_type.match_prim_spine() {
-                    Some((Prim::ArrayType, [App(_, elem_type)])) => (None, elem_type),
-                    Some((Prim::Array8Type, [App(_, len), App(_, elem_type)])) => {
+                    Some((Prim::ArrayType, [App(_, _, elem_type)])) => (None, elem_type),
+                    Some((Prim::Array8Type, [App(_, _, len), App(_, _, elem_type)])) => {
                         (Some(len), elem_type)
                     }
-                    Some((Prim::Array16Type, [App(_, len), App(_, elem_type)])) => {
+                    Some((Prim::Array16Type, [App(_, _, len), App(_, _, elem_type)])) => {
                         (Some(len), elem_type)
                     }
-                    Some((Prim::Array32Type, [App(_, len), App(_, elem_type)])) => {
+                    Some((Prim::Array32Type, [App(_, _, len), App(_, _, elem_type)])) => {
                         (Some(len), elem_type)
                     }
-                    Some((Prim::Array64Type, [App(_, len), App(_, elem_type)])) => {
+                    Some((Prim::Array64Type, [App(_, _, len), App(_, _, elem_type)])) => {
                         (Some(len), elem_type)
                     }
                     Some((Prim::ReportedError, _)) => {
@@ -1252,6 +1689,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Some((Prim::U16Type, [])) => self.parse_ascii(*range, *lit, Const::U16),
                     Some((Prim::U32Type, [])) => self.parse_ascii(*range, *lit, Const::U32),
                     Some((Prim::U64Type, [])) => self.parse_ascii(*range, *lit, Const::U64),
+                    Some((Prim::U128Type, [])) => self.parse_ascii(*range, *lit, Const::u128),
+                    Some((Prim::StringType, [])) => Some(Const::String(*lit)),
                     // Some((Prim::Array8Type, [len, _])) => todo!(),
                     // Some((Prim::Array16Type, [len, _])) => todo!(),
                     // Some((Prim::Array32Type, [len, _])) => todo!(),
@@ -1278,10 +1717,16 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Some((Prim::U16Type, [])) => self.parse_number_radix(*range, *lit, Const::U16),
                     Some((Prim::U32Type, [])) => self.parse_number_radix(*range, *lit, Const::U32),
                     Some((Prim::U64Type, [])) => self.parse_number_radix(*range, *lit, Const::U64),
-                    Some((Prim::S8Type, [])) => self.parse_number(*range, *lit, Const::S8),
-                    Some((Prim::S16Type, [])) => self.parse_number(*range, *lit, Const::S16),
-                    Some((Prim::S32Type, [])) => self.parse_number(*range, *lit, Const::S32),
-                    Some((Prim::S64Type, [])) => self.parse_number(*range, *lit, Const::S64),
+                    Some((Prim::U128Type, [])) => {
+                        self.parse_number_radix(*range, *lit, Const::u128)
+                    }
+                    Some((Prim::S8Type, [])) => self.parse_signed_number(*range, *lit, Const::S8),
+                    Some((Prim::S16Type, [])) => self.parse_signed_number(*range, *lit, Const::S16),
+                    Some((Prim::S32Type, [])) => self.parse_signed_number(*range, *lit, Const::S32),
+                    Some((Prim::S64Type, [])) => self.parse_signed_number(*range, *lit, Const::S64),
+                    Some((Prim::S128Type, [])) => {
+                        self.parse_signed_number(*range, *lit, Const::s128)
+                    }
                     Some((Prim::F32Type, [])) => self.parse_number(*range, *lit, Const::F32),
                     Some((Prim::F64Type, [])) => self.parse_number(*range, *lit, Const::F64),
                     Some((Prim::ReportedError, _)) => None,
@@ -1364,10 +1809,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             Term::Paren(_, term) => self.synth(term),
             Term::Name(range, name) => {
                 if let Some((term, r#type)) = self.get_local_name(*name) {
-                    return (
-                        core::Term::LocalVar(file_range.into(), term),
-                        r#type.clone(),
-                    );
+                    let r#type = r#type.clone();
+                    self.local_env.mark_used(term);
+                    return (core::Term::LocalVar(file_range.into(), term), r#type);
                 }
                 if let Some((term, r#type)) = self.get_item_name(*name) {
                     return (core::Term::ItemVar(file_range.into(), term), r#type.clone());
@@ -1375,6 +1819,20 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 if let Some((prim, r#type)) = self.prim_env.get_name(*name) {
                     return (core::Term::Prim(file_range.into(), prim), r#type.clone());
                 }
+                match self.get_endian_format_name(*name) {
+                    Some(Some(prim)) => {
+                        let format_type = self.format_type.clone();
+                        return (core::Term::Prim(file_range.into(), prim), format_type);
+                    }
+                    Some(None) => {
+                        self.push_message(Message::MissingDefaultEndianness {
+                            range: file_range,
+                            name: *name,
+                        });
+                        return self.synth_reported_error(*range);
+                    }
+                    None => {}
+                }
 
                 let candidates = self
                     .local_env
@@ -1399,6 +1857,14 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 let r#type = self.push_unsolved_type(type_source);
                 let expr = self.push_unsolved_term(expr_source, r#type.clone());
 
+                self.holes.push(Hole {
+                    range: file_range,
+                    name: *name,
+                    r#type: r#type.clone(),
+                    local_names: self.local_env.names.iter().copied().collect(),
+                    local_types: self.local_env.types.iter().cloned().collect(),
+                });
+
                 (expr, r#type)
             }
             Term::Placeholder(_) => {
@@ -1411,7 +1877,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (expr, r#type)
             }
             Term::Ann(_, expr, r#type) => {
-                let r#type = self.check(r#type, &self.universe.clone());
+                let r#type = self.check_ann_type(r#type);
                 let type_value = self.eval_env().eval(&r#type);
                 let expr = self.check(expr, &type_value);
 
@@ -1424,6 +1890,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (ann_expr, type_value)
             }
             Term::Let(_, def_pattern, def_type, def_expr, body_expr) => {
+                let def_range = def_pattern.range();
                 let (def_pattern, def_type, def_type_value) =
                     self.synth_ann_pattern(def_pattern, *def_type);
                 let def_expr = self.check(def_expr, &def_type_value);
@@ -1431,7 +1898,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
                 let def_name = self.push_local_def(def_pattern, def_expr_value, def_type_value);
                 let (body_expr, body_type) = self.synth(body_expr);
-                self.local_env.pop();
+                self.pop_local_def(def_range);
 
                 let let_expr = core::Term::Let(
                     file_range.into(),
@@ -1443,6 +1910,13 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
                 (let_expr, body_type)
             }
+            Term::LetRecord(range, def, body_expr) => {
+                let defs = self.push_let_record(*range, def);
+                let (body_expr, body_type) = self.synth(body_expr);
+                let let_expr = self.pop_let_record(*range, def.labels, defs, body_expr);
+
+                (let_expr, body_type)
+            }
             Term::If(_, cond_expr, then_expr, else_expr) => {
                 let cond_expr = self.check(cond_expr, &self.bool_type.clone());
                 let (then_expr, r#type) = self.synth(then_expr);
@@ -1453,8 +1927,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     self.scope.to_scope(cond_expr),
                     // NOTE: in lexicographic order: in Rust, `false < true`
                     self.scope.to_scope_from_iter([
-                        (Const::Bool(false), else_expr),
-                        (Const::Bool(true), then_expr),
+                        (ConstPattern::Const(Const::Bool(false)), else_expr),
+                        (ConstPattern::Const(Const::Bool(true)), then_expr),
                     ]),
                     None,
                 );
@@ -1478,7 +1952,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 let param_type = self.check(param_type, &universe);
                 let param_type_value = self.eval_env().eval(&param_type);
 
-                self.local_env.push_param(None, param_type_value);
+                self.local_env.push_param(None, None, param_type_value);
                 let body_type = self.check(body_type, &universe);
                 self.local_env.pop();
 
@@ -1601,7 +2075,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     let r#type = self.check(&type_field.r#type, &universe);
                     let type_value = self.eval_env().eval(&r#type);
                     self.local_env
-                        .push_param(Some(type_field.label.1), type_value);
+                        .push_param(Some(type_field.label.1), None, type_value);
                     types.push(r#type);
                 }
                 self.local_env.truncate(initial_local_len);
@@ -1630,9 +2104,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 )
             }
             Term::Tuple(_, elem_exprs) => {
-                let mut interner = self.interner.borrow_mut();
-                let labels = interner.get_tuple_labels(0..elem_exprs.len());
-                let labels = self.scope.to_scope_from_iter(labels.iter().copied());
+                let labels = self.tuple_labels(elem_exprs.len());
 
                 let mut exprs = SliceVec::new(self.scope, labels.len());
                 let mut types = SliceVec::new(self.scope, labels.len());
@@ -1654,9 +2126,11 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
                 'labels: for (label_range, proj_label) in *labels {
                     head_type = self.elim_env().force(&head_type);
+                    let mut record_labels: &[StringId] = &[];
                     match (&head_expr, head_type.as_ref()) {
                         // Ensure that the head of the projection is a record
                         (_, Value::RecordType(labels, types)) => {
+                            record_labels = labels;
                             let mut labels = labels.iter().copied();
                             let mut types = types.clone();
 
@@ -1683,7 +2157,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                                     // value of this field in the rest of the types and continue
                                     // looking for the field.
                                     let head_expr = head_expr_value.clone();
-                                    let expr = self.elim_env().record_proj(head_expr, label);
+                                    let expr =
+                                        self.elim_env().record_proj(Span::Empty, head_expr, label);
                                     types = next_types(expr);
                                 }
                             }
@@ -1706,7 +2181,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     let suggestion = suggest_name(
                         &self.interner.borrow(),
                         *proj_label,
-                        labels.iter().map(|(_, label)| *label),
+                        record_labels.iter().copied(),
                     );
                     self.push_message(Message::UnknownField {
                         head_range: self.file_range(head_range),
@@ -1720,6 +2195,93 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
                 (head_expr, head_type)
             }
+            Term::RecordUpdate(range, head_expr, expr_fields) => {
+                let head_range = head_expr.range();
+                let (head_expr, head_type) = self.synth_and_insert_implicit_apps(head_expr);
+                let head_type = self.elim_env().force(&head_type);
+
+                let (labels, types) = match (&head_expr, head_type.as_ref()) {
+                    (_, Value::RecordType(labels, types)) => (*labels, types.clone()),
+                    // There's been an error when elaborating the head of the
+                    // update, so avoid trying to elaborate any further to
+                    // prevent cascading type errors.
+                    (core::Term::Prim(_, Prim::ReportedError), _)
+                    | (_, Value::Stuck(Head::Prim(Prim::ReportedError), _)) => {
+                        return self.synth_reported_error(*range);
+                    }
+                    // The head expression was not a record, so none of the
+                    // fields can be updated.
+                    _ => (&[][..], Telescope::new(SharedEnv::new(), &[])),
+                };
+
+                let (_, expr_fields) =
+                    self.report_duplicate_labels(*range, expr_fields, |f| f.label);
+                let expr_fields = expr_fields.collect::<Vec<_>>();
+
+                // Ensure that the updated fields are present in the record type
+                let mut has_unknown_fields = false;
+                for expr_field in &expr_fields {
+                    let (label_range, label) = expr_field.label;
+                    if !labels.contains(&label) {
+                        has_unknown_fields = true;
+                        let head_type = self.pretty_print_value(&head_type);
+                        let suggestion =
+                            suggest_name(&self.interner.borrow(), label, labels.iter().copied());
+                        self.push_message(Message::UnknownField {
+                            head_range: self.file_range(head_range),
+                            head_type,
+                            label_range: self.file_range(label_range),
+                            label,
+                            suggestion,
+                        });
+                    }
+                }
+                if has_unknown_fields {
+                    return self.synth_reported_error(*range);
+                }
+
+                let head_expr = self.scope.to_scope(head_expr);
+                let head_expr_value = self.eval_env().eval(head_expr);
+
+                // The types of the fields in the updated record, and the types
+                // of the fields in the head expression.
+                let mut types = types;
+                let mut head_types = types.clone();
+                let mut labels_iter = labels.iter();
+                let mut exprs = SliceVec::new(self.scope, labels.len());
+
+                while let Some((
+                    label,
+                    ((r#type, next_types), (head_field_type, next_head_types)),
+                )) = Option::zip(
+                    labels_iter.next(),
+                    Option::zip(
+                        self.elim_env().split_telescope(types),
+                        self.elim_env().split_telescope(head_types),
+                    ),
+                ) {
+                    let expr = match expr_fields.iter().find(|f| f.label.1 == *label) {
+                        Some(expr_field) => self.check(&expr_field.expr, &r#type),
+                        // Fields that are not updated are projected from the
+                        // head expression. Their types may depend on fields
+                        // that have been updated, so they need to be checked
+                        // against the types in the updated record.
+                        None => {
+                            let expr = core::Term::RecordProj(file_range.into(), head_expr, *label);
+                            self.coerce(*range, expr, &head_field_type, &r#type)
+                        }
+                    };
+
+                    let head_field_expr =
+                        (self.elim_env()).record_proj(Span::Empty, head_expr_value.clone(), *label);
+                    types = next_types(self.eval_env().eval(&expr));
+                    head_types = next_head_types(head_field_expr);
+                    exprs.push(expr);
+                }
+
+                let expr = core::Term::RecordLit(file_range.into(), labels, exprs.into());
+                (expr, head_type)
+            }
             Term::ArrayLiteral(range, _) => {
                 self.push_message(Message::AmbiguousArrayLiteral { range: file_range });
                 self.synth_reported_error(*range)
@@ -1739,7 +2301,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (expr, self.bool_type.clone())
             }
             Term::FormatRecord(range, format_fields) => {
-                let (labels, formats) = self.check_format_fields(*range, format_fields);
+                let warn_unused_fields = self.warn_unused_fields;
+                let (labels, formats) =
+                    self.check_format_fields(*range, format_fields, warn_unused_fields);
                 let format_record = core::Term::FormatRecord(file_range.into(), labels, formats);
                 (format_record, self.format_type.clone())
             }
@@ -1749,9 +2313,13 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 let format_value = self.eval_env().eval(&format);
                 let repr_type = self.elim_env().format_repr(&format_value);
 
-                self.local_env.push_param(Some(*name), repr_type);
+                // Check the predicate with its parameter bound to the
+                // representation of the format, ensuring that it is always a
+                // function from `Repr format` to `Bool`.
+                self.local_env.push_param(Some(*name), None, repr_type);
                 let bool_type = self.bool_type.clone();
                 let pred_expr = self.check(pred, &bool_type);
+                self.check_format_cond_pred(file_range, pred.range(), &pred_expr);
                 self.local_env.pop();
 
                 let cond_format = core::Term::FormatCond(
@@ -1764,12 +2332,18 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 (cond_format, format_type)
             }
             Term::FormatOverlap(range, format_fields) => {
-                let (labels, formats) = self.check_format_fields(*range, format_fields);
+                let (labels, formats) = self.check_format_fields(*range, format_fields, false);
                 let overlap_format = core::Term::FormatOverlap(file_range.into(), labels, formats);
+                self.check_overlap_sizes(file_range, format_fields, &overlap_format);
 
                 (overlap_format, self.format_type.clone())
             }
             Term::BinOp(range, lhs, op, rhs) => self.synth_bin_op(*range, lhs, *op, rhs),
+            Term::ElidedElems(range, _) => {
+                let file_range = self.file_range(*range);
+                self.push_message(Message::ElidedElemsNotSupported { range: file_range });
+                self.synth_reported_error(*range)
+            }
             Term::ReportedError(range) => self.synth_reported_error(*range),
         }
     }
@@ -1814,7 +2388,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     Value::FunType(Plicity::Implicit, param_name, param_type, next_body_type)
                         if param.plicity == Plicity::Explicit =>
                     {
-                        let arg_expr = self.local_env.push_param(*param_name, param_type.clone());
+                        let arg_expr =
+                            self.local_env
+                                .push_param(*param_name, None, param_type.clone());
                         let body_type = self.elim_env().apply_closure(next_body_type, arg_expr);
                         let body_expr = self.check_fun_lit(range, params, body_expr, &body_type);
                         self.local_env.pop();
@@ -1902,6 +2478,150 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         (fun_lit, fun_type)
     }
 
+    /// Elaborate the definition of a record destructuring let expression,
+    /// pushing a local definition for the record, followed by a local
+    /// definition for each of its fields. The returned definitions should be
+    /// wrapped around the body with [`Context::pop_let_record`].
+    fn push_let_record(
+        &mut self,
+        range: ByteRange,
+        def: &LetRecordDef<'_, ByteRange>,
+    ) -> Vec<(Option<StringId>, core::Term<'arena>, core::Term<'arena>)> {
+        let labels = def.labels;
+        let pattern_range = match (labels.first(), labels.last()) {
+            (Some((start, _)), Some((end, _))) => ByteRange::merge(*start, *end),
+            _ => range,
+        };
+        let file_range = self.file_range(pattern_range);
+        let (def_expr, def_type, def_type_value) = match &def.r#type {
+            None => {
+                let (expr, type_value) = self.synth_and_insert_implicit_apps(&def.expr);
+                let r#type = self.quote_env().quote(self.scope, &type_value);
+                (expr, r#type, type_value)
+            }
+            Some(r#type) => {
+                let r#type = self.check_ann_type(r#type);
+                let type_value = self.eval_env().eval(&r#type);
+                (self.check(&def.expr, &type_value), r#type, type_value)
+            }
+        };
+        let def_type_value = self.elim_env().force(&def_type_value);
+        let def_expr_value = self.eval_env().eval(&def_expr);
+
+        // Ensure that the pattern binds each of the fields in the record type,
+        // in any order
+        let field_types = match (&def_expr, def_type_value.as_ref()) {
+            (_, Value::RecordType(type_labels, types))
+                if type_labels.len() == labels.len()
+                    && (type_labels.iter())
+                        .all(|type_label| labels.iter().any(|(_, label)| label == type_label)) =>
+            {
+                // Find the types of the fields in the order that they appear in
+                // the record type, as later types can depend on earlier fields
+                let mut types = types.clone();
+                let mut field_types = Vec::with_capacity(type_labels.len());
+                for label in type_labels.iter() {
+                    let (type_value, next_types) = match self.elim_env().split_telescope(types) {
+                        Some(split) => split,
+                        None => break,
+                    };
+                    let expr_value =
+                        (self.elim_env()).record_proj(Span::Empty, def_expr_value.clone(), *label);
+                    field_types.push((*label, type_value));
+                    types = next_types(expr_value);
+                }
+                Some(field_types)
+            }
+            (_, Value::RecordType(type_labels, _)) => {
+                self.push_message(Message::MismatchedFieldLabels {
+                    range: file_range,
+                    expr_labels: (labels.iter())
+                        .map(|(range, label)| (self.file_range(*range), *label))
+                        .collect(),
+                    type_labels: type_labels.to_vec(),
+                });
+                None
+            }
+            // There's been an error when elaborating the definition, so avoid
+            // reporting any further errors.
+            (core::Term::Prim(_, Prim::ReportedError), _)
+            | (_, Value::Stuck(Head::Prim(Prim::ReportedError), _)) => None,
+            _ => {
+                let expected_type = self.pretty_print_value(&def_type_value);
+                self.push_message(Message::RecordPatternNotSupported {
+                    range: file_range,
+                    expected_type,
+                });
+                None
+            }
+        };
+
+        let mut defs = Vec::with_capacity(labels.len() + 1);
+        let record_pattern = CheckedPattern::Placeholder(file_range);
+        self.push_local_def(record_pattern, def_expr_value.clone(), def_type_value);
+        defs.push((None, def_type, def_expr));
+
+        // Bind the fields to projections on the record. If the pattern did not
+        // match the record type, the fields are still bound, so that their
+        // uses in the body do not result in further errors.
+        for (record_var, (range, label)) in Iterator::zip(env::indices(), labels.iter()) {
+            let label_range = self.file_range(*range);
+            let type_value = (field_types.iter().flatten())
+                .find_map(|(field_label, type_value)| (field_label == label).then_some(type_value));
+            let (expr, expr_value, type_value) = match type_value {
+                Some(type_value) => {
+                    let record_expr = core::Term::LocalVar(label_range.into(), record_var);
+                    let expr = core::Term::RecordProj(
+                        label_range.into(),
+                        self.scope.to_scope(record_expr),
+                        *label,
+                    );
+                    let expr_value =
+                        (self.elim_env()).record_proj(Span::Empty, def_expr_value.clone(), *label);
+                    (expr, expr_value, type_value.clone())
+                }
+                None => {
+                    let (expr, type_value) = self.synth_reported_error(*range);
+                    let expr_value = self.eval_env().eval(&expr);
+                    (expr, expr_value, type_value)
+                }
+            };
+            let r#type = self.quote_env().quote(self.scope, &type_value);
+
+            let pattern = CheckedPattern::Binder(label_range, *label);
+            let name = self.push_local_def(pattern, expr_value, type_value);
+            defs.push((name, r#type, expr));
+        }
+
+        defs
+    }
+
+    /// Pop the local definitions pushed by [`Context::push_let_record`],
+    /// wrapping them around the elaborated body expression.
+    fn pop_let_record(
+        &mut self,
+        range: ByteRange,
+        labels: &[(ByteRange, StringId)],
+        defs: Vec<(Option<StringId>, core::Term<'arena>, core::Term<'arena>)>,
+        body_expr: core::Term<'arena>,
+    ) -> core::Term<'arena> {
+        for (label_range, _) in labels.iter().rev() {
+            self.pop_local_def(*label_range);
+        }
+        self.pop_local_def(range);
+
+        let file_range = self.file_range(range);
+        (defs.into_iter().rev()).fold(body_expr, |body_expr, (def_name, def_type, def_expr)| {
+            core::Term::Let(
+                file_range.into(),
+                def_name,
+                self.scope.to_scope(def_type),
+                self.scope.to_scope(def_expr),
+                self.scope.to_scope(body_expr),
+            )
+        })
+    }
+
     fn synth_bin_op(
         &mut self,
         range: ByteRange,
@@ -1924,31 +2644,37 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             (Mul(_), Some(((U16Type, []), (U16Type, [])))) => (U16Mul, U16Type),
             (Mul(_), Some(((U32Type, []), (U32Type, [])))) => (U32Mul, U32Type),
             (Mul(_), Some(((U64Type, []), (U64Type, [])))) => (U64Mul, U64Type),
+            (Mul(_), Some(((U128Type, []), (U128Type, [])))) => (U128Mul, U128Type),
 
             (Mul(_), Some(((S8Type, []), (S8Type, [])))) => (S8Mul, S8Type),
             (Mul(_), Some(((S16Type, []), (S16Type, [])))) => (S16Mul, S16Type),
             (Mul(_), Some(((S32Type, []), (S32Type, [])))) => (S32Mul, S32Type),
             (Mul(_), Some(((S64Type, []), (S64Type, [])))) => (S64Mul, S64Type),
+            (Mul(_), Some(((S128Type, []), (S128Type, [])))) => (S128Mul, S128Type),
 
             (Div(_), Some(((U8Type, []), (U8Type, [])))) => (U8Div, U8Type),
             (Div(_), Some(((U16Type, []), (U16Type, [])))) => (U16Div, U16Type),
             (Div(_), Some(((U32Type, []), (U32Type, [])))) => (U32Div, U32Type),
             (Div(_), Some(((U64Type, []), (U64Type, [])))) => (U64Div, U64Type),
+            (Div(_), Some(((U128Type, []), (U128Type, [])))) => (U128Div, U128Type),
 
             (Div(_), Some(((S8Type, []), (S8Type, [])))) => (S8Div, S8Type),
             (Div(_), Some(((S16Type, []), (S16Type, [])))) => (S16Div, S16Type),
             (Div(_), Some(((S32Type, []), (S32Type, [])))) => (S32Div, S32Type),
             (Div(_), Some(((S64Type, []), (S64Type, [])))) => (S64Div, S64Type),
+            (Div(_), Some(((S128Type, []), (S128Type, [])))) => (S128Div, S128Type),
 
             (Add(_), Some(((U8Type, []), (U8Type, [])))) => (U8Add, U8Type),
             (Add(_), Some(((U16Type, []), (U16Type, [])))) => (U16Add, U16Type),
             (Add(_), Some(((U32Type, []), (U32Type, [])))) => (U32Add, U32Type),
             (Add(_), Some(((U64Type, []), (U64Type, [])))) => (U64Add, U64Type),
+            (Add(_), Some(((U128Type, []), (U128Type, [])))) => (U128Add, U128Type),
 
             (Add(_), Some(((S8Type, []), (S8Type, [])))) => (S8Add, S8Type),
             (Add(_), Some(((S16Type, []), (S16Type, [])))) => (S16Add, S16Type),
             (Add(_), Some(((S32Type, []), (S32Type, [])))) => (S32Add, S32Type),
             (Add(_), Some(((S64Type, []), (S64Type, [])))) => (S64Add, S64Type),
+            (Add(_), Some(((S128Type, []), (S128Type, [])))) => (S128Add, S128Type),
 
             (Add(_), Some(((PosType, []), (U8Type, [])))) => (PosAddU8, PosType),
             (Add(_), Some(((PosType, []), (U16Type, [])))) => (PosAddU16, PosType),
@@ -1959,74 +2685,91 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             (Sub(_), Some(((U16Type, []), (U16Type, [])))) => (U16Sub, U16Type),
             (Sub(_), Some(((U32Type, []), (U32Type, [])))) => (U32Sub, U32Type),
             (Sub(_), Some(((U64Type, []), (U64Type, [])))) => (U64Sub, U64Type),
+            (Sub(_), Some(((U128Type, []), (U128Type, [])))) => (U128Sub, U128Type),
 
             (Sub(_), Some(((S8Type, []), (S8Type, [])))) => (S8Sub, S8Type),
             (Sub(_), Some(((S16Type, []), (S16Type, [])))) => (S16Sub, S16Type),
             (Sub(_), Some(((S32Type, []), (S32Type, [])))) => (S32Sub, S32Type),
             (Sub(_), Some(((S64Type, []), (S64Type, [])))) => (S64Sub, S64Type),
+            (Sub(_), Some(((S128Type, []), (S128Type, [])))) => (S128Sub, S128Type),
 
             (Eq(_), Some(((BoolType, []), (BoolType, [])))) => (BoolEq, BoolType),
             (Neq(_), Some(((BoolType, []), (BoolType, [])))) => (BoolNeq, BoolType),
 
+            (Eq(_), Some(((StringType, []), (StringType, [])))) => (StringEq, BoolType),
+            (Neq(_), Some(((StringType, []), (StringType, [])))) => (StringNeq, BoolType),
+
             (Eq(_), Some(((U8Type, []), (U8Type, [])))) => (U8Eq, BoolType),
             (Eq(_), Some(((U16Type, []), (U16Type, [])))) => (U16Eq, BoolType),
             (Eq(_), Some(((U32Type, []), (U32Type, [])))) => (U32Eq, BoolType),
             (Eq(_), Some(((U64Type, []), (U64Type, [])))) => (U64Eq, BoolType),
+            (Eq(_), Some(((U128Type, []), (U128Type, [])))) => (U128Eq, BoolType),
 
             (Eq(_), Some(((S8Type, []), (S8Type, [])))) => (S8Eq, BoolType),
             (Eq(_), Some(((S16Type, []), (S16Type, [])))) => (S16Eq, BoolType),
             (Eq(_), Some(((S32Type, []), (S32Type, [])))) => (S32Eq, BoolType),
             (Eq(_), Some(((S64Type, []), (S64Type, [])))) => (S64Eq, BoolType),
+            (Eq(_), Some(((S128Type, []), (S128Type, [])))) => (S128Eq, BoolType),
 
             (Neq(_), Some(((U8Type, []), (U8Type, [])))) => (U8Neq, BoolType),
             (Neq(_), Some(((U16Type, []), (U16Type, [])))) => (U16Neq, BoolType),
             (Neq(_), Some(((U32Type, []), (U32Type, [])))) => (U32Neq, BoolType),
             (Neq(_), Some(((U64Type, []), (U64Type, [])))) => (U64Neq, BoolType),
+            (Neq(_), Some(((U128Type, []), (U128Type, [])))) => (U128Neq, BoolType),
 
             (Neq(_), Some(((S8Type, []), (S8Type, [])))) => (S8Neq, BoolType),
             (Neq(_), Some(((S16Type, []), (S16Type, [])))) => (S16Neq, BoolType),
             (Neq(_), Some(((S32Type, []), (S32Type, [])))) => (S32Neq, BoolType),
             (Neq(_), Some(((S64Type, []), (S64Type, [])))) => (S64Neq, BoolType),
+            (Neq(_), Some(((S128Type, []), (S128Type, [])))) => (S128Neq, BoolType),
 
             (Lt(_), Some(((U8Type, []), (U8Type, [])))) => (U8Lt, BoolType),
             (Lt(_), Some(((U16Type, []), (U16Type, [])))) => (U16Lt, BoolType),
             (Lt(_), Some(((U32Type, []), (U32Type, [])))) => (U32Lt, BoolType),
             (Lt(_), Some(((U64Type, []), (U64Type, [])))) => (U64Lt, BoolType),
+            (Lt(_), Some(((U128Type, []), (U128Type, [])))) => (U128Lt, BoolType),
 
             (Lt(_), Some(((S8Type, []), (S8Type, [])))) => (S8Lt, BoolType),
             (Lt(_), Some(((S16Type, []), (S16Type, [])))) => (S16Lt, BoolType),
             (Lt(_), Some(((S32Type, []), (S32Type, [])))) => (S32Lt, BoolType),
             (Lt(_), Some(((S64Type, []), (S64Type, [])))) => (S64Lt, BoolType),
+            (Lt(_), Some(((S128Type, []), (S128Type, [])))) => (S128Lt, BoolType),
 
             (Lte(_), Some(((U8Type, []), (U8Type, [])))) => (U8Lte, BoolType),
             (Lte(_), Some(((U16Type, []), (U16Type, [])))) => (U16Lte, BoolType),
             (Lte(_), Some(((U32Type, []), (U32Type, [])))) => (U32Lte, BoolType),
             (Lte(_), Some(((U64Type, []), (U64Type, [])))) => (U64Lte, BoolType),
+            (Lte(_), Some(((U128Type, []), (U128Type, [])))) => (U128Lte, BoolType),
 
             (Lte(_), Some(((S8Type, []), (S8Type, [])))) => (S8Lte, BoolType),
             (Lte(_), Some(((S16Type, []), (S16Type, [])))) => (S16Lte, BoolType),
             (Lte(_), Some(((S32Type, []), (S32Type, [])))) => (S32Lte, BoolType),
             (Lte(_), Some(((S64Type, []), (S64Type, [])))) => (S64Lte, BoolType),
+            (Lte(_), Some(((S128Type, []), (S128Type, [])))) => (S128Lte, BoolType),
 
             (Gt(_), Some(((U8Type, []), (U8Type, [])))) => (U8Gt, BoolType),
             (Gt(_), Some(((U16Type, []), (U16Type, [])))) => (U16Gt, BoolType),
             (Gt(_), Some(((U32Type, []), (U32Type, [])))) => (U32Gt, BoolType),
             (Gt(_), Some(((U64Type, []), (U64Type, [])))) => (U64Gt, BoolType),
+            (Gt(_), Some(((U128Type, []), (U128Type, [])))) => (U128Gt, BoolType),
 
             (Gt(_), Some(((S8Type, []), (S8Type, [])))) => (S8Gt, BoolType),
             (Gt(_), Some(((S16Type, []), (S16Type, [])))) => (S16Gt, BoolType),
             (Gt(_), Some(((S32Type, []), (S32Type, [])))) => (S32Gt, BoolType),
             (Gt(_), Some(((S64Type, []), (S64Type, [])))) => (S64Gt, BoolType),
+            (Gt(_), Some(((S128Type, []), (S128Type, [])))) => (S128Gt, BoolType),
 
             (Gte(_), Some(((U8Type, []), (U8Type, [])))) => (U8Gte, BoolType),
             (Gte(_), Some(((U16Type, []), (U16Type, [])))) => (U16Gte, BoolType),
             (Gte(_), Some(((U32Type, []), (U32Type, [])))) => (U32Gte, BoolType),
             (Gte(_), Some(((U64Type, []), (U64Type, [])))) => (U64Gte, BoolType),
+            (Gte(_), Some(((U128Type, []), (U128Type, [])))) => (U128Gte, BoolType),
 
             (Gte(_), Some(((S8Type, []), (S8Type, [])))) => (S8Gte, BoolType),
             (Gte(_), Some(((S16Type, []), (S16Type, [])))) => (S16Gte, BoolType),
             (Gte(_), Some(((S32Type, []), (S32Type, [])))) => (S32Gte, BoolType),
             (Gte(_), Some(((S64Type, []), (S64Type, [])))) => (S64Gte, BoolType),
+            (Gte(_), Some(((S128Type, []), (S128Type, [])))) => (S128Gte, BoolType),
 
             _ => {
                 let lhs_pretty = self.pretty_print_value(&lhs_type);
@@ -2044,14 +2787,17 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         };
 
         let fun_head = core::Term::Prim(self.file_range(op.range()).into(), fun);
+        let lhs_span = Span::merge(&lhs_expr.span(), &rhs_expr.span());
+        let fun_head = self.scope.to_scope(fun_head);
+        let lhs_expr = self.scope.to_scope(lhs_expr);
         let fun_app = core::Term::FunApp(
             self.file_range(range).into(),
             Plicity::Explicit,
             self.scope.to_scope(core::Term::FunApp(
-                Span::merge(&lhs_expr.span(), &rhs_expr.span()),
+                lhs_span,
                 Plicity::Explicit,
-                self.scope.to_scope(fun_head),
-                self.scope.to_scope(lhs_expr),
+                fun_head,
+                lhs_expr,
             )),
             self.scope.to_scope(rhs_expr),
         );
@@ -2088,41 +2834,49 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             (Add(_), U16Type) => (U16Add, U16Type),
             (Add(_), U32Type) => (U32Add, U32Type),
             (Add(_), U64Type) => (U64Add, U64Type),
+            (Add(_), U128Type) => (U128Add, U128Type),
 
             (Add(_), S8Type) => (S8Add, S8Type),
             (Add(_), S16Type) => (S16Add, S16Type),
             (Add(_), S32Type) => (S32Add, S32Type),
             (Add(_), S64Type) => (S64Add, S64Type),
+            (Add(_), S128Type) => (S128Add, S128Type),
 
             (Sub(_), U8Type) => (U8Sub, U8Type),
             (Sub(_), U16Type) => (U16Sub, U16Type),
             (Sub(_), U32Type) => (U32Sub, U32Type),
             (Sub(_), U64Type) => (U64Sub, U64Type),
+            (Sub(_), U128Type) => (U128Sub, U128Type),
 
             (Sub(_), S8Type) => (S8Sub, S8Type),
             (Sub(_), S16Type) => (S16Sub, S16Type),
             (Sub(_), S32Type) => (S32Sub, S32Type),
             (Sub(_), S64Type) => (S64Sub, S64Type),
+            (Sub(_), S128Type) => (S128Sub, S128Type),
 
             (Mul(_), U8Type) => (U8Mul, U8Type),
             (Mul(_), U16Type) => (U16Mul, U16Type),
             (Mul(_), U32Type) => (U32Mul, U32Type),
             (Mul(_), U64Type) => (U64Mul, U64Type),
+            (Mul(_), U128Type) => (U128Mul, U128Type),
 
             (Mul(_), S8Type) => (S8Mul, S8Type),
             (Mul(_), S16Type) => (S16Mul, S16Type),
             (Mul(_), S32Type) => (S32Mul, S32Type),
             (Mul(_), S64Type) => (S64Mul, S64Type),
+            (Mul(_), S128Type) => (S128Mul, S128Type),
 
             (Div(_), U8Type) => (U8Div, U8Type),
             (Div(_), U16Type) => (U16Div, U16Type),
             (Div(_), U32Type) => (U32Div, U32Type),
             (Div(_), U64Type) => (U64Div, U64Type),
+            (Div(_), U128Type) => (U128Div, U128Type),
 
             (Div(_), S8Type) => (S8Div, S8Type),
             (Div(_), S16Type) => (S16Div, S16Type),
             (Div(_), S32Type) => (S32Div, S32Type),
             (Div(_), S64Type) => (S64Div, S64Type),
+            (Div(_), S128Type) => (S128Div, S128Type),
 
             _ => {
                 let (expr, synth_type) = self.synth_bin_op(range, lhs, op, rhs);
@@ -2136,14 +2890,17 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         let rhs_expr = self.check(rhs, &expected_type);
 
         let fun_head = core::Term::Prim(self.file_range(op.range()).into(), fun);
+        let lhs_span = Span::merge(&lhs_expr.span(), &rhs_expr.span());
+        let fun_head = self.scope.to_scope(fun_head);
+        let lhs_expr = self.scope.to_scope(lhs_expr);
         core::Term::FunApp(
             self.file_range(range).into(),
             Plicity::Explicit,
             self.scope.to_scope(core::Term::FunApp(
-                Span::merge(&lhs_expr.span(), &rhs_expr.span()),
+                lhs_span,
                 Plicity::Explicit,
-                self.scope.to_scope(fun_head),
-                self.scope.to_scope(lhs_expr),
+                fun_head,
+                lhs_expr,
             )),
             self.scope.to_scope(rhs_expr),
         )
@@ -2156,11 +2913,14 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         (expr, r#type)
     }
 
-    /// Check a series of format fields
+    /// Elaborate the fields of a record or overlap format. If
+    /// `warn_unused_fields` is set, fixed-size fields that are never referenced
+    /// by later fields are reported.
     fn check_format_fields(
         &mut self,
         range: ByteRange,
         format_fields: &[FormatField<'_, ByteRange>],
+        warn_unused_fields: bool,
     ) -> (&'arena [StringId], &'arena [core::Term<'arena>]) {
         let universe = self.universe.clone();
         let format_type = self.format_type.clone();
@@ -2171,6 +2931,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 FormatField::Format { label, .. } | FormatField::Computed { label, .. } => *label,
             });
         let mut formats = SliceVec::new(self.scope, labels.len());
+        // Fixed-size fields that may be reported if they are never referenced
+        let mut fixed_size_fields = Vec::new();
 
         for format_field in format_fields {
             match format_field {
@@ -2180,11 +2942,23 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     pred,
                 } => {
                     let label_range = self.file_range(*label_range);
+                    let format_range = format.range();
                     let format = self.check(format, &format_type);
                     let format_value = self.eval_env().eval(&format);
                     let r#type = self.elim_env().format_repr(&format_value);
 
-                    self.local_env.push_param(Some(*label), r#type);
+                    if let Some((Prim::VoidType, [])) =
+                        self.elim_env().force(&r#type).match_prim_spine()
+                    {
+                        let range = self.file_range(format_range);
+                        self.push_message(Message::UninhabitedFormat { range });
+                    }
+
+                    if warn_unused_fields && self.elim_env().static_size(&format_value).is_some() {
+                        let level = self.local_env.len().next_level();
+                        fixed_size_fields.push((level, label_range, *label));
+                    }
+                    self.local_env.push_param(Some(*label), None, r#type);
 
                     match pred {
                         None => formats.push(format),
@@ -2194,6 +2968,10 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                             // in preparation for checking the the next format field.
                             let cond_expr = self.check(pred, &self.bool_type.clone());
 
+                            let cond_range = ByteRange::merge(format_range, pred.range());
+                            let cond_range = self.file_range(cond_range);
+                            self.check_format_cond_pred(cond_range, pred.range(), &cond_expr);
+
                             let field_span = Span::merge(&label_range.into(), &cond_expr.span());
                             formats.push(core::Term::FormatCond(
                                 field_span,
@@ -2224,31 +3002,109 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     };
 
                     let field_span = Span::merge(&label_range.into(), &expr.span());
+                    let succeed = self
+                        .scope
+                        .to_scope(core::Term::Prim(field_span, Prim::FormatSucceed));
+                    let r#type = self.scope.to_scope(r#type);
                     let format = core::Term::FunApp(
                         field_span,
                         Plicity::Explicit,
                         self.scope.to_scope(core::Term::FunApp(
                             field_span,
                             Plicity::Explicit,
-                            self.scope
-                                .to_scope(core::Term::Prim(field_span, Prim::FormatSucceed)),
-                            self.scope.to_scope(r#type),
+                            succeed,
+                            r#type,
                         )),
                         self.scope.to_scope(expr),
                     );
 
                     // Assume that `Repr ${type_value} ${expr} = ${type_value}`
-                    self.local_env.push_param(Some(*label), type_value);
+                    self.local_env.push_param(Some(*label), None, type_value);
                     formats.push(format);
                 }
             }
         }
 
+        // Report the fixed-size fields that were never referenced. Names
+        // starting with an underscore are exempt.
+        for (level, range, name) in fixed_size_fields {
+            let used = self.local_env.uses.get_level(level).copied();
+            if used == Some(false)
+                && !self
+                    .interner
+                    .borrow()
+                    .resolve(name)
+                    .unwrap()
+                    .starts_with('_')
+            {
+                self.push_message(Message::UnusedFormatField { range, name });
+            }
+        }
+
         self.local_env.truncate(initial_local_len);
 
         (labels, formats.into())
     }
 
+    /// Warn if the predicate of a conditional format is known to always be
+    /// `false`, meaning that the format can never be read successfully. The
+    /// predicate's parameter is expected to be bound in the local environment.
+    fn check_format_cond_pred(
+        &mut self,
+        range: FileRange,
+        pred_range: ByteRange,
+        pred_expr: &core::Term<'arena>,
+    ) {
+        let pred_value = self.eval_env().eval(pred_expr);
+        if let Value::ConstLit(Const::Bool(false)) = pred_value.as_ref() {
+            let pred_range = self.file_range(pred_range);
+            self.push_message(Message::FalseFormatCondition { range, pred_range });
+        }
+    }
+
+    /// Report an error if the fields of an overlap format are known to occupy
+    /// different numbers of bytes. Fields with sizes that depend on runtime
+    /// data, along with computed fields, are skipped.
+    fn check_overlap_sizes(
+        &mut self,
+        range: FileRange,
+        format_fields: &[FormatField<'_, ByteRange>],
+        overlap_format: &core::Term<'arena>,
+    ) {
+        let overlap_format = self.eval_env().eval(overlap_format);
+        let (labels, mut formats) = match overlap_format.as_ref() {
+            Value::FormatOverlap(labels, formats) => (*labels, formats.clone()),
+            _ => return,
+        };
+
+        let mut field_sizes = Vec::new();
+        for label in labels {
+            let var = semantics::ElimEnv::telescope_var(&formats);
+            let (format, next_formats) = match self.elim_env().split_telescope(formats) {
+                Some(split) => split,
+                None => break,
+            };
+            let is_computed = matches!(format.match_prim_spine(), Some((Prim::FormatSucceed, _)));
+            if let (false, Some(size)) = (is_computed, self.elim_env().static_size(&format)) {
+                let label_range = format_fields.iter().find_map(|field| match field {
+                    FormatField::Format {
+                        label: (label_range, field_label),
+                        ..
+                    } if field_label == label => Some(*label_range),
+                    _ => None,
+                });
+                let label_range = label_range.map_or(range, |r| self.file_range(r));
+                field_sizes.push((label_range, *label, size));
+            }
+
+            formats = next_formats(var);
+        }
+
+        if (field_sizes.iter()).any(|(_, _, size)| *size != field_sizes[0].2) {
+            self.push_message(Message::MismatchedOverlapSizes { range, field_sizes });
+        }
+    }
+
     /// Elaborate a match expression in checking mode
     fn check_match(
         &mut self,
@@ -2302,7 +3158,8 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                         let def_type_value = match_info.scrutinee.r#type.clone();
                         let def_type = self.quote_env().quote(self.scope, &def_type_value);
 
-                        self.local_env.push_def(def_name, def_expr, def_type_value);
+                        self.local_env
+                            .push_def(def_name, None, def_expr, def_type_value);
                         let body_expr = self.check(body_expr, &match_info.expected_type);
                         self.local_env.pop();
 
@@ -2332,7 +3189,16 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                         self.check_match_reachable(is_reachable, range);
 
                         let body_expr = self.check(body_expr, &match_info.expected_type);
-                        let const_equation = (range, r#const, body_expr);
+                        let const_equation = (range, ConstPattern::Const(r#const), body_expr);
+
+                        self.elab_match_const(match_info, is_reachable, const_equation, equations)
+                    }
+                    CheckedPattern::ConstRange(range, bounds) => {
+                        self.check_match_reachable(is_reachable, range);
+
+                        let body_expr = self.check(body_expr, &match_info.expected_type);
+                        let pattern = ConstPattern::Range(bounds.0, bounds.1);
+                        let const_equation = (range, pattern, body_expr);
 
                         self.elab_match_const(match_info, is_reachable, const_equation, equations)
                     }
@@ -2362,13 +3228,13 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         &mut self,
         match_info: &MatchInfo<'arena>,
         is_reachable: bool,
-        (const_range, r#const, body_expr): (FileRange, Const, core::Term<'arena>),
+        (const_range, pattern, body_expr): (FileRange, ConstPattern, core::Term<'arena>),
         mut equations: impl Iterator<Item = &'a (Pattern<ByteRange>, Term<'a, ByteRange>)>,
     ) -> core::Term<'arena> {
         // The full range of this series of patterns
         let mut full_span = Span::merge(&const_range.into(), &body_expr.span());
         // Temporary vector for accumulating branches
-        let mut branches = vec![(r#const, body_expr)];
+        let mut branches = vec![(pattern, body_expr)];
 
         // Elaborate a run of constant patterns.
         'patterns: while let Some((pattern, body_expr)) = equations.next() {
@@ -2384,18 +3250,31 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 CheckedPattern::ConstLit(range, r#const) => {
                     let body_expr = self.check(body_expr, &match_info.expected_type);
 
-                    // Find insertion index of the branch
-                    let insertion_index = branches.binary_search_by(|(probe_const, _)| {
-                        Const::partial_cmp(probe_const, &r#const)
-                            .expect("attempt to compare non-ordered value")
-                    });
+                    let pattern = ConstPattern::Const(r#const);
 
-                    match insertion_index {
+                    match search_const_branches(&branches, &pattern) {
                         Ok(_) => self.push_message(Message::UnreachablePattern { range }),
                         Err(index) => {
                             // This has not yet been covered, so it should be reachable.
                             self.check_match_reachable(is_reachable, range);
-                            branches.insert(index, (r#const, body_expr));
+                            branches.insert(index, (pattern, body_expr));
+                        }
+                    }
+
+                    // No default case yet, continue looking for constant patterns.
+                    continue 'patterns;
+                }
+                // Accumulate range pattern, reporting an error if it overlaps
+                // with any of the accumulated branches.
+                CheckedPattern::ConstRange(range, bounds) => {
+                    let body_expr = self.check(body_expr, &match_info.expected_type);
+                    let pattern = ConstPattern::Range(bounds.0, bounds.1);
+
+                    match search_const_branches(&branches, &pattern) {
+                        Ok(_) => self.push_message(Message::OverlappingRangePattern { range }),
+                        Err(index) => {
+                            self.check_match_reachable(is_reachable, range);
+                            branches.insert(index, (pattern, body_expr));
                         }
                     }
 
@@ -2413,7 +3292,11 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     // scrutinee to a let binding with the elaborated body, and
                     // add it to the branches. This will simplify the
                     // distillation of if expressions.
-                    (self.local_env).push_param(Some(name), match_info.scrutinee.r#type.clone());
+                    (self.local_env).push_param(
+                        Some(name),
+                        Some(range),
+                        match_info.scrutinee.r#type.clone(),
+                    );
                     let default_expr = self.check(body_expr, &match_info.expected_type);
                     default_branch = (Some(name), self.scope.to_scope(default_expr) as &_);
                     self.local_env.pop();
@@ -2421,13 +3304,13 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 CheckedPattern::Placeholder(range) => {
                     self.check_match_reachable(is_reachable, range);
 
-                    (self.local_env).push_param(None, match_info.scrutinee.r#type.clone());
+                    (self.local_env).push_param(None, None, match_info.scrutinee.r#type.clone());
                     let default_expr = self.check(body_expr, &match_info.expected_type);
                     default_branch = (None, self.scope.to_scope(default_expr) as &_);
                     self.local_env.pop();
                 }
                 CheckedPattern::ReportedError(range) => {
-                    (self.local_env).push_param(None, match_info.scrutinee.r#type.clone());
+                    (self.local_env).push_param(None, None, match_info.scrutinee.r#type.clone());
                     let default_expr = core::Term::Prim(range.into(), Prim::ReportedError);
                     default_branch = (None, self.scope.to_scope(default_expr) as &_);
                     self.local_env.pop();
@@ -2493,14 +3376,23 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
     }
 }
 
-trait FromStrRadix: Sized {
-    fn from_str_radix(src: &str, radix: u32) -> Result<Self, std::num::ParseIntError>;
+trait FromStrRadix: Sized + std::fmt::Display {
+    /// The name of the corresponding primitive type
+    const NAME: &'static str;
+    const MIN: Self;
+    const MAX: Self;
+
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
 }
 
 macro_rules! impl_from_str_radix {
-    ($t:ty) => {
+    ($t:ty, $name:literal) => {
         impl FromStrRadix for $t {
-            fn from_str_radix(src: &str, radix: u32) -> Result<Self, std::num::ParseIntError> {
+            const NAME: &'static str = $name;
+            const MIN: Self = <$t>::MIN;
+            const MAX: Self = <$t>::MAX;
+
+            fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
                 // calls base implementation, not trait function
                 Self::from_str_radix(src, radix)
             }
@@ -2508,10 +3400,16 @@ macro_rules! impl_from_str_radix {
     };
 }
 
-impl_from_str_radix!(u8);
-impl_from_str_radix!(u16);
-impl_from_str_radix!(u32);
-impl_from_str_radix!(u64);
+impl_from_str_radix!(u8, "U8");
+impl_from_str_radix!(u16, "U16");
+impl_from_str_radix!(u32, "U32");
+impl_from_str_radix!(u64, "U64");
+impl_from_str_radix!(u128, "U128");
+impl_from_str_radix!(i8, "S8");
+impl_from_str_radix!(i16, "S16");
+impl_from_str_radix!(i32, "S32");
+impl_from_str_radix!(i64, "S64");
+impl_from_str_radix!(i128, "S128");
 
 /// Simple patterns that have had some initial elaboration performed on them
 #[derive(Debug)]
@@ -2522,10 +3420,27 @@ enum CheckedPattern {
     Placeholder(FileRange),
     /// Constant literals
     ConstLit(FileRange, Const),
+    /// Inclusive ranges of integer constants
+    ConstRange(FileRange, Box<(Const, Const)>),
     /// Error sentinel
     ReportedError(FileRange),
 }
 
+/// Binary search a list of non-overlapping constant branches, listed in
+/// lexicographic order. Returns the index of a branch that overlaps with the
+/// pattern, or the index where the pattern should be inserted to keep the
+/// branches in order.
+fn search_const_branches(
+    branches: &[(ConstPattern, core::Term<'_>)],
+    pattern: &ConstPattern,
+) -> Result<usize, usize> {
+    let index = branches.partition_point(|(probe, _)| probe.end() < pattern.start());
+    match branches.get(index) {
+        Some((probe, _)) if probe.overlaps(pattern) => Ok(index),
+        _ => Err(index),
+    }
+}
+
 /// Scrutinee of a match expression
 struct Scrutinee<'arena> {
     range: ByteRange,
@@ -2544,11 +3459,329 @@ struct MatchInfo<'arena> {
 
 #[cfg(test)]
 mod tests {
+    use codespan_reporting::diagnostic::Severity;
+
     use super::*;
+    use crate::testing::{self, assert_normalizes, elab_term, parse_module, parse_term};
+
+    #[test]
+    fn octal_literal_elaborates_to_octal_const() {
+        assert_normalizes("0o17 : U8", "0o17");
+    }
+
+    #[test]
+    fn elided_elems_are_reported() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let mut messages = Vec::new();
+        let mut context = Context::new(testing::file_id(), &interner, &scope, ItemEnv::new());
+        let surface_term = Term::ElidedElems(ByteRange::new(0, 12), 5);
+        let (term, _) = context.elab_term(&scope, &surface_term, &mut |m| messages.push(m));
+
+        assert!(term.is_error());
+        assert!(matches!(
+            messages[..],
+            [Message::ElidedElemsNotSupported { .. }]
+        ));
+    }
+
+    #[test]
+    fn items_report_their_types() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let source = "def width : U16 = 640; def is_wide : Bool = u16_gt width 320;";
+
+        let surface_module = parse_module(&interner, &scope, source);
+        let mut context = Context::new(testing::file_id(), &interner, &scope, ItemEnv::new());
+        context.elab_module(&scope, &surface_module, &mut |m| panic!("{m:?}"));
+
+        let items = context.items(&scope);
+        let interner = interner.borrow();
+        let items = items.iter().map(|item| match item {
+            core::Item::Def { label, r#type, .. } => (interner.resolve(*label).unwrap(), r#type),
+        });
+        let items = items.collect::<Vec<_>>();
+
+        assert!(matches!(
+            items[..],
+            [
+                ("width", core::Term::Prim(_, Prim::U16Type)),
+                ("is_wide", core::Term::Prim(_, Prim::BoolType)),
+            ]
+        ));
+    }
+
+    #[test]
+    fn names_refer_to_prims() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let source =
+            "fun (x : U8) (f : Format) => (u8_add x 1, bool_not true, repeat_len8 x f, byte)";
+
+        let surface_term = parse_term(&interner, &scope, source);
+        let mut context = Context::new(testing::file_id(), &interner, &scope, ItemEnv::new());
+
+        // Embedders can add their own names for primitives
+        let u8_name = interner.borrow_mut().get_or_intern_static("u8");
+        let byte_name = interner.borrow_mut().get_or_intern_static("byte");
+        assert!(context
+            .prim_env_mut()
+            .define_name(byte_name, Prim::FormatU8));
+        // Removing the default name doesn't prevent other names being defined
+        assert!(context.prim_env_mut().remove_name(u8_name).is_some());
+        assert!(context.prim_env_mut().define_name(u8_name, Prim::FormatU8));
+
+        let (term, _) = context.elab_term(&scope, &surface_term, &mut |m| panic!("{m:?}"));
+
+        let prim_head = |term: &core::Term<'_>| match term {
+            core::Term::FunApp(_, _, head, _) => match head {
+                core::Term::FunApp(_, _, core::Term::Prim(_, prim), _) => Some(*prim),
+                core::Term::Prim(_, prim) => Some(*prim),
+                _ => None,
+            },
+            core::Term::Prim(_, prim) => Some(*prim),
+            _ => None,
+        };
+        match term {
+            core::Term::FunLit(
+                _,
+                _,
+                _,
+                core::Term::FunLit(_, _, _, core::Term::RecordLit(_, _, exprs)),
+            ) => {
+                let prims = exprs.iter().map(prim_head).collect::<Vec<_>>();
+                assert_eq!(
+                    prims,
+                    [
+                        Some(Prim::U8Add),
+                        Some(Prim::BoolNot),
+                        Some(Prim::FormatRepeatLen8),
+                        Some(Prim::FormatU8),
+                    ],
+                );
+            }
+            term => panic!("unexpected term: {term:?}"),
+        }
+
+        // Primitive names can also be removed
+        assert!(context.prim_env_mut().remove_name(byte_name).is_some());
+        assert!(context.prim_env_mut().get_name(byte_name).is_none());
+    }
+
+    /// Elaborate a module, panicking on errors, and returning any warnings.
+    fn elab_module<'arena>(
+        interner: &RefCell<StringInterner>,
+        scope: &'arena Scope<'arena>,
+        source: &str,
+    ) -> (core::Module<'arena>, Vec<Message>) {
+        let surface_module = parse_module(interner, scope, source);
+        let mut context = Context::new(testing::file_id(), interner, scope, ItemEnv::new());
+        let mut warnings = Vec::new();
+        let module = context.elab_module(scope, &surface_module, &mut |m| match m
+            .to_diagnostic(interner)
+            .severity
+        {
+            Severity::Error | Severity::Bug => panic!("{m:?}"),
+            _ => warnings.push(m),
+        });
+        (module, warnings)
+    }
+
+    #[test]
+    fn unused_format_fields_are_reported() {
+        const SOURCE: &str = r#"
+            def header = {
+                len <- u8,
+                version <- u16be,
+                data <- repeat_len8 len u8,
+                _reserved <- u8,
+            };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let surface_module = parse_module(&interner, &scope, SOURCE);
+
+        let mut context = Context::new(testing::file_id(), &interner, &scope, ItemEnv::new());
+        context.set_warn_unused_fields(true);
+        let mut messages = Vec::new();
+        context.elab_module(&scope, &surface_module, &mut |m| messages.push(m));
+
+        // `len` is referenced by `data`, `data` is not fixed-size, and
+        // `_reserved` is exempt, leaving only `version` to be reported
+        let version = interner.borrow_mut().get_or_intern("version");
+        assert!(
+            matches!(&messages[..], [Message::UnusedFormatField { name, .. }] if *name == version),
+            "{messages:?}",
+        );
+    }
+
+    #[test]
+    fn unused_let_bindings_are_reported_in_source_order() {
+        const SOURCE: &str = r#"
+            def f : U8 = let x : U8 = 1; let y : U8 = 2; let z : U8 = 3; z;
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (_, warnings) = elab_module(&interner, &scope, SOURCE);
+        let names = (warnings.iter())
+            .map(|warning| match warning {
+                Message::UnusedLetBinding { name, .. } => *name,
+                warning => panic!("unexpected warning: {warning:?}"),
+            })
+            .collect::<Vec<_>>();
+        let [x, y] = ["x", "y"].map(|name| interner.borrow_mut().get_or_intern(name));
+        assert_eq!(names, [x, y]);
+    }
+
+    #[test]
+    fn explicit_distillation_elaborates_to_equal_terms() {
+        const SOURCE: &str = r#"
+            def len : U8 = 3;
+            def f (n : U8) (xs : Array8 n U8) : _ = fun (n : U16) (ys : Array16 n U8) => xs;
+            def g (x : U8) : U8 = let len : U8 = x + len; len * 2;
+            def header = { len <- u8, data <- repeat_len8 len u8 };
+        "#;
+
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let (module, warnings) = elab_module(&interner, &scope, SOURCE);
+        assert!(warnings.is_empty(), "{warnings:?}");
+
+        let mut context = Context::new(testing::file_id(), &interner, &scope, ItemEnv::new());
+        for item in module.items {
+            let core::Item::Def {
+                label,
+                r#type,
+                expr,
+                ..
+            } = item;
+            let type_value = context.eval_env().eval(r#type);
+            let expr_value = context.eval_env().eval(expr);
+            context
+                .item_env
+                .push_definition(*label, type_value, expr_value);
+        }
+        let mut distillation_context = context.distillation_context(&scope);
+        distillation_context.set_explicit(true);
+        let surface_module = distillation_context.distill_module(&module);
+        let source = pretty::Context::new(&interner, &scope)
+            .module(&surface_module)
+            .pretty(usize::MAX)
+            .to_string();
+
+        // The parameter of the inner function is renamed to avoid capturing
+        // the outer parameter in the type of `f`.
+        assert!(
+            source.contains("fun (n1 : U16) -> Array16 n1 U8 -> Array8 n U8"),
+            "{source}"
+        );
+
+        let (emitted_module, warnings) = elab_module(&interner, &scope, &source);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        // Items are evaluated in order, so that later items can refer to the
+        // values of earlier items. The items of both modules are in the same
+        // order, so their values can be compared in the same item environment.
+        fn eval_items<'arena>(
+            module: &core::Module<'arena>,
+        ) -> (
+            UniqueEnv<ArcValue<'arena>>,
+            Vec<(ArcValue<'arena>, ArcValue<'arena>)>,
+        ) {
+            let mut item_exprs = UniqueEnv::new();
+            let meta_exprs = UniqueEnv::new();
+            let mut items = Vec::new();
+            for core::Item::Def { r#type, expr, .. } in module.items {
+                let elim_env = semantics::ElimEnv::new(&item_exprs, &meta_exprs);
+                let mut local_exprs = SharedEnv::new();
+                let mut eval_env = semantics::EvalEnv::new(elim_env, &mut local_exprs);
+                let (r#type, expr) = (eval_env.eval(r#type), eval_env.eval(expr));
+                item_exprs.push(expr.clone());
+                items.push((r#type, expr));
+            }
+            (item_exprs, items)
+        }
+
+        let (item_exprs, items) = eval_items(&module);
+        let (_, emitted_items) = eval_items(&emitted_module);
+        assert_eq!(items.len(), emitted_items.len());
+        let meta_exprs = UniqueEnv::new();
+        let elim_env = semantics::ElimEnv::new(&item_exprs, &meta_exprs);
+        let mut conversion_env = semantics::ConversionEnv::new(elim_env, EnvLen::new());
+        for ((r#type, expr), (emitted_type, emitted_expr)) in items.iter().zip(&emitted_items) {
+            assert!(conversion_env.is_equal(r#type, emitted_type));
+            assert!(conversion_env.is_equal(expr, emitted_expr));
+        }
+    }
 
     #[test]
     #[cfg(target_pointer_width = "64")]
     fn checked_pattern_size() {
-        assert_eq!(std::mem::size_of::<CheckedPattern>(), 32);
+        assert_eq!(std::mem::size_of::<CheckedPattern>(), 40);
+    }
+
+    #[test]
+    fn arrow_has_no_param_name() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        match elab_term(&interner, &scope, "U8 -> U8") {
+            core::Term::FunType(_, Plicity::Explicit, None, param_type, body_type) => {
+                assert!(matches!(param_type, core::Term::Prim(_, Prim::U8Type)));
+                assert!(matches!(body_type, core::Term::Prim(_, Prim::U8Type)));
+            }
+            term => panic!("unexpected term: {term:?}"),
+        }
+    }
+
+    #[test]
+    fn placeholder_binders_have_no_name() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+
+        match elab_term(&interner, &scope, "(fun _ => Type) : U8 -> Type") {
+            core::Term::Ann(_, core::Term::FunLit(_, Plicity::Explicit, None, body_expr), _) => {
+                assert!(matches!(body_expr, core::Term::Universe(_)));
+            }
+            term => panic!("unexpected term: {term:?}"),
+        }
+
+        match elab_term(&interner, &scope, "let _ : U8 = 1; Type") {
+            core::Term::Let(_, None, _, _, body_expr) => {
+                assert!(matches!(body_expr, core::Term::Universe(_)));
+            }
+            term => panic!("unexpected term: {term:?}"),
+        }
+    }
+
+    #[test]
+    fn arrow_is_right_associative() {
+        assert_normalizes("U8 -> U16 -> U32", "U8 -> U16 -> U32");
+        assert_normalizes("(U8 -> U16) -> U32", "(U8 -> U16) -> U32");
+    }
+
+    #[test]
+    fn grouped_params() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let x = interner.borrow_mut().get_or_intern("x");
+        let y = interner.borrow_mut().get_or_intern("y");
+
+        match elab_term(&interner, &scope, "fun (x y : U8) -> U8") {
+            core::Term::FunType(_, _, Some(name), param_type, body_type) => {
+                assert_eq!(name, x);
+                assert!(matches!(param_type, core::Term::Prim(_, Prim::U8Type)));
+                match body_type {
+                    core::Term::FunType(_, _, Some(name), param_type, body_type) => {
+                        assert_eq!(*name, y);
+                        assert!(matches!(param_type, core::Term::Prim(_, Prim::U8Type)));
+                        assert!(matches!(body_type, core::Term::Prim(_, Prim::U8Type)));
+                    }
+                    term => panic!("unexpected term: {term:?}"),
+                }
+            }
+            term => panic!("unexpected term: {term:?}"),
+        }
     }
 }
@@ -0,0 +1,417 @@
+//! Incremental elaboration of modules.
+//!
+//! Editor integrations elaborate a module again each time it changes, but most
+//! edits only affect a small number of items. A [`ModuleCache`] retains the
+//! elaborated items of a module between elaborations, keyed by a hash of the
+//! source of each item and of the items it depends on, allowing unchanged items
+//! to be reused without elaborating them again. Items that were only moved by
+//! edits elsewhere in the file are reused with their spans moved along with
+//! them.
+
+use std::hash::{Hash, Hasher};
+
+use codespan_reporting::diagnostic::Severity;
+use fxhash::{FxHashMap, FxHasher64};
+
+use scoped_arena::Scope;
+
+use crate::core;
+use crate::core::visit::TermFolder;
+use crate::env::{self, Level};
+use crate::files::FileId;
+use crate::source::{BytePos, ByteRange, FileRange, Span, StringId};
+use crate::surface::elaboration::reporting::Message;
+use crate::surface::elaboration::{order, Context, MetaSource};
+use crate::surface::{Item, Module};
+
+/// Elaborated items, retained between elaborations of a module.
+///
+/// Items are identified by their names, and are reused if their source, the
+/// items they depend on, and the names of the primitives in scope are
+/// unchanged.
+#[derive(Default)]
+pub struct ModuleCache<'arena> {
+    entries: FxHashMap<StringId, CacheEntry<'arena>>,
+    /// The items that were elaborated during the last elaboration, rather
+    /// than being reused from the cache.
+    elaborated_items: Vec<StringId>,
+}
+
+struct CacheEntry<'arena> {
+    /// A hash of the item's source, and of the items it depends on.
+    key: u64,
+    /// The range of the item in the source that it was elaborated from.
+    range: ByteRange,
+    r#type: &'arena core::Term<'arena>,
+    expr: &'arena core::Term<'arena>,
+    /// Messages that were produced while elaborating the item.
+    messages: Vec<Message>,
+}
+
+impl<'arena> ModuleCache<'arena> {
+    /// Construct a new, empty cache.
+    pub fn new() -> ModuleCache<'arena> {
+        ModuleCache::default()
+    }
+
+    /// The items that were elaborated, rather than reused from the cache, by
+    /// the last call to [`Context::elab_module_incremental`], in elaboration
+    /// order.
+    pub fn elaborated_items(&self) -> &[StringId] {
+        &self.elaborated_items
+    }
+}
+
+impl<'interner, 'arena> Context<'interner, 'arena> {
+    /// Elaborate a module, reusing the items in `cache` that are unaffected by
+    /// changes made since the module was last elaborated with it.
+    ///
+    /// The resulting module is the same as the one returned by
+    /// [`Context::elab_module`], other than the numbering of any unsolved
+    /// metavariables. Items are only added to the cache if they were
+    /// elaborated without errors, and without leaving metavariables to be
+    /// solved by later items.
+    pub fn elab_module_incremental(
+        &mut self,
+        source: &str,
+        surface_module: &Module<'_, ByteRange>,
+        cache: &mut ModuleCache<'arena>,
+        on_message: &mut dyn FnMut(Message),
+    ) -> core::Module<'arena> {
+        let (elab_order, item_deps) =
+            order::elaboration_order_with_dependencies(self, surface_module);
+        let mut items = Vec::with_capacity(surface_module.items.len());
+        self.item_env.reserve(surface_module.items.len());
+
+        // Entries for items that are no longer in the module are discarded.
+        let mut old_entries = std::mem::take(&mut cache.entries);
+        cache.elaborated_items.clear();
+        // The levels of the items elaborated so far, along with their cache
+        // keys if they were cached.
+        let mut item_keys = FxHashMap::default();
+        let prims_key = self.prims_key();
+
+        for index in elab_order {
+            let item = match &surface_module.items[index] {
+                Item::Def(item) => item,
+                Item::ReportedError(_) => continue,
+            };
+            let label = item.label.1;
+            let level = self.item_env.names.len().next_level();
+            let key = self.item_key(source, item.range, prims_key, &item_deps[index], &item_keys);
+
+            let cached_entry = old_entries.remove(&label);
+            let (r#type, expr, key) = match (key, cached_entry) {
+                (Some(key), Some(mut entry)) if entry.key == key => {
+                    if entry.range != item.range {
+                        self.move_entry(&mut entry, item.range);
+                    }
+                    let (r#type, expr) = (entry.r#type, entry.expr);
+                    self.messages.extend(entry.messages.iter().cloned());
+                    cache.entries.insert(label, entry);
+                    (r#type, expr, Some(key))
+                }
+                (key, _) => {
+                    cache.elaborated_items.push(label);
+
+                    let initial_meta_len = self.meta_env.exprs.len();
+                    let initial_messages_len = self.messages.len();
+                    let initial_holes_len = self.holes.len();
+                    let (expr, r#type) =
+                        self.synth_fun_lit(item.range, item.params, item.expr, item.r#type);
+
+                    let is_cacheable =
+                        (self.messages[initial_messages_len..].iter()).all(|message| {
+                            message.to_diagnostic(self.interner).severity < Severity::Error
+                        }) && self.holes.len() == initial_holes_len
+                            && self.are_metas_solved(initial_meta_len.next_level());
+
+                    match key.filter(|_| is_cacheable) {
+                        Some(key) => {
+                            let scope = self.scope;
+                            let r#type = self.eval_env().unfold_metas(scope, &r#type);
+                            let expr = self.eval_env().unfold_metas(scope, &expr);
+                            let entry = CacheEntry {
+                                key,
+                                range: item.range,
+                                r#type: self.scope.to_scope(r#type),
+                                expr: self.scope.to_scope(expr),
+                                messages: self.messages[initial_messages_len..].to_vec(),
+                            };
+                            let (r#type, expr) = (entry.r#type, entry.expr);
+                            cache.entries.insert(label, entry);
+                            (r#type, expr, Some(key))
+                        }
+                        None => {
+                            let r#type = self.scope.to_scope(r#type);
+                            let expr = self.scope.to_scope(expr);
+                            (&*r#type, &*expr, None)
+                        }
+                    }
+                }
+            };
+
+            item_keys.insert(label, (level, key));
+
+            let expr_value = self.eval_env().eval(expr);
+            let type_value = self.eval_env().eval(r#type);
+            self.item_env.push_definition(label, type_value, expr_value);
+            items.push(core::Item::Def {
+                doc: self.scope.to_scope_from_iter(item.doc.iter().copied()),
+                label,
+                r#type,
+                expr,
+            });
+        }
+
+        // Unfold all unification solutions
+        let scope = self.scope;
+        let items = scope.to_scope_from_iter(items.into_iter().map(|item| match item {
+            core::Item::Def {
+                doc,
+                label,
+                r#type,
+                expr,
+            } => {
+                let r#type = self.eval_env().unfold_metas(scope, r#type);
+                let expr = self.eval_env().unfold_metas(scope, expr);
+
+                core::Item::Def {
+                    doc: scope.to_scope_from_iter(doc.iter().copied()),
+                    label,
+                    r#type: scope.to_scope(r#type),
+                    expr: scope.to_scope(expr),
+                }
+            }
+        }));
+
+        self.handle_messages(on_message);
+
+        core::Module { items }
+    }
+
+    /// Compute a hash of the names of the primitives in scope, which can be
+    /// changed by embedders between elaborations.
+    fn prims_key(&self) -> u64 {
+        let mut names: Vec<_> = self.prim_env.names().collect();
+        names.sort_unstable_by_key(|(name, _)| *name);
+
+        let mut hasher = FxHasher64::default();
+        names.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Compute the cache key of an item, returning `None` if any of the items
+    /// it depends on could not be cached.
+    ///
+    /// The key does not depend on the position of the item in the source, so
+    /// that items can be reused after edits earlier in the file.
+    fn item_key(
+        &self,
+        source: &str,
+        range: ByteRange,
+        prims_key: u64,
+        deps: &[StringId],
+        item_keys: &FxHashMap<StringId, (Level, Option<u64>)>,
+    ) -> Option<u64> {
+        let mut hasher = FxHasher64::default();
+        self.file_id.hash(&mut hasher);
+        self.default_endianness.hash(&mut hasher);
+        prims_key.hash(&mut hasher);
+        source.get(std::ops::Range::from(range))?.hash(&mut hasher);
+
+        for dep in deps {
+            let (level, key) = item_keys.get(dep)?;
+            (dep, level, (*key)?).hash(&mut hasher);
+        }
+
+        Some(hasher.finish())
+    }
+
+    /// Move the spans of a cached item and its messages to the item's new
+    /// range in the source.
+    fn move_entry(&self, entry: &mut CacheEntry<'arena>, range: ByteRange) {
+        let mut mover = MoveSpans {
+            scope: self.scope,
+            file_id: self.file_id,
+            old_range: entry.range,
+            new_start: range.start(),
+        };
+
+        entry.r#type = self.scope.to_scope(mover.fold_term(entry.r#type));
+        entry.expr = self.scope.to_scope(mover.fold_term(entry.expr));
+        for message in &mut entry.messages {
+            message.map_ranges(&mut |range| mover.move_range(range));
+        }
+        entry.range = range;
+    }
+
+    /// Returns `true` if the metavariables created since `initial_level` are
+    /// all solved, and are not named holes.
+    fn are_metas_solved(&self, initial_level: Level) -> bool {
+        let mut metas = Iterator::zip(env::levels(), self.meta_env.exprs.iter())
+            .zip(self.meta_env.sources.iter())
+            .skip_while(|((level, _), _)| *level < initial_level);
+
+        metas.all(|((_, expr), source)| {
+            expr.is_some() && !matches!(source, MetaSource::HoleExpr(_, _))
+        })
+    }
+}
+
+/// Moves the spans within an item from its old range in the source to a new
+/// starting position.
+struct MoveSpans<'arena> {
+    scope: &'arena Scope<'arena>,
+    file_id: FileId,
+    old_range: ByteRange,
+    new_start: BytePos,
+}
+
+impl<'arena> MoveSpans<'arena> {
+    fn move_range(&self, range: &mut FileRange) {
+        let (start, end) = (range.start(), range.end());
+        let old_start = self.old_range.start();
+
+        // Spans outside of the item, for example in the definitions of other
+        // items, are left where they are.
+        if range.file_id() == self.file_id && old_start <= start && end <= self.old_range.end() {
+            let byte_range = ByteRange::new(
+                start - old_start + self.new_start,
+                end - old_start + self.new_start,
+            );
+            *range = FileRange::new(self.file_id, byte_range);
+        }
+    }
+}
+
+impl<'arena> TermFolder<'arena, 'arena> for MoveSpans<'arena> {
+    fn scope(&self) -> &'arena Scope<'arena> {
+        self.scope
+    }
+
+    fn fold_span(&mut self, span: Span) -> Span {
+        match span {
+            Span::Range(mut range) => {
+                self.move_range(&mut range);
+                Span::Range(range)
+            }
+            Span::Empty => Span::Empty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use scoped_arena::Scope;
+
+    use super::*;
+    use crate::files::{FileId, Files};
+    use crate::source::StringInterner;
+    use crate::surface::elaboration::ItemEnv;
+    use crate::testing::parse_module;
+
+    const SOURCE: &str = r#"
+        def len : U8 = 3;
+        def other : U8 = let unused : U8 = 7; 7;
+        def data = repeat_len8 len u8;
+        def main = { data <- data };
+    "#;
+
+    /// Elaborate a module, returning the pretty printed result, along with
+    /// the elaborated terms and messages including their spans.
+    fn elab_module<'arena>(
+        interner: &RefCell<StringInterner>,
+        scope: &'arena Scope<'arena>,
+        file_id: FileId,
+        source: &str,
+        cache: Option<&mut ModuleCache<'arena>>,
+    ) -> (String, String) {
+        let surface_scope = Scope::new();
+        let surface_module = parse_module(interner, &surface_scope, source);
+        let mut context = Context::new(file_id, interner, scope, ItemEnv::new());
+        let mut messages = Vec::new();
+        let on_message = &mut |message| messages.push(message);
+        let module = match cache {
+            Some(cache) => {
+                context.elab_module_incremental(source, &surface_module, cache, on_message)
+            }
+            None => context.elab_module(scope, &surface_module, on_message),
+        };
+
+        let pretty_context = core::pretty::Context::new(interner);
+        let doc = pretty_context.module(&module);
+        let mut spanned = format!("{messages:?}");
+        for item in module.items {
+            let core::Item::Def {
+                label,
+                r#type,
+                expr,
+                ..
+            } = item;
+            spanned.push_str(&format!("\n{label:?}: {type:?} = {expr:?}"));
+        }
+
+        (doc.pretty(80).to_string(), spanned)
+    }
+
+    #[test]
+    fn editing_an_item_invalidates_its_dependents() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let mut files = Files::new();
+        let file_id = files.add("test.fathom", SOURCE.to_owned());
+        let [len, other, data, main] =
+            ["len", "other", "data", "main"].map(|name| interner.borrow_mut().get_or_intern(name));
+        let mut cache = ModuleCache::new();
+
+        let mut check = |source: &str, elaborated_items: &[StringId]| {
+            let output = elab_module(&interner, &scope, file_id, source, Some(&mut cache));
+            assert_eq!(cache.elaborated_items(), elaborated_items);
+            assert_eq!(
+                output,
+                elab_module(&interner, &Scope::new(), file_id, source, None)
+            );
+        };
+
+        check(SOURCE, &[len, other, data, main]);
+        check(SOURCE, &[]);
+
+        let edited = SOURCE.replace("U8 = 3", "U8 = 4");
+        check(&edited, &[len, data, main]);
+
+        let edited = edited.replace("U8 = 7", "U8 = 8");
+        check(&edited, &[other]);
+    }
+
+    #[test]
+    fn moved_items_are_reused_with_their_spans_moved() {
+        let interner = RefCell::new(StringInterner::new());
+        let scope = Scope::new();
+        let mut files = Files::new();
+        let file_id = files.add("test.fathom", SOURCE.to_owned());
+        let [len, other, data, main] =
+            ["len", "other", "data", "main"].map(|name| interner.borrow_mut().get_or_intern(name));
+        let mut cache = ModuleCache::new();
+
+        let mut check = |source: &str, elaborated_items: &[StringId]| {
+            let output = elab_module(&interner, &scope, file_id, source, Some(&mut cache));
+            assert_eq!(cache.elaborated_items(), elaborated_items);
+            assert_eq!(
+                output,
+                elab_module(&interner, &Scope::new(), file_id, source, None)
+            );
+        };
+
+        check(SOURCE, &[len, other, data, main]);
+
+        // `other` moves, along with the warning about its unused binding
+        let edited = SOURCE.replace("U8 = 3", "U8 = 30");
+        check(&edited, &[len, data, main]);
+
+        let edited = format!("\n\n{edited}");
+        check(&edited, &[]);
+    }
+}
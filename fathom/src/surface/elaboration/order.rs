@@ -32,11 +32,21 @@ pub fn elaboration_order(
     elab_context: &mut elaboration::Context,
     surface_module: &Module<'_, ByteRange>,
 ) -> Vec<usize> {
+    elaboration_order_with_dependencies(elab_context, surface_module).0
+}
+
+/// Determine the elaboration order of the items in a module, along with the
+/// names of the items that each item depends on.
+pub fn elaboration_order_with_dependencies(
+    elab_context: &mut elaboration::Context,
+    surface_module: &Module<'_, ByteRange>,
+) -> (Vec<usize>, Vec<Vec<StringId>>) {
     let item_names = item_names(surface_module);
     let item_deps = collect_item_dependencies(surface_module, &item_names);
 
     let context = ModuleOrderContext::new(elab_context);
-    context.determine_order(surface_module.items, &item_names, &item_deps)
+    let order = context.determine_order(surface_module.items, &item_names, &item_deps);
+    (order, item_deps)
 }
 
 fn item_names(surface_module: &Module<'_, ByteRange>) -> FxHashMap<StringId, usize> {
@@ -192,6 +202,15 @@ fn term_deps(
             term_deps(body_expr, item_names, local_names, deps);
             pop_pattern(pattern, local_names);
         }
+        Term::LetRecord(_, def, body_expr) => {
+            if let Some(r#type) = &def.r#type {
+                term_deps(r#type, item_names, local_names, deps);
+            }
+            term_deps(&def.expr, item_names, local_names, deps);
+            local_names.extend(def.labels.iter().map(|(_, label)| *label));
+            term_deps(body_expr, item_names, local_names, deps);
+            local_names.truncate(local_names.len() - def.labels.len());
+        }
         Term::If(_, cond_expr, then_expr, else_expr) => {
             term_deps(cond_expr, item_names, local_names, deps);
             term_deps(then_expr, item_names, local_names, deps);
@@ -247,6 +266,12 @@ fn term_deps(
         Term::Tuple(_, terms) => terms
             .iter()
             .for_each(|term| term_deps(term, item_names, local_names, deps)),
+        Term::RecordUpdate(_, head_expr, expr_fields) => {
+            term_deps(head_expr, item_names, local_names, deps);
+            for expr_field in *expr_fields {
+                term_deps(&expr_field.expr, item_names, local_names, deps);
+            }
+        }
         Term::Proj(_, head_expr, _) => {
             term_deps(head_expr, item_names, local_names, deps);
         }
@@ -277,6 +302,7 @@ fn term_deps(
         | Term::StringLiteral(_, _)
         | Term::NumberLiteral(_, _)
         | Term::BooleanLiteral(_, _)
+        | Term::ElidedElems(_, _)
         | Term::ReportedError(_) => {}
     }
 }
@@ -334,6 +360,7 @@ fn push_pattern(pattern: &Pattern<ByteRange>, local_names: &mut Vec<StringId>) {
         Pattern::Placeholder(_) => {}
         Pattern::StringLiteral(_, _) => {}
         Pattern::NumberLiteral(_, _) => {}
+        Pattern::NumberRange(_, _, _) => {}
         Pattern::BooleanLiteral(_, _) => {}
     }
 }
@@ -346,6 +373,7 @@ fn pop_pattern(pattern: &Pattern<ByteRange>, local_names: &mut Vec<StringId>) {
         Pattern::Placeholder(_) => {}
         Pattern::StringLiteral(_, _) => {}
         Pattern::NumberLiteral(_, _) => {}
+        Pattern::NumberRange(_, _, _) => {}
         Pattern::BooleanLiteral(_, _) => {}
     }
 }
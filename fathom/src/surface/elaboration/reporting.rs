@@ -3,8 +3,9 @@ use std::cell::RefCell;
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use itertools::Itertools;
 
+use crate::core::semantics::{Mismatch, MismatchStep};
 use crate::files::FileId;
-use crate::source::{FileRange, StringId, StringInterner};
+use crate::source::{FileRange, Span, StringId, StringInterner};
 use crate::surface::elaboration::{unification, MetaSource};
 use crate::surface::{BinOp, Plicity};
 use crate::BUG_REPORT_URL;
@@ -18,6 +19,12 @@ pub enum Message {
         name: StringId,
         suggestion: Option<StringId>,
     },
+    /// An endianness-agnostic number format was used without a default
+    /// endianness.
+    MissingDefaultEndianness {
+        range: FileRange,
+        name: StringId,
+    },
     RefutablePattern {
         pattern_range: FileRange,
     },
@@ -28,6 +35,21 @@ pub enum Message {
     UnreachablePattern {
         range: FileRange,
     },
+    /// A let binding shadows a name that is already in scope.
+    ShadowedBinding {
+        range: FileRange,
+        prev_range: Option<FileRange>,
+        name: StringId,
+    },
+    /// A let-bound name was never referenced in its body.
+    UnusedLetBinding {
+        range: FileRange,
+        name: StringId,
+    },
+    UnusedFormatField {
+        range: FileRange,
+        name: StringId,
+    },
     UnexpectedParameter {
         param_range: FileRange,
     },
@@ -61,10 +83,27 @@ pub enum Message {
         range: FileRange,
         labels: Vec<(FileRange, StringId)>,
     },
+    MismatchedOverlapSizes {
+        range: FileRange,
+        field_sizes: Vec<(FileRange, StringId, u64)>,
+    },
+    /// The representation of a format is `Void`, so it can never be read.
+    UninhabitedFormat {
+        range: FileRange,
+    },
+    /// The predicate of a conditional format always evaluates to `false`.
+    FalseFormatCondition {
+        range: FileRange,
+        pred_range: FileRange,
+    },
     ArrayLiteralNotSupported {
         range: FileRange,
         expected_type: String,
     },
+    RecordPatternNotSupported {
+        range: FileRange,
+        expected_type: String,
+    },
     MismatchedArrayLength {
         range: FileRange,
         found_len: usize,
@@ -99,15 +138,41 @@ pub enum Message {
     AmbiguousNumericLiteral {
         range: FileRange,
     },
+    RangePatternNotSupported {
+        range: FileRange,
+        expected_type: String,
+    },
+    EmptyRangePattern {
+        range: FileRange,
+    },
+    OverlappingRangePattern {
+        range: FileRange,
+    },
     BooleanLiteralNotSupported {
         range: FileRange,
     },
+    /// Elided array elements were elaborated. These are only produced when
+    /// distilling terms for display.
+    ElidedElemsNotSupported {
+        range: FileRange,
+    },
     /// Unification errors.
     FailedToUnify {
         range: FileRange,
         found: String,
         expected: String,
         error: unification::Error,
+        /// Where the found and expected types differ, if this is known.
+        mismatch: Option<Mismatch>,
+        /// The type annotation that the expected type came from, if any.
+        annotation: Option<FileRange>,
+    },
+    /// A metavariable would have to be solved with a value containing itself.
+    InfiniteSolution {
+        range: FileRange,
+        source: MetaSource,
+        found: String,
+        expected: String,
     },
     BinOpMismatchedTypes {
         range: FileRange,
@@ -120,8 +185,8 @@ pub enum Message {
     /// A solution for a metavariable could not be found.
     UnsolvedMetaVar {
         source: MetaSource,
-        // TODO: add type
-        // type: Doc<_>,
+        expr: String,
+        r#type: String,
     },
     HoleSolution {
         range: FileRange,
@@ -130,6 +195,13 @@ pub enum Message {
         // type: Doc<_>,
         expr: String,
     },
+    /// The inferred type of a hole, along with the local bindings in scope.
+    HoleType {
+        range: FileRange,
+        name: StringId,
+        r#type: String,
+        locals: Vec<(Option<StringId>, String)>,
+    },
     /// A cycle between module items was detected.
     CycleDetected {
         names: Vec<StringId>,
@@ -141,6 +213,127 @@ pub enum Message {
 }
 
 impl Message {
+    /// Apply a function to each of the source ranges in the message, for
+    /// example to move the message along with the item it was reported in.
+    pub fn map_ranges(&mut self, f: &mut dyn FnMut(&mut FileRange)) {
+        match self {
+            Message::UnboundName { range, .. }
+            | Message::MissingDefaultEndianness { range, .. }
+            | Message::UnreachablePattern { range }
+            | Message::UnusedLetBinding { range, .. }
+            | Message::UnusedFormatField { range, .. }
+            | Message::UninhabitedFormat { range }
+            | Message::ArrayLiteralNotSupported { range, .. }
+            | Message::RecordPatternNotSupported { range, .. }
+            | Message::MismatchedArrayLength { range, .. }
+            | Message::AmbiguousArrayLiteral { range }
+            | Message::AmbiguousStringLiteral { range }
+            | Message::MismatchedStringLiteralByteLength { range, .. }
+            | Message::StringLiteralNotSupported { range, .. }
+            | Message::InvalidNumericLiteral { range, .. }
+            | Message::NumericLiteralNotSupported { range, .. }
+            | Message::AmbiguousNumericLiteral { range }
+            | Message::RangePatternNotSupported { range, .. }
+            | Message::EmptyRangePattern { range }
+            | Message::OverlappingRangePattern { range }
+            | Message::BooleanLiteralNotSupported { range }
+            | Message::ElidedElemsNotSupported { range }
+            | Message::HoleSolution { range, .. }
+            | Message::HoleType { range, .. }
+            | Message::MissingSpan { range }
+            | Message::RefutablePattern {
+                pattern_range: range,
+            }
+            | Message::UnexpectedParameter { param_range: range }
+            | Message::NonAsciiStringLiteral {
+                invalid_range: range,
+            } => f(range),
+            Message::NonExhaustiveMatchExpr {
+                match_expr_range,
+                scrutinee_expr_range,
+            } => {
+                f(match_expr_range);
+                f(scrutinee_expr_range);
+            }
+            Message::ShadowedBinding {
+                range, prev_range, ..
+            } => {
+                f(range);
+                prev_range.iter_mut().for_each(f);
+            }
+            Message::UnexpectedArgument {
+                head_range,
+                arg_range,
+                ..
+            }
+            | Message::PlicityArgumentMismatch {
+                head_range,
+                arg_range,
+                ..
+            }
+            | Message::UnknownField {
+                head_range,
+                label_range: arg_range,
+                ..
+            }
+            | Message::FalseFormatCondition {
+                range: head_range,
+                pred_range: arg_range,
+            } => {
+                f(head_range);
+                f(arg_range);
+            }
+            Message::MismatchedFieldLabels {
+                range,
+                expr_labels: labels,
+                ..
+            }
+            | Message::DuplicateFieldLabels { range, labels } => {
+                f(range);
+                labels.iter_mut().for_each(|(range, _)| f(range));
+            }
+            Message::MismatchedOverlapSizes { range, field_sizes } => {
+                f(range);
+                field_sizes.iter_mut().for_each(|(range, _, _)| f(range));
+            }
+            Message::FailedToUnify {
+                range,
+                mismatch,
+                annotation,
+                ..
+            } => {
+                f(range);
+                if let Some(mismatch) = mismatch {
+                    let (found_span, expected_span) = &mut mismatch.spans;
+                    for span in [found_span, expected_span] {
+                        if let Span::Range(range) = span {
+                            f(range);
+                        }
+                    }
+                }
+                annotation.iter_mut().for_each(f);
+            }
+            Message::InfiniteSolution { range, source, .. } => {
+                f(range);
+                f(source.range_mut());
+            }
+            Message::BinOpMismatchedTypes {
+                range,
+                lhs_range,
+                rhs_range,
+                op,
+                ..
+            } => {
+                f(range);
+                f(lhs_range);
+                f(rhs_range);
+                f(op.range_mut());
+            }
+            Message::UnsolvedMetaVar { source, .. } => f(source.range_mut()),
+            Message::CycleDetected { .. } => {}
+        }
+    }
+
     pub fn to_diagnostic(&self, interner: &RefCell<StringInterner>) -> Diagnostic<FileId> {
         let primary_label = |range: &FileRange| Label::primary(range.file_id(), *range);
         let secondary_label = |range: &FileRange| Label::secondary(range.file_id(), *range);
@@ -166,6 +359,19 @@ impl Message {
                 }
                 diagnostic
             }
+            Message::MissingDefaultEndianness { range, name } => {
+                let interner = interner.borrow();
+                let name = interner.resolve(*name).unwrap();
+
+                Diagnostic::error()
+                    .with_message(format!("no default endianness for `{name}`"))
+                    .with_labels(vec![
+                        primary_label(range).with_message("endianness-agnostic number format")
+                    ])
+                    .with_notes(vec![format!(
+                        "help: use `{name}be` or `{name}le`, or set a default endianness"
+                    )])
+            }
             Message::RefutablePattern { pattern_range } => Diagnostic::error()
                 .with_message("refutable patterns found in binding")
                 .with_labels(vec![
@@ -184,6 +390,50 @@ impl Message {
             Message::UnreachablePattern { range } => Diagnostic::warning()
                 .with_message("unreachable pattern")
                 .with_labels(vec![primary_label(range)]),
+            Message::ShadowedBinding {
+                range,
+                prev_range,
+                name,
+            } => {
+                let interner = interner.borrow();
+                let name = interner.resolve(*name).unwrap();
+
+                let mut labels = vec![primary_label(range).with_message("shadowing binding")];
+                if let Some(prev_range) = prev_range {
+                    labels.push(secondary_label(prev_range).with_message("previous binding"));
+                }
+
+                Diagnostic::warning()
+                    .with_message(format!("`{name}` shadows an existing binding"))
+                    .with_labels(labels)
+                    .with_notes(vec![
+                        "help: consider renaming one of the bindings".to_owned()
+                    ])
+            }
+            Message::UnusedLetBinding { range, name } => {
+                let interner = interner.borrow();
+                let name = interner.resolve(*name).unwrap();
+
+                Diagnostic::warning()
+                    .with_message(format!("unused let binding `{name}`"))
+                    .with_labels(vec![primary_label(range).with_message("unused binding")])
+                    .with_notes(vec![format!(
+                        "help: if this is intentional, prefix it with an underscore: `_{name}`"
+                    )])
+            }
+            Message::UnusedFormatField { range, name } => {
+                let interner = interner.borrow();
+                let name = interner.resolve(*name).unwrap();
+
+                Diagnostic::note()
+                    .with_message(format!("unused format field `{name}`"))
+                    .with_labels(vec![primary_label(range)
+                        .with_message("never referenced by the fields that follow")])
+                    .with_notes(vec![format!(
+                        "help: if this field is only read to skip over its data, prefix it \
+                         with an underscore: `_{name}`"
+                    )])
+            }
             Message::UnexpectedParameter { param_range } => Diagnostic::error()
                 .with_message("too many parameters in function literal")
                 .with_labels(vec![
@@ -195,12 +445,13 @@ impl Message {
                 head_type,
                 arg_range,
             } => Diagnostic::error()
-                .with_message("expression was applied to an unexpected argument")
+                .with_message(format!("cannot apply value of type `{head_type}`"))
                 .with_labels(vec![
                     primary_label(arg_range).with_message("unexpected argument"),
                     secondary_label(head_range)
                         .with_message(format!("expression of type {head_type}")),
-                ]),
+                ])
+                .with_notes(vec!["only functions can be applied to arguments".to_owned()]),
             Message::PlicityArgumentMismatch {
                 head_range,
                 head_plicity,
@@ -326,6 +577,41 @@ impl Message {
                             .format_with(", ", |label, f| f(&format_args!("`{label}`")))
                     )])
             }
+            Message::MismatchedOverlapSizes { range, field_sizes } => {
+                let interner = interner.borrow();
+                let diagnostic_labels = (field_sizes.iter())
+                    .map(|(range, _, size)| {
+                        primary_label(range).with_message(format!("{size} bytes"))
+                    })
+                    .chain(std::iter::once(
+                        secondary_label(range).with_message("the overlap format"),
+                    ))
+                    .collect();
+
+                Diagnostic::error()
+                    .with_message("overlapping fields have different sizes")
+                    .with_labels(diagnostic_labels)
+                    .with_notes(vec![format!(
+                        "field sizes: {}",
+                        (field_sizes.iter()).format_with(", ", |(_, label, size), f| {
+                            let label = interner.resolve(*label).unwrap();
+                            f(&format_args!("`{label}` is {size} bytes"))
+                        })
+                    )])
+            }
+            Message::UninhabitedFormat { range } => Diagnostic::warning()
+                .with_message("format can never succeed")
+                .with_labels(vec![
+                    primary_label(range).with_message("this format is represented as `Void`")
+                ])
+                .with_notes(vec!["reading this format will always fail".to_owned()]),
+            Message::FalseFormatCondition { range, pred_range } => Diagnostic::warning()
+                .with_message("format condition is always false")
+                .with_labels(vec![
+                    primary_label(pred_range).with_message("this condition is always false"),
+                    secondary_label(range).with_message("the conditional format"),
+                ])
+                .with_notes(vec!["reading this format will always fail".to_owned()]),
             Message::ArrayLiteralNotSupported {
                 range,
                 expected_type,
@@ -335,6 +621,15 @@ impl Message {
                     primary_label(range).with_message(format!("expected `{expected_type}`"))
                 ])
                 .with_notes(vec![format!("expected `{expected_type}`")]),
+            Message::RecordPatternNotSupported {
+                range,
+                expected_type,
+            } => Diagnostic::error()
+                .with_message("record pattern not supported")
+                .with_labels(vec![
+                    primary_label(range).with_message(format!("expected `{expected_type}`"))
+                ])
+                .with_notes(vec![format!("expected `{expected_type}`")]),
             Message::MismatchedArrayLength {
                 range,
                 found_len,
@@ -402,6 +697,27 @@ impl Message {
                 .with_labels(vec![
                     primary_label(range).with_message("type annotations needed")
                 ]),
+            Message::RangePatternNotSupported {
+                range,
+                expected_type,
+            } => Diagnostic::error()
+                .with_message("range pattern not supported")
+                .with_labels(vec![
+                    primary_label(range).with_message(format!("expected `{expected_type}`"))
+                ])
+                .with_notes(vec![
+                    "range patterns are only supported for integer types".to_owned()
+                ]),
+            Message::EmptyRangePattern { range } => Diagnostic::error()
+                .with_message("empty range pattern")
+                .with_labels(vec![
+                    primary_label(range).with_message("lower bound is greater than upper bound")
+                ]),
+            Message::OverlappingRangePattern { range } => Diagnostic::error()
+                .with_message("range pattern overlaps with a previous pattern")
+                .with_labels(vec![
+                    primary_label(range).with_message("overlapping pattern")
+                ]),
             Message::BooleanLiteralNotSupported { range } => Diagnostic::error()
                 .with_message("boolean literal not supported for expected type")
                 .with_labels(vec![primary_label(range)]),
@@ -425,21 +741,50 @@ impl Message {
                 found,
                 expected,
                 error,
+                mismatch,
+                annotation,
             } => {
                 use unification::{Error, RenameError, SpineError};
 
                 // TODO: Make these errors more user-friendly
                 match error {
-                    Error::Mismatch => Diagnostic::error()
-                        .with_message("mismatched types")
-                        .with_labels(vec![primary_label(range).with_message(format!(
+                    Error::Mismatch => {
+                        let mut labels = vec![primary_label(range).with_message(format!(
                             "type mismatch, expected `{expected}`, found `{found}`"
-                        ))])
-                        .with_notes(vec![[
+                        ))];
+                        let mut notes = vec![[
                             format!("expected `{expected}`"),
                             format!("   found `{found}`"),
                         ]
-                        .join("\n")]),
+                        .join("\n")];
+
+                        if let Some(mismatch) = mismatch.as_ref().filter(|m| !m.path.is_empty()) {
+                            let (found_span, expected_span) = mismatch.spans;
+                            if let Span::Range(found_range) = found_span {
+                                labels.push(secondary_label(&found_range).with_message("found"));
+                            }
+                            if let Span::Range(expected_range) = expected_span {
+                                labels.push(
+                                    secondary_label(&expected_range).with_message("expected"),
+                                );
+                            }
+
+                            let path = mismatch_path(&interner.borrow(), &mismatch.path);
+                            notes.push(format!("the types differ in {path}"));
+                        }
+
+                        if let Some(annotation) = annotation {
+                            labels.push(
+                                secondary_label(annotation)
+                                    .with_message("expected due to this annotation"),
+                            );
+                        }
+
+                        Diagnostic::error()
+                            .with_message("mismatched types")
+                            .with_labels(labels)
+                            .with_notes(notes)
+                    }
                     // TODO: reduce confusion around ‘problem spines’
                     Error::Spine(error) => match error {
                         SpineError::NonLinearSpine(_var) => Diagnostic::error()
@@ -459,12 +804,38 @@ impl Message {
                         RenameError::EscapingLocalVar(_var) => Diagnostic::error()
                             .with_message("escaping local variable")
                             .with_labels(vec![primary_label(range)]),
-                        RenameError::InfiniteSolution => Diagnostic::error()
-                            .with_message("infinite solution")
-                            .with_labels(vec![primary_label(range)]),
+                        RenameError::InfiniteSolution(_) => {
+                            unreachable!("reported with `Message::InfiniteSolution`")
+                        }
                     },
                 }
             }
+            Message::InfiniteSolution {
+                range,
+                source,
+                found,
+                expected,
+            } => {
+                let (source_range, source_name) = meta_source_name(source);
+
+                Diagnostic::error()
+                    .with_message("infinite type inferred")
+                    .with_labels(vec![
+                        primary_label(range).with_message(format!(
+                            "occurs check failed, expected `{expected}`, found `{found}`"
+                        )),
+                        secondary_label(source_range)
+                            .with_message(format!("the {source_name} would contain itself")),
+                    ])
+                    .with_notes(vec![
+                        [
+                            format!("expected `{expected}`"),
+                            format!("   found `{found}`"),
+                        ]
+                        .join("\n"),
+                        format!("solving the {source_name} requires an infinitely large type"),
+                    ])
+            }
             Message::HoleSolution { range, name, expr } => {
                 let interner = interner.borrow();
                 let name = interner.resolve(*name).unwrap();
@@ -476,28 +847,41 @@ impl Message {
                         "hole `?{name}` can be replaced with `{expr}`",
                     )])
             }
-            Message::UnsolvedMetaVar { source } => {
-                let (range, source_name) = match source {
-                    MetaSource::ImplicitArg(range, _) => (range, "implicit argument"),
-                    MetaSource::HoleExpr(range, _) => (range, "hole expression"),
-                    MetaSource::PlaceholderExpr(range) => (range, "placeholder expression"),
-                    MetaSource::PlaceholderPatternType(range) => {
-                        (range, "placeholder pattern type")
-                    }
-                    MetaSource::NamedPatternType(range, _) => (range, "named pattern type"),
-                    MetaSource::MatchExprType(range) => (range, "match expression type"),
+            Message::HoleType {
+                range,
+                name,
+                r#type,
+                locals,
+            } => {
+                let interner = interner.borrow();
+                let name = interner.resolve(*name).unwrap();
+                let mut notes = Vec::new();
+                if !locals.is_empty() {
+                    let locals = locals.iter().map(|(name, r#type)| {
+                        let name = name.map_or("_", |name| interner.resolve(name).unwrap());
+                        format!("\n    {name} : {}", r#type)
+                    });
+                    notes.push(format!("bindings in scope:{}", locals.format("")));
+                }
 
-                    // The following should never appear in user-facing output:
-                    MetaSource::HoleType(range, _) => (range, "hole type"),
-                    MetaSource::PlaceholderType(range) => (range, "placeholder type"),
-                    MetaSource::ReportedErrorType(range) => (range, "error type"),
-                };
+                Diagnostic::note()
+                    .with_message(format!("hole `?{name}` has type `{}`", r#type))
+                    .with_labels(vec![primary_label(range).with_message("hole")])
+                    .with_notes(notes)
+            }
+            Message::UnsolvedMetaVar {
+                source,
+                expr,
+                r#type,
+            } => {
+                let (range, source_name) = meta_source_name(source);
 
                 Diagnostic::error()
                     .with_message(format!("failed to infer {source_name}"))
                     .with_labels(vec![
                         primary_label(range).with_message(format!("unsolved {source_name}"))
                     ])
+                    .with_notes(vec![format!("could not infer `{expr} : {}`", r#type)])
             }
             Message::CycleDetected { names } => {
                 let interner = interner.borrow();
@@ -510,6 +894,12 @@ impl Message {
                     .with_message("cycle detected")
                     .with_notes(vec![cycle])
             }
+            Message::ElidedElemsNotSupported { range } => Diagnostic::error()
+                .with_message("elided array elements not supported")
+                .with_labels(vec![primary_label(range)])
+                .with_notes(vec![
+                    "elided array elements are only used when displaying terms".to_owned(),
+                ]),
             Message::MissingSpan { range } => Diagnostic::bug()
                 .with_message("produced core term without span")
                 .with_labels(vec![primary_label(range)])
@@ -519,3 +909,33 @@ impl Message {
         }
     }
 }
+
+/// The range and user-facing description of a metavariable's source.
+fn meta_source_name(source: &MetaSource) -> (&FileRange, &'static str) {
+    match source {
+        MetaSource::ImplicitArg(range, _) => (range, "implicit argument"),
+        MetaSource::HoleExpr(range, _) => (range, "hole expression"),
+        MetaSource::PlaceholderExpr(range) => (range, "placeholder expression"),
+        MetaSource::PlaceholderPatternType(range) => (range, "placeholder pattern type"),
+        MetaSource::NamedPatternType(range, _) => (range, "named pattern type"),
+        MetaSource::MatchExprType(range) => (range, "match expression type"),
+
+        // The following should never appear in user-facing output:
+        MetaSource::HoleType(range, _) => (range, "hole type"),
+        MetaSource::PlaceholderType(range) => (range, "placeholder type"),
+        MetaSource::ReportedErrorType(range) => (range, "error type"),
+    }
+}
+
+/// Describe the location of a type mismatch, from the innermost step outwards.
+fn mismatch_path(interner: &StringInterner, path: &[MismatchStep]) -> String {
+    let steps = path.iter().rev().map(|step| match step {
+        MismatchStep::FunParamType => "the parameter type".to_owned(),
+        MismatchStep::FunBodyType => "the return type".to_owned(),
+        MismatchStep::FunBodyExpr => "the function body".to_owned(),
+        MismatchStep::Field(label) => format!("field `{}`", interner.resolve(*label).unwrap()),
+        MismatchStep::ArrayElem(index) => format!("element {index}"),
+        MismatchStep::Arg(index) => format!("argument {}", index + 1),
+    });
+    steps.format(" of ").to_string()
+}
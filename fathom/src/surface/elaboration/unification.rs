@@ -23,9 +23,9 @@ use crate::alloc::SliceVec;
 use crate::core::semantics::{
     self, ArcValue, Branches, Closure, Elim, Head, SplitBranches, Telescope, Value,
 };
-use crate::core::{Prim, Term};
+use crate::core::{to_scope_str, Const, Prim, Term, UIntStyle};
 use crate::env::{EnvLen, Index, Level, SharedEnv, SliceEnv, UniqueEnv};
-use crate::source::{Spanned, StringId};
+use crate::source::{Span, Spanned, StringId};
 use crate::surface::Plicity;
 
 /// Errors encountered during unification.
@@ -151,7 +151,7 @@ pub enum RenameError {
     /// In some type systems we could solve this using [equi-recursive types].
     ///
     /// [equi-recursive types]: https://www.cs.cornell.edu/courses/cs4110/2012fa/lectures/lecture27.pdf
-    InfiniteSolution,
+    InfiniteSolution(Level),
 }
 
 /// Unification context.
@@ -276,6 +276,21 @@ impl<'arena, 'env> Context<'arena, 'env> {
                 }
                 Ok(())
             }
+            (Value::ByteArrayLit(bytes0), Value::ByteArrayLit(bytes1)) => match bytes0 == bytes1 {
+                true => Ok(()),
+                false => Err(Error::Mismatch),
+            },
+            (Value::ByteArrayLit(_), Value::ArrayLit(_))
+            | (Value::ArrayLit(_), Value::ByteArrayLit(_)) => {
+                let elem_exprs0 = value0.array_elems().ok_or(Error::Mismatch)?;
+                let elem_exprs1 = value1.array_elems().ok_or(Error::Mismatch)?;
+                for (elem_expr0, elem_expr1) in
+                    Iterator::zip(elem_exprs0.iter(), elem_exprs1.iter())
+                {
+                    self.unify(elem_expr0, elem_expr1)?;
+                }
+                Ok(())
+            }
 
             (Value::FormatRecord(labels0, formats0), Value::FormatRecord(labels1, formats1)) => {
                 if labels0 != labels1 {
@@ -296,6 +311,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
             }
 
             (Value::ConstLit(const0), Value::ConstLit(const1)) if const0 == const1 => Ok(()),
+            (Value::StringLit(string0), Value::StringLit(string1)) if string0 == string1 => Ok(()),
 
             // Meta-local cases
             //
@@ -319,13 +335,13 @@ impl<'arena, 'env> Context<'arena, 'env> {
         }
         for (elim0, elim1) in Iterator::zip(spine0.iter(), spine1.iter()) {
             match (elim0, elim1) {
-                (Elim::FunApp(plicity0, arg_expr0), Elim::FunApp(plicity1, arg_expr1))
+                (Elim::FunApp(_, plicity0, arg_expr0), Elim::FunApp(_, plicity1, arg_expr1))
                     if plicity0 == plicity1 =>
                 {
                     self.unify(arg_expr0, arg_expr1)?;
                 }
-                (Elim::RecordProj(label0), Elim::RecordProj(label1)) if label0 == label1 => {}
-                (Elim::ConstMatch(branches0), Elim::ConstMatch(branches1)) => {
+                (Elim::RecordProj(_, label0), Elim::RecordProj(_, label1)) if label0 == label1 => {}
+                (Elim::ConstMatch(_, branches0), Elim::ConstMatch(_, branches1)) => {
                     self.unify_branches(branches0, branches1)?;
                 }
                 (_, _) => {
@@ -433,7 +449,9 @@ impl<'arena, 'env> Context<'arena, 'env> {
         value: &ArcValue<'arena>,
     ) -> Result<(), Error> {
         let var = Spanned::empty(Arc::new(Value::local_var(self.local_exprs.next_level())));
-        let value = self.elim_env().fun_app(plicity, value.clone(), var.clone());
+        let value = self
+            .elim_env()
+            .fun_app(Span::Empty, plicity, value.clone(), var.clone());
         let body_expr = self.elim_env().apply_closure(body_expr, var);
 
         self.local_exprs.push();
@@ -455,7 +473,9 @@ impl<'arena, 'env> Context<'arena, 'env> {
         value: &ArcValue<'arena>,
     ) -> Result<(), Error> {
         for (label, expr) in Iterator::zip(labels.iter(), exprs.iter()) {
-            let field_value = self.elim_env().record_proj(value.clone(), *label);
+            let field_value = self
+                .elim_env()
+                .record_proj(Span::Empty, value.clone(), *label);
             self.unify(expr, &field_value)?;
         }
         Ok(())
@@ -498,7 +518,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
         for elim in spine {
             match elim {
-                Elim::FunApp(_, arg_expr) => match self.elim_env().force(arg_expr).as_ref() {
+                Elim::FunApp(_, _, arg_expr) => match self.elim_env().force(arg_expr).as_ref() {
                     Value::Stuck(Head::LocalVar(source_var), spine)
                         if spine.is_empty() && self.renaming.set_local(*source_var) => {}
                     Value::Stuck(Head::LocalVar(source_var), _) => {
@@ -506,8 +526,8 @@ impl<'arena, 'env> Context<'arena, 'env> {
                     }
                     _ => return Err(SpineError::NonLocalFunApp),
                 },
-                Elim::RecordProj(label) => return Err(SpineError::RecordProj(*label)),
-                Elim::ConstMatch(_) => return Err(SpineError::ConstMatch),
+                Elim::RecordProj(_, label) => return Err(SpineError::RecordProj(*label)),
+                Elim::ConstMatch(_, _) => return Err(SpineError::ConstMatch),
             }
         }
 
@@ -518,10 +538,10 @@ impl<'arena, 'env> Context<'arena, 'env> {
     /// correspond to the given `spine`.
     fn fun_intros(&self, spine: &[Elim<'arena>], term: Term<'arena>) -> Term<'arena> {
         spine.iter().fold(term, |term, elim| match elim {
-            Elim::FunApp(plicity, _) => {
+            Elim::FunApp(_, plicity, _) => {
                 Term::FunLit(term.span(), *plicity, None, self.scope.to_scope(term))
             }
-            Elim::RecordProj(_) | Elim::ConstMatch(_) => {
+            Elim::RecordProj(_, _) | Elim::ConstMatch(_, _) => {
                 unreachable!("should have been caught by `init_renaming`")
             }
         })
@@ -551,32 +571,32 @@ impl<'arena, 'env> Context<'arena, 'env> {
                         Some(target_var) => Term::LocalVar(span, target_var),
                     },
                     Head::MetaVar(var) => match *var {
-                        var if meta_var == var => return Err(RenameError::InfiniteSolution),
+                        var if meta_var == var => return Err(RenameError::InfiniteSolution(var)),
                         var => Term::MetaVar(span, var),
                     },
                 };
 
                 spine.iter().try_fold(head_expr, |head_expr, elim| {
                     Ok(match elim {
-                        Elim::FunApp(plicity, arg_expr) => Term::FunApp(
+                        Elim::FunApp(_, plicity, arg_expr) => Term::FunApp(
                             span,
                             *plicity,
                             self.scope.to_scope(head_expr),
                             self.scope.to_scope(self.rename(meta_var, arg_expr)?),
                         ),
-                        Elim::RecordProj(label) => {
+                        Elim::RecordProj(_, label) => {
                             Term::RecordProj(span, self.scope.to_scope(head_expr), *label)
                         }
-                        Elim::ConstMatch(branches) => {
+                        Elim::ConstMatch(_, branches) => {
                             let mut branches = branches.clone();
                             let mut pattern_branches =
                                 SliceVec::new(self.scope, branches.num_patterns());
 
                             let default_branch = loop {
                                 match self.elim_env().split_branches(branches) {
-                                    SplitBranches::Branch((r#const, body_expr), next_branch) => {
+                                    SplitBranches::Branch((pattern, body_expr), next_branch) => {
                                         pattern_branches
-                                            .push((r#const, self.rename(meta_var, &body_expr)?));
+                                            .push((pattern, self.rename(meta_var, &body_expr)?));
                                         branches = next_branch;
                                     }
                                     SplitBranches::Default(default_name, default_expr) => {
@@ -648,6 +668,15 @@ impl<'arena, 'env> Context<'arena, 'env> {
 
                 Ok(Term::ArrayLit(span, new_elem_exprs.into()))
             }
+            Value::ByteArrayLit(bytes) => {
+                let elem_exprs = (bytes.iter())
+                    .map(|byte| Term::ConstLit(span, Const::U8(*byte, UIntStyle::Decimal)));
+
+                Ok(Term::ArrayLit(
+                    span,
+                    self.scope.to_scope_from_iter(elem_exprs),
+                ))
+            }
 
             Value::FormatRecord(labels, formats) => {
                 let formats = self.rename_telescope(meta_var, formats)?;
@@ -671,6 +700,7 @@ impl<'arena, 'env> Context<'arena, 'env> {
             }
 
             Value::ConstLit(constant) => Ok(Term::ConstLit(span, *constant)),
+            Value::StringLit(string) => Ok(Term::StringLit(span, to_scope_str(self.scope, string))),
         }
     }
 
@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use logos::{Filter, Logos};
 
@@ -5,7 +7,8 @@ use crate::files::FileId;
 use crate::source::{BytePos, ByteRange, ProgramSource};
 
 pub const KEYWORDS: &[&str] = &[
-    "def", "else", "false", "fun", "if", "let", "match", "overlap", "then", "true", "Type", "where",
+    "def", "else", "false", "fun", "if", "in", "let", "match", "module", "overlap", "then", "true",
+    "Type", "where",
 ];
 
 pub fn is_keyword(word: &str) -> bool {
@@ -22,8 +25,10 @@ pub enum Token<'source> {
     Hole(&'source str),
     #[regex(r#""([^"\\]|\\.)*""#, |lex| &lex.slice()[1..(lex.slice().len() - 1)])]
     StringLiteral(&'source str),
-    #[regex(r"[+-]?[0-9][a-zA-Z0-9_]*")]
+    #[regex(r"[0-9][a-zA-Z0-9_]*")]
     NumberLiteral(&'source str),
+    #[regex(r"///([^/\n].*)?\n", doc_comment)]
+    DocComment(&'source str),
 
     #[token("def")]
     KeywordDef,
@@ -35,10 +40,14 @@ pub enum Token<'source> {
     KeywordFun,
     #[token("if")]
     KeywordIf,
+    #[token("in")]
+    KeywordIn,
     #[token("let")]
     KeywordLet,
     #[token("match")]
     KeywordMatch,
+    #[token("module")]
+    KeywordModule,
     #[token("overlap")]
     KeywordOverlap,
     #[token("then")]
@@ -74,6 +83,8 @@ pub enum Token<'source> {
     Less,
     #[token(".")]
     FullStop,
+    #[token("..=")]
+    FullStopFullStopEquals,
     #[token("/")]
     ForwardSlash,
     #[token("->")]
@@ -132,6 +143,12 @@ where
     ByteRange::new(span.start as BytePos, span.end as BytePos)
 }
 
+/// The text of a doc comment, without its leading `///` or line ending.
+fn doc_comment<'source>(lexer: &mut logos::Lexer<'source, Token<'source>>) -> &'source str {
+    let line = &lexer.slice()[3..(lexer.slice().len() - 1)];
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
 fn block_comment<'source>(lexer: &mut logos::Lexer<'source, Token<'source>>) -> Filter<Error> {
     let mut comment_lexer = lexer.to_owned().morph::<BlockComment>();
     let first_open = lexer_range(&comment_lexer);
@@ -209,7 +226,7 @@ impl Error {
 pub fn tokens(
     source: &ProgramSource,
 ) -> impl Iterator<Item = Result<Spanned<Token<'_>, BytePos>, Error>> {
-    Token::lexer(source).spanned().map(move |(token, range)| {
+    let tokens = Token::lexer(source).spanned().map(move |(token, range)| {
         let start = range.start as BytePos;
         let end = range.end as BytePos;
         match token {
@@ -219,6 +236,35 @@ pub fn tokens(
             }),
             token => Ok((start, token, end)),
         }
+    });
+
+    skip_detached_doc_comments(tokens)
+}
+
+/// Doc comments are only attached to top-level items, so doc comments that
+/// are not followed by a `def` keyword are skipped, like other comments.
+fn skip_detached_doc_comments<'source>(
+    mut tokens: impl Iterator<Item = Result<Spanned<Token<'source>, BytePos>, Error>>,
+) -> impl Iterator<Item = Result<Spanned<Token<'source>, BytePos>, Error>> {
+    let mut doc_comments = Vec::new();
+    let mut pending_tokens = VecDeque::new();
+
+    std::iter::from_fn(move || loop {
+        if let Some(token) = pending_tokens.pop_front() {
+            return Some(token);
+        }
+
+        match tokens.next()? {
+            Ok(token @ (_, Token::DocComment(_), _)) => doc_comments.push(token),
+            Ok(token @ (_, Token::KeywordDef, _)) => {
+                pending_tokens.extend(doc_comments.drain(..).map(Ok));
+                pending_tokens.push_back(Ok(token));
+            }
+            token => {
+                doc_comments.clear();
+                return Some(token);
+            }
+        }
     })
 }
 
@@ -229,13 +275,16 @@ impl<'source> Token<'source> {
             Token::Hole(_) => "hole",
             Token::StringLiteral(_) => "string literal",
             Token::NumberLiteral(_) => "number literal",
+            Token::DocComment(_) => "doc comment",
             Token::KeywordDef => "def",
             Token::KeywordElse => "else",
             Token::KeywordFalse => "false",
             Token::KeywordFun => "fun",
             Token::KeywordIf => "if",
+            Token::KeywordIn => "in",
             Token::KeywordLet => "let",
             Token::KeywordMatch => "match",
+            Token::KeywordModule => "module",
             Token::KeywordOverlap => "overlap",
             Token::KeywordThen => "then",
             Token::KeywordTrue => "true",
@@ -248,6 +297,7 @@ impl<'source> Token<'source> {
             Token::EqualsGreater => "=>",
             Token::ForwardSlash => "/",
             Token::FullStop => ".",
+            Token::FullStopFullStopEquals => "..=",
             Token::HyphenGreater => "->",
             Token::LessHyphen => "<-",
             Token::Minus => "-",
@@ -51,8 +51,11 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
 
     fn item<Range>(&'arena self, item: &Item<'_, Range>) -> DocBuilder<'interner, 'arena> {
         match item {
-            Item::Def(item) => self
-                .concat([
+            Item::Def(item) => self.concat([
+                self.concat(item.doc.iter().map(|line| {
+                    self.concat([self.text("///"), self.string_id(*line), self.hardline()])
+                })),
+                self.concat([
                     self.text("def"),
                     self.space(),
                     match item.r#type {
@@ -80,6 +83,7 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                     self.text(";"),
                 ])
                 .group(),
+            ]),
             Item::ReportedError(_) => self.text("#error"),
         }
     }
@@ -90,6 +94,11 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
             Pattern::Name(_, name) => self.ident(*name),
             Pattern::StringLiteral(_, number) => self.string_id(*number),
             Pattern::NumberLiteral(_, number) => self.string_id(*number),
+            Pattern::NumberRange(_, start, end) => self.concat([
+                self.string_id(*start),
+                self.text("..="),
+                self.string_id(*end),
+            ]),
             Pattern::BooleanLiteral(_, boolean) => match *boolean {
                 true => self.text("true"),
                 false => self.text("false"),
@@ -176,6 +185,36 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 self.line(),
                 self.term(body_expr),
             ]),
+            Term::LetRecord(_, def, body_expr) => self.concat([
+                self.concat([
+                    self.text("let"),
+                    self.space(),
+                    self.sequence(
+                        true,
+                        self.text("{"),
+                        def.labels.iter().map(|(_, label)| self.ident(*label)),
+                        self.text(","),
+                        self.text("}"),
+                    ),
+                    match &def.r#type {
+                        None => self.nil(),
+                        Some(r#type) => self.concat([
+                            self.space(),
+                            self.text(":"),
+                            self.softline(),
+                            self.term(r#type),
+                        ]),
+                    },
+                    self.space(),
+                    self.text("="),
+                    self.softline(),
+                    self.term(&def.expr),
+                    self.text(";"),
+                ])
+                .group(),
+                self.line(),
+                self.term(body_expr),
+            ]),
             Term::If(_, cond_expr, then_expr, mut else_expr) => {
                 let mut branches = Vec::new();
 
@@ -266,13 +305,23 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 });
                 self.sequence(true, self.text("{"), fields, self.text(","), self.text("}"))
             }
-            Term::RecordLiteral(_, fields) => {
+            Term::RecordLiteral(_, fields) => self.record_literal(
+                (fields.iter()).map(|field| (field.label.1, self.term(&field.expr))),
+            ),
+            Term::RecordUpdate(_, head_expr, fields) => {
                 let fields = fields.iter().map(|field| {
                     self.ident(field.label.1)
                         .append(" = ")
                         .append(self.term(&field.expr))
                 });
-                self.sequence(true, self.text("{"), fields, self.text(","), self.text("}"))
+                let start_delim = self.concat([
+                    self.text("{"),
+                    self.space(),
+                    self.term(head_expr),
+                    self.space(),
+                    self.text("|"),
+                ]);
+                self.sequence(true, start_delim, fields, self.text(","), self.text("}"))
             }
             Term::Tuple(_, terms) if terms.len() == 1 => {
                 self.text("(").append(self.term(&terms[0]).append(",)"))
@@ -288,9 +337,9 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
                 ),
             ]),
             Term::ArrayLiteral(_, terms) => {
-                let terms = terms.iter().map(|term| self.term(term));
-                self.sequence(false, self.text("["), terms, self.text(","), self.text("]"))
+                self.array_literal(terms.iter().map(|term| self.term(term)))
             }
+            Term::ElidedElems(_, len) => self.text(format!("... ({len} more)")),
             Term::StringLiteral(_, number) => {
                 self.concat([self.text("\""), self.string_id(*number), self.text("\"")])
             }
@@ -389,6 +438,43 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
         }
     }
 
+    /// Pretty prints a record literal, given the labels of its fields and
+    /// their pretty printed expressions.
+    pub fn record_literal(
+        &'arena self,
+        fields: impl ExactSizeIterator<Item = (StringId, DocBuilder<'interner, 'arena>)> + Clone,
+    ) -> DocBuilder<'interner, 'arena> {
+        let fields = fields.map(|(label, expr)| self.ident(label).append(" = ").append(expr));
+        self.sequence(true, self.text("{"), fields, self.text(","), self.text("}"))
+    }
+
+    /// Pretty prints an array literal, given its pretty printed elements.
+    pub fn array_literal(
+        &'arena self,
+        elems: impl ExactSizeIterator<Item = DocBuilder<'interner, 'arena>> + Clone,
+    ) -> DocBuilder<'interner, 'arena> {
+        self.sequence(false, self.text("["), elems, self.text(","), self.text("]"))
+    }
+
+    /// Pretty prints an array of bytes as a hexdump, in the style of
+    /// `hexdump -C`. Each line shows the offset of the line within the array,
+    /// followed by up to sixteen bytes in hexadecimal, and then as ASCII.
+    pub fn hexdump(&'arena self, bytes: &[u8]) -> DocBuilder<'interner, 'arena> {
+        if bytes.is_empty() {
+            return self.text("[]");
+        }
+
+        let lines = (bytes.chunks(16).enumerate())
+            .map(|(index, bytes)| self.text(hexdump_line(index * 16, bytes)));
+        self.concat([
+            self.text("["),
+            self.concat([self.hardline(), self.intersperse(lines, self.hardline())])
+                .nest(INDENT),
+            self.hardline(),
+            self.text("]"),
+        ])
+    }
+
     /// Wrap a document in parens.
     fn paren(&'arena self, doc: DocBuilder<'interner, 'arena>) -> DocBuilder<'interner, 'arena> {
         self.concat([self.text("("), doc, self.text(")")])
@@ -426,6 +512,28 @@ impl<'interner, 'arena> Context<'interner, 'arena> {
     }
 }
 
+/// Formats a line of a hexdump, starting at `offset`.
+fn hexdump_line(offset: usize, bytes: &[u8]) -> String {
+    let mut line = format!("{offset:08x}");
+    for index in 0..16 {
+        if index % 8 == 0 {
+            line.push(' ');
+        }
+        match bytes.get(index) {
+            Some(byte) => line.push_str(&format!(" {byte:02x}")),
+            None => line.push_str("   "),
+        }
+    }
+    line.push_str("  |");
+    line.extend(bytes.iter().map(|&byte| match byte {
+        b' ' => ' ',
+        _ if byte.is_ascii_graphic() => char::from(byte),
+        _ => '.',
+    }));
+    line.push('|');
+    line
+}
+
 impl<'interner, 'arena, A: 'arena> DocAllocator<'arena, A> for Context<'interner, 'arena> {
     type Doc = RefDoc<'arena, A>;
 
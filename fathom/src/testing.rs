@@ -0,0 +1,123 @@
+//! Helpers for writing unit tests.
+
+use std::cell::RefCell;
+
+use scoped_arena::Scope;
+
+use crate::core;
+use crate::files::FileId;
+use crate::source::{ByteRange, ProgramSource, StringInterner};
+use crate::surface::elaboration::{self, ItemEnv};
+use crate::surface::{self, Module, Term};
+
+/// The file that test sources are elaborated in.
+pub fn file_id() -> FileId {
+    FileId::try_from(1).unwrap()
+}
+
+/// Parse a module, allocating it in `scope`.
+///
+/// # Panics
+///
+/// This panics if the source fails to parse.
+#[track_caller]
+pub fn parse_module<'arena>(
+    interner: &RefCell<StringInterner>,
+    scope: &'arena Scope<'arena>,
+    source: &str,
+) -> Module<'arena, ByteRange> {
+    let source = ProgramSource::try_from(source.to_owned()).unwrap();
+    let (module, messages) = Module::parse(interner, scope, &source);
+    assert!(messages.is_empty(), "{messages:?}");
+
+    module
+}
+
+/// Parse a term, allocating it in `scope`.
+///
+/// # Panics
+///
+/// This panics if the source fails to parse.
+#[track_caller]
+pub fn parse_term<'arena>(
+    interner: &RefCell<StringInterner>,
+    scope: &'arena Scope<'arena>,
+    source: &str,
+) -> Term<'arena, ByteRange> {
+    let source = ProgramSource::try_from(source.to_owned()).unwrap();
+    let (term, messages) = Term::parse(interner, scope, &source);
+    assert!(messages.is_empty(), "{messages:?}");
+
+    term
+}
+
+/// Parse and elaborate a module, allocating it in `scope`. The items of the
+/// module are also returned, so that other terms can be elaborated with
+/// access to them.
+///
+/// # Panics
+///
+/// This panics if the source fails to parse, or if elaboration produces any
+/// diagnostics.
+#[track_caller]
+pub fn elab_module<'arena>(
+    interner: &RefCell<StringInterner>,
+    scope: &'arena Scope<'arena>,
+    source: &str,
+) -> (core::Module<'arena>, ItemEnv<'arena>) {
+    let surface_module = parse_module(interner, scope, source);
+    let mut context = elaboration::Context::new(file_id(), interner, scope, ItemEnv::new());
+    let module = context.elab_module(scope, &surface_module, &mut |m| panic!("{m:?}"));
+
+    (module, context.finish())
+}
+
+/// Parse and elaborate a term, allocating it in `scope`.
+///
+/// # Panics
+///
+/// This panics if the source fails to parse, or if elaboration produces any
+/// diagnostics.
+#[track_caller]
+pub fn elab_term<'arena>(
+    interner: &RefCell<StringInterner>,
+    scope: &'arena Scope<'arena>,
+    source: &str,
+) -> core::Term<'arena> {
+    let surface_term = parse_term(interner, scope, source);
+    let mut context = elaboration::Context::new(file_id(), interner, scope, ItemEnv::new());
+    let (term, _) = context.elab_term(scope, &surface_term, &mut |m| panic!("{m:?}"));
+
+    term
+}
+
+/// Parse, elaborate, and normalize a term, then distill it back to surface
+/// syntax and compare its pretty printed form to `expected`.
+///
+/// Leading and trailing whitespace in `expected` is ignored, so that longer
+/// terms can be written as indented raw string literals.
+///
+/// # Panics
+///
+/// This panics if the source fails to parse, if elaboration produces any
+/// diagnostics, or if the normalized term does not match `expected`.
+#[track_caller]
+pub fn assert_normalizes(source: &str, expected: &str) {
+    let interner = RefCell::new(StringInterner::new());
+    let core_scope = Scope::new();
+    let surface_scope = Scope::new();
+
+    let surface_term = parse_term(&interner, &surface_scope, source);
+    let mut context = elaboration::Context::new(file_id(), &interner, &core_scope, ItemEnv::new());
+    let (term, _) = context.elab_term(&core_scope, &surface_term, &mut |m| panic!("{m:?}"));
+    let term = context.eval_env().normalize(&core_scope, &term);
+
+    let distillation_scope = Scope::new();
+    let term = context
+        .distillation_context(&distillation_scope)
+        .check(&term);
+    let pretty_context = surface::pretty::Context::new(&interner, &distillation_scope);
+    let found = pretty_context.term(&term).into_doc().pretty(80).to_string();
+
+    assert_eq!(found.trim(), expected.trim());
+}
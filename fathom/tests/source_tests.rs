@@ -41,6 +41,7 @@ impl TestMode {
 struct Config {
     #[serde(default = "DEFAULT_ALLOW_ERRORS")]
     allow_errors: bool,
+    endianness: Option<String>,
     mode: Option<TestMode>,
     #[serde(default = "DEFAULT_IGNORE")]
     ignore: bool,
@@ -302,6 +303,9 @@ impl<'a> TestCommand<'a> {
         if self.config.allow_errors {
             command.arg("--allow-errors");
         }
+        if let Some(endianness) = &self.config.endianness {
+            command.args(["--endianness", endianness]);
+        }
 
         match command.output() {
             Ok(output) => {